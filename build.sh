@@ -2,8 +2,13 @@
 
 set -e
 
-cargo build --target $TARGET --all-features --release
+# Every feature that builds for a bare metal target. `std`, `test-util` and `simulator` need a
+# host, and `slint` needs a global allocator, which the examples don't set up.
+FEATURES=graphics,mono-font,framebuffer,defmt,trace-commands,parallel,i2c,spi,tiny-text,ufmt,frame-dump,bmp,mirror,panic-screen,ffi,shared,metrics
+
+cargo build --target $TARGET --features $FEATURES,slint --release
 
 if [ -z $DISABLE_EXAMPLES ]; then
-	cargo build --target $TARGET --all-features --examples
-fi
\ No newline at end of file
+	# Unoptimised builds of the examples don't fit into the flash of the STM32F103
+	cargo build --target $TARGET --features $FEATURES --examples --release
+fi
@@ -0,0 +1,293 @@
+//! Frame by frame animations stored in flash
+//!
+//! An [`Animation`] plays a sequence of equally sized frames, each shown for its own duration,
+//! e.g. a boot animation. The frames are borrowed, so they can stay in flash as statics, either
+//! packed 1bpp bitmaps like those drawn with
+//! [`GraphicsMode::draw_bitmap`](crate::mode::GraphicsMode::draw_bitmap) or run-length encoded
+//! images, see the [`rle`](crate::rle) module.
+//!
+//! ```rust,ignore
+//! static FRAMES: [&[u8]; 3] = [&LOGO_0, &LOGO_1, &LOGO_2];
+//!
+//! let mut animation = Animation::new(&FRAMES, &[80, 80, 500], (64, 32), FrameFormat::Rle)
+//!     .at(32, 64);
+//! animation.play(&mut display, &mut delay)?;
+//! ```
+//!
+//! Every frame is drawn over the area of the previous one and sent with `flush_dirty`, so only
+//! the pages the animation covers go out. `play` blocks until the last frame was shown. For a
+//! looping animation, or one that runs alongside other work, call [`Animation::tick`] from the
+//! main loop instead, which can also be paused and resumed.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::blocking::delay::DelayMs;
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::{storage::FrameBufferStorage, GraphicsMode},
+};
+
+/// How the frames of an [`Animation`] are stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FrameFormat {
+    /// Packed 1bpp bitmaps, every row starting on a new byte with the leftmost pixel in the most
+    /// significant bit
+    Packed,
+    /// Run-length encoded, see the [`rle`](crate::rle) module
+    Rle,
+}
+
+/// A sequence of frames played on a [`GraphicsMode`], see the [module documentation](self)
+#[derive(Clone, Debug)]
+pub struct Animation<'a> {
+    frames: &'a [&'a [u8]],
+    durations_ms: &'a [u16],
+    size: (u32, u32),
+    format: FrameFormat,
+    position: (u32, u32),
+    looping: bool,
+    paused: bool,
+    finished: bool,
+    current: usize,
+    /// Time the current frame has been shown for
+    shown_ms: u32,
+}
+
+impl<'a> Animation<'a> {
+    /// Create an animation of `frames` of `size` (width, height) pixels in `format`, shown at
+    /// the top left corner of the display. Frame `i` is shown for `durations_ms[i]`
+    /// milliseconds; frames without a duration of their own use the last one.
+    ///
+    /// Panics if `frames` or `durations_ms` is empty.
+    pub fn new(
+        frames: &'a [&'a [u8]],
+        durations_ms: &'a [u16],
+        size: (u32, u32),
+        format: FrameFormat,
+    ) -> Self {
+        assert!(!frames.is_empty(), "animation without frames");
+        assert!(
+            !durations_ms.is_empty(),
+            "animation without frame durations"
+        );
+
+        Self {
+            frames,
+            durations_ms,
+            size,
+            format,
+            position: (0, 0),
+            looping: false,
+            paused: false,
+            finished: false,
+            current: 0,
+            shown_ms: 0,
+        }
+    }
+
+    /// Show the animation with its top left corner at `x` and `y`
+    pub fn at(self, x: u32, y: u32) -> Self {
+        Self {
+            position: (x, y),
+            ..self
+        }
+    }
+
+    /// Start over with the first frame after the last one instead of stopping there. Defaults to
+    /// `false`.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    /// Stop advancing the frames on `tick`. The current frame stays on the screen.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Continue a paused animation where it stopped
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Whether the animation is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether the last frame of an animation that does not loop was shown for its duration
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Get the index of the frame currently shown
+    pub fn current_frame(&self) -> usize {
+        self.current
+    }
+
+    /// Go back to the first frame, without drawing it
+    pub fn restart(&mut self) {
+        self.current = 0;
+        self.shown_ms = 0;
+        self.finished = false;
+    }
+
+    /// Draw the current frame and send it with `flush_dirty`
+    pub fn show<DI, const N: usize, R, S>(
+        &self,
+        display: &mut GraphicsMode<DI, N, R, S>,
+    ) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+        S: FrameBufferStorage,
+    {
+        let frame = self.frames[self.current];
+        let (x, y) = self.position;
+        let (width, height) = self.size;
+
+        match self.format {
+            FrameFormat::Packed => display.draw_bitmap(x, y, width, height, frame),
+            FrameFormat::Rle => display.draw_rle_image((x, y), frame, (width, height)),
+        }
+
+        display.flush_dirty().map(|_| ())
+    }
+
+    /// Tell the animation that `elapsed_ms` milliseconds passed, and show the next frame on
+    /// `display` if it is due. Returns whether a frame was drawn. Nothing happens while the
+    /// animation is paused or finished.
+    pub fn tick<DI, const N: usize, R, S>(
+        &mut self,
+        display: &mut GraphicsMode<DI, N, R, S>,
+        elapsed_ms: u32,
+    ) -> Result<bool, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+        S: FrameBufferStorage,
+    {
+        if self.paused || self.finished {
+            return Ok(false);
+        }
+
+        let mut advanced = false;
+        self.shown_ms = self.shown_ms.saturating_add(elapsed_ms);
+        while self.shown_ms >= self.duration_ms() {
+            self.shown_ms -= self.duration_ms();
+            advanced = true;
+
+            if self.current + 1 < self.frames.len() {
+                self.current += 1;
+            } else if self.looping {
+                self.current = 0;
+            } else {
+                self.finished = true;
+                self.shown_ms = 0;
+                return Ok(false);
+            }
+        }
+
+        if !advanced {
+            return Ok(false);
+        }
+
+        self.show(display).map(|_| true)
+    }
+
+    /// Play the animation from the current frame to the end, waiting with `delay` for every
+    /// frame's duration. The last frame stays on the screen. This plays through once even if the
+    /// animation loops, and ignores `pause`.
+    pub fn play<DI, const N: usize, R, S, DELAY>(
+        &mut self,
+        display: &mut GraphicsMode<DI, N, R, S>,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+        S: FrameBufferStorage,
+        DELAY: DelayMs<u16>,
+    {
+        self.finished = false;
+        self.show(display)?;
+
+        loop {
+            delay.delay_ms(self.remaining_ms());
+            self.shown_ms = 0;
+
+            if self.current + 1 == self.frames.len() {
+                self.finished = true;
+                return Ok(());
+            }
+
+            self.current += 1;
+            self.show(display)?;
+        }
+    }
+
+    /// Duration of the current frame
+    fn duration_ms(&self) -> u32 {
+        // Zero durations count as 1ms, so `tick` always moves on
+        let last = self.durations_ms[self.durations_ms.len() - 1];
+        u32::from(*self.durations_ms.get(self.current).unwrap_or(&last)).max(1)
+    }
+
+    /// Time the current frame still has to be shown for
+    fn remaining_ms(&self) -> u16 {
+        let remaining = self.duration_ms().saturating_sub(self.shown_ms);
+
+        u16::try_from(remaining).unwrap_or(u16::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{CaptureInterface, DelayRecorder},
+        Builder,
+    };
+
+    static FRAMES: [&[u8]; 3] = [&[0x80], &[0x40], &[0x20]];
+
+    #[test]
+    fn frames_advance_with_their_durations() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        let mut animation =
+            Animation::new(&FRAMES, &[100, 50], (8, 1), FrameFormat::Packed).at(8, 0);
+
+        animation.show(&mut display).unwrap();
+        assert!(display.get_pixel(8, 0));
+        assert!(!animation.tick(&mut display, 99).unwrap());
+        assert!(animation.tick(&mut display, 1).unwrap());
+        assert!(!display.get_pixel(8, 0) && display.get_pixel(9, 0));
+
+        // A long tick skips frames, the last one uses the last duration
+        animation.pause();
+        assert!(!animation.tick(&mut display, 500).unwrap());
+        animation.resume();
+        iface.clear();
+        assert!(animation.tick(&mut display, 50).unwrap());
+        assert_eq!(animation.current_frame(), 2);
+        assert_eq!(
+            iface.data(),
+            [0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00]
+        );
+        assert!(!animation.tick(&mut display, 49).unwrap());
+        animation.tick(&mut display, 1).unwrap();
+        assert!(animation.is_finished());
+
+        animation.set_looping(true);
+        animation.restart();
+        assert!(animation.tick(&mut display, 400).unwrap());
+        assert_eq!(animation.current_frame(), 0);
+
+        let mut delay = DelayRecorder::new();
+        animation.play(&mut display, &mut delay).unwrap();
+        assert_eq!(delay.delays(), [100, 50, 50]);
+        assert!(display.get_pixel(10, 0) && animation.is_finished());
+    }
+}
@@ -0,0 +1,75 @@
+//! Display brightness presets and a perceptual brightness curve
+
+/// Brightness preset, setting the contrast together with the precharge period. Contrast alone
+/// only changes the segment current, which gives little visible range on these panels; a longer
+/// precharge additionally lets each pixel reach a higher voltage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Brightness {
+    /// Lowest contrast with the shortest precharge
+    Dimmest,
+    /// Low contrast with a short precharge
+    Dim,
+    /// The contrast and precharge used after `init`
+    Normal,
+    /// High contrast with a long precharge
+    Bright,
+    /// Highest contrast with the longest precharge
+    Brightest,
+}
+
+impl Brightness {
+    /// Contrast register value of the preset
+    pub const fn contrast(self) -> u8 {
+        match self {
+            Brightness::Dimmest => 0x00,
+            Brightness::Dim => 0x30,
+            Brightness::Normal => 0x80,
+            Brightness::Bright => 0xC0,
+            Brightness::Brightest => 0xFF,
+        }
+    }
+
+    /// Precharge `(phase1, phase2)` periods of the preset in display clocks, see
+    /// [`DisplayProperties::set_precharge`](crate::properties::DisplayProperties::set_precharge)
+    pub const fn precharge(self) -> (u8, u8) {
+        match self {
+            Brightness::Dimmest => (0x1, 0x2),
+            Brightness::Dim => (0x4, 0x2),
+            Brightness::Normal => (0x8, 0x2),
+            Brightness::Bright => (0xC, 0x2),
+            Brightness::Brightest => (0xF, 0x2),
+        }
+    }
+}
+
+/// Contrast register value that looks `percent` bright, from 0 to 100, as set by
+/// [`set_brightness_percent`](crate::properties::DisplayProperties::set_brightness_percent) of
+/// the display properties. Values above 100 are treated as 100.
+///
+/// The light output of the panel follows the contrast about linearly, but the eye sees small
+/// changes at low light levels much more strongly than at high ones. The contrast therefore
+/// follows a gamma curve of 2.2, `255 * (percent / 100)^2.2`, so equal steps in percent look
+/// like equal steps in brightness. Even a contrast of 0 leaves lit pixels visible, so the lowest
+/// few percent all map to it.
+pub const fn contrast_for_percent(percent: u8) -> u8 {
+    let percent = if percent > 100 { 100 } else { percent };
+
+    GAMMA_CONTRAST[percent as usize]
+}
+
+/// `255 * (p / 100)^2.2` rounded, for `p` from 0 to 100
+#[rustfmt::skip]
+const GAMMA_CONTRAST: [u8; 101] = [
+    0, 0, 0, 0, 0, 0, 1, 1, 1, 1,
+    2, 2, 2, 3, 3, 4, 5, 5, 6, 7,
+    7, 8, 9, 10, 11, 12, 13, 14, 15, 17,
+    18, 19, 21, 22, 24, 25, 27, 29, 30, 32,
+    34, 36, 38, 40, 42, 44, 46, 48, 51, 53,
+    55, 58, 60, 63, 66, 68, 71, 74, 77, 80,
+    83, 86, 89, 92, 96, 99, 102, 106, 109, 113,
+    116, 120, 124, 128, 131, 135, 139, 143, 148, 152,
+    156, 160, 165, 169, 174, 178, 183, 188, 192, 197,
+    202, 207, 212, 217, 223, 228, 233, 238, 244, 249,
+    255,
+];
@@ -21,18 +21,18 @@
 //! Builder::new().connect(spi_interface);
 //! ```
 //!
-//! Connect over I2C, changing rotation
+//! Connect over I2C to a display with its SA0 pin pulled high, changing rotation. This needs the
+//! `i2c` feature.
 //!
 //! ```rust,ignore
-//! use display_interface_i2c::I2CInterface;
+//! use sh1108::i2c_interface::I2C_ADDRESS_SA0_HIGH;
 //!
 //! let i2c = /* I2C interface from your HAL of choice */;
 //!
-//! let i2c_interface = I2CInterface::new(i2c, 0x3D, 0x40);
-//!
 //! Builder::new()
 //!     .with_rotation(DisplayRotation::Rotate180)
-//!     .connect(i2c_interface);
+//!     .with_i2c_address(I2C_ADDRESS_SA0_HIGH)
+//!     .connect_i2c(i2c);
 //! ```
 //!
 //! The above examples will produce a [RawMode](../mode/raw/struct.RawMode.html) instance
@@ -46,11 +46,14 @@
 use core::marker::PhantomData;
 use hal::{self, digital::v2::OutputPin};
 
+#[cfg(feature = "i2c")]
+use crate::i2c_interface::{I2CDisplayInterface, I2CInterface, I2C_ADDRESS_SA0_LOW};
 use crate::{
     displayrotation::DisplayRotation,
     displaysize::DisplaySize,
+    error::ConfigError,
     mode::{displaymode::DisplayMode, raw::RawMode},
-    properties::DisplayProperties,
+    properties::{ChargePump, DisplayProperties, FlushEvent, PowerTiming, RAM_COLUMNS, RAM_PAGES},
 };
 
 /// Builder struct. Driver options and interface are set using its methods.
@@ -58,6 +61,19 @@ use crate::{
 pub struct Builder {
     display_size: DisplaySize,
     rotation: DisplayRotation,
+    mirror: (bool, bool),
+    bus_clock_hint: u32,
+    power_budget: u8,
+    ram_offset: (u8, u8),
+    clock_divider: (u8, u8),
+    precharge: (u8, u8),
+    vcom_level: u8,
+    charge_pump: ChargePump,
+    power_timing: PowerTiming,
+    pixel_shift_range: u8,
+    flush_hook: Option<fn(FlushEvent)>,
+    #[cfg(feature = "i2c")]
+    i2c_address: u8,
 }
 
 impl Default for Builder {
@@ -72,6 +88,19 @@ impl Builder {
         Builder {
             display_size: DisplaySize::Display128x160,
             rotation: DisplayRotation::Rotate0,
+            mirror: (false, false),
+            bus_clock_hint: 400_000,
+            power_budget: 100,
+            ram_offset: (0, 0),
+            clock_divider: (0x6, 0x0),
+            precharge: (0x8, 0x2),
+            vcom_level: 0x35,
+            charge_pump: ChargePump::Internal(0),
+            power_timing: PowerTiming::default(),
+            pixel_shift_range: 0,
+            flush_hook: None,
+            #[cfg(feature = "i2c")]
+            i2c_address: I2C_ADDRESS_SA0_LOW,
         }
     }
 }
@@ -90,12 +119,193 @@ impl Builder {
         Self { rotation, ..self }
     }
 
-    /// Finish the builder and use the given interface to communicate with the display.
+    /// Mirror the displayed image independent of the rotation, e.g. for a panel mounted behind a
+    /// mirror, see [`DisplayProperties::set_mirror`]. Takes effect on `init`. Defaults to no
+    /// mirroring.
+    pub fn with_mirror(self, horizontal: bool, vertical: bool) -> Self {
+        Self {
+            mirror: (horizontal, vertical),
+            ..self
+        }
+    }
+
+    /// Set the clock frequency of the display bus in Hz. This is only used to estimate transfer
+    /// times, see [`DisplayProperties::estimated_transfer_us`]. Defaults to 400kHz. For a
+    /// parallel interface, give nine times the number of write cycles per second.
+    pub fn with_bus_clock_hint(self, bus_clock_hint: u32) -> Self {
+        Self {
+            bus_clock_hint,
+            ..self
+        }
+    }
+
+    /// Limit the display current by dimming frames that light more than `max_on_pixels_percent`
+    /// percent of the pixels, see [`DisplayProperties::apply_power_budget`]. This protects a
+    /// current limited supply from e.g. an all white screen. Defaults to 100, i.e. no limit.
+    pub fn with_power_budget(self, max_on_pixels_percent: u8) -> Self {
+        Self {
+            power_budget: max_on_pixels_percent,
+            ..self
+        }
+    }
+
+    /// Shift the image by `column_offset` columns of display RAM, for modules whose visible area
+    /// starts at a different column than the display size implies. Defaults to 0.
+    pub fn with_column_offset(self, column_offset: u8) -> Self {
+        Self {
+            ram_offset: (column_offset, self.ram_offset.1),
+            ..self
+        }
+    }
+
+    /// Shift the image by `page_offset` pages of 8 rows of display RAM, for modules whose visible
+    /// area does not start at the first row. Defaults to 0.
+    pub fn with_page_offset(self, page_offset: u8) -> Self {
+        Self {
+            ram_offset: (self.ram_offset.0, page_offset),
+            ..self
+        }
+    }
+
+    /// Set the oscillator frequency and clock divide ratio minus one sent during init, see
+    /// [`DisplayProperties::set_clock_divider`]. Defaults to `(0x6, 0x0)`.
+    pub fn with_clock_divider(self, fosc: u8, divide_ratio: u8) -> Self {
+        Self {
+            clock_divider: (fosc, divide_ratio),
+            ..self
+        }
+    }
+
+    /// Set the precharge and discharge periods sent during init, see
+    /// [`DisplayProperties::set_precharge`]. Defaults to `(0x8, 0x2)`.
+    pub fn with_precharge(self, phase1: u8, phase2: u8) -> Self {
+        Self {
+            precharge: (phase1, phase2),
+            ..self
+        }
+    }
+
+    /// Set the VCOM deselect level sent during init, see
+    /// [`DisplayProperties::set_vcom_level`]. Defaults to 0x35.
+    pub fn with_vcom_level(self, vcom_level: u8) -> Self {
+        Self { vcom_level, ..self }
+    }
+
+    /// Set where the panel drive voltage comes from, see
+    /// [`DisplayProperties::set_charge_pump`]. Modules powered from an external VPP need
+    /// `ChargePump::External` so init disables the built-in DC-DC converter. Defaults to
+    /// `ChargePump::Internal(0)`.
+    pub fn with_charge_pump(self, charge_pump: ChargePump) -> Self {
+        Self {
+            charge_pump,
+            ..self
+        }
+    }
+
+    /// Set the delays of the power-on sequence run by the `init_with_timing` methods of the
+    /// modes, see [`DisplayProperties::set_power_timing`]. Defaults to `PowerTiming::default()`.
+    pub fn with_power_timing(self, power_timing: PowerTiming) -> Self {
+        Self {
+            power_timing,
+            ..self
+        }
+    }
+
+    /// Allow the image to be moved by up to `range` pixels in every direction to prevent burn-in,
+    /// see [`DisplayProperties::advance_pixel_shift`]. Defaults to 0, i.e. no shift.
+    pub fn with_pixel_shift_range(self, range: u8) -> Self {
+        Self {
+            pixel_shift_range: range,
+            ..self
+        }
+    }
+
+    /// Call `hook` with the progress of every flush, e.g. to measure frame times, see
+    /// [`DisplayProperties::set_flush_hook`]. Defaults to no hook.
+    pub fn with_flush_hook(self, hook: fn(FlushEvent)) -> Self {
+        Self {
+            flush_hook: Some(hook),
+            ..self
+        }
+    }
+
+    /// Set the 7 bit I2C address used by [`connect_i2c`](Self::connect_i2c), e.g.
+    /// [`I2C_ADDRESS_SA0_HIGH`](crate::i2c_interface::I2C_ADDRESS_SA0_HIGH) for a display with
+    /// the SA0 pin pulled high. Defaults to `I2C_ADDRESS_SA0_LOW`, i.e. `0x3C`.
+    #[cfg(feature = "i2c")]
+    pub fn with_i2c_address(self, address: u8) -> Self {
+        Self {
+            i2c_address: address,
+            ..self
+        }
+    }
+
+    /// Finish the builder and talk to the display over `i2c` through the bundled
+    /// [`I2CDisplayInterface`], at the address set with `with_i2c_address`.
+    #[cfg(feature = "i2c")]
+    pub fn connect_i2c<I2C>(self, i2c: I2C) -> DisplayMode<RawMode<I2CInterface<I2C>>>
+    where
+        I2C: hal::blocking::i2c::Write,
+    {
+        let address = self.i2c_address;
+
+        self.connect(I2CDisplayInterface::new_custom_address(i2c, address))
+    }
+
+    /// Check the configuration against the limits of the controller: the visible area, moved by
+    /// the display size and the RAM offsets, has to lie within the 160 columns and 20 pages of
+    /// display RAM, and the charge pump frequency must be at most 7. Every rotation works with
+    /// every size, as the display RAM is rotated as a whole.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let (width, height) = self.display_size.dimensions();
+        let (column_offset, page_offset) = self.ram_offset;
+
+        let end_column = u16::from(self.display_size.column_offset())
+            + u16::from(column_offset)
+            + u16::from(width);
+        if end_column > u16::from(RAM_COLUMNS) {
+            return Err(ConfigError::ColumnOffset);
+        }
+        if u16::from(page_offset) + u16::from(height / 8) > u16::from(RAM_PAGES) {
+            return Err(ConfigError::PageOffset);
+        }
+        if matches!(self.charge_pump, ChargePump::Internal(frequency) if frequency > 7) {
+            return Err(ConfigError::ChargePumpFrequency);
+        }
+
+        Ok(())
+    }
+
+    /// Like [`connect`](Self::connect), but check the configuration with
+    /// [`validate`](Self::validate) first, instead of sending addresses the controller wraps
+    /// around and showing garbage on the panel.
+    pub fn try_connect<DI>(self, interface: DI) -> Result<DisplayMode<RawMode<DI>>, ConfigError>
+    where
+        DI: display_interface::WriteOnlyDataCommand,
+    {
+        self.validate()?;
+
+        Ok(self.connect(interface))
+    }
+
+    /// Finish the builder and use the given interface to communicate with the display. The
+    /// configuration is not checked, see [`try_connect`](Self::try_connect).
     pub fn connect<DI>(self, interface: DI) -> DisplayMode<RawMode<DI>>
     where
         DI: display_interface::WriteOnlyDataCommand,
     {
-        let properties = DisplayProperties::new(interface, self.display_size, self.rotation);
+        let mut properties = DisplayProperties::new(interface, self.display_size, self.rotation);
+        properties.set_initial_mirror(self.mirror.0, self.mirror.1);
+        properties.set_bus_clock_hint(self.bus_clock_hint);
+        properties.set_power_budget(self.power_budget);
+        properties.set_ram_offset(self.ram_offset.0, self.ram_offset.1);
+        properties.set_clock_divider(self.clock_divider.0, self.clock_divider.1);
+        properties.set_precharge(self.precharge.0, self.precharge.1);
+        properties.set_vcom_level(self.vcom_level);
+        properties.set_charge_pump(self.charge_pump);
+        properties.set_power_timing(self.power_timing);
+        properties.set_pixel_shift_range(self.pixel_shift_range);
+        properties.set_flush_hook(self.flush_hook);
         DisplayMode::<RawMode<DI>>::new(properties)
     }
 }
@@ -113,6 +323,12 @@ impl<PinE> NoOutputPin<PinE> {
     }
 }
 
+impl<PinE> Default for NoOutputPin<PinE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<PinE> OutputPin for NoOutputPin<PinE> {
     type Error = PinE;
     fn set_low(&mut self) -> Result<(), PinE> {
@@ -125,7 +341,8 @@ impl<PinE> OutputPin for NoOutputPin<PinE> {
 
 #[cfg(test)]
 mod tests {
-    use super::NoOutputPin;
+    use super::{Builder, NoOutputPin};
+    use crate::test_helpers::StubInterface;
     use embedded_hal::digital::v2::OutputPin;
 
     enum SomeError {}
@@ -139,7 +356,64 @@ mod tests {
     fn test_output_pin() {
         let p = NoOutputPin::new();
         let _d = SomeDriver { p };
+    }
+
+    #[test]
+    fn try_connect_checks_the_ram_limits() {
+        use crate::{displaysize::DisplaySize, error::ConfigError, properties::ChargePump};
+
+        let connect = |builder: Builder| builder.try_connect(StubInterface).err();
+
+        assert_eq!(connect(Builder::new().with_column_offset(16)), None);
+        assert_eq!(
+            connect(Builder::new().with_column_offset(17)),
+            Some(ConfigError::ColumnOffset)
+        );
+        assert_eq!(
+            connect(
+                Builder::new()
+                    .with_size(DisplaySize::Display160x160)
+                    .with_column_offset(1)
+            ),
+            Some(ConfigError::ColumnOffset)
+        );
+        assert_eq!(
+            connect(Builder::new().with_page_offset(1)),
+            Some(ConfigError::PageOffset)
+        );
+        assert_eq!(
+            connect(Builder::new().with_charge_pump(ChargePump::Internal(8))),
+            Some(ConfigError::ChargePumpFrequency)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "i2c")]
+    fn connect_i2c_uses_the_configured_address() {
+        use crate::i2c_interface::I2C_ADDRESS_SA0_HIGH;
+        use embedded_hal::blocking::i2c::Write;
+        use std::vec::Vec;
+
+        /// Records the address of every I2C write
+        struct AddressLog(Vec<u8>);
+
+        impl Write for AddressLog {
+            type Error = ();
+
+            fn write(&mut self, address: u8, _bytes: &[u8]) -> Result<(), ()> {
+                self.0.push(address);
+                Ok(())
+            }
+        }
+
+        let mut display = Builder::new()
+            .with_i2c_address(I2C_ADDRESS_SA0_HIGH)
+            .connect_i2c(AddressLog(Vec::new()))
+            .into_graphics_mode();
+        display.init().unwrap();
 
-        assert!(true);
+        let AddressLog(addresses) = display.release_interface().release();
+        assert!(!addresses.is_empty());
+        assert!(addresses.iter().all(|&address| address == 0x3D));
     }
 }
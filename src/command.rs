@@ -2,11 +2,13 @@
 
 use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
 
-/// Commands
-#[derive(Debug)]
+/// Commands, e.g. for `send_command`. New variants can be added in minor releases.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[allow(dead_code)]
 pub enum Command {
-    /// Set contrast. Higher number is higher contrast. Default = 0x7F
+    /// Set contrast. Higher number is higher contrast. Default = 0x80
     Contrast(u8),
     /// Turn entire display on. If set, all pixels will
     /// be set to on, if not, the value in memory will be used.
@@ -39,18 +41,76 @@ pub enum Command {
     DisplayClockDiv(u8, u8),
     /// Set up phase 1 and 2 of precharge period. each value is from 0-63
     PreChargePeriod(u8, u8),
+    /// Set up the built-in DC-DC converter.
+    /// First value enables the converter, which then turns on with the display.
+    /// Second value is the switching frequency, from 0 (0.6 x 500kHz) to 7 (1.3 x 500kHz)
+    DcDc(bool, u8),
+    /// Set the common pad output voltage at the deselect stage, as
+    /// `VCOMH = (0.430 + value * 0.006415) * VREF`. Values from 0x40 give `VCOMH = VREF`.
+    /// Default = 0x35
+    VcomDeselectLevel(u8),
+    /// Set the VSEGM pad output voltage at the deselect stage, using the same scale as
+    /// `VcomDeselectLevel`. Default = 0x35
+    VsegmDeselectLevel(u8),
+    /// Set the segment output discharge voltage level, from 0 (0V) to 15 (0.45 x VREF).
+    /// Default = 0
+    DischargeVslLevel(u8),
+    /// Start read-modify-write mode. The column address is only incremented by writes until
+    /// `End` is sent, which returns it to where this command was issued.
+    ReadModifyWrite,
+    /// End read-modify-write mode
+    End,
     /// NOOP
     Noop,
 }
 
 impl Command {
     /// Send command to sh1108
-    pub fn send<DI>(self, iface: &mut DI) -> Result<(), DisplayError>
+    pub(crate) fn send<DI>(self, iface: &mut DI) -> Result<(), DisplayError>
     where
         DI: WriteOnlyDataCommand,
     {
-        // Transform command into a fixed size array of 7 u8 and the real length for sending
-        let (data, len) = match self {
+        #[cfg(feature = "trace-commands")]
+        defmt::trace!("sh1108 command {}", self);
+        let (data, len) = self.encode();
+
+        // Send command over the interface
+        iface.send_commands(DataFormat::U8(&data[0..len]))
+    }
+
+    /// Send several commands to sh1108 in as few transfers as possible, e.g. a single I2C
+    /// transaction instead of one per command
+    pub(crate) fn send_batch<DI, I>(commands: I, iface: &mut DI) -> Result<(), DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        I: IntoIterator<Item = Command>,
+    {
+        let mut batch = [0; 16];
+        let mut batch_len = 0;
+
+        for command in commands {
+            #[cfg(feature = "trace-commands")]
+            defmt::trace!("sh1108 command {}", command);
+            let (data, len) = command.encode();
+            if batch_len + len > batch.len() {
+                iface.send_commands(DataFormat::U8(&batch[..batch_len]))?;
+                batch_len = 0;
+            }
+
+            batch[batch_len..batch_len + len].copy_from_slice(&data[..len]);
+            batch_len += len;
+        }
+
+        if batch_len > 0 {
+            iface.send_commands(DataFormat::U8(&batch[..batch_len]))?;
+        }
+
+        Ok(())
+    }
+
+    /// Transform command into a fixed size array of 7 u8 and the real length for sending
+    fn encode(self) -> ([u8; 7], usize) {
+        match self {
             Command::Contrast(val) => ([0x81, val, 0, 0, 0, 0, 0], 2),
             Command::AllOn(on) => ([0xA4 | (on as u8), 0, 0, 0, 0, 0, 0], 1),
             Command::Invert(inv) => ([0xA6 | (inv as u8), 0, 0, 0, 0, 0, 0], 1),
@@ -69,11 +129,17 @@ impl Command {
                 [0xD9, ((0xF & phase2) << 4) | (0xF & phase1), 0, 0, 0, 0, 0],
                 2,
             ),
+            Command::DcDc(on, freq) => (
+                [0xAD, 0x80 | ((0x7 & freq) << 1) | (on as u8), 0, 0, 0, 0, 0],
+                2,
+            ),
+            Command::VcomDeselectLevel(level) => ([0xDB, level, 0, 0, 0, 0, 0], 2),
+            Command::VsegmDeselectLevel(level) => ([0xDC, level, 0, 0, 0, 0, 0], 2),
+            Command::DischargeVslLevel(level) => ([0x30 | (0xF & level), 0, 0, 0, 0, 0, 0], 1),
+            Command::ReadModifyWrite => ([0xE0, 0, 0, 0, 0, 0, 0], 1),
+            Command::End => ([0xEE, 0, 0, 0, 0, 0, 0], 1),
             Command::Noop => ([0xE3, 0, 0, 0, 0, 0, 0], 1),
-        };
-
-        // Send command over the interface
-        iface.send_commands(DataFormat::U8(&data[0..len]))
+        }
     }
 }
 
@@ -111,3 +177,54 @@ pub enum VcomLevel {
     /// 0.43 * Vcc
     V0834 = 0x3f,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::CaptureInterface;
+
+    #[test]
+    fn encodings() {
+        let cases = [
+            (Command::DcDc(true, 0), &[0xAD, 0x81][..]),
+            (Command::DcDc(true, 5), &[0xAD, 0x8B]),
+            (Command::DcDc(false, 2), &[0xAD, 0x84]),
+            (Command::DcDc(true, 7), &[0xAD, 0x8F]),
+            (Command::VcomDeselectLevel(0x40), &[0xDB, 0x40]),
+            (Command::VsegmDeselectLevel(0x12), &[0xDC, 0x12]),
+            (Command::DischargeVslLevel(0x1F), &[0x3F]),
+            (Command::ReadModifyWrite, &[0xE0]),
+            (Command::End, &[0xEE]),
+            (Command::Noop, &[0xE3]),
+        ];
+
+        for (command, bytes) in cases {
+            let mut iface = CaptureInterface::new();
+            command.send(&mut iface).unwrap();
+            assert_eq!(iface.commands(), bytes);
+        }
+    }
+    #[test]
+    fn batches_share_transfers() {
+        use crate::test_helpers::Sent;
+        use std::vec;
+
+        let mut iface = CaptureInterface::new();
+        Command::send_batch(
+            [
+                Command::PageAddress(3),
+                Command::ColumnAddressLow(0x5),
+                Command::ColumnAddressHigh(0x1),
+            ],
+            &mut iface,
+        )
+        .unwrap();
+        assert_eq!(iface.sent(), [Sent::Commands(vec![0xB0, 0x03, 0x05, 0x11])]);
+
+        // Batches that do not fit the buffer are split between commands
+        let mut iface = CaptureInterface::new();
+        Command::send_batch((0..9).map(Command::Contrast), &mut iface).unwrap();
+        assert_eq!(iface.sent().len(), 2);
+        assert_eq!(iface.commands().len(), 18);
+    }
+}
@@ -0,0 +1,179 @@
+//! Names from the `ssd1306` crate, for projects moving over to this driver
+//!
+//! Code written for [ssd1306](https://docs.rs/ssd1306) sets up the display with `Ssd1306::new`
+//! and a mode conversion, which [`Ssd1306`] accepts the same way, and uses a few methods that are
+//! called differently here, which the [`Ssd1306Compat`] and [`BufferedCompat`] traits add under
+//! their old names:
+//!
+//! ```rust,ignore
+//! use sh1108::{compat::*, prelude::*, I2CDisplayInterface};
+//!
+//! let interface = I2CDisplayInterface::new(i2c);
+//! let mut display = Ssd1306::new(interface, DisplaySize::Display128x160, DisplayRotation::Rotate0)
+//!     .into_buffered_graphics_mode();
+//! display.init()?;
+//! display.set_brightness(Brightness::BRIGHT)?;
+//! display.clear_buffer();
+//! Text::new("Hello", Point::new(0, 10), style).draw(&mut display)?;
+//! display.flush()?;
+//! ```
+//!
+//! `init`, `flush`, `set_brightness`, `set_rotation`, `clear` from `DrawTarget` and the
+//! embedded-graphics support work as before, and the `ssd1306` brightness constants like
+//! `Brightness::BRIGHTEST` are there as well. The differences left over are small:
+//!
+//! - `GraphicsMode::set_pixel` takes the pixel value as a `u8`, `0` for off
+//! - `flush` returns [`FlushStats`](crate::mode::FlushStats) instead of `()`
+//! - display sizes are the variants of [`DisplaySize`] instead of separate types
+//! - `Brightness::custom` has no equivalent, use `set_contrast` and `apply_precharge` instead
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use crate::{
+    brightness::Brightness,
+    builder::Builder,
+    displayrotation::{DisplayRotation, RotationMode},
+    displaysize::DisplaySize,
+    mode::TerminalMode,
+    mode::{displaymode::DisplayMode, storage::FrameBufferStorage, GraphicsMode, RawMode},
+};
+
+/// A display that is not in a drawing mode yet, set up like an `ssd1306::Ssd1306`
+pub struct Ssd1306<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    display: DisplayMode<RawMode<DI>>,
+}
+
+impl<DI> Ssd1306<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Set up a display of `size` on `interface`, otherwise with the defaults of [`Builder`]
+    pub fn new(interface: DI, size: DisplaySize, rotation: DisplayRotation) -> Self {
+        Self {
+            display: Builder::new()
+                .with_size(size)
+                .with_rotation(rotation)
+                .connect(interface),
+        }
+    }
+
+    /// Switch to the buffered [`GraphicsMode`]
+    pub fn into_buffered_graphics_mode(self) -> GraphicsMode<DI> {
+        self.display.into_graphics_mode()
+    }
+
+    /// Switch to the unbuffered [`TerminalMode`]
+    pub fn into_terminal_mode(self) -> TerminalMode<DI> {
+        self.display.into_terminal_mode()
+    }
+}
+
+/// The `ssd1306` brightness presets, which are constants there
+impl Brightness {
+    /// Like [`Brightness::Dimmest`]
+    pub const DIMMEST: Brightness = Brightness::Dimmest;
+    /// Like [`Brightness::Dim`]
+    pub const DIM: Brightness = Brightness::Dim;
+    /// Like [`Brightness::Normal`]
+    pub const NORMAL: Brightness = Brightness::Normal;
+    /// Like [`Brightness::Bright`]
+    pub const BRIGHT: Brightness = Brightness::Bright;
+    /// Like [`Brightness::Brightest`]
+    pub const BRIGHTEST: Brightness = Brightness::Brightest;
+}
+
+/// Methods of all `ssd1306` modes under their `ssd1306` names
+pub trait Ssd1306Compat {
+    /// The interface the display was set up with
+    type Interface;
+
+    /// Turn the display on or off, like `display_on`
+    fn set_display_on(&mut self, on: bool) -> Result<(), DisplayError>;
+
+    /// Destroy the driver and return the interface, like `release_interface`
+    fn release(self) -> Self::Interface;
+}
+
+/// Methods of the `ssd1306` buffered graphics mode under their `ssd1306` names
+pub trait BufferedCompat {
+    /// Turn all pixels of the framebuffer off, like `clear`
+    fn clear_buffer(&mut self);
+}
+
+impl<DI, const N: usize, R, S> Ssd1306Compat for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    type Interface = DI;
+
+    fn set_display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.display_on(on)
+    }
+
+    fn release(self) -> DI {
+        self.release_interface()
+    }
+}
+
+impl<DI> Ssd1306Compat for TerminalMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Interface = DI;
+
+    fn set_display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.display_on(on)
+    }
+
+    fn release(self) -> DI {
+        self.release_interface()
+    }
+}
+
+impl<DI, const N: usize, R, S> BufferedCompat for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    fn clear_buffer(&mut self) {
+        self.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::CaptureInterface;
+
+    #[test]
+    fn ssd1306_names_drive_the_display() {
+        let iface = CaptureInterface::new();
+        let mut display = Ssd1306::new(
+            iface.clone(),
+            DisplaySize::Display96x160,
+            DisplayRotation::Rotate90,
+        )
+        .into_buffered_graphics_mode();
+        display.init().unwrap();
+        assert_eq!(display.get_dimensions(), (160, 96));
+
+        display.set_pixel(1, 1, 1);
+        display.clear_buffer();
+        assert!(!display.get_pixel(1, 1));
+
+        iface.clear();
+        display.set_display_on(false).unwrap();
+        assert_eq!(iface.commands(), [0xAE]);
+
+        iface.clear();
+        display.set_brightness(Brightness::BRIGHTEST).unwrap();
+        assert!(iface.commands().windows(2).any(|c| c == [0x81, 0xFF]));
+        assert_eq!(display.release().commands(), iface.commands());
+    }
+}
@@ -0,0 +1,162 @@
+//! Differences between two framebuffers
+//!
+//! [`frame_diff`] compares two framebuffers in the display RAM layout (see
+//! [`FramebufferLayout::VerticalPages`](crate::mode::FramebufferLayout::VerticalPages)) and lists
+//! the spans of each page that need to be sent to turn the first frame into the second. This can
+//! be used to only update the changed parts of the screen, or to compute the update regions ahead
+//! of time, e.g. on a host, and ship only those to a constrained device.
+//!
+//! Spans never cross a page boundary. Within a page, two changed runs separated by at most
+//! [`MERGE_GAP`] unchanged columns are merged into one span: re-sending a few unchanged bytes is
+//! cheaper than setting a new RAM address, which costs 4 command bytes.
+//!
+//! ```rust
+//! use sh1108::diff::{frame_diff, PageSpan};
+//!
+//! let a = [0u8; 32];
+//! let mut b = a;
+//! b[1] = 0xFF;
+//! b[3] = 0x01;
+//! b[16 + 12] = 0x80;
+//!
+//! let spans: Vec<PageSpan> = frame_diff(&a, &b, 16).collect();
+//! assert_eq!(
+//!     spans,
+//!     [
+//!         PageSpan { page: 0, start: 1, end: 4 },
+//!         PageSpan { page: 1, start: 12, end: 13 },
+//!     ]
+//! );
+//! ```
+
+/// Largest number of unchanged columns between two changed runs of a page which are still merged
+/// into a single span
+pub const MERGE_GAP: usize = 4;
+
+/// Changed columns `start..end` of one 8 pixel high page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PageSpan {
+    /// Page index, counting from the top of the display RAM window
+    pub page: u8,
+    /// First changed column
+    pub start: u8,
+    /// One past the last changed column
+    pub end: u8,
+}
+
+/// Iterator over the [`PageSpan`]s that differ between two framebuffers, see [`frame_diff`]
+#[derive(Clone, Debug)]
+pub struct FrameDiff<'a> {
+    a: &'a [u8],
+    b: &'a [u8],
+    width: usize,
+    offset: usize,
+}
+
+/// Compare the framebuffers `a` and `b`, both `width` columns wide and in the display RAM layout,
+/// and iterate over the spans that differ, in page then column order. If the buffers have
+/// different lengths, only the common part is compared.
+pub fn frame_diff<'a>(a: &'a [u8], b: &'a [u8], width: u8) -> FrameDiff<'a> {
+    let length = a.len().min(b.len());
+
+    FrameDiff {
+        a: &a[..length],
+        b: &b[..length],
+        width: width.into(),
+        offset: 0,
+    }
+}
+
+impl Iterator for FrameDiff<'_> {
+    type Item = PageSpan;
+
+    fn next(&mut self) -> Option<PageSpan> {
+        if self.width == 0 {
+            return None;
+        }
+
+        let differs = |offset: usize| self.a[offset] != self.b[offset];
+
+        let start = (self.offset..self.a.len()).find(|&offset| differs(offset))?;
+        let page_end = (start / self.width + 1) * self.width;
+        let page_end = page_end.min(self.a.len());
+
+        let mut end = start + 1;
+        let mut offset = end;
+        while offset < page_end && offset - end <= MERGE_GAP {
+            if differs(offset) {
+                end = offset + 1;
+            }
+            offset += 1;
+        }
+        self.offset = end;
+
+        Some(PageSpan {
+            page: (start / self.width) as u8,
+            start: (start % self.width) as u8,
+            end: ((end - 1) % self.width + 1) as u8,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    #[test]
+    fn merges_small_gaps_only() {
+        let a = [0u8; 3 * 20];
+        let mut b = a;
+        // Gap of exactly MERGE_GAP columns, merged
+        b[0] = 1;
+        b[5] = 1;
+        // Gap of MERGE_GAP + 1 columns, split
+        b[11] = 1;
+        // Last column of page 0 and first of page 1 are never merged
+        b[19] = 1;
+        b[20] = 1;
+        // Whole page 2
+        b[40..].fill(0xAA);
+
+        let spans: Vec<_> = frame_diff(&a, &b, 20).collect();
+
+        assert_eq!(
+            spans,
+            [
+                PageSpan {
+                    page: 0,
+                    start: 0,
+                    end: 6
+                },
+                PageSpan {
+                    page: 0,
+                    start: 11,
+                    end: 12
+                },
+                PageSpan {
+                    page: 0,
+                    start: 19,
+                    end: 20
+                },
+                PageSpan {
+                    page: 1,
+                    start: 0,
+                    end: 1
+                },
+                PageSpan {
+                    page: 2,
+                    start: 0,
+                    end: 20
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_frames() {
+        let a = [0x55u8; 40];
+
+        assert_eq!(frame_diff(&a, &a, 20).next(), None);
+    }
+}
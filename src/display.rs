@@ -0,0 +1,115 @@
+//! A driver-agnostic interface to buffered monochrome displays
+//!
+//! Application code written against [`MonoDisplay`] instead of [`GraphicsMode`] works with any
+//! driver that implements it, so the display of a board can be picked with a feature flag in one
+//! place instead of at every call site:
+//!
+//! ```rust,ignore
+//! fn draw_battery<D: MonoDisplay>(display: &mut D, percent: u32) -> Result<(), D::Error> {
+//!     let (width, _) = display.dimensions();
+//!     for x in 0..width * percent / 100 {
+//!         display.set_pixel(x, 0, true);
+//!     }
+//!
+//!     display.flush()
+//! }
+//! ```
+//!
+//! The trait is kept small on purpose, so wrapping another driver in a newtype and implementing
+//! it takes a few lines. Drawing beyond single pixels is best done with embedded-graphics, which
+//! works with the same displays through `DrawTarget`.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::{storage::FrameBufferStorage, GraphicsMode},
+};
+
+/// A monochrome display with a framebuffer, see the [module documentation](self)
+pub trait MonoDisplay {
+    /// Error returned when talking to the display fails
+    type Error;
+
+    /// Get the width and height of the display in pixels, taking rotation into account
+    fn dimensions(&self) -> (u32, u32);
+
+    /// Turn a pixel of the framebuffer on or off. Pixels outside the display are ignored.
+    fn set_pixel(&mut self, x: u32, y: u32, on: bool);
+
+    /// Send the framebuffer to the display, at least the parts that changed since the last flush
+    fn flush(&mut self) -> Result<(), Self::Error>;
+
+    /// Turn the display on or off, keeping the content of its RAM
+    fn display_on(&mut self, on: bool) -> Result<(), Self::Error>;
+
+    /// Set the contrast of the display, higher is brighter
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error>;
+}
+
+impl<DI, const N: usize, R, S> MonoDisplay for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    type Error = DisplayError;
+
+    fn dimensions(&self) -> (u32, u32) {
+        let (width, height) = self.get_dimensions();
+
+        (width.into(), height.into())
+    }
+
+    fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        GraphicsMode::set_pixel(self, x, y, u8::from(on));
+    }
+
+    /// Sends only what changed, see `GraphicsMode::flush_dirty`
+    fn flush(&mut self) -> Result<(), Self::Error> {
+        self.flush_dirty().map(|_| ())
+    }
+
+    fn display_on(&mut self, on: bool) -> Result<(), Self::Error> {
+        GraphicsMode::display_on(self, on)
+    }
+
+    fn set_contrast(&mut self, contrast: u8) -> Result<(), Self::Error> {
+        GraphicsMode::set_contrast(self, contrast)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{displayrotation::DisplayRotation, test_helpers::CaptureInterface, Builder};
+
+    fn draw_bottom_right<D: MonoDisplay>(display: &mut D) -> Result<(), D::Error> {
+        let (width, height) = display.dimensions();
+        display.set_pixel(width - 1, height - 1, true);
+        display.set_contrast(0x20)?;
+
+        display.flush()
+    }
+
+    #[test]
+    fn graphics_mode_is_a_mono_display() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        display.flush().unwrap();
+        assert_eq!(MonoDisplay::dimensions(&display), (160, 128));
+
+        iface.clear();
+        draw_bottom_right(&mut display).unwrap();
+        assert!(display.get_pixel(159, 127));
+        assert!(iface
+            .commands()
+            .windows(2)
+            .any(|bytes| bytes == [0x81, 0x20]));
+        assert_eq!(iface.data().len(), 1);
+    }
+}
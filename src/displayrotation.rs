@@ -1,7 +1,8 @@
 //! Display rotation
 
 /// Display rotation
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DisplayRotation {
     /// No rotation, normal display
     Rotate0,
@@ -12,3 +13,65 @@ pub enum DisplayRotation {
     /// Rotate 270 degress clockwise
     Rotate270,
 }
+
+/// Choice between a rotation fixed at compile time and one set at runtime, used as the `R`
+/// parameter of [`GraphicsMode`](crate::mode::GraphicsMode)
+///
+/// With a fixed rotation the coordinate transform of every pixel access is resolved by the
+/// compiler, so e.g. [`FixedRotate0`] drawing compiles down to a plain framebuffer index
+/// computation. The rotation set on the [`Builder`](crate::Builder) is then ignored in favour
+/// of the fixed one.
+pub trait RotationMode {
+    /// The rotation chosen at compile time, or `None` if it is set at runtime
+    const FIXED: Option<DisplayRotation>;
+
+    /// Whether display `x` and `y` map to panel rows and columns respectively, given the
+    /// `current` runtime rotation. Only uses `current` if the rotation is not fixed.
+    #[inline(always)]
+    fn swaps_axes(current: DisplayRotation) -> bool {
+        matches!(
+            Self::FIXED.unwrap_or(current),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270
+        )
+    }
+}
+
+/// Rotation set at runtime with `set_rotation`. This is the default.
+#[derive(Clone, Copy, Debug)]
+pub struct RuntimeRotation;
+
+/// Rotation fixed to [`DisplayRotation::Rotate0`] at compile time
+#[derive(Clone, Copy, Debug)]
+pub struct FixedRotate0;
+
+/// Rotation fixed to [`DisplayRotation::Rotate90`] at compile time
+#[derive(Clone, Copy, Debug)]
+pub struct FixedRotate90;
+
+/// Rotation fixed to [`DisplayRotation::Rotate180`] at compile time
+#[derive(Clone, Copy, Debug)]
+pub struct FixedRotate180;
+
+/// Rotation fixed to [`DisplayRotation::Rotate270`] at compile time
+#[derive(Clone, Copy, Debug)]
+pub struct FixedRotate270;
+
+impl RotationMode for RuntimeRotation {
+    const FIXED: Option<DisplayRotation> = None;
+}
+
+impl RotationMode for FixedRotate0 {
+    const FIXED: Option<DisplayRotation> = Some(DisplayRotation::Rotate0);
+}
+
+impl RotationMode for FixedRotate90 {
+    const FIXED: Option<DisplayRotation> = Some(DisplayRotation::Rotate90);
+}
+
+impl RotationMode for FixedRotate180 {
+    const FIXED: Option<DisplayRotation> = Some(DisplayRotation::Rotate180);
+}
+
+impl RotationMode for FixedRotate270 {
+    const FIXED: Option<DisplayRotation> = Some(DisplayRotation::Rotate270);
+}
@@ -2,6 +2,7 @@
 
 /// Display size enumeration
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum DisplaySize {
     /// 64 by 160 pixels
     Display64x160 = 0x0,
@@ -15,7 +16,7 @@ pub enum DisplaySize {
 
 impl DisplaySize {
     /// Get integral dimensions from DisplaySize
-    pub fn dimensions(self) -> (u8, u8) {
+    pub const fn dimensions(self) -> (u8, u8) {
         match self {
             DisplaySize::Display64x160 => (64, 160),
             DisplaySize::Display96x160 => (96, 160),
@@ -24,6 +25,14 @@ impl DisplaySize {
         }
     }
 
+    /// Get the number of framebuffer bytes needed for this size, e.g. to size a
+    /// [`GraphicsMode`](crate::mode::GraphicsMode) exactly
+    pub const fn framebuffer_size(self) -> usize {
+        let (width, height) = self.dimensions();
+
+        width as usize * height as usize / 8
+    }
+
     /// Get the panel column offset from DisplaySize
     pub fn column_offset(self) -> u8 {
         match self {
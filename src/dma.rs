@@ -0,0 +1,204 @@
+//! Background flushes through DMA
+//!
+//! A normal [`GraphicsMode::flush`](crate::mode::GraphicsMode::flush) blocks until every page is
+//! sent. An interface that can send data with DMA implements [`DmaInterface`] on top of
+//! `WriteOnlyDataCommand`, and
+//! [`GraphicsMode::flush_dma`](crate::mode::GraphicsMode::flush_dma) then hands the framebuffer
+//! to it page by page while the CPU does other work:
+//!
+//! ```rust,ignore
+//! let frame = cortex_m::singleton!(: [u8; 2560] = [0; 2560]).unwrap();
+//! let mut display: GraphicsMode<_, 0, RuntimeRotation, &'static mut [u8]> =
+//!     GraphicsMode::with_storage(properties, &mut frame[..]);
+//!
+//! let mut flush = display.flush_dma();
+//! while !flush.poll()? {
+//!     do_other_work();
+//! }
+//! let display = flush.into_display();
+//! ```
+//!
+//! The DMA controller reads the pages straight out of the framebuffer, so it has to be kept in
+//! `'static` memory, with a `&'static mut [u8]` [storage](crate::mode::storage). `flush_dma`
+//! takes the display by value and [`into_display`](DmaFlush::into_display) gives it back once no
+//! transfer is running, so the framebuffer cannot change while it is read. Each page goes to the
+//! interface as an owned [`DmaPage`], an embedded-dma `ReadBuffer` that can be passed on to the
+//! DMA transfers of a HAL, and comes back when the transfer is done. A leaked or dropped flush
+//! leaves the framebuffer unreachable, so no `unsafe` is needed to implement the interface.
+//!
+//! The pages go out without copying with the default [`FramebufferLayout::VerticalPages`]
+//! layout and no pixel shift. Otherwise the flush falls back to a blocking `flush`.
+//!
+//! [`FramebufferLayout::VerticalPages`]: crate::mode::FramebufferLayout::VerticalPages
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embedded_dma::ReadBuffer;
+
+use crate::{displayrotation::RotationMode, mode::GraphicsMode};
+
+/// A page of a framebuffer in `'static` memory, lent to a [`DmaInterface`] for one transfer
+///
+/// The bytes stay unchanged until the interface gives the page back from `poll_data` or
+/// `start_data`.
+// Not `Copy`, so the driver knows when the interface is done with the page
+#[allow(missing_copy_implementations)]
+#[derive(Debug)]
+pub struct DmaPage {
+    data: *const u8,
+    len: usize,
+}
+
+impl DmaPage {
+    /// A page of `len` bytes from `data` on
+    ///
+    /// # Safety
+    ///
+    /// The bytes must be `'static` and must not change until the page is dropped.
+    #[allow(unsafe_code)]
+    pub(crate) unsafe fn new(data: *const u8, len: usize) -> Self {
+        Self { data, len }
+    }
+
+    /// The bytes of the page, e.g. for interfaces that copy them into a buffer of their own
+    pub fn as_slice(&self) -> &[u8] {
+        // The bytes are `'static` and unchanged for as long as the page exists
+        #[allow(unsafe_code)]
+        unsafe {
+            core::slice::from_raw_parts(self.data, self.len)
+        }
+    }
+}
+
+// The memory stays valid and unchanged for as long as the page exists, see `DmaPage::new`
+#[allow(unsafe_code)]
+unsafe impl ReadBuffer for DmaPage {
+    type Word = u8;
+
+    unsafe fn read_buffer(&self) -> (*const u8, usize) {
+        (self.data, self.len)
+    }
+}
+
+/// A display interface that can send display data in the background
+pub trait DmaInterface: WriteOnlyDataCommand {
+    /// Start sending `page` as display data, i.e. with the D/C line high, and return without
+    /// waiting for the transfer to complete. The interface keeps the page until the transfer is
+    /// done and then gives it back from `poll_data`. The driver sends no commands until then.
+    ///
+    /// If the transfer cannot be started, return the page together with the error.
+    fn start_data(&mut self, page: DmaPage) -> Result<(), (DmaPage, DisplayError)>;
+
+    /// Whether the transfer started by `start_data` completed. Returns `None` while it is still
+    /// running, and the page with the outcome of the transfer once it is done.
+    fn poll_data(&mut self) -> Option<(DmaPage, Result<(), DisplayError>)>;
+}
+
+/// A flush in progress, see [`GraphicsMode::flush_dma`]
+pub struct DmaFlush<DI, const N: usize, R>
+where
+    DI: DmaInterface,
+    R: RotationMode,
+{
+    display: GraphicsMode<DI, N, R, &'static mut [u8]>,
+    /// Next page to send, and the number of pages once the flush started
+    page: u8,
+    pages: Option<u8>,
+    /// Whether a page transfer is running
+    busy: bool,
+}
+
+impl<DI, const N: usize, R> DmaFlush<DI, N, R>
+where
+    DI: DmaInterface,
+    R: RotationMode,
+{
+    /// A flush of `display` that starts with the first `poll`
+    pub(crate) fn new(display: GraphicsMode<DI, N, R, &'static mut [u8]>) -> Self {
+        Self {
+            display,
+            page: 0,
+            pages: None,
+            busy: false,
+        }
+    }
+
+    /// Push the flush forward: start the next page once the previous one is done. Returns
+    /// `Ok(true)` once the whole frame has been sent, and `Ok(false)` if it has to be called
+    /// again. After an error the flush stops, and the next flush sends the whole frame again.
+    pub fn poll(&mut self) -> Result<bool, DisplayError> {
+        let pages = match self.pages {
+            Some(pages) => pages,
+            None => {
+                // Nothing more to send if starting fails
+                self.pages = Some(0);
+                let Some(lit_pixels) = self.display.dma_lit_pixels() else {
+                    self.display.flush()?;
+                    return Ok(true);
+                };
+                let pages = self.display.begin_dma_flush(lit_pixels)?;
+                self.pages = Some(pages);
+                pages
+            }
+        };
+
+        if self.busy {
+            let Some((_, result)) = self.display.poll_dma_page() else {
+                return Ok(false);
+            };
+
+            self.busy = false;
+            self.page += 1;
+            result.inspect_err(|_| self.page = pages)?;
+        }
+
+        if self.page >= pages {
+            if pages > 0 {
+                self.display.end_dma_flush();
+            }
+            return Ok(true);
+        }
+
+        let frame: *const [u8] = self.display.framebuffer();
+        // The framebuffer is `'static`, and the display is owned by the flush, which does not
+        // change it before the page is back
+        #[allow(unsafe_code)]
+        unsafe { self.display.start_dma_page_of(&*frame, self.page) }
+            .inspect_err(|_| self.page = pages)?;
+        self.busy = true;
+
+        Ok(false)
+    }
+
+    /// Whether all pages have been sent
+    pub fn is_done(&self) -> bool {
+        !self.busy && self.pages.is_some_and(|pages| self.page >= pages)
+    }
+
+    /// Wait for a running page transfer and give the display back. Pages that were not started
+    /// yet are not sent.
+    pub fn into_display(mut self) -> GraphicsMode<DI, N, R, &'static mut [u8]> {
+        while self.busy {
+            self.busy = self.display.poll_dma_page().is_none();
+        }
+
+        self.display
+    }
+}
+
+// Records the page right away and gives it back with the next poll
+#[cfg(any(test, feature = "test-util"))]
+impl DmaInterface for crate::test_helpers::CaptureInterface {
+    fn start_data(&mut self, page: DmaPage) -> Result<(), (DmaPage, DisplayError)> {
+        match self.send_data(display_interface::DataFormat::U8(page.as_slice())) {
+            Ok(()) => {
+                self.lend(page);
+                Ok(())
+            }
+            Err(error) => Err((page, error)),
+        }
+    }
+
+    fn poll_data(&mut self) -> Option<(DmaPage, Result<(), DisplayError>)> {
+        self.take_lent().map(|page| (page, Ok(())))
+    }
+}
@@ -0,0 +1,256 @@
+//! Error types
+
+use core::fmt;
+use display_interface::DisplayError;
+
+use crate::mode::graphics::Mismatch;
+
+/// Error returned by methods that check their arguments before talking to the display, to tell
+/// an invalid request apart from a failure of the interface
+#[derive(Clone, Debug)]
+pub enum Error {
+    /// Communicating with the display failed
+    Interface(DisplayError),
+    /// A position, size or index lies outside the display, draw area or sprite sheet
+    OutOfBounds,
+    /// A buffer passed in holds fewer bytes than its dimensions require
+    BufferTooSmall,
+}
+
+impl From<DisplayError> for Error {
+    fn from(error: DisplayError) -> Self {
+        Error::Interface(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Interface(error) => write!(f, "display interface error: {:?}", error),
+            Error::OutOfBounds => f.write_str("out of bounds"),
+            Error::BufferTooSmall => f.write_str("buffer too small for its dimensions"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            Error::Interface(error) => {
+                defmt::write!(f, "Interface({})", defmt::Debug2Format(error))
+            }
+            Error::OutOfBounds => defmt::write!(f, "OutOfBounds"),
+            Error::BufferTooSmall => defmt::write!(f, "BufferTooSmall"),
+        }
+    }
+}
+
+/// Error returned by [`Builder::try_connect`](crate::Builder::try_connect) for a configuration
+/// the controller cannot display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConfigError {
+    /// The visible columns, moved by the column offset, reach past the 160 columns of display
+    /// RAM
+    ColumnOffset,
+    /// The pages of the display, moved by the page offset, reach past the 20 pages of display
+    /// RAM
+    PageOffset,
+    /// The frequency of the built-in charge pump is above 7
+    ChargePumpFrequency,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ColumnOffset => f.write_str("column offset reaches past display RAM"),
+            ConfigError::PageOffset => f.write_str("page offset reaches past display RAM"),
+            ConfigError::ChargePumpFrequency => f.write_str("charge pump frequency above 7"),
+        }
+    }
+}
+
+impl core::error::Error for ConfigError {}
+
+/// Error returned when resetting and initialising the display in one go, e.g. by
+/// [`GraphicsMode::init_with_reset`](crate::mode::GraphicsMode::init_with_reset)
+#[derive(Clone, Debug)]
+pub enum InitError<PinE> {
+    /// Driving the reset pin failed
+    Pin(PinE),
+    /// Sending the initialisation commands failed
+    Display(DisplayError),
+}
+
+impl<PinE> From<DisplayError> for InitError<PinE> {
+    fn from(error: DisplayError) -> Self {
+        InitError::Display(error)
+    }
+}
+
+impl<PinE: fmt::Debug> fmt::Display for InitError<PinE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::Pin(error) => write!(f, "reset pin error: {:?}", error),
+            InitError::Display(error) => write!(f, "display interface error: {:?}", error),
+        }
+    }
+}
+
+impl<PinE: fmt::Debug> core::error::Error for InitError<PinE> {}
+
+#[cfg(feature = "defmt")]
+impl<PinE: defmt::Format> defmt::Format for InitError<PinE> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            InitError::Pin(error) => defmt::write!(f, "Pin({})", error),
+            InitError::Display(error) => {
+                defmt::write!(f, "Display({})", defmt::Debug2Format(error))
+            }
+        }
+    }
+}
+
+/// Error returned by [`GraphicsMode::verify`](crate::mode::GraphicsMode::verify) and
+/// [`GraphicsMode::flush_verified`](crate::mode::GraphicsMode::flush_verified)
+#[derive(Clone, Debug)]
+pub enum VerifyError {
+    /// Communicating with the display failed
+    Display(DisplayError),
+    /// The display RAM read back differs from the framebuffer
+    Mismatch(Mismatch),
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerifyError::Display(error) => write!(f, "display interface error: {:?}", error),
+            VerifyError::Mismatch(mismatch) => write!(
+                f,
+                "display RAM differs in {} bytes on {} pages",
+                mismatch.bytes, mismatch.pages
+            ),
+        }
+    }
+}
+
+impl core::error::Error for VerifyError {}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for VerifyError {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            VerifyError::Display(error) => {
+                defmt::write!(f, "Display({})", defmt::Debug2Format(error))
+            }
+            VerifyError::Mismatch(mismatch) => defmt::write!(f, "Mismatch({})", mismatch),
+        }
+    }
+}
+
+/// Error returned by
+/// [`GraphicsMode::flush_dirty_mirrored`](crate::mode::GraphicsMode::flush_dirty_mirrored)
+#[cfg(feature = "mirror")]
+#[derive(Clone, Debug)]
+pub enum MirrorError<SinkE> {
+    /// Flushing to the display failed, so nothing was mirrored
+    Display(DisplayError),
+    /// Writing to the mirror sink failed after the display was flushed
+    Sink(SinkE),
+}
+
+#[cfg(feature = "mirror")]
+impl<SinkE: fmt::Debug> fmt::Display for MirrorError<SinkE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MirrorError::Display(error) => write!(f, "display interface error: {:?}", error),
+            MirrorError::Sink(error) => write!(f, "mirror sink error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "mirror")]
+impl<SinkE: fmt::Debug> core::error::Error for MirrorError<SinkE> {}
+
+#[cfg(all(feature = "mirror", feature = "defmt"))]
+impl<SinkE: defmt::Format> defmt::Format for MirrorError<SinkE> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            MirrorError::Display(error) => {
+                defmt::write!(f, "Display({})", defmt::Debug2Format(error))
+            }
+            MirrorError::Sink(error) => defmt::write!(f, "Sink({})", error),
+        }
+    }
+}
+
+/// Error returned by [`ExternalMode`](crate::mode::ExternalMode)
+#[cfg(feature = "graphics")]
+#[derive(Clone, Debug)]
+pub enum ExternalError<RamE> {
+    /// Communicating with the display failed
+    Display(DisplayError),
+    /// Reading or writing the external RAM holding the framebuffer failed
+    Ram(RamE),
+}
+
+#[cfg(feature = "graphics")]
+impl<RamE: fmt::Debug> fmt::Display for ExternalError<RamE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExternalError::Display(error) => write!(f, "display interface error: {:?}", error),
+            ExternalError::Ram(error) => write!(f, "external RAM error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<RamE: fmt::Debug> core::error::Error for ExternalError<RamE> {}
+
+#[cfg(all(feature = "graphics", feature = "defmt"))]
+impl<RamE: defmt::Format> defmt::Format for ExternalError<RamE> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            ExternalError::Display(error) => {
+                defmt::write!(f, "Display({})", defmt::Debug2Format(error))
+            }
+            ExternalError::Ram(error) => defmt::write!(f, "Ram({})", error),
+        }
+    }
+}
+
+/// Error returned by [`SpiRam`](crate::mode::SpiRam)
+#[cfg(feature = "graphics")]
+#[derive(Clone, Debug)]
+pub enum SpiRamError<SpiE, PinE> {
+    /// The SPI transfer failed
+    Spi(SpiE),
+    /// Driving the chip select pin failed
+    Pin(PinE),
+}
+
+#[cfg(feature = "graphics")]
+impl<SpiE: fmt::Debug, PinE: fmt::Debug> fmt::Display for SpiRamError<SpiE, PinE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpiRamError::Spi(error) => write!(f, "SPI error: {:?}", error),
+            SpiRamError::Pin(error) => write!(f, "chip select pin error: {:?}", error),
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<SpiE: fmt::Debug, PinE: fmt::Debug> core::error::Error for SpiRamError<SpiE, PinE> {}
+
+#[cfg(all(feature = "graphics", feature = "defmt"))]
+impl<SpiE: defmt::Format, PinE: defmt::Format> defmt::Format for SpiRamError<SpiE, PinE> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        match self {
+            SpiRamError::Spi(error) => defmt::write!(f, "Spi({})", error),
+            SpiRamError::Pin(error) => defmt::write!(f, "Pin({})", error),
+        }
+    }
+}
@@ -0,0 +1,391 @@
+//! C bindings, behind the `ffi` feature
+//!
+//! A small `extern "C"` API over [`GraphicsMode`] for firmware that mixes C and Rust, or vendor
+//! SDKs written in C. Link a static library crate that depends on this crate with the `ffi`
+//! feature into the C project, and declare the functions in a header:
+//!
+//! ```c
+//! typedef struct {
+//!     void *context;
+//!     int (*send_commands)(void *context, const uint8_t *bytes, size_t len);
+//!     int (*send_data)(void *context, const uint8_t *bytes, size_t len);
+//! } sh1108_interface_t;
+//!
+//! typedef struct sh1108_display sh1108_display_t;
+//!
+//! size_t sh1108_display_size(void);
+//! sh1108_display_t *sh1108_new(void *memory, size_t len, sh1108_interface_t interface);
+//! int sh1108_init(sh1108_display_t *display);
+//! int sh1108_set_pixel(sh1108_display_t *display, uint32_t x, uint32_t y, bool on);
+//! int sh1108_blit(sh1108_display_t *display, uint32_t x, uint32_t y, uint32_t w, uint32_t h,
+//!                 const uint8_t *data, size_t len);
+//! int sh1108_flush(sh1108_display_t *display);
+//! int sh1108_set_contrast(sh1108_display_t *display, uint8_t contrast);
+//! int sh1108_display_on(sh1108_display_t *display, bool on);
+//! ```
+//!
+//! The C side sends the bytes over its own bus driver in the two callbacks, which return `0` on
+//! success. The driver does not allocate: the display lives in memory provided by the caller,
+//! e.g. a static array of at least `sh1108_display_size()` bytes, aligned to 8 bytes. It is set
+//! up with the default [`Builder`] settings, a 128x160 display without rotation.
+//!
+//! All functions except `sh1108_new` return one of the [`Sh1108Status`] values. The driver is not
+//! reentrant, a display must only be used from one context at a time.
+
+// Exported symbols and raw pointers from C need unsafe code, which is kept to this module
+#![allow(unsafe_code)]
+
+use core::{
+    ffi::c_void,
+    mem::{align_of, size_of},
+    ptr, slice,
+};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+use crate::{builder::Builder, mode::GraphicsMode};
+
+/// A callback that sends `len` bytes from `bytes` to the display, returning `0` on success
+pub type Sh1108Send = extern "C" fn(context: *mut c_void, bytes: *const u8, len: usize) -> i32;
+
+/// The bus driver of the C side, passed to `sh1108_new`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Sh1108Interface {
+    /// Passed to both callbacks unchanged, e.g. a pointer to the bus handle
+    pub context: *mut c_void,
+    /// Send command bytes, i.e. with D/C low or the command control byte over I2C
+    pub send_commands: Option<Sh1108Send>,
+    /// Send display data bytes, i.e. with D/C high or the data control byte over I2C
+    pub send_data: Option<Sh1108Send>,
+}
+
+impl Sh1108Interface {
+    fn send(&self, send: Option<Sh1108Send>, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        let send = send.ok_or(DisplayError::BusWriteError)?;
+        let call = |bytes: &[u8]| match send(self.context, bytes.as_ptr(), bytes.len()) {
+            0 => Ok(()),
+            _ => Err(DisplayError::BusWriteError),
+        };
+
+        match format {
+            DataFormat::U8(bytes) => call(bytes),
+            DataFormat::U8Iter(iter) => {
+                // Collect the bytes in chunks so the callback is not called for every byte
+                let mut chunk = [0; 32];
+                let mut len = 0;
+                for byte in iter {
+                    chunk[len] = byte;
+                    len += 1;
+                    if len == chunk.len() {
+                        call(&chunk)?;
+                        len = 0;
+                    }
+                }
+
+                if len > 0 {
+                    call(&chunk[..len])?;
+                }
+
+                Ok(())
+            }
+            _ => Err(DisplayError::DataFormatNotImplemented),
+        }
+    }
+}
+
+impl WriteOnlyDataCommand for Sh1108Interface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(self.send_commands, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(self.send_data, buf)
+    }
+}
+
+/// Result of the C functions
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sh1108Status {
+    /// The call succeeded
+    Ok = 0,
+    /// A callback of the interface failed or is missing
+    InterfaceError = -1,
+    /// A null pointer or a buffer too short for the given size was passed, or the display was
+    /// flushed before `sh1108_init` succeeded
+    InvalidArgument = -2,
+}
+
+impl From<Result<(), DisplayError>> for Sh1108Status {
+    fn from(result: Result<(), DisplayError>) -> Self {
+        match result {
+            Ok(()) => Sh1108Status::Ok,
+            Err(_) => Sh1108Status::InterfaceError,
+        }
+    }
+}
+
+/// A display driven from C, only handled through pointers on the C side
+pub struct Sh1108Display {
+    display: GraphicsMode<Sh1108Interface>,
+}
+
+/// Get the number of bytes `sh1108_new` needs for a display
+#[no_mangle]
+pub extern "C" fn sh1108_display_size() -> usize {
+    size_of::<Sh1108Display>()
+}
+
+/// Set up a display with the default settings in `memory`, which must be at least
+/// `sh1108_display_size()` bytes long and aligned to 8 bytes. Returns null if it is not. The
+/// display is not initialised yet, see `sh1108_init`.
+///
+/// # Safety
+///
+/// `memory` must be null or valid for writes of `len` bytes, and must not be used for anything
+/// else while the display is in use.
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_new(
+    memory: *mut c_void,
+    len: usize,
+    interface: Sh1108Interface,
+) -> *mut Sh1108Display {
+    let display = memory.cast::<Sh1108Display>();
+    if display.is_null() || len < size_of::<Sh1108Display>() || !display.is_aligned() {
+        return ptr::null_mut();
+    }
+    debug_assert!(align_of::<Sh1108Display>() <= 8);
+
+    // SAFETY: the memory is valid, large enough and aligned as checked above
+    unsafe {
+        display.write(Sh1108Display {
+            display: Builder::new().connect(interface).into(),
+        });
+    }
+
+    display
+}
+
+/// Run `f` on the display behind `display`, or report an invalid argument for null
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+unsafe fn with_display(
+    display: *mut Sh1108Display,
+    f: impl FnOnce(&mut GraphicsMode<Sh1108Interface>) -> Result<(), DisplayError>,
+) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    match unsafe { display.as_mut() } {
+        Some(display) => f(&mut display.display).into(),
+        None => Sh1108Status::InvalidArgument,
+    }
+}
+
+/// Like `with_display`, but also report an invalid argument if the display is not initialised,
+/// for the calls that need it
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+unsafe fn with_initialized_display(
+    display: *mut Sh1108Display,
+    f: impl FnOnce(&mut GraphicsMode<Sh1108Interface>) -> Result<(), DisplayError>,
+) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    match unsafe { display.as_ref() } {
+        Some(initialized) if !initialized.display.is_initialized() => Sh1108Status::InvalidArgument,
+        // SAFETY: guaranteed by the caller
+        _ => unsafe { with_display(display, f) },
+    }
+}
+
+/// Initialise the display and clear it. Reset the display before, if its reset pin is connected.
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_init(display: *mut Sh1108Display) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    unsafe {
+        with_display(display, |display| {
+            display.init()?;
+            display.clear();
+            display.flush().map(|_| ())
+        })
+    }
+}
+
+/// Turn a pixel of the framebuffer on or off, pixels outside the display are ignored
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_set_pixel(
+    display: *mut Sh1108Display,
+    x: u32,
+    y: u32,
+    on: bool,
+) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    unsafe {
+        with_display(display, |display| {
+            display.set_pixel(x, y, u8::from(on));
+            Ok(())
+        })
+    }
+}
+
+/// Draw a packed bitmap of `w` x `h` pixels at `x`, `y` into the framebuffer, like
+/// [`GraphicsMode::draw_bitmap`]. `data` holds `len` bytes, at least `h` rows of `(w + 7) / 8`.
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere, and
+/// `data` must be null or valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_blit(
+    display: *mut Sh1108Display,
+    x: u32,
+    y: u32,
+    w: u32,
+    h: u32,
+    data: *const u8,
+    len: usize,
+) -> Sh1108Status {
+    let needed = (w as usize).div_ceil(8).saturating_mul(h as usize);
+    if data.is_null() || len < needed {
+        return Sh1108Status::InvalidArgument;
+    }
+
+    // SAFETY: guaranteed by the caller
+    let data = unsafe { slice::from_raw_parts(data, len) };
+    // SAFETY: guaranteed by the caller
+    unsafe {
+        with_display(display, |display| {
+            display.draw_bitmap(x, y, w, h, data);
+            Ok(())
+        })
+    }
+}
+
+/// Send the parts of the framebuffer that changed since the last flush to the display. Fails
+/// with `InvalidArgument` until `sh1108_init` succeeded.
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_flush(display: *mut Sh1108Display) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    unsafe { with_initialized_display(display, |display| display.flush_dirty().map(|_| ())) }
+}
+
+/// Set the contrast of the display
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_set_contrast(
+    display: *mut Sh1108Display,
+    contrast: u8,
+) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    unsafe { with_display(display, |display| display.set_contrast(contrast)) }
+}
+
+/// Turn the display on or off, keeping the content of its RAM
+///
+/// # Safety
+///
+/// `display` must be null or a pointer returned by `sh1108_new` that is not used elsewhere
+#[no_mangle]
+pub unsafe extern "C" fn sh1108_display_on(display: *mut Sh1108Display, on: bool) -> Sh1108Status {
+    // SAFETY: guaranteed by the caller
+    unsafe { with_display(display, |display| display.display_on(on)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::MaybeUninit;
+    use std::vec::Vec;
+
+    use super::*;
+
+    extern "C" fn record(context: *mut c_void, bytes: *const u8, len: usize) -> i32 {
+        // SAFETY: the tests pass a `Vec<u8>` as context, and the driver a valid slice
+        unsafe {
+            let sent = &mut *context.cast::<Vec<u8>>();
+            sent.extend_from_slice(slice::from_raw_parts(bytes, len));
+        }
+        0
+    }
+
+    extern "C" fn ignore(_context: *mut c_void, _bytes: *const u8, _len: usize) -> i32 {
+        0
+    }
+
+    #[test]
+    fn display_is_driven_through_callbacks() {
+        let mut commands: Vec<u8> = Vec::new();
+        let mut data: Vec<u8> = Vec::new();
+        let mut memory = MaybeUninit::<Sh1108Display>::uninit();
+        let size = sh1108_display_size();
+
+        unsafe {
+            // Without a command callback the display can't be initialised and isn't flushed
+            let interface = Sh1108Interface {
+                context: ptr::null_mut(),
+                send_commands: None,
+                send_data: Some(ignore),
+            };
+            let display = sh1108_new(memory.as_mut_ptr().cast(), size, interface);
+            assert_eq!(sh1108_init(display), Sh1108Status::InterfaceError);
+            assert_eq!(sh1108_flush(display), Sh1108Status::InvalidArgument);
+
+            // Without a data callback only commands go through
+            let interface = Sh1108Interface {
+                context: ptr::from_mut(&mut commands).cast(),
+                send_commands: Some(record),
+                send_data: None,
+            };
+            assert!(sh1108_new(memory.as_mut_ptr().cast(), 16, interface).is_null());
+            let display = sh1108_new(memory.as_mut_ptr().cast(), size, interface);
+            assert_eq!(sh1108_init(display), Sh1108Status::InterfaceError);
+            assert_eq!(sh1108_set_contrast(display, 0x40), Sh1108Status::Ok);
+            assert!(commands.ends_with(&[0x81, 0x40]));
+
+            let interface = Sh1108Interface {
+                context: ptr::from_mut(&mut data).cast(),
+                send_commands: Some(ignore),
+                send_data: Some(record),
+            };
+            let display = sh1108_new(memory.as_mut_ptr().cast(), size, interface);
+            assert_eq!(sh1108_init(display), Sh1108Status::Ok);
+            assert_eq!(data.len(), 128 * 160 / 8);
+
+            data.clear();
+            assert_eq!(sh1108_set_pixel(display, 1, 0, true), Sh1108Status::Ok);
+            assert_eq!(
+                sh1108_blit(display, 8, 0, 8, 1, [0x80].as_ptr(), 1),
+                Sh1108Status::Ok
+            );
+            assert_eq!(
+                sh1108_blit(display, 0, 0, 8, 2, [0x80].as_ptr(), 1),
+                Sh1108Status::InvalidArgument
+            );
+            assert_eq!(sh1108_flush(display), Sh1108Status::Ok);
+            // The blit also marks its off pixels as changed
+            assert_eq!(data.len(), 15);
+            assert_eq!(data[..8], [0x01, 0, 0, 0, 0, 0, 0, 0x01]);
+            assert_eq!(
+                sh1108_display_on(ptr::null_mut(), true),
+                Sh1108Status::InvalidArgument
+            );
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Flushes driven by polling, without an async runtime
+//!
+//! A [`FlushStateMachine`] splits a flush into small steps, each sending at most a given number
+//! of bytes, so a large frame can go out from an idle loop, a timer interrupt or an RTIC task
+//! without blocking any of them for long:
+//!
+//! ```rust,ignore
+//! let mut flush = FlushStateMachine::new(64);
+//!
+//! loop {
+//!     draw_ui(&mut display);
+//!     while flush.poll(&mut display)? == FlushPoll::Pending {
+//!         handle_input();
+//!     }
+//! }
+//! ```
+//!
+//! Unlike a [`DmaFlush`](crate::dma::DmaFlush), the state machine does not hold on to the
+//! display between steps, so both can live in separate places, e.g. a task's local resources and a
+//! shared one. Drawing in between steps is fine, see
+//! [`GraphicsMode::flush_chunked`](crate::mode::GraphicsMode::flush_chunked), which does the
+//! actual sending.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::{storage::FrameBufferStorage, GraphicsMode},
+};
+
+/// Progress of a flush, returned by [`FlushStateMachine::poll`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlushPoll {
+    /// Part of the frame is still to be sent, `poll` has to be called again
+    Pending,
+    /// Everything has been sent
+    Complete,
+}
+
+/// A flush split into steps, see the [module documentation](self)
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlushStateMachine {
+    chunk_bytes: usize,
+    full_frame: bool,
+    /// Whether a flush was started and not completed yet
+    running: bool,
+}
+
+impl FlushStateMachine {
+    /// Send what changed since the last flush, like `flush_dirty`, at most `chunk_bytes` bytes of
+    /// pixel data per step. A `chunk_bytes` of 0 is treated as 1.
+    pub fn new(chunk_bytes: usize) -> Self {
+        Self {
+            chunk_bytes,
+            full_frame: false,
+            running: false,
+        }
+    }
+
+    /// Send the whole frame on every flush, like `flush`, e.g. to refresh a display RAM that may
+    /// have been disturbed
+    pub fn full_frame(chunk_bytes: usize) -> Self {
+        Self {
+            full_frame: true,
+            ..Self::new(chunk_bytes)
+        }
+    }
+
+    /// Send the next step of the flush, starting a new one if none is running. Returns
+    /// [`FlushPoll::Complete`] once the frame has been sent, the next call then starts over.
+    ///
+    /// After an error the flush stops, and the parts it did not send go out with the next one.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn poll<DI, const N: usize, R, S>(
+        &mut self,
+        display: &mut GraphicsMode<DI, N, R, S>,
+    ) -> Result<FlushPoll, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+        S: FrameBufferStorage,
+    {
+        if !self.running && self.full_frame {
+            display.mark_all_dirty();
+        }
+        self.running = true;
+
+        let done = display.flush_chunked(self.chunk_bytes).inspect_err(|_| {
+            self.running = false;
+        })?;
+        if !done {
+            return Ok(FlushPoll::Pending);
+        }
+
+        self.running = false;
+
+        Ok(FlushPoll::Complete)
+    }
+
+    /// Whether a flush was started and has not completed yet
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_helpers::CaptureInterface, Builder};
+
+    #[test]
+    fn flush_is_sent_in_steps() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.flush().unwrap();
+
+        let mut flush = FlushStateMachine::new(100);
+        for (x, y) in [(0, 0), (127, 0), (0, 8), (127, 8)] {
+            display.set_pixel(x, y, 1);
+        }
+        iface.clear();
+        assert_eq!(flush.poll(&mut display).unwrap(), FlushPoll::Pending);
+        assert!(flush.is_running());
+        assert_eq!(iface.data().len(), 100);
+        assert_eq!(flush.poll(&mut display).unwrap(), FlushPoll::Pending);
+        assert_eq!(flush.poll(&mut display).unwrap(), FlushPoll::Complete);
+        assert_eq!(iface.data().len(), 256);
+        assert!(!flush.is_running());
+
+        // Nothing changed
+        assert_eq!(flush.poll(&mut display).unwrap(), FlushPoll::Complete);
+        assert_eq!(iface.data().len(), 256);
+
+        let mut flush = FlushStateMachine::full_frame(2560);
+        iface.clear();
+        assert_eq!(flush.poll(&mut display).unwrap(), FlushPoll::Complete);
+        assert_eq!(iface.data().len(), 128 * 160 / 8);
+    }
+}
@@ -0,0 +1,233 @@
+//! Fonts for [`TerminalMode`](crate::mode::TerminalMode)
+//!
+//! The terminal prints with any font implementing [`TerminalFont`]. [`Font5x8`] is built in and
+//! used by default. With the `mono-font` feature, the `MonoFont`s of embedded-graphics can be
+//! used as well, including its larger and non-ASCII fonts:
+//!
+//! ```rust,ignore
+//! use embedded_graphics::mono_font::iso_8859_1::FONT_6X10;
+//!
+//! let mut display = Builder::new().connect(interface).into_terminal_mode().with_font(FONT_6X10);
+//! ```
+
+/// A monospaced font that [`TerminalMode`](crate::mode::TerminalMode) can print with
+pub trait TerminalFont {
+    /// Size of a character cell in pixels as `(width, height)`, including the spacing to the
+    /// next character
+    fn cell_size(&self) -> (u8, u8);
+
+    /// Whether the pixel at `x` and `y` of the glyph for `c` is on. `x` and `y` are inside of
+    /// `cell_size`. Fonts should draw a replacement glyph for characters they do not have.
+    fn pixel(&self, c: char, x: u8, y: u8) -> bool;
+}
+
+/// The built-in 5x8 pixel font with printable ASCII characters, printed in 6x8 pixel cells
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Font5x8;
+
+impl TerminalFont for Font5x8 {
+    fn cell_size(&self) -> (u8, u8) {
+        (6, 8)
+    }
+
+    fn pixel(&self, c: char, x: u8, y: u8) -> bool {
+        glyph(c)
+            .get(usize::from(x))
+            .is_some_and(|column| column & (1 << y) != 0)
+    }
+}
+
+#[cfg(feature = "mono-font")]
+use embedded_graphics::{
+    geometry::{OriginDimensions, Point, Size},
+    image::GetPixel,
+    mono_font::MonoFont,
+    pixelcolor::BinaryColor,
+};
+
+#[cfg(feature = "mono-font")]
+impl TerminalFont for MonoFont<'_> {
+    fn cell_size(&self) -> (u8, u8) {
+        let Size { width, height } = self.character_size;
+        let saturate = |size: u32| u8::try_from(size).unwrap_or(u8::MAX);
+
+        (saturate(width + self.character_spacing), saturate(height))
+    }
+
+    fn pixel(&self, c: char, x: u8, y: u8) -> bool {
+        let Size { width, height } = self.character_size;
+        let (x, y) = (u32::from(x), u32::from(y));
+        let glyphs_per_row = self.image.size().width / width.max(1);
+        if x >= width || y >= height || glyphs_per_row == 0 {
+            // Character spacing
+            return false;
+        }
+
+        // Glyphs are laid out left to right, top to bottom in the font image
+        let index = self.glyph_mapping.index(c) as u32;
+        let glyph_x = index % glyphs_per_row * width + x;
+        let glyph_y = index / glyphs_per_row * height + y;
+
+        self.image.pixel(Point::new(glyph_x as i32, glyph_y as i32)) == Some(BinaryColor::On)
+    }
+}
+
+/// Get the glyph for `c` as 5 column bytes with the least significant bit at the top
+fn glyph(c: char) -> &'static [u8; 5] {
+    let index = match c {
+        ' '..='~' => c as usize - ' ' as usize,
+        _ => '?' as usize - ' ' as usize,
+    };
+
+    &FONT_5X8[index]
+}
+
+/// Printable ASCII glyphs from `' '` to `'~'`, taken from the public domain X11 misc-fixed 5x8
+/// font
+#[rustfmt::skip]
+const FONT_5X8: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5E, 0x00, 0x00], // '!'
+    [0x00, 0x0E, 0x00, 0x0E, 0x00], // '"'
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // '#'
+    [0x04, 0x2A, 0x7F, 0x2A, 0x10], // '$'
+    [0x00, 0x16, 0x08, 0x34, 0x00], // '%'
+    [0x36, 0x49, 0x36, 0x40, 0x00], // '&'
+    [0x00, 0x00, 0x0E, 0x00, 0x00], // '\''
+    [0x00, 0x3C, 0x42, 0x00, 0x00], // '('
+    [0x00, 0x42, 0x3C, 0x00, 0x00], // ')'
+    [0x54, 0x38, 0x38, 0x54, 0x00], // '*'
+    [0x10, 0x10, 0x7C, 0x10, 0x10], // '+'
+    [0x00, 0x80, 0x60, 0x20, 0x00], // ','
+    [0x10, 0x10, 0x10, 0x10, 0x00], // '-'
+    [0x00, 0x40, 0xE0, 0x40, 0x00], // '.'
+    [0x60, 0x10, 0x08, 0x06, 0x00], // '/'
+    [0x00, 0x3C, 0x42, 0x3C, 0x00], // '0'
+    [0x00, 0x44, 0x7E, 0x40, 0x00], // '1'
+    [0x64, 0x52, 0x52, 0x4C, 0x00], // '2'
+    [0x22, 0x4A, 0x4E, 0x32, 0x00], // '3'
+    [0x18, 0x14, 0x7E, 0x10, 0x00], // '4'
+    [0x2E, 0x4A, 0x4A, 0x32, 0x00], // '5'
+    [0x3C, 0x4A, 0x4A, 0x30, 0x00], // '6'
+    [0x02, 0x62, 0x1A, 0x06, 0x00], // '7'
+    [0x34, 0x4A, 0x4A, 0x34, 0x00], // '8'
+    [0x0C, 0x52, 0x52, 0x3C, 0x00], // '9'
+    [0x00, 0x6C, 0x6C, 0x00, 0x00], // ':'
+    [0x00, 0x80, 0x6C, 0x2C, 0x00], // ';'
+    [0x00, 0x18, 0x24, 0x42, 0x00], // '<'
+    [0x28, 0x28, 0x28, 0x28, 0x00], // '='
+    [0x00, 0x42, 0x24, 0x18, 0x00], // '>'
+    [0x00, 0x04, 0x52, 0x0C, 0x00], // '?'
+    [0x3C, 0x42, 0x99, 0xA5, 0x1E], // '@'
+    [0x7C, 0x12, 0x12, 0x7C, 0x00], // 'A'
+    [0x7E, 0x4A, 0x4A, 0x34, 0x00], // 'B'
+    [0x3C, 0x42, 0x42, 0x24, 0x00], // 'C'
+    [0x7E, 0x42, 0x42, 0x3C, 0x00], // 'D'
+    [0x7E, 0x4A, 0x4A, 0x42, 0x00], // 'E'
+    [0x7E, 0x0A, 0x0A, 0x02, 0x00], // 'F'
+    [0x3C, 0x42, 0x52, 0x34, 0x00], // 'G'
+    [0x7E, 0x08, 0x08, 0x7E, 0x00], // 'H'
+    [0x00, 0x42, 0x7E, 0x42, 0x00], // 'I'
+    [0x20, 0x42, 0x3E, 0x02, 0x00], // 'J'
+    [0x7E, 0x08, 0x34, 0x42, 0x00], // 'K'
+    [0x7E, 0x40, 0x40, 0x40, 0x00], // 'L'
+    [0x7E, 0x0C, 0x0C, 0x7E, 0x00], // 'M'
+    [0x7E, 0x0C, 0x38, 0x7E, 0x00], // 'N'
+    [0x3C, 0x42, 0x42, 0x3C, 0x00], // 'O'
+    [0x7E, 0x12, 0x12, 0x0C, 0x00], // 'P'
+    [0x3C, 0x52, 0x62, 0xBC, 0x00], // 'Q'
+    [0x7E, 0x12, 0x12, 0x6C, 0x00], // 'R'
+    [0x24, 0x4A, 0x52, 0x24, 0x00], // 'S'
+    [0x00, 0x02, 0x7E, 0x02, 0x00], // 'T'
+    [0x3E, 0x40, 0x40, 0x3E, 0x00], // 'U'
+    [0x1E, 0x60, 0x60, 0x1E, 0x00], // 'V'
+    [0x7E, 0x30, 0x30, 0x7E, 0x00], // 'W'
+    [0x66, 0x18, 0x18, 0x66, 0x00], // 'X'
+    [0x06, 0x08, 0x70, 0x08, 0x06], // 'Y'
+    [0x62, 0x52, 0x4A, 0x46, 0x00], // 'Z'
+    [0x00, 0x7E, 0x42, 0x42, 0x00], // '['
+    [0x06, 0x08, 0x10, 0x60, 0x00], // '\\'
+    [0x00, 0x42, 0x42, 0x7E, 0x00], // ']'
+    [0x00, 0x04, 0x02, 0x04, 0x00], // '^'
+    [0x80, 0x80, 0x80, 0x80, 0x00], // '_'
+    [0x00, 0x02, 0x04, 0x00, 0x00], // '`'
+    [0x30, 0x48, 0x48, 0x78, 0x00], // 'a'
+    [0x7E, 0x48, 0x48, 0x30, 0x00], // 'b'
+    [0x00, 0x30, 0x48, 0x48, 0x00], // 'c'
+    [0x30, 0x48, 0x48, 0x7E, 0x00], // 'd'
+    [0x30, 0x68, 0x58, 0x10, 0x00], // 'e'
+    [0x10, 0x7C, 0x12, 0x04, 0x00], // 'f'
+    [0x10, 0xA8, 0xA8, 0x70, 0x00], // 'g'
+    [0x7E, 0x08, 0x08, 0x70, 0x00], // 'h'
+    [0x00, 0x48, 0x7A, 0x40, 0x00], // 'i'
+    [0x00, 0x40, 0x80, 0x7A, 0x00], // 'j'
+    [0x7E, 0x10, 0x10, 0x68, 0x00], // 'k'
+    [0x00, 0x42, 0x7E, 0x40, 0x00], // 'l'
+    [0x78, 0x08, 0x70, 0x08, 0x70], // 'm'
+    [0x78, 0x08, 0x08, 0x70, 0x00], // 'n'
+    [0x30, 0x48, 0x48, 0x30, 0x00], // 'o'
+    [0xF8, 0x28, 0x28, 0x10, 0x00], // 'p'
+    [0x10, 0x28, 0x28, 0xF8, 0x00], // 'q'
+    [0x78, 0x10, 0x08, 0x10, 0x00], // 'r'
+    [0x00, 0x50, 0x58, 0x28, 0x00], // 's'
+    [0x08, 0x3E, 0x48, 0x20, 0x00], // 't'
+    [0x38, 0x40, 0x40, 0x78, 0x00], // 'u'
+    [0x00, 0x38, 0x40, 0x38, 0x00], // 'v'
+    [0x38, 0x40, 0x30, 0x40, 0x38], // 'w'
+    [0x48, 0x30, 0x30, 0x48, 0x00], // 'x'
+    [0x58, 0xA0, 0xA0, 0x78, 0x00], // 'y'
+    [0x48, 0x68, 0x58, 0x48, 0x00], // 'z'
+    [0x08, 0x2A, 0x55, 0x41, 0x00], // '{'
+    [0x00, 0x00, 0x7E, 0x00, 0x00], // '|'
+    [0x41, 0x55, 0x2A, 0x08, 0x00], // '}'
+    [0x04, 0x02, 0x04, 0x02, 0x00], // '~'
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_font_replaces_missing_glyphs() {
+        assert_eq!(Font5x8.cell_size(), (6, 8));
+        // The '!' bar is in glyph column 2, rows 1 to 4 and 6
+        assert!(Font5x8.pixel('!', 2, 1));
+        assert!(!Font5x8.pixel('!', 2, 5));
+        assert!(!Font5x8.pixel('!', 5, 1));
+        assert!(
+            (0..6).all(|x| (0..8).all(|y| Font5x8.pixel('é', x, y) == Font5x8.pixel('?', x, y)))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "mono-font")]
+    fn mono_font_pixels_match_text_rendering() {
+        use embedded_graphics::{
+            mock_display::MockDisplay,
+            mono_font::{iso_8859_1::FONT_6X10, MonoTextStyle},
+            prelude::*,
+            text::{Baseline, Text},
+        };
+
+        assert_eq!(FONT_6X10.cell_size(), (6, 10));
+        for c in ['A', 'g', 'é'] {
+            let mut display = MockDisplay::new();
+            let mut text = [0; 4];
+            Text::with_baseline(
+                c.encode_utf8(&mut text),
+                Point::zero(),
+                MonoTextStyle::new(&FONT_6X10, BinaryColor::On),
+                Baseline::Top,
+            )
+            .draw(&mut display)
+            .unwrap();
+
+            for x in 0..6 {
+                for y in 0..10 {
+                    let on = display.get_pixel(Point::new(x.into(), y.into()));
+                    assert_eq!(FONT_6X10.pixel(c, x, y), on == Some(BinaryColor::On));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! Ready made I2C interface for the SH1108, with the `i2c` feature
+//!
+//! The SH1108 expects a control byte in front of every I2C transfer, `0x00` for commands and
+//! `0x40` for display data. [`I2CDisplayInterface`] sets up a
+//! [`display_interface_i2c::I2CInterface`] with those values, so no compatible interface crate
+//! has to be found separately:
+//!
+//! ```rust,ignore
+//! let i2c = /* an embedded-hal `blocking::i2c::Write` implementor */;
+//! let interface = I2CDisplayInterface::new(i2c);
+//! let mut disp: GraphicsMode<_> = Builder::new().connect(interface).into();
+//! ```
+//!
+//! The address depends on the level of the SA0 pin. A display at the wrong address does not
+//! acknowledge anything, which most HALs only report as a generic bus error, so check the SA0
+//! strapping of the module when nothing shows up. `Builder::connect_i2c` picks the address set
+//! with `Builder::with_i2c_address`.
+//!
+//! [`I2CInterface`] only writes. [`I2CReadInterface`] also implements [`ReadInterface`] for
+//! buses that support `blocking::i2c::WriteRead`.
+//! A read returns the status register or display data depending on the C/D bit of the control
+//! byte sent before it, so every read writes a control byte and then reads with a repeated start.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::read::ReadInterface;
+
+pub use display_interface_i2c::I2CInterface;
+
+/// 7 bit address of a display with the SA0 pin pulled low, the default
+pub const I2C_ADDRESS_SA0_LOW: u8 = 0x3C;
+
+/// 7 bit address of a display with the SA0 pin pulled high
+pub const I2C_ADDRESS_SA0_HIGH: u8 = 0x3D;
+
+/// Constructor for an [`I2CInterface`] set up for the SH1108
+#[derive(Clone, Copy, Debug)]
+pub struct I2CDisplayInterface;
+
+impl I2CDisplayInterface {
+    /// Create an interface for a display at the default address `0x3C`, i.e. with the SA0 pin
+    /// pulled low
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<I2C>(i2c: I2C) -> I2CInterface<I2C>
+    where
+        I2C: Write,
+    {
+        Self::new_custom_address(i2c, I2C_ADDRESS_SA0_LOW)
+    }
+
+    /// Create an interface for a display at the alternate address `0x3D`, i.e. with the SA0 pin
+    /// pulled high
+    pub fn new_alternate_address<I2C>(i2c: I2C) -> I2CInterface<I2C>
+    where
+        I2C: Write,
+    {
+        Self::new_custom_address(i2c, I2C_ADDRESS_SA0_HIGH)
+    }
+
+    /// Create an interface for a display at a custom 7 bit `address`
+    pub fn new_custom_address<I2C>(i2c: I2C, address: u8) -> I2CInterface<I2C>
+    where
+        I2C: Write,
+    {
+        I2CInterface::new(i2c, address, 0x40)
+    }
+}
+
+/// Control byte announcing commands
+const CONTROL_COMMAND: u8 = 0x00;
+
+/// Control byte announcing display data
+const CONTROL_DATA: u8 = 0x40;
+
+/// An I2C interface that can read back the display as well as write to it
+#[derive(Debug)]
+pub struct I2CReadInterface<I2C> {
+    i2c: I2C,
+    address: u8,
+}
+
+impl<I2C> I2CReadInterface<I2C>
+where
+    I2C: Write + WriteRead,
+{
+    /// Create an interface for a display at the 7 bit `address`, usually
+    /// [`I2C_ADDRESS_SA0_LOW`] or [`I2C_ADDRESS_SA0_HIGH`]
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// Release the I2C bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Write `bytes` behind `control`, 16 bytes per transfer
+    fn write(&mut self, control: u8, bytes: impl Iterator<Item = u8>) -> Result<(), DisplayError> {
+        let mut buf = [control; 17];
+        let mut len = 1;
+
+        for byte in bytes {
+            buf[len] = byte;
+            len += 1;
+
+            if len == buf.len() {
+                self.i2c
+                    .write(self.address, &buf)
+                    .map_err(|_| DisplayError::BusWriteError)?;
+                len = 1;
+            }
+        }
+
+        if len > 1 {
+            self.i2c
+                .write(self.address, &buf[..len])
+                .map_err(|_| DisplayError::BusWriteError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read `buf.len()` bytes after a control byte selecting what is read
+    fn read(&mut self, control: u8, buf: &mut [u8]) -> Result<(), DisplayError> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+
+        self.i2c
+            .write_read(self.address, &[control], buf)
+            .map_err(|_| DisplayError::BusWriteError)
+    }
+}
+
+impl<I2C> WriteOnlyDataCommand for I2CReadInterface<I2C>
+where
+    I2C: Write + WriteRead,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        match cmd {
+            DataFormat::U8(slice) => self.write(CONTROL_COMMAND, slice.iter().copied()),
+            _ => Err(DisplayError::DataFormatNotImplemented),
+        }
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        match buf {
+            DataFormat::U8(slice) => self.write(CONTROL_DATA, slice.iter().copied()),
+            DataFormat::U8Iter(iter) => self.write(CONTROL_DATA, iter),
+            _ => Err(DisplayError::DataFormatNotImplemented),
+        }
+    }
+}
+
+impl<I2C> ReadInterface for I2CReadInterface<I2C>
+where
+    I2C: Write + WriteRead,
+{
+    fn read_status(&mut self) -> Result<u8, DisplayError> {
+        let mut status = [0];
+        self.read(CONTROL_COMMAND, &mut status)?;
+        Ok(status[0])
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+        self.read(CONTROL_DATA, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec::Vec;
+
+    /// Records the transfers and answers reads with the control byte they follow
+    #[derive(Default)]
+    struct Bus {
+        writes: Vec<(u8, Vec<u8>)>,
+    }
+
+    impl Write for Bus {
+        type Error = ();
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), ()> {
+            self.writes.push((address, bytes.to_vec()));
+            Ok(())
+        }
+    }
+
+    impl WriteRead for Bus {
+        type Error = ();
+
+        fn write_read(&mut self, address: u8, bytes: &[u8], buf: &mut [u8]) -> Result<(), ()> {
+            self.writes.push((address, bytes.to_vec()));
+            buf.fill(bytes[0] | 0x01);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn reads_follow_a_control_byte() {
+        let mut iface = I2CReadInterface::new(Bus::default(), I2C_ADDRESS_SA0_HIGH);
+
+        iface.send_commands(DataFormat::U8(&[0xB0, 0x10])).unwrap();
+        iface
+            .send_data(DataFormat::U8Iter(&mut (0..20).map(|_| 0xFF)))
+            .unwrap();
+        assert_eq!(iface.read_status().unwrap(), 0x01);
+        let mut buf = [0; 3];
+        iface.read_data(&mut buf).unwrap();
+        assert_eq!(buf, [0x41; 3]);
+        iface.read_data(&mut []).unwrap();
+
+        let writes = iface.release().writes;
+        assert!(writes.iter().all(|(address, _)| *address == 0x3D));
+        let bytes: Vec<&[u8]> = writes.iter().map(|(_, bytes)| &bytes[..]).collect();
+        let mut data = [0xFF; 17];
+        data[0] = 0x40;
+        assert_eq!(
+            bytes,
+            [&[0x00, 0xB0, 0x10][..], &data, &data[..5], &[0x00], &[0x40]]
+        );
+    }
+}
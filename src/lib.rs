@@ -25,6 +25,61 @@
 //! It's possible to customise the driver to suit your display/application. Take a look at the
 //! [Builder] for available options.
 //!
+//! To debug the init sequence of a new module without a logic analyzer, enable the
+//! `trace-commands` feature. It logs every command and the length of every data transfer through
+//! [defmt](https://defmt.ferrous-systems.com) at trace level before they are sent. Like all defmt
+//! trace output, the messages are only compiled in when the firmware is built with
+//! `DEFMT_LOG=sh1108=trace` or similar.
+//!
+//! With the `i2c` feature, `I2CDisplayInterface` sets up an I2C interface with the control bytes
+//! of the SH1108, so no compatible interface crate has to be found separately. The `spi` feature
+//! does the same for 4-wire and 3-wire SPI with `SPIDisplayInterface`.
+//!
+//! Displays wired for the 8-bit 8080 or 6800 parallel bus can be driven over GPIO pins with the
+//! interfaces in `parallel_interface`, behind the `parallel` feature.
+//!
+//! Projects without embedded-graphics can still print labels and numbers with
+//! `GraphicsMode::draw_text` and the built-in 5x8 font, behind the `tiny-text` feature. On
+//! targets where `core::fmt` is too large, `TerminalMode` implements `ufmt::uWrite` with the
+//! `ufmt` feature.
+//!
+//! Scenes rendered into an embedded-graphics `Framebuffer<BinaryColor, RawU1, ..>` can be flushed
+//! with `GraphicsMode::flush_eg_framebuffer` and the display buffer copied back into one with
+//! `GraphicsMode::copy_to_framebuffer`, behind the `framebuffer` feature.
+//!
+//! Splash screens stored as 1 bit per pixel BMP files can be drawn a row at a time with
+//! `GraphicsMode::draw_bmp` and [tinybmp](https://docs.rs/tinybmp), behind the `bmp` feature.
+//!
+//! For support diagnostics, `GraphicsMode::dump_frame` writes the framebuffer as hex text to any
+//! `core::fmt::Write`, e.g. a debug UART, and `GraphicsMode::dump_ascii` draws it with `#` and
+//! `.` characters, behind the `frame-dump` feature.
+//!
+//! UI layouts can be tried out on a PC with `simulator::SimulatorInterface`, which models the
+//! display RAM and scan directions of the controller and renders them with
+//! embedded-graphics-simulator, behind the `simulator` feature.
+//!
+//! The screen can be mirrored live to a PC during development with
+//! `GraphicsMode::flush_dirty_mirrored`, which writes the changed spans of every flush to an
+//! [embedded-io](https://docs.rs/embedded-io) sink, and decoded on the host with
+//! `mirror::MirrorDecoder`, behind the `mirror` feature.
+//!
+//! Panic handlers can print the panic message on the display with `panic::draw_panic_screen`,
+//! which initialises it from scratch without a framebuffer, behind the `panic-screen` feature.
+//!
+//! Mixed C and Rust firmware can drive the display through the `extern "C"` functions in `ffi`,
+//! which send the bytes with callbacks provided by the C side, behind the `ffi` feature.
+//!
+//! Embassy tasks can share one display through `shared::SharedDisplay`, which serializes drawing
+//! and flushes with an [embassy-sync](https://docs.rs/embassy-sync) mutex, behind the `shared`
+//! feature.
+//!
+//! Long-running devices can report flushes, bytes sent, pixels drawn and bus errors from
+//! `GraphicsMode::metrics`, behind the `metrics` feature.
+//!
+//! [Slint](https://slint.dev) UIs can be drawn with its software renderer through
+//! `slint::LineBufferAdapter`, which packs the rendered lines to on and off pixels and flushes
+//! them band by band, behind the `slint` feature.
+//!
 //! # Examples
 //!
 //! Examples can be found in
@@ -113,13 +168,56 @@
 #![deny(unused_qualifications)]
 
 extern crate embedded_hal as hal;
+#[cfg(any(test, feature = "std"))]
+extern crate std;
 
+pub mod animation;
+pub mod brightness;
 pub mod builder;
-mod command;
+pub mod command;
+pub mod compat;
+pub mod diff;
+pub mod display;
 pub mod displayrotation;
 mod displaysize;
+pub mod dma;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod flush;
+pub mod font;
+#[cfg(feature = "i2c")]
+pub mod i2c_interface;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "mirror")]
+pub mod mirror;
 pub mod mode;
+#[cfg(feature = "panic-screen")]
+pub mod panic;
+#[cfg(feature = "parallel")]
+pub mod parallel_interface;
+mod power;
 pub mod prelude;
 pub mod properties;
+pub mod read;
+pub mod rle;
+#[cfg(feature = "shared")]
+pub mod shared;
+#[cfg(feature = "simulator")]
+pub mod simulator;
+#[cfg(feature = "slint")]
+pub mod slint;
+#[cfg(any(test, feature = "test-util"))]
+pub mod snapshot;
+#[cfg(feature = "spi")]
+pub mod spi_interface;
+pub mod sprite;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_helpers;
 
 pub use crate::builder::{Builder, NoOutputPin};
+#[cfg(feature = "i2c")]
+pub use crate::i2c_interface::I2CDisplayInterface;
+#[cfg(feature = "spi")]
+pub use crate::spi_interface::SPIDisplayInterface;
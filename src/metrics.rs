@@ -0,0 +1,182 @@
+//! Runtime counters, behind the `metrics` feature
+//!
+//! With the feature enabled, the driver counts what it does, so long-running devices can report
+//! display and bus utilisation over their telemetry channel:
+//!
+//! ```rust,ignore
+//! let metrics = display.metrics();
+//! telemetry.send(metrics.flushes, metrics.data_bytes, metrics.errors)?;
+//! ```
+//!
+//! Bytes and errors are counted on the display interface, so they include everything the driver
+//! sends, commands as well as pixel data. All counters wrap around on overflow, differences
+//! between two readings taken with `wrapping_sub` stay correct across that.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+
+use crate::{
+    dma::{DmaInterface, DmaPage},
+    read::ReadInterface,
+};
+
+/// What the driver did since it was created or the counters were reset, see the
+/// [module documentation](self)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Metrics {
+    /// Completed flushes of a framebuffer or other drawing, of any kind
+    pub flushes: u32,
+    /// Command bytes sent, including RAM addresses
+    pub command_bytes: u32,
+    /// Display data bytes sent
+    pub data_bytes: u32,
+    /// Pixels drawn into the framebuffer, whether on or off. Counted are the pixel and bitmap
+    /// methods like `set_pixel`, `set_pixel_unchecked` and `draw_bitmap`, the area methods like
+    /// `clear_region`, the embedded-graphics drawing and split regions, with everything built on
+    /// them. Pixels that are only moved or replaced as a whole, by `clear`, `scroll_up` and
+    /// `scroll_down`, rotation and layout changes, `framebuffer_mut` and `flush_framebuffer`, are
+    /// not counted.
+    pub pixels_set: u32,
+    /// Transfers that failed on the display interface
+    pub errors: u32,
+}
+
+/// The display interface of a [`DisplayProperties`](crate::properties::DisplayProperties),
+/// counting what goes through it
+pub(crate) struct Counted<DI> {
+    pub(crate) inner: DI,
+    pub(crate) metrics: Metrics,
+}
+
+impl<DI> From<DI> for Counted<DI> {
+    fn from(inner: DI) -> Self {
+        Self {
+            inner,
+            metrics: Metrics::default(),
+        }
+    }
+}
+
+impl<DI> Counted<DI> {
+    /// Count the outcome of a transfer
+    fn count<T>(&mut self, result: Result<T, DisplayError>) -> Result<T, DisplayError> {
+        if result.is_err() {
+            self.metrics.errors = self.metrics.errors.wrapping_add(1);
+        }
+
+        result
+    }
+}
+
+/// Send `format` with `send`, counting its bytes in `bytes`
+fn send_counted(
+    format: DataFormat<'_>,
+    bytes: &mut u32,
+    send: impl FnOnce(DataFormat<'_>) -> Result<(), DisplayError>,
+) -> Result<(), DisplayError> {
+    match format {
+        DataFormat::U8(buf) => {
+            *bytes = bytes.wrapping_add(buf.len() as u32);
+            send(DataFormat::U8(buf))
+        }
+        DataFormat::U8Iter(iter) => send(DataFormat::U8Iter(&mut iter.inspect(|_| {
+            *bytes = bytes.wrapping_add(1);
+        }))),
+        other => send(other),
+    }
+}
+
+impl<DI> WriteOnlyDataCommand for Counted<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let inner = &mut self.inner;
+        let result = send_counted(cmd, &mut self.metrics.command_bytes, |cmd| {
+            inner.send_commands(cmd)
+        });
+
+        self.count(result)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let inner = &mut self.inner;
+        let result = send_counted(buf, &mut self.metrics.data_bytes, |buf| {
+            inner.send_data(buf)
+        });
+
+        self.count(result)
+    }
+}
+
+impl<DI> DmaInterface for Counted<DI>
+where
+    DI: DmaInterface,
+{
+    fn start_data(&mut self, page: DmaPage) -> Result<(), (DmaPage, DisplayError)> {
+        let len = page.as_slice().len() as u32;
+        self.metrics.data_bytes = self.metrics.data_bytes.wrapping_add(len);
+        let result = self.inner.start_data(page);
+        if result.is_err() {
+            self.metrics.errors = self.metrics.errors.wrapping_add(1);
+        }
+
+        result
+    }
+
+    fn poll_data(&mut self) -> Option<(DmaPage, Result<(), DisplayError>)> {
+        let (page, result) = self.inner.poll_data()?;
+
+        Some((page, self.count(result)))
+    }
+}
+
+impl<DI> ReadInterface for Counted<DI>
+where
+    DI: ReadInterface,
+{
+    fn read_status(&mut self) -> Result<u8, DisplayError> {
+        let result = self.inner.read_status();
+
+        self.count(result)
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+        let result = self.inner.read_data(buf);
+
+        self.count(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mode::GraphicsMode, test_helpers::CaptureInterface, Builder};
+
+    #[test]
+    fn flushes_bytes_and_errors_are_counted() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.reset_metrics();
+        iface.clear();
+
+        display.set_pixel(0, 0, 1);
+        display.draw_bitmap(8, 8, 8, 2, &[0xFF, 0x00]);
+        display.flush_dirty().unwrap();
+        let metrics = display.metrics();
+        assert_eq!(metrics.flushes, 1);
+        assert_eq!(metrics.pixels_set, 17);
+        display.set_pixel_unchecked(1, 0, true);
+        display.scroll_down(1);
+        display.clear();
+        assert_eq!(display.metrics().pixels_set, 18);
+        assert_eq!(metrics.data_bytes, iface.data().len() as u32);
+        assert_eq!(metrics.command_bytes, iface.commands().len() as u32);
+        assert_eq!(metrics.errors, 0);
+
+        iface.set_fail_data(true);
+        assert!(display.flush().is_err());
+        assert_eq!(display.metrics().errors, 1);
+        assert_eq!(display.metrics().flushes, 1);
+    }
+}
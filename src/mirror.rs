@@ -0,0 +1,239 @@
+//! Mirroring the screen to a host, behind the `mirror` feature
+//!
+//! [`GraphicsMode::flush_dirty_mirrored`](crate::mode::GraphicsMode::flush_dirty_mirrored)
+//! flushes like `flush_dirty` and then writes what it sent to a second
+//! [`embedded_io::Write`](https://docs.rs/embedded-io) sink, e.g. a debug UART or a USB serial
+//! port, so a PC can show a live copy of the screen during development. Only the changed spans
+//! of each page go out, in the same display RAM layout the panel receives, so a mirrored frame
+//! costs about as many bytes as the flush itself.
+//!
+//! Every flush is written as one frame:
+//!
+//! | Bytes       | Content                                                           |
+//! |-------------|-------------------------------------------------------------------|
+//! | 1           | `0x5A`, the start of a frame                                      |
+//! | 1           | Width of the display RAM in columns                               |
+//! | 1           | Height of the display RAM in pages of 8 rows                      |
+//! | per span    | Page, first column and number of columns, then one byte per column |
+//! | 1           | `0xFF`, the end of the frame                                      |
+//!
+//! Each column byte holds 8 rows of the page with the top one in the least significant bit.
+//! Page numbers are below 20, so the end marker cannot be mistaken for a span.
+//!
+//! On the host, [`MirrorDecoder`] (behind the `std` feature) reassembles the frames from the
+//! received bytes, in whatever pieces they arrive, and keeps a copy of the display RAM:
+//!
+//! ```rust,ignore
+//! let mut decoder = MirrorDecoder::new();
+//! loop {
+//!     let received = serial_port.read(&mut buffer)?;
+//!     if decoder.push(&buffer[..received]) > 0 {
+//!         redraw_window(&decoder);
+//!     }
+//! }
+//! ```
+//!
+//! The copy only catches up with the screen as changes are mirrored, so start the host before
+//! the first mirrored flush, or `clear` and redraw the display to send a complete frame.
+
+use embedded_io::Write;
+
+/// First byte of a mirrored frame
+pub const FRAME_START: u8 = 0x5A;
+
+/// Last byte of a mirrored frame
+pub const FRAME_END: u8 = 0xFF;
+
+/// Write the header of a frame for display RAM `width` columns wide and `pages` pages high
+pub(crate) fn write_header<W: Write>(sink: &mut W, width: u8, pages: u8) -> Result<(), W::Error> {
+    sink.write_all(&[FRAME_START, width, pages])
+}
+
+/// Write the span of `page` from column `start` holding `data`
+pub(crate) fn write_span<W: Write>(
+    sink: &mut W,
+    page: u8,
+    start: u8,
+    data: &[u8],
+) -> Result<(), W::Error> {
+    sink.write_all(&[page, start, data.len() as u8])?;
+    sink.write_all(data)
+}
+
+/// Write the end of a frame
+pub(crate) fn write_end<W: Write>(sink: &mut W) -> Result<(), W::Error> {
+    sink.write_all(&[FRAME_END])
+}
+
+/// Host side decoder of mirrored frames, see the [module documentation](self)
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, Default)]
+pub struct MirrorDecoder {
+    width: u8,
+    pages: u8,
+    ram: std::vec::Vec<u8>,
+    received: std::vec::Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl MirrorDecoder {
+    /// Create a decoder that has not seen a frame yet
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add bytes received from the device. Returns the number of frames they completed, which
+    /// were applied to the copy of the display RAM. Bytes that do not form a valid frame, e.g.
+    /// when the host started listening in the middle of one, are skipped up to the next frame
+    /// start.
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        self.received.extend_from_slice(bytes);
+
+        let mut frames = 0;
+        loop {
+            match self.parse() {
+                Parsed::Frame(length) => {
+                    self.apply(length);
+                    self.received.drain(..length);
+                    frames += 1;
+                }
+                Parsed::Invalid => {
+                    // Resynchronise on the next frame start
+                    let skip = self.received[1..]
+                        .iter()
+                        .position(|&byte| byte == FRAME_START)
+                        .map_or(self.received.len(), |position| position + 1);
+                    self.received.drain(..skip);
+                }
+                Parsed::Incomplete => return frames,
+            }
+        }
+    }
+
+    /// Get the width in columns and the height in rows of the mirrored display RAM, or `(0, 0)`
+    /// before the first frame
+    pub fn dimensions(&self) -> (u32, u32) {
+        (u32::from(self.width), u32::from(self.pages) * 8)
+    }
+
+    /// Get the copy of the display RAM, one page after the other, in the layout of
+    /// [`FramebufferLayout::VerticalPages`](crate::mode::FramebufferLayout::VerticalPages)
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Whether the pixel at `column` and `row` of the display RAM is lit. Pixels outside of it
+    /// read as off.
+    pub fn get_pixel(&self, column: u32, row: u32) -> bool {
+        let (width, height) = self.dimensions();
+        if column >= width || row >= height {
+            return false;
+        }
+
+        self.ram[(row / 8 * width + column) as usize] & (1 << (row % 8)) != 0
+    }
+
+    /// Check the received bytes for a complete frame at their start
+    fn parse(&self) -> Parsed {
+        let received = &self.received[..];
+        let Some(&first) = received.first() else {
+            return Parsed::Incomplete;
+        };
+        if first != FRAME_START {
+            return Parsed::Invalid;
+        }
+        if received.len() < 3 {
+            return Parsed::Incomplete;
+        }
+
+        let (width, pages) = (received[1], received[2]);
+        if !(1..=160).contains(&width) || !(1..=20).contains(&pages) {
+            return Parsed::Invalid;
+        }
+
+        let mut position = 3;
+        loop {
+            match received.get(position..position + 3) {
+                Some(&[page, start, length]) => {
+                    if page >= pages
+                        || length == 0
+                        || usize::from(start) + usize::from(length) > usize::from(width)
+                    {
+                        return Parsed::Invalid;
+                    }
+                    position += 3 + usize::from(length);
+                }
+                _ if received.get(position) == Some(&FRAME_END) => {
+                    return Parsed::Frame(position + 1);
+                }
+                _ => return Parsed::Incomplete,
+            }
+        }
+    }
+
+    /// Apply the valid frame of `length` bytes at the start of the received bytes
+    fn apply(&mut self, length: usize) {
+        let (width, pages) = (self.received[1], self.received[2]);
+        if (width, pages) != (self.width, self.pages) {
+            self.width = width;
+            self.pages = pages;
+            self.ram = std::vec![0; usize::from(width) * usize::from(pages)];
+        }
+
+        let mut position = 3;
+        while position < length - 1 {
+            let (page, start, count) = (
+                usize::from(self.received[position]),
+                usize::from(self.received[position + 1]),
+                usize::from(self.received[position + 2]),
+            );
+            let offset = page * usize::from(width) + start;
+            self.ram[offset..offset + count]
+                .copy_from_slice(&self.received[position + 3..position + 3 + count]);
+            position += 3 + count;
+        }
+    }
+}
+
+/// Outcome of looking for a frame at the start of the received bytes
+#[cfg(feature = "std")]
+enum Parsed {
+    /// A valid frame of this many bytes
+    Frame(usize),
+    /// More bytes are needed
+    Incomplete,
+    /// The bytes do not start a valid frame
+    Invalid,
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{mode::GraphicsMode, test_helpers::StubInterface, Builder};
+
+    #[test]
+    fn decoder_follows_mirrored_flushes() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+        display.init().unwrap();
+        let mut sink = std::vec::Vec::new();
+        let mut decoder = MirrorDecoder::new();
+
+        display.set_pixel(3, 9, 1);
+        display.flush_dirty_mirrored(&mut sink).unwrap();
+        // Noise before the frame and a frame split in two
+        decoder.push(&[0x00, 0x12]);
+        assert_eq!(decoder.push(&sink[..100]), 0);
+        assert_eq!(decoder.push(&sink[100..]), 1);
+        assert_eq!(decoder.dimensions(), (128, 160));
+        assert!(decoder.get_pixel(3, 9));
+
+        sink.clear();
+        display.set_pixel(3, 9, 0);
+        display.set_pixel(100, 150, 1);
+        let stats = display.flush_dirty_mirrored(&mut sink).unwrap();
+        assert_eq!(sink.len(), 3 + 2 * 4 + 1);
+        assert_eq!(stats.bytes, 2);
+        assert_eq!(decoder.push(&sink), 1);
+        assert!(!decoder.get_pixel(3, 9) && decoder.get_pixel(100, 150));
+    }
+}
@@ -0,0 +1,327 @@
+//! Banded rendering mode for microcontrollers without RAM for a whole framebuffer
+//!
+//! ```rust,ignore
+//! let interface = /* your preferred `display-interface` implementor */;
+//! let mut display: BandedMode<_, 2> = Builder::new().connect(interface).into();
+//!
+//! display.init().unwrap();
+//! display
+//!     .render(|band| {
+//!         Circle::new(Point::new(20, 20), 40)
+//!             .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+//!             .draw(band)
+//!             .unwrap();
+//!     })
+//!     .unwrap();
+//! ```
+//!
+//! The display is rendered in horizontal bands of `PAGES` pages of 8 pixel rows each, so the mode
+//! only buffers `PAGES * 160` bytes instead of a whole frame. `render` calls the closure once for
+//! every band with a [`Band`] to draw the complete scene to. The band keeps the pixels that fall
+//! inside of it and drops the rest, and is written to the display as soon as the closure returns.
+//! The scene must therefore be drawn the same way on every call. More pages per band take more
+//! RAM but call the closure fewer times.
+//!
+//! The bands follow the pages of the panel, so with the display rotated by 90 or 270 degrees they
+//! are vertical strips of the rotated image instead.
+
+use core::convert::Infallible;
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+use hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
+
+use crate::{
+    displayrotation::DisplayRotation,
+    error::InitError,
+    mode::{
+        displaymode::{DisplayModeTrait, DisplayTuning},
+        raw::RawMode,
+    },
+    properties::DisplayProperties,
+};
+
+/// Banded rendering mode handler
+///
+/// `PAGES` is the height of a band in pages of 8 pixel rows.
+pub struct BandedMode<DI, const PAGES: usize = 1>
+where
+    DI: WriteOnlyDataCommand,
+{
+    properties: DisplayProperties<DI>,
+    buffer: [[u8; 160]; PAGES],
+}
+
+impl<DI, const PAGES: usize> DisplayModeTrait<DI> for BandedMode<DI, PAGES>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new BandedMode instance
+    ///
+    /// Panics if `PAGES` is 0.
+    fn new(properties: DisplayProperties<DI>) -> Self {
+        assert!(PAGES > 0, "bands must be at least one page high");
+
+        BandedMode {
+            properties,
+            buffer: [[0; 160]; PAGES],
+        }
+    }
+
+    /// Release all resources used by BandedMode
+    fn release(self) -> DisplayProperties<DI> {
+        self.properties
+    }
+}
+
+impl<DI, const PAGES: usize> DisplayTuning<DI> for BandedMode<DI, PAGES>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn properties_mut(&mut self) -> &mut DisplayProperties<DI> {
+        &mut self.properties
+    }
+}
+
+impl<DI, const PAGES: usize> BandedMode<DI, PAGES>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Switch back to [`RawMode`]. The display keeps showing the last rendered frame.
+    pub fn into_raw_mode(self) -> RawMode<DI> {
+        RawMode::new(self.properties)
+    }
+
+    /// Destroy the driver and return the interface
+    pub fn release_interface(self) -> DI {
+        self.properties.release()
+    }
+
+    /// Reset display. This is very important on the SH1108!
+    ///
+    /// This should be called before `init` or any other methods.
+    pub fn reset<RST, DELAY, PinE>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), PinE>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.mark_uninitialized();
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(1);
+        rst.set_high()?;
+        delay.delay_ms(1);
+        Ok(())
+    }
+
+    /// Initialise the display and clear it
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.properties.init_column_mode()?;
+        self.render(|_| {})
+    }
+
+    /// Reset the display with `rst`, see [`BandedMode::reset`], then `init` it
+    pub fn init_with_reset<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.reset(rst, delay).map_err(InitError::Pin)?;
+        self.init()?;
+
+        Ok(())
+    }
+
+    /// Render a frame band by band. `draw` is called once for every band, from the top pages of
+    /// the panel to the bottom ones, and has to draw the whole scene to the band every time. Each
+    /// band is written to the display right after `draw` returns.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn render<F>(&mut self, mut draw: F) -> Result<(), DisplayError>
+    where
+        F: FnMut(&mut Band<'_, PAGES>),
+    {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display rendered before init()"
+        );
+
+        let display_size = self.properties.get_size();
+        let (width, height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+        let total_pages = height / 8;
+        let swap = matches!(
+            self.properties.get_rotation(),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270
+        );
+
+        for first_page in (0..total_pages).step_by(PAGES) {
+            let pages = PAGES.min(usize::from(total_pages - first_page)) as u8;
+
+            self.buffer = [[0; 160]; PAGES];
+            draw(&mut Band {
+                buffer: &mut self.buffer,
+                first_page,
+                pages,
+                size: (width, height),
+                swap,
+            });
+
+            self.properties.set_draw_area_unchecked(
+                (column_offset, first_page),
+                (column_offset + width, first_page + pages),
+            )?;
+            for page in &self.buffer[..pages as usize] {
+                self.properties.draw(&page[..width as usize])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set the display rotation. This takes effect with the next `render`.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.properties.set_rotation(rot)
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.display_on(on)
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.properties.set_contrast(contrast)
+    }
+}
+
+/// One band of a frame being rendered by [`BandedMode::render`]
+///
+/// The band covers the whole display, but only keeps the pixels inside its pages.
+pub struct Band<'a, const PAGES: usize> {
+    buffer: &'a mut [[u8; 160]; PAGES],
+    first_page: u8,
+    pages: u8,
+    /// Panel width and height in pixels
+    size: (u8, u8),
+    /// Whether display `x` and `y` are swapped to get the panel column and row
+    swap: bool,
+}
+
+impl<const PAGES: usize> Band<'_, PAGES> {
+    /// Panel rows `start..end` covered by the band, i.e. display rows unless the display is
+    /// rotated by 90 or 270 degrees, in which case they are display columns. Scenes can use this
+    /// to skip drawing what falls outside of the band.
+    pub fn rows(&self) -> (u32, u32) {
+        let start = u32::from(self.first_page) * 8;
+
+        (start, start + u32::from(self.pages) * 8)
+    }
+}
+
+impl<const PAGES: usize> DrawTarget for Band<'_, PAGES> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        let (start, end) = self.rows();
+
+        for Pixel(pos, color) in pixels {
+            let (Ok(x), Ok(y)) = (u32::try_from(pos.x), u32::try_from(pos.y)) else {
+                continue;
+            };
+            let (column, row) = if self.swap { (y, x) } else { (x, y) };
+
+            if column < u32::from(self.size.0) && (start..end).contains(&row) {
+                let byte = &mut self.buffer[((row - start) / 8) as usize][column as usize];
+                let bit = 1 << (row % 8);
+
+                if color.is_on() {
+                    *byte |= bit;
+                } else {
+                    *byte &= !bit;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<const PAGES: usize> OriginDimensions for Band<'_, PAGES> {
+    fn size(&self) -> Size {
+        let (width, height) = self.size;
+
+        if self.swap {
+            Size::new(height.into(), width.into())
+        } else {
+            Size::new(width.into(), height.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mode::GraphicsMode, test_helpers::CaptureInterface, Builder};
+    use embedded_graphics::{
+        prelude::*,
+        primitives::{Circle, PrimitiveStyle},
+    };
+
+    fn scene<D: DrawTarget<Color = BinaryColor>>(target: &mut D) {
+        let _ = Circle::new(Point::new(10, 5), 50)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 3))
+            .draw(target);
+        let _ = Pixel(Point::new(127, 159), BinaryColor::On).draw(target);
+    }
+
+    #[test]
+    fn bands_match_the_full_frame() {
+        for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate90] {
+            let full = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new()
+                .with_rotation(rotation)
+                .connect(full.clone())
+                .into();
+            display.init().unwrap();
+            full.clear();
+            scene(&mut display);
+            display.flush().unwrap();
+
+            let banded = CaptureInterface::new();
+            let mut display: BandedMode<_, 3> = Builder::new()
+                .with_rotation(rotation)
+                .connect(banded.clone())
+                .into();
+            display.init().unwrap();
+            banded.clear();
+
+            let mut calls = 0;
+            display
+                .render(|band| {
+                    calls += 1;
+                    scene(band);
+                })
+                .unwrap();
+
+            // 20 pages in bands of 3, the last one only 2 pages high
+            assert_eq!(calls, 7);
+            assert_eq!(banded.data(), full.data());
+        }
+    }
+}
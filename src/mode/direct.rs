@@ -0,0 +1,398 @@
+//! Unbuffered drawing mode for microcontrollers without RAM for a framebuffer
+//!
+//! ```rust,ignore
+//! let interface = /* your preferred `display-interface` implementor */;
+//! let mut display: DirectMode<_> = Builder::new().connect(interface).into();
+//!
+//! display.init().unwrap();
+//! Circle::new(Point::new(20, 20), 40)
+//!     .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+//!     .draw(&mut display)
+//!     .unwrap();
+//! ```
+//!
+//! Every drawing goes straight to the display RAM, without a framebuffer. The display interface
+//! is write only, so the display RAM cannot be read back to combine new pixels with the ones
+//! already on the screen. Instead, the mode caches the page of 8 pixel rows that is being drawn
+//! to, which is at most 160 bytes. Pixels drawn to the cached page combine as usual, and the
+//! changed columns of the cache are written to the display when drawing moves to another page
+//! and at the end of every drawing.
+//!
+//! Moving to another page starts the cache out empty. When a later drawing comes back to a page,
+//! the columns it changes therefore lose what was drawn there before in the 8 rows of that page.
+//! Text, images and filled shapes draw their rows in order and are not affected, but drawings
+//! that overlap each other in the same columns of a page can be. Use
+//! [`GraphicsMode`](crate::mode::GraphicsMode) where overlapping drawings have to combine.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
+
+use crate::{
+    displayrotation::DisplayRotation,
+    error::{Error, InitError},
+    mode::{
+        displaymode::{DisplayModeTrait, DisplayTuning},
+        raw::RawMode,
+    },
+    properties::DisplayProperties,
+    read::ReadInterface,
+};
+
+/// Direct drawing mode handler
+pub struct DirectMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    properties: DisplayProperties<DI>,
+    /// Display RAM contents of the cached page, as far as they were drawn through the cache
+    cache: [u8; 160],
+    /// Page held in `cache`, if any
+    cached_page: Option<u8>,
+    /// Columns `start..end` of the cached page that were not written to the display yet
+    dirty: (u8, u8),
+}
+
+impl<DI> DisplayModeTrait<DI> for DirectMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new DirectMode instance
+    fn new(properties: DisplayProperties<DI>) -> Self {
+        DirectMode {
+            properties,
+            cache: [0; 160],
+            cached_page: None,
+            dirty: (u8::MAX, 0),
+        }
+    }
+
+    /// Release all resources used by DirectMode
+    fn release(self) -> DisplayProperties<DI> {
+        self.properties
+    }
+}
+
+impl<DI> DisplayTuning<DI> for DirectMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn properties_mut(&mut self) -> &mut DisplayProperties<DI> {
+        &mut self.properties
+    }
+}
+
+impl<DI> DirectMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Switch back to [`RawMode`]. The display keeps showing what was drawn, except for pixels
+    /// that were not written out with `flush` yet.
+    pub fn into_raw_mode(self) -> RawMode<DI> {
+        RawMode::new(self.properties)
+    }
+
+    /// Destroy the driver and return the interface
+    pub fn release_interface(self) -> DI {
+        self.properties.release()
+    }
+
+    /// Reset display. This is very important on the SH1108!
+    ///
+    /// This should be called before `init` or any other methods.
+    pub fn reset<RST, DELAY, PinE>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), PinE>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.mark_uninitialized();
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(1);
+        rst.set_high()?;
+        delay.delay_ms(1);
+        Ok(())
+    }
+
+    /// Initialise the display and clear it
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.properties.init_column_mode()?;
+        self.clear()
+    }
+
+    /// Initialise the display again after it lost its settings and RAM contents, e.g. to a
+    /// brown-out on its supply, see [`DisplayProperties::reinit`]. Only the page cache would
+    /// remember any pixels, so the display is cleared like by `init`.
+    pub fn reinit(&mut self) -> Result<(), DisplayError> {
+        self.properties.reinit()?;
+        self.clear()
+    }
+
+    /// Reset the display with `rst`, see [`DirectMode::reset`], then `init` it
+    pub fn init_with_reset<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.reset(rst, delay).map_err(InitError::Pin)?;
+        self.init()?;
+
+        Ok(())
+    }
+
+    /// Clear the display and the page cache
+    pub fn clear(&mut self) -> Result<(), DisplayError> {
+        let display_size = self.properties.get_size();
+        let (display_width, display_height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+
+        self.properties.set_draw_area_unchecked(
+            (column_offset, 0),
+            (display_width + column_offset, display_height / 8),
+        )?;
+        for _ in 0..display_height / 8 {
+            self.properties.draw(&[0; 160][..display_width as usize])?;
+        }
+
+        self.cache = [0; 160];
+        self.dirty = (u8::MAX, 0);
+
+        Ok(())
+    }
+
+    /// Turn the pixel at `x` and `y` on or off, in display coordinates taking rotation into
+    /// account. Pixels outside the display are ignored.
+    ///
+    /// The pixel goes into the page cache and is written to the display by the next `flush`, or
+    /// as soon as a pixel in another page is set.
+    pub fn set_pixel(&mut self, x: u32, y: u32, value: bool) -> Result<(), DisplayError> {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        let (column, row) = match self.properties.get_rotation() {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+        };
+
+        if column >= display_width as u32 || row >= display_height as u32 {
+            return Ok(());
+        }
+
+        let (column, page) = (column as u8, (row / 8) as u8);
+        if self.cached_page != Some(page) {
+            self.flush()?;
+            self.cache = [0; 160];
+            self.cached_page = Some(page);
+        }
+
+        let bit = 1 << (row % 8);
+        if value {
+            self.cache[column as usize] |= bit;
+        } else {
+            self.cache[column as usize] &= !bit;
+        }
+        self.dirty = (self.dirty.0.min(column), self.dirty.1.max(column + 1));
+
+        Ok(())
+    }
+
+    /// Write the columns of the cached page that changed since the last flush to the display.
+    /// The `DrawTarget` implementation does this at the end of every drawing.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush(&mut self) -> Result<(), DisplayError> {
+        let (start, end) = self.dirty;
+        let Some(page) = self.cached_page.filter(|_| start < end) else {
+            return Ok(());
+        };
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+
+        let column_offset = self.properties.get_size().column_offset();
+        self.properties.set_draw_area_unchecked(
+            (column_offset + start, page),
+            (column_offset + end, page + 1),
+        )?;
+        self.properties
+            .draw(&self.cache[start as usize..end as usize])?;
+        self.dirty = (u8::MAX, 0);
+
+        Ok(())
+    }
+
+    /// Stream a whole frame of packed column mode data straight to the display, see
+    /// [`DisplayProperties::stream_frame`]. The frame replaces everything drawn before, so pixels
+    /// that were not flushed yet are dropped and the page cache starts out empty.
+    pub fn stream_frame<I>(&mut self, frame: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        self.cached_page = None;
+        self.dirty = (u8::MAX, 0);
+
+        self.properties.stream_frame(frame)
+    }
+
+    /// Set the display rotation. This does not move what is already on the display.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.flush()?;
+        self.properties.set_rotation(rot)
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.display_on(on)
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.properties.set_contrast(contrast)
+    }
+}
+
+impl<DI> DirectMode<DI>
+where
+    DI: ReadInterface,
+{
+    /// Turn the pixel at `x` and `y` on or off right in display RAM, in display coordinates
+    /// taking rotation into account. Pixels outside the display are ignored.
+    ///
+    /// Unlike `set_pixel`, this reads the byte back from the display first, see
+    /// [`DisplayProperties::set_pixel_direct`], so the pixel always combines with what is
+    /// already on the screen. It needs an interface that can read, and costs a read and a write
+    /// per pixel. The page cache is flushed first and starts out empty afterwards.
+    pub fn modify_pixel(&mut self, x: u32, y: u32, value: bool) -> Result<(), Error> {
+        self.flush()?;
+        self.cached_page = None;
+
+        self.properties.set_pixel_direct(x, y, value)
+    }
+}
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+
+#[cfg(feature = "graphics")]
+impl<DI> DrawTarget for DirectMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(pos.x), u32::try_from(pos.y)) {
+                self.set_pixel(x, y, color.is_on())?;
+            }
+        }
+
+        self.flush()
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI> OriginDimensions for DirectMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        let (w, h) = self.properties.get_dimensions();
+
+        Size::new(w.into(), h.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{CaptureInterface, RamInterface},
+        Builder,
+    };
+
+    #[test]
+    fn pixels_combine_within_a_page() {
+        let iface = CaptureInterface::new();
+        let mut display: DirectMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        iface.clear();
+
+        display.set_pixel(3, 10, true).unwrap();
+        display.set_pixel(4, 11, true).unwrap();
+        display.set_pixel(3, 12, true).unwrap();
+        display.flush().unwrap();
+
+        assert_eq!(iface.commands()[..4], [0xB0, 0x01, 0x03, 0x11]);
+        assert_eq!(iface.data(), [0x14, 0x08]);
+
+        // Nothing changed, so nothing is sent
+        iface.clear();
+        display.flush().unwrap();
+        assert!(iface.sent().is_empty());
+
+        // Moving to another page writes out the cached one
+        display.set_pixel(3, 10, false).unwrap();
+        display.set_pixel(0, 0, true).unwrap();
+        assert_eq!(iface.data(), [0x10]);
+        display.flush().unwrap();
+        assert_eq!(iface.data(), [0x10, 0x01]);
+    }
+
+    #[test]
+    fn modified_pixels_combine_with_the_display() {
+        let iface = RamInterface::new();
+        let mut display: DirectMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+
+        // Something else drew to the display, which the page cache cannot know about
+        iface.set_ram(16 + 3, 1, &[0x81]);
+        display.set_pixel(4, 8, true).unwrap();
+        display.modify_pixel(3, 10, true).unwrap();
+        display.modify_pixel(3, 15, false).unwrap();
+        display.modify_pixel(300, 0, true).unwrap();
+
+        assert_eq!(iface.page(1)[16 + 3..][..2], [0x05, 0x01]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn draw_target_flushes_every_drawing() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{PrimitiveStyle, Rectangle},
+        };
+
+        let iface = CaptureInterface::new();
+        let mut display: DirectMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        iface.clear();
+
+        assert_eq!(display.size(), Size::new(160, 128));
+        Rectangle::new(Point::new(6, 1), Size::new(4, 2))
+            .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+            .draw(&mut display)
+            .unwrap();
+
+        // Display rows run along the panel columns when rotated, so every row of the rectangle
+        // crosses from page 0 into page 1
+        assert_eq!(iface.data(), [0xC0, 0x03, 0xC0, 0x03]);
+    }
+}
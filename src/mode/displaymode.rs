@@ -1,8 +1,11 @@
 //! Abstraction of different operating modes for the sh1108
 
-use display_interface::WriteOnlyDataCommand;
+use display_interface::{DisplayError, WriteOnlyDataCommand};
 
-use crate::properties::DisplayProperties;
+use crate::{
+    mode::{graphics::GraphicsMode, raw::RawMode, terminal::TerminalMode},
+    properties::DisplayProperties,
+};
 
 /// Display mode abstraction
 pub struct DisplayMode<MODE>(pub MODE);
@@ -16,6 +19,35 @@ pub trait DisplayModeTrait<DI> {
     fn release(self) -> DisplayProperties<DI>;
 }
 
+/// Settings of a running display that every mode can change without being released, by
+/// forwarding to the [`DisplayProperties`] it drives
+pub trait DisplayTuning<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// The properties of the display driven by the mode
+    fn properties_mut(&mut self) -> &mut DisplayProperties<DI>;
+
+    /// Change the frame clock of the running display to trade refresh rate for power. See
+    /// [`DisplayProperties::apply_clock_divider`].
+    fn apply_clock_divider(&mut self, fosc: u8, divide_ratio: u8) -> Result<(), DisplayError> {
+        self.properties_mut()
+            .apply_clock_divider(fosc, divide_ratio)
+    }
+
+    /// Change the precharge and discharge periods of the running display. See
+    /// [`DisplayProperties::apply_precharge`].
+    fn apply_precharge(&mut self, phase1: u8, phase2: u8) -> Result<(), DisplayError> {
+        self.properties_mut().apply_precharge(phase1, phase2)
+    }
+
+    /// Change the VCOM deselect level of the running display. See
+    /// [`DisplayProperties::apply_vcom_level`].
+    fn apply_vcom_level(&mut self, level: u8) -> Result<(), DisplayError> {
+        self.properties_mut().apply_vcom_level(level)
+    }
+}
+
 impl<MODE> DisplayMode<MODE> {
     /// Setup display to run in requested mode
     pub fn new<DI>(properties: DisplayProperties<DI>) -> Self
@@ -37,3 +69,23 @@ impl<MODE> DisplayMode<MODE> {
         NMODE::new(properties)
     }
 }
+
+impl<DI> DisplayMode<RawMode<DI>>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Switch to the buffered [`GraphicsMode`]
+    pub fn into_graphics_mode(self) -> GraphicsMode<DI> {
+        self.0.into_graphics_mode()
+    }
+
+    /// Switch to the unbuffered [`TerminalMode`]
+    pub fn into_terminal_mode(self) -> TerminalMode<DI> {
+        self.0.into_terminal_mode()
+    }
+
+    /// Show a run-length encoded boot logo, see [`RawMode::show_splash`]
+    pub fn show_splash(&mut self, rle: &[u8]) -> Result<(), DisplayError> {
+        self.0.show_splash(rle)
+    }
+}
@@ -0,0 +1,362 @@
+//! Double buffering on top of [`GraphicsMode`]
+//!
+//! ```rust,ignore
+//! let display: GraphicsMode<_> = Builder::new().connect(interface).into();
+//! let mut display = DoubleBuffered::new(display);
+//!
+//! display.init().unwrap();
+//! loop {
+//!     display.back_mut().clear();
+//!     draw_frame(display.back_mut());
+//!     display.swap_and_flush().unwrap();
+//! }
+//! ```
+//!
+//! [`DoubleBuffered`] keeps a second framebuffer next to the one of its [`GraphicsMode`]. All
+//! drawing goes to the back buffer, the framebuffer of the `GraphicsMode`, while the front buffer
+//! holds the last finished frame and does not change until the next swap. Code that sends frames
+//! itself, e.g. from an interrupt or with DMA, can keep reading [`DoubleBuffered::front`] while
+//! the next frame is being drawn, so a frame never goes out half drawn.
+//!
+//! With an interface that can send in the background, see the [`dma`](crate::dma) module,
+//! [`DoubleBuffered::swap_and_flush_dma`] makes the finished frame the front buffer and starts
+//! sending it, and drawing the next frame to the back buffer can start right away. Both buffers
+//! then have to be kept in `'static` memory:
+//!
+//! ```rust,ignore
+//! let back = cortex_m::singleton!(: [u8; 2560] = [0; 2560]).unwrap();
+//! let front = cortex_m::singleton!(: [u8; 2560] = [0; 2560]).unwrap();
+//! let display = GraphicsMode::with_storage(properties, &mut back[..]);
+//! let mut display = DoubleBuffered::with_front(display, &mut front[..]);
+//!
+//! loop {
+//!     display.back_mut().clear();
+//!     draw_frame(display.back_mut());
+//!     display.swap_and_flush_dma().unwrap();
+//!     while !display.poll_flush().unwrap() {
+//!         do_other_work();
+//!     }
+//! }
+//! ```
+//!
+//! A swap exchanges the buffers, so the back buffer then holds the frame before the one that was
+//! just finished, two frames old, and all of it is marked as changed for `flush_dirty`.
+//! Animations that redraw everything start every frame with `clear`, and ones that only change
+//! parts of the last frame start with [`DoubleBuffered::copy_front_to_back`].
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Pixel,
+};
+
+use crate::{
+    displayrotation::{RotationMode, RuntimeRotation},
+    dma::DmaInterface,
+    mode::{
+        graphics::{FlushStats, GraphicsMode, MAX_BUFFER_SIZE},
+        storage::FrameBufferStorage,
+    },
+};
+
+/// A [`GraphicsMode`] with a front and a back buffer, see the [module documentation](self). Both
+/// are kept in an `S`, inline arrays by default.
+pub struct DoubleBuffered<DI, const N: usize = MAX_BUFFER_SIZE, R = RuntimeRotation, S = [u8; N]>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    display: GraphicsMode<DI, N, R, S>,
+    front: S,
+    /// Next page of the front buffer to send with `poll_flush`, and the number of pages
+    flush: (u8, u8),
+    /// Whether a page transfer of the front buffer is running
+    busy: bool,
+}
+
+impl<DI, const N: usize, R: RotationMode> DoubleBuffered<DI, N, R>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Add a front buffer to `display`. Its framebuffer becomes the back buffer, and the front
+    /// buffer starts out blank.
+    pub fn new(display: GraphicsMode<DI, N, R>) -> Self {
+        Self::with_front(display, [0; N])
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> DoubleBuffered<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Add `front` as the front buffer to `display`, e.g. one in `'static` memory for
+    /// `swap_and_flush_dma`. Its framebuffer becomes the back buffer, and the front buffer is
+    /// cleared.
+    ///
+    /// Panics if `front` is smaller than the framebuffer of `display`.
+    pub fn with_front(display: GraphicsMode<DI, N, R, S>, mut front: S) -> Self {
+        assert!(
+            front.bytes().len() >= display.framebuffer().len(),
+            "front buffer smaller than the framebuffer"
+        );
+        front.bytes_mut().fill(0);
+
+        Self {
+            display,
+            front,
+            flush: (0, 0),
+            busy: false,
+        }
+    }
+
+    /// Initialise the display, see [`GraphicsMode::init`]
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.display.init()
+    }
+
+    /// Get the display with the back buffer
+    pub fn back(&self) -> &GraphicsMode<DI, N, R, S> {
+        &self.display
+    }
+
+    /// Get the display with the back buffer mutably, to draw the next frame with any of the
+    /// `GraphicsMode` methods. While a flush started with `swap_and_flush_dma` runs, only draw
+    /// to it, as anything sent to the display before `poll_flush` returned `Ok(true)` would
+    /// corrupt the frame going out.
+    pub fn back_mut(&mut self) -> &mut GraphicsMode<DI, N, R, S> {
+        &mut self.display
+    }
+
+    /// The last finished frame, in the layout of the framebuffer, see
+    /// [`GraphicsMode::framebuffer`]. It only changes with the next swap.
+    pub fn front(&self) -> &[u8] {
+        let length = self.display.framebuffer().len();
+
+        &self.front.bytes()[..length]
+    }
+
+    /// Whether a flush started with `swap_and_flush_dma` is still running
+    pub fn is_flushing(&self) -> bool {
+        self.busy || self.flush.0 < self.flush.1
+    }
+
+    /// Copy the front buffer to the back buffer, to draw the next frame on top of the last one
+    pub fn copy_front_to_back(&mut self) {
+        let length = self.display.framebuffer().len();
+
+        self.display
+            .framebuffer_mut()
+            .copy_from_slice(&self.front.bytes()[..length]);
+    }
+
+    /// Make the back buffer the front buffer, without sending anything. The back buffer then
+    /// holds the previous front buffer, two frames old, marked as changed for `flush_dirty`.
+    ///
+    /// Panics if a flush started with `swap_and_flush_dma` is still running.
+    pub fn swap(&mut self) {
+        assert!(!self.is_flushing(), "front buffer swapped while being sent");
+
+        self.display.swap_framebuffer(&mut self.front);
+    }
+
+    /// Send the finished back buffer to the display with [`GraphicsMode::flush`] and make it the
+    /// front buffer. If the flush fails the buffers are not swapped, so it can be tried again.
+    ///
+    /// Panics if a flush started with `swap_and_flush_dma` is still running.
+    pub fn swap_and_flush(&mut self) -> Result<FlushStats, DisplayError> {
+        assert!(!self.is_flushing(), "front buffer swapped while being sent");
+
+        let stats = self.display.flush()?;
+        self.swap();
+
+        Ok(stats)
+    }
+
+    /// Take the display back out, with the back buffer as its framebuffer
+    ///
+    /// Panics if a flush started with `swap_and_flush_dma` is still running.
+    pub fn release(self) -> GraphicsMode<DI, N, R, S> {
+        assert!(!self.is_flushing(), "display released while being flushed");
+
+        self.display
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode> DoubleBuffered<DI, N, R, &'static mut [u8]>
+where
+    DI: DmaInterface,
+{
+    /// Make the finished back buffer the front buffer and start sending it in the background,
+    /// see the [module documentation](self). Waits for a flush that is still running first.
+    ///
+    /// The pages go out as with [`GraphicsMode::flush_dma`], one started by every call to
+    /// `poll_flush`, and the back buffer can be drawn to in the meantime. Without the
+    /// [`FramebufferLayout::VerticalPages`] layout, or with a pixel shift, this is
+    /// `swap_and_flush` and nothing is left to poll.
+    ///
+    /// [`FramebufferLayout::VerticalPages`]: crate::mode::FramebufferLayout::VerticalPages
+    pub fn swap_and_flush_dma(&mut self) -> Result<(), DisplayError> {
+        self.wait_for_flush()?;
+
+        let Some(lit_pixels) = self.display.dma_lit_pixels() else {
+            return self.swap_and_flush().map(|_| ());
+        };
+        let pages = self.display.begin_dma_flush(lit_pixels)?;
+        self.swap();
+        self.flush = (0, pages);
+
+        self.poll_flush().map(|_| ())
+    }
+
+    /// Push a flush started with `swap_and_flush_dma` forward: start sending the next page of
+    /// the front buffer once the previous one is done. Returns `Ok(true)` once the whole frame
+    /// has been sent, or if no flush is running. After an error the flush stops.
+    pub fn poll_flush(&mut self) -> Result<bool, DisplayError> {
+        if self.busy {
+            let Some((_, result)) = self.display.poll_dma_page() else {
+                return Ok(false);
+            };
+
+            self.busy = false;
+            self.flush.0 += 1;
+            result.inspect_err(|_| self.flush = (0, 0))?;
+        }
+
+        let (page, pages) = self.flush;
+        if page >= pages {
+            if pages > 0 {
+                self.display.end_dma_flush();
+                self.flush = (0, 0);
+            }
+            return Ok(true);
+        }
+
+        let length = self.display.framebuffer().len();
+        let front: *const [u8] = &self.front[..length];
+        // The front buffer is `'static` and only changes with a swap, which waits for the flush
+        #[allow(unsafe_code)]
+        unsafe { self.display.start_dma_page_of(&*front, page) }
+            .inspect_err(|_| self.flush = (0, 0))?;
+        self.busy = true;
+
+        Ok(false)
+    }
+
+    /// Wait until a flush started with `swap_and_flush_dma` is done
+    pub fn wait_for_flush(&mut self) -> Result<(), DisplayError> {
+        while !self.poll_flush()? {}
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> DrawTarget
+    for DoubleBuffered<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.display.draw_iter(pixels)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.display.fill_solid(area, color)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> OriginDimensions
+    for DoubleBuffered<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        self.display.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        displayrotation::DisplayRotation,
+        displaysize::DisplaySize,
+        properties::DisplayProperties,
+        test_helpers::{static_framebuffer, CaptureInterface},
+        Builder,
+    };
+
+    #[test]
+    fn front_buffer_stays_put_while_drawing() {
+        let iface = CaptureInterface::new();
+        let mut display: DoubleBuffered<_> =
+            DoubleBuffered::new(Builder::new().connect(iface.clone()).into());
+        display.init().unwrap();
+
+        display.back_mut().set_pixel(0, 0, 1);
+        iface.clear();
+        let stats = display.swap_and_flush().unwrap();
+        assert_eq!(stats.bytes, 128 * 20);
+        assert_eq!(iface.data()[0], 0x01);
+        assert_eq!(display.front()[0], 0x01);
+
+        // The back buffer now holds the blank initial front buffer
+        assert!(!display.back().get_pixel(0, 0));
+        display.back_mut().set_pixel(1, 0, 1);
+        assert_eq!(display.front()[..2], [0x01, 0x00]);
+
+        display.swap();
+        assert_eq!(display.front()[..2], [0x00, 0x01]);
+        assert!(display.back().get_pixel(0, 0));
+    }
+
+    #[test]
+    fn dma_flush_sends_the_front_buffer_while_drawing() {
+        let iface = CaptureInterface::new();
+        let properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let display: GraphicsMode<_, 0, RuntimeRotation, _> =
+            GraphicsMode::with_storage(properties, static_framebuffer(MAX_BUFFER_SIZE));
+        let mut display = DoubleBuffered::with_front(display, static_framebuffer(MAX_BUFFER_SIZE));
+        display.init().unwrap();
+
+        display.back_mut().set_pixel(0, 0, 1);
+        iface.clear();
+        display.swap_and_flush_dma().unwrap();
+        assert!(display.is_flushing());
+        assert_eq!(iface.data().len(), 128);
+
+        // Drawing the next frame does not change the one going out
+        display.copy_front_to_back();
+        display.back_mut().set_pixel(0, 100, 1);
+        display.wait_for_flush().unwrap();
+        assert!(!display.is_flushing());
+        assert_eq!(iface.data().len(), 128 * 20);
+        assert_eq!(iface.data()[0], 0x01);
+        assert!(iface.data()[1..].iter().all(|&byte| byte == 0));
+        assert!(display.back().get_pixel(0, 0));
+
+        // The next swap sends the frame drawn in the meantime
+        iface.clear();
+        display.swap_and_flush_dma().unwrap();
+        display.wait_for_flush().unwrap();
+        assert_eq!(iface.data()[128 * 12], 0x10);
+        // The back buffer holds the first frame again, which the display no longer shows
+        assert!(display.back_mut().flush_dirty().unwrap().changed());
+    }
+}
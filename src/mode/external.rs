@@ -0,0 +1,404 @@
+//! Framebuffer in external serial RAM, for microcontrollers with only a few KB of SRAM
+//!
+//! ```rust,ignore
+//! // A 23LC1024 or an APS6404 PSRAM on its own chip select
+//! let ram = SpiRam::new(ram_spi, ram_cs);
+//! let properties =
+//!     DisplayProperties::new(interface, DisplaySize::Display128x160, DisplayRotation::Rotate0);
+//! let mut display = ExternalMode::new(properties, ram);
+//!
+//! display.init().unwrap();
+//! Circle::new(Point::new(20, 20), 40)
+//!     .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+//!     .draw(&mut display)
+//!     .unwrap();
+//! display.flush().unwrap();
+//! ```
+//!
+//! [`ExternalMode`] keeps the frame in an [`ExternalRam`], with the pages of the panel one after
+//! the other in the layout of [`FramebufferLayout::VerticalPages`], and only a single page of
+//! 160 bytes in internal RAM. Drawing works on that page and writes it back to the external RAM
+//! when a pixel on another page is drawn, so scenes drawn page by page, like text lines on an
+//! unrotated display, cost the fewest transfers. `flush` then streams the frame through the page
+//! buffer to the display.
+//!
+//! Unlike [`BandedMode`](crate::mode::BandedMode), the frame persists between flushes, so only
+//! what changed has to be drawn, at the cost of a read and a write of the external RAM for every
+//! page touched.
+//!
+//! [`FramebufferLayout::VerticalPages`]: crate::mode::FramebufferLayout::VerticalPages
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+use hal::{
+    blocking::spi::{Transfer, Write},
+    digital::v2::OutputPin,
+};
+
+use crate::{
+    displayrotation::DisplayRotation,
+    error::{ExternalError, SpiRamError},
+    mode::graphics::FlushStats,
+    properties::DisplayProperties,
+};
+
+/// Memory outside the microcontroller that holds the framebuffer of an [`ExternalMode`]
+pub trait ExternalRam {
+    /// Error of a failed transfer
+    type Error;
+
+    /// Read `buffer.len()` bytes starting at `address`
+    fn read(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// Write `data` starting at `address`
+    fn write(&mut self, address: u32, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+/// Serial SRAM or PSRAM on an SPI bus, e.g. a 23LC1024 or an APS6404, using the `03h` read and
+/// `02h` write commands with 24 bit addresses in sequential mode, the power-on default of these
+/// chips
+#[derive(Debug)]
+pub struct SpiRam<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+    base: u32,
+}
+
+impl<SPI, CS> SpiRam<SPI, CS> {
+    /// Use the RAM on `spi` selected by driving `cs` low, with the framebuffer at address 0
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        Self::with_base_address(spi, cs, 0)
+    }
+
+    /// Like [`new`](Self::new), with the framebuffer at `base`, to share the chip with other
+    /// data
+    pub fn with_base_address(spi: SPI, cs: CS, base: u32) -> Self {
+        Self { spi, cs, base }
+    }
+
+    /// Return the bus and the chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    fn select<F, R, SpiE, PinE>(&mut self, transfer: F) -> Result<R, SpiRamError<SpiE, PinE>>
+    where
+        CS: OutputPin<Error = PinE>,
+        F: FnOnce(&mut SPI) -> Result<R, SpiE>,
+    {
+        self.cs.set_low().map_err(SpiRamError::Pin)?;
+        let result = transfer(&mut self.spi).map_err(SpiRamError::Spi);
+        self.cs.set_high().map_err(SpiRamError::Pin)?;
+
+        result
+    }
+}
+
+impl<SPI, CS, SpiE, PinE> ExternalRam for SpiRam<SPI, CS>
+where
+    SPI: Transfer<u8, Error = SpiE> + Write<u8, Error = SpiE>,
+    CS: OutputPin<Error = PinE>,
+{
+    type Error = SpiRamError<SpiE, PinE>;
+
+    fn read(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let [_, a2, a1, a0] = (self.base + address).to_be_bytes();
+
+        self.select(|spi| {
+            spi.write(&[0x03, a2, a1, a0])?;
+            spi.transfer(buffer).map(|_| ())
+        })
+    }
+
+    fn write(&mut self, address: u32, data: &[u8]) -> Result<(), Self::Error> {
+        let [_, a2, a1, a0] = (self.base + address).to_be_bytes();
+
+        self.select(|spi| {
+            spi.write(&[0x02, a2, a1, a0])?;
+            spi.write(data)
+        })
+    }
+}
+
+/// External RAM framebuffer mode handler, see the [module documentation](self)
+pub struct ExternalMode<DI, RAM>
+where
+    DI: WriteOnlyDataCommand,
+{
+    properties: DisplayProperties<DI>,
+    ram: RAM,
+    /// The working buffer, holding page `cached` of the framebuffer
+    page: [u8; 160],
+    cached: Option<u8>,
+    /// Whether `page` changed since it was read from the external RAM
+    modified: bool,
+}
+
+impl<DI, RAM> ExternalMode<DI, RAM>
+where
+    DI: WriteOnlyDataCommand,
+    RAM: ExternalRam,
+{
+    /// Create the mode with the framebuffer in `ram`. Nothing is read or written before the
+    /// first `clear`, so call `init` first to start from an empty frame.
+    pub fn new(properties: DisplayProperties<DI>, ram: RAM) -> Self {
+        ExternalMode {
+            properties,
+            ram,
+            page: [0; 160],
+            cached: None,
+            modified: false,
+        }
+    }
+
+    /// Destroy the mode and return the display properties and the external RAM
+    pub fn into_parts(self) -> (DisplayProperties<DI>, RAM) {
+        (self.properties, self.ram)
+    }
+
+    /// Initialise the display and clear the framebuffer. The display RAM is only cleared by the
+    /// next `flush`.
+    pub fn init(&mut self) -> Result<(), ExternalError<RAM::Error>> {
+        self.properties
+            .init_column_mode()
+            .map_err(ExternalError::Display)?;
+        self.clear()
+    }
+
+    /// Clear the framebuffer in the external RAM. You need to call `flush` for any effect on the
+    /// screen.
+    pub fn clear(&mut self) -> Result<(), ExternalError<RAM::Error>> {
+        let (width, height) = self.properties.get_size().dimensions();
+
+        self.page = [0; 160];
+        for index in 0..height / 8 {
+            self.ram
+                .write(self.address(index), &self.page[..width.into()])
+                .map_err(ExternalError::Ram)?;
+        }
+        self.cached = Some(0);
+        self.modified = false;
+
+        Ok(())
+    }
+
+    /// Turn the pixel at `x` and `y` on or off, in display coordinates taking rotation into
+    /// account. Pixels outside the display are ignored.
+    pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) -> Result<(), ExternalError<RAM::Error>> {
+        let (width, height) = self.properties.get_size().dimensions();
+
+        // Like the framebuffer of `GraphicsMode`, the frame follows the panel and rotation only
+        // swaps the axes
+        let (column, row) = match self.properties.get_rotation() {
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
+        };
+
+        if column >= u32::from(width) || row >= u32::from(height) {
+            return Ok(());
+        }
+
+        self.load(row as u8 / 8)?;
+        let byte = &mut self.page[column as usize];
+        let bit = 1 << (row % 8);
+        if on {
+            *byte |= bit;
+        } else {
+            *byte &= !bit;
+        }
+        self.modified = true;
+
+        Ok(())
+    }
+
+    /// Write the whole framebuffer to the display, reading it from the external RAM one page at a
+    /// time
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush(&mut self) -> Result<FlushStats, ExternalError<RAM::Error>> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+
+        let display_size = self.properties.get_size();
+        let (width, height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+        let pages = height / 8;
+
+        self.properties
+            .set_draw_area_unchecked((column_offset, 0), (column_offset + width, pages))
+            .map_err(ExternalError::Display)?;
+        for index in 0..pages {
+            self.load(index)?;
+            self.properties
+                .draw(&self.page[..width.into()])
+                .map_err(ExternalError::Display)?;
+        }
+
+        Ok(FlushStats {
+            pages: pages.into(),
+            bytes: usize::from(pages) * usize::from(width),
+        })
+    }
+
+    /// Set the display rotation. Switching between a landscape and a portrait orientation
+    /// scrambles the frame, so clear and redraw it.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.properties.set_rotation(rot)
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.display_on(on)
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.properties.set_contrast(contrast)
+    }
+
+    /// Address of page `index` in the external RAM
+    fn address(&self, index: u8) -> u32 {
+        let (width, _) = self.properties.get_size().dimensions();
+
+        u32::from(index) * u32::from(width)
+    }
+
+    /// Make page `index` the one in the working buffer, writing the previous one back if it
+    /// changed
+    fn load(&mut self, index: u8) -> Result<(), ExternalError<RAM::Error>> {
+        if self.cached == Some(index) {
+            return Ok(());
+        }
+
+        let width = usize::from(self.properties.get_size().dimensions().0);
+        if let (Some(cached), true) = (self.cached, self.modified) {
+            self.ram
+                .write(self.address(cached), &self.page[..width])
+                .map_err(ExternalError::Ram)?;
+        }
+
+        // Forget the old page first, so a failed read is retried on the next access
+        self.cached = None;
+        self.modified = false;
+        let address = self.address(index);
+        self.ram
+            .read(address, &mut self.page[..width])
+            .map_err(ExternalError::Ram)?;
+        self.cached = Some(index);
+
+        Ok(())
+    }
+}
+
+impl<DI, RAM> DrawTarget for ExternalMode<DI, RAM>
+where
+    DI: WriteOnlyDataCommand,
+    RAM: ExternalRam,
+{
+    type Color = BinaryColor;
+    type Error = ExternalError<RAM::Error>;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(pos.x), u32::try_from(pos.y)) {
+                self.set_pixel(x, y, color.is_on())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<DI, RAM> OriginDimensions for ExternalMode<DI, RAM>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        let (width, height) = self.properties.get_dimensions();
+
+        Size::new(width.into(), height.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        displaysize::DisplaySize, mode::GraphicsMode, test_helpers::CaptureInterface, Builder,
+    };
+    use core::convert::Infallible;
+    use embedded_graphics::{
+        prelude::*,
+        primitives::{Circle, PrimitiveStyle},
+    };
+    use std::vec::Vec;
+
+    struct VecRam {
+        bytes: Vec<u8>,
+    }
+
+    impl ExternalRam for VecRam {
+        type Error = Infallible;
+
+        fn read(&mut self, address: u32, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            let address = address as usize;
+            buffer.copy_from_slice(&self.bytes[address..address + buffer.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, address: u32, data: &[u8]) -> Result<(), Self::Error> {
+            let address = address as usize;
+            self.bytes[address..address + data.len()].copy_from_slice(data);
+            Ok(())
+        }
+    }
+
+    fn scene<D: DrawTarget<Color = BinaryColor>>(target: &mut D) {
+        let _ = Circle::new(Point::new(10, 5), 50)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 3))
+            .draw(target);
+        let _ = Pixel(Point::new(127, 159), BinaryColor::On).draw(target);
+    }
+
+    #[test]
+    fn frame_streamed_from_external_ram_matches_graphics_mode() {
+        let full = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(full.clone()).into();
+        display.init().unwrap();
+        scene(&mut display);
+        full.clear();
+        display.flush().unwrap();
+
+        let external = CaptureInterface::new();
+        let properties = DisplayProperties::new(
+            external.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let ram = VecRam {
+            bytes: std::vec![0xAA; 4096],
+        };
+        let mut display = ExternalMode::new(properties, ram);
+        display.init().unwrap();
+        scene(&mut display);
+        external.clear();
+        let stats = display.flush().unwrap();
+
+        assert_eq!((stats.pages, stats.bytes), (20, 2560));
+        assert_eq!(external.data(), full.data());
+        let (_, ram) = display.into_parts();
+        assert!(ram.bytes[2560..].iter().all(|&byte| byte == 0xAA));
+        assert_eq!(ram.bytes[19 * 128 + 127], 0x80);
+    }
+}
@@ -15,35 +15,222 @@
 //! display.flush().unwrap();
 //! ```
 
+use core::{cell::Cell, marker::PhantomData};
+
 use display_interface::{DisplayError, WriteOnlyDataCommand};
-use hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
+use hal::{
+    blocking::delay::{DelayMs, DelayUs},
+    digital::v2::OutputPin,
+};
 
+#[cfg(feature = "tiny-text")]
+use crate::font::{Font5x8, TerminalFont};
 use crate::{
-    displayrotation::DisplayRotation, mode::displaymode::DisplayModeTrait,
-    properties::DisplayProperties,
+    brightness::Brightness,
+    command::Command,
+    diff::frame_diff,
+    displayrotation::{DisplayRotation, RotationMode, RuntimeRotation},
+    displaysize::DisplaySize,
+    dma::{DmaFlush, DmaInterface, DmaPage},
+    error::{Error, InitError, VerifyError},
+    mode::{
+        displaymode::{DisplayModeTrait, DisplayTuning},
+        raw::RawMode,
+        split::{RegionMut, Shared},
+        storage::FrameBufferStorage,
+        terminal::TerminalMode,
+    },
+    properties::{DisplayProperties, FlushEvent, TestPattern},
+    read::ReadInterface,
+    rle::RleDecoder,
+    sprite::{Sprite, SpriteSheet},
 };
 
-const BUFFER_SIZE: usize = 160 * 160 / 8;
+/// Framebuffer size in bytes that fits every supported display size
+pub const MAX_BUFFER_SIZE: usize = 160 * 160 / 8;
+
+/// Largest number of pages of any supported display size
+const MAX_PAGES: usize = 160 / 8;
+
+/// Bytes in a packed 1bpp row as wide as the widest display
+#[cfg(feature = "bmp")]
+const MAX_ROW_BYTES: usize = 160 / 8;
+
+/// Dirty column range of a page that has not been touched since the last flush
+const CLEAN: (u8, u8) = (u8::MAX, 0);
+
+/// 4x4 ordered dither (Bayer) matrix, indexed by `[row % 4][column % 4]`
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Memory layout of the [`GraphicsMode`] framebuffer
+///
+/// The SH1108 display RAM is organised in pages: every byte holds a column of 8 vertically
+/// stacked pixels. Keeping the framebuffer in the same layout makes `flush` a straight copy, but
+/// some image formats and drawing code are easier to reason about row by row. `RowMajor` trades
+/// a transposition of every page at flush time for that simpler layout. `DisplayRows` keeps the
+/// rows of the rotated view instead, which on a display rotated by 90 or 270 degrees lines up
+/// with the pages again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FramebufferLayout {
+    /// Each byte holds 8 vertically stacked pixels with the least significant bit at the top,
+    /// matching the display RAM. This is the default.
+    VerticalPages,
+    /// Each byte holds 8 horizontally adjacent pixels with the most significant bit on the left,
+    /// and rows follow each other top to bottom. `flush` transposes the data into pages before
+    /// sending it, which costs some CPU time per frame.
+    RowMajor,
+    /// Like `RowMajor`, but with the rows of the display as seen after rotation rather than those
+    /// of the panel. Unrotated, the two are the same. Rotated by 90 or 270 degrees, a row of the
+    /// display runs down a column of the panel, so `flush` takes every display RAM byte from a
+    /// single framebuffer byte without transposing, and `draw_bitmap` and `flush_framebuffer`
+    /// copy whole bytes in every rotation. This suits text heavy UIs on rotated displays.
+    ///
+    /// Switching between a landscape and a portrait orientation with `set_rotation` changes the
+    /// length of the rows, which scrambles the framebuffer. Use `set_rotation_remap` or redraw.
+    DisplayRows,
+}
+
+/// Dithering algorithm used by [`GraphicsMode::draw_gray_image`]
+#[cfg(feature = "graphics")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Dither {
+    /// Compare every pixel against a 4x4 ordered (Bayer) pattern anchored to display coordinates.
+    /// This is fast and keeps a regular texture, which suits gradients and anti-aliased edges.
+    Ordered,
+    /// Floyd-Steinberg error diffusion, which spreads the error of every pixel to its neighbours.
+    /// This keeps more detail in photos, but a small change to the image can change the pattern
+    /// everywhere below it.
+    FloydSteinberg,
+}
+
+/// How [`GraphicsMode::draw_bitmap_with`] combines a bitmap with the framebuffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BlitOp {
+    /// Replace the pixels, like `draw_bitmap`
+    Copy,
+    /// Turn on the pixels that are on in the bitmap and leave the others, for transparent
+    /// overlays
+    Or,
+    /// Turn off the pixels that are off in the bitmap and leave the others, for cutting out the
+    /// mask of a sprite before drawing it with `Or`
+    And,
+    /// Swap the pixels that are on in the bitmap, for cursors that are removed by drawing them
+    /// again
+    Xor,
+}
+
+impl BlitOp {
+    /// Combine the framebuffer byte `dest` with the bits of `src` selected by `mask`
+    fn apply(self, dest: u8, src: u8, mask: u8) -> u8 {
+        match self {
+            BlitOp::Copy => (dest & !mask) | (src & mask),
+            BlitOp::Or => dest | (src & mask),
+            BlitOp::And => dest & (src | !mask),
+            BlitOp::Xor => dest ^ (src & mask),
+        }
+    }
+}
+
+/// Summary of the framebuffer contents, see [`GraphicsMode::framebuffer_stats`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FbStats {
+    /// Number of pixels that are turned on
+    pub lit_pixels: u32,
+    /// Smallest box containing all lit pixels as `(top_left, bottom_right)` in display
+    /// coordinates, with the bottom right corner exclusive. `None` if no pixel is lit.
+    pub bounding_box: Option<((u32, u32), (u32, u32))>,
+    /// Share of the display that is lit, in percent rounded down
+    pub coverage_percent: u8,
+}
+
+/// What a flush sent, returned by [`GraphicsMode::flush`] and [`GraphicsMode::flush_dirty`],
+/// e.g. to pace frames or to report how busy the bus is
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FlushStats {
+    /// Number of display RAM pages that were sent, completely or in part
+    pub pages: usize,
+    /// Number of bytes of pixel data that were sent, not counting address commands
+    pub bytes: usize,
+}
+
+/// Where [`GraphicsMode::verify`] found the display RAM to differ from the framebuffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Mismatch {
+    /// Number of pages with at least one differing byte
+    pub pages: usize,
+    /// Number of differing bytes
+    pub bytes: usize,
+    /// Page and panel column of the first differing byte
+    pub first: (u8, u8),
+}
+
+impl FlushStats {
+    /// Whether anything was sent at all. A `flush_dirty` without changes sends nothing.
+    pub fn changed(&self) -> bool {
+        self.bytes > 0
+    }
+}
 
 /// Graphics mode handler
-pub struct GraphicsMode<DI>
+///
+/// `N` is the size of the framebuffer in bytes. It defaults to [`MAX_BUFFER_SIZE`], which fits
+/// every display size. Smaller displays can save RAM with an exactly sized framebuffer, see
+/// [`DisplaySize::framebuffer_size`](crate::prelude::DisplaySize::framebuffer_size):
+///
+/// ```rust,ignore
+/// let display: GraphicsMode<_, { DisplaySize::Display64x160.framebuffer_size() }> = Builder::new()
+///     .with_size(DisplaySize::Display64x160)
+///     .connect(interface)
+///     .into();
+/// ```
+///
+/// The framebuffer is stored inline, so it lives wherever the `GraphicsMode` value is placed. To
+/// put it in a particular RAM region, e.g. one reachable by DMA, store the display in a static in
+/// that region, like one created with `cortex_m::singleton!` or given a `#[link_section]`. `S`
+/// keeps it elsewhere instead, e.g. in a borrowed slice, see [`storage`](crate::mode::storage).
+///
+/// `R` selects whether the rotation is set at runtime, the default, or fixed at compile time with
+/// one of the [`RotationMode`] types. A fixed rotation drops the per-pixel rotation check and
+/// `set_rotation`, and overrides the rotation given to the builder:
+///
+/// ```rust,ignore
+/// let display: GraphicsMode<_, MAX_BUFFER_SIZE, FixedRotate0> =
+///     Builder::new().connect(interface).into();
+/// ```
+pub struct GraphicsMode<DI, const N: usize = MAX_BUFFER_SIZE, R = RuntimeRotation, S = [u8; N]>
 where
     DI: WriteOnlyDataCommand,
+    R: RotationMode,
 {
     properties: DisplayProperties<DI>,
-    buffer: [u8; BUFFER_SIZE],
+    buffer: S,
+    layout: FramebufferLayout,
+    clip: Option<((u32, u32), (u32, u32))>,
+    /// Columns `start..end` of each page that changed since the last flush
+    dirty: [(u8, u8); MAX_PAGES],
+    /// Columns `start..end` of each display RAM page that `flush_chunked` still has to send
+    pending: [(u8, u8); MAX_PAGES],
+    /// Pixels drawn outside the display since the last `reset_out_of_bounds`
+    out_of_bounds: u32,
+    strict_bounds: bool,
+    /// Pixels set through [`RegionMut`]s, which cannot reach the metrics of `properties`
+    region_pixels: Cell<u32>,
+    rotation: PhantomData<R>,
 }
 
-impl<DI> DisplayModeTrait<DI> for GraphicsMode<DI>
+impl<DI, const N: usize, R: RotationMode> DisplayModeTrait<DI> for GraphicsMode<DI, N, R>
 where
     DI: WriteOnlyDataCommand,
 {
     /// Create new GraphicsMode instance
+    ///
+    /// Panics if the framebuffer is too small for the display size.
     fn new(properties: DisplayProperties<DI>) -> Self {
-        GraphicsMode {
-            properties,
-            buffer: [0; BUFFER_SIZE],
-        }
+        Self::from_parts(properties, [0; N])
     }
 
     /// Release all resources used by GraphicsMode
@@ -52,13 +239,320 @@ where
     }
 }
 
-impl<DI> GraphicsMode<DI>
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> DisplayTuning<DI>
+    for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn properties_mut(&mut self) -> &mut DisplayProperties<DI> {
+        &mut self.properties
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode> GraphicsMode<DI, N, R>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create the mode from `properties` set up by hand, e.g. after a custom init sequence or
+    /// with non-standard addressing, to use the drawing methods on top. If the display was
+    /// initialised from outside the driver, record that with
+    /// [`DisplayProperties::assume_initialized`]. The framebuffer starts out cleared.
+    ///
+    /// Panics if the framebuffer is too small for the display size.
+    pub fn from_properties(properties: DisplayProperties<DI>) -> Self {
+        <Self as DisplayModeTrait<DI>>::new(properties)
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
 where
     DI: WriteOnlyDataCommand,
 {
+    /// Create the mode with the framebuffer kept in `storage` instead of inline, see
+    /// [`storage`](crate::mode::storage). The storage is cleared.
+    ///
+    /// Panics if the storage is too small for the display size.
+    pub fn with_storage(properties: DisplayProperties<DI>, mut storage: S) -> Self {
+        storage.bytes_mut().fill(0);
+        Self::from_parts(properties, storage)
+    }
+
+    /// Exchange the framebuffer with `other`, for [`DoubleBuffered`](crate::mode::DoubleBuffered).
+    /// Everything is marked as changed, as the new contents are not what the display shows.
+    pub(crate) fn swap_framebuffer(&mut self, other: &mut S) {
+        core::mem::swap(&mut self.buffer, other);
+        self.mark_all_dirty();
+    }
+
+    fn from_parts(mut properties: DisplayProperties<DI>, buffer: S) -> Self {
+        assert!(
+            buffer.bytes().len() >= properties.get_size().framebuffer_size(),
+            "framebuffer too small for the display size"
+        );
+
+        // A fixed rotation is only sent by `init`, so a display that was initialised with a
+        // different one has to be initialised again
+        if let Some(rotation) = R::FIXED {
+            if properties.get_rotation() != rotation {
+                properties.set_initial_rotation(rotation);
+                properties.mark_uninitialized();
+            }
+        }
+
+        GraphicsMode {
+            properties,
+            buffer,
+            layout: FramebufferLayout::VerticalPages,
+            clip: None,
+            dirty: [(0, u8::MAX); MAX_PAGES],
+            pending: [CLEAN; MAX_PAGES],
+            out_of_bounds: 0,
+            strict_bounds: false,
+            region_pixels: Cell::new(0),
+            rotation: PhantomData,
+        }
+    }
+
+    /// Give up the framebuffer and return the display properties, e.g. to send a custom
+    /// sequence and then continue with [`from_properties`](Self::from_properties). The display
+    /// RAM keeps showing the last flushed frame.
+    pub fn into_properties(self) -> DisplayProperties<DI> {
+        self.properties
+    }
+
+    /// Switch back to [`RawMode`], freeing the framebuffer. The display RAM keeps showing the
+    /// last flushed frame.
+    pub fn into_raw_mode(self) -> RawMode<DI> {
+        RawMode::new(self.properties)
+    }
+
+    /// Switch to the unbuffered [`TerminalMode`], freeing the framebuffer. The interface and
+    /// display settings are kept, and text is printed over the last flushed frame until the
+    /// terminal is cleared.
+    pub fn into_terminal_mode(self) -> TerminalMode<DI> {
+        TerminalMode::new(self.properties)
+    }
+
+    /// Destroy the driver and return the interface, e.g. to share the bus with other devices or
+    /// to create the driver again with different settings. The display keeps showing the last
+    /// flushed frame.
+    pub fn release_interface(self) -> DI {
+        self.properties.release()
+    }
+
+    /// Destroy the mode and return the display properties together with the framebuffer storage
+    pub fn into_parts(self) -> (DisplayProperties<DI>, S) {
+        (self.properties, self.buffer)
+    }
+
     /// Clear the display buffer. You need to call `disp.flush()` for any effect on the screen
     pub fn clear(&mut self) {
-        self.buffer = [0; BUFFER_SIZE];
+        self.buffer.bytes_mut().fill(0);
+        self.mark_all_dirty();
+    }
+
+    pub(crate) fn mark_all_dirty(&mut self) {
+        self.dirty = [(0, u8::MAX); MAX_PAGES];
+    }
+
+    /// Get the memory layout of the framebuffer
+    pub fn get_framebuffer_layout(&self) -> FramebufferLayout {
+        self.layout
+    }
+
+    /// Change the memory layout of the framebuffer. See [`FramebufferLayout`] for the tradeoffs.
+    ///
+    /// Changing the layout clears the framebuffer.
+    pub fn set_framebuffer_layout(&mut self, layout: FramebufferLayout) {
+        self.layout = layout;
+        self.clear();
+    }
+
+    /// The part of the framebuffer used by the display size, in the current layout. For DMA or
+    /// checksums, e.g. to skip a flush when nothing changed.
+    ///
+    /// The framebuffer always follows the panel, before rotation, so a 128x160 display has 128
+    /// columns and 160 rows here whatever the rotation. With the default
+    /// [`FramebufferLayout::VerticalPages`] it holds one page of 8 rows after the other, each
+    /// page one byte per column from left to right, with the topmost row of the page in the
+    /// least significant bit. The pixel at column `x` and row `y` is therefore bit `y % 8` of
+    /// byte `y / 8 * width + x`. With [`FramebufferLayout::RowMajor`] it holds one row after the
+    /// other instead, 8 pixels per byte with the leftmost in the most significant bit, so the
+    /// pixel is bit `7 - x % 8` of byte `y * width / 8 + x / 8`. [`FramebufferLayout::DisplayRows`]
+    /// is the exception to following the panel: it holds the rows of the rotated view the same
+    /// way, with `x`, `y` and `width` in display coordinates.
+    pub fn framebuffer(&self) -> &[u8] {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        &self.buffer.bytes()[..display_width as usize * display_height as usize / 8]
+    }
+
+    /// The framebuffer as a mutable slice, laid out as described for `framebuffer`, so
+    /// compositors and asset pipelines can write to it directly and `flush` the result. The
+    /// whole framebuffer is marked as changed for `flush_dirty`.
+    pub fn framebuffer_mut(&mut self) -> &mut [u8] {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        self.mark_all_dirty();
+
+        &mut self.buffer.bytes_mut()[..display_width as usize * display_height as usize / 8]
+    }
+
+    /// The bytes of page `index` exactly as `flush` sends them to the display RAM, as one
+    /// contiguous slice of the framebuffer.
+    ///
+    /// Returns `None` if the page does not exist, or if the page is not stored as is because the
+    /// layout is not [`FramebufferLayout::VerticalPages`] or a pixel shift is set.
+    pub fn framebuffer_page(&self, index: u8) -> Option<&[u8]> {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        if index >= display_height / 8
+            || self.layout != FramebufferLayout::VerticalPages
+            || self.properties.get_pixel_shift() != (0, 0)
+        {
+            return None;
+        }
+
+        let width = display_width as usize;
+        Some(&self.buffer.bytes()[index as usize * width..][..width])
+    }
+
+    /// Split the framebuffer into the rows above `y` and the rows from `y` down, as two borrowed
+    /// [`RegionMut`]s that can be drawn to side by side, see the [`split`](crate::mode::split)
+    /// module. `flush_dirty` sends what changed in both once they are dropped.
+    ///
+    /// Returns `Error::OutOfBounds` if `y` is not a multiple of 8 within the display, or if the
+    /// display is rotated by 90 or 270 degrees, where rows of the display cross the pages of the
+    /// panel. Use `split_columns_mut` there.
+    pub fn split_rows_mut(&mut self, y: u32) -> Result<(RegionMut<'_>, RegionMut<'_>), Error> {
+        let (width, height) = self.get_dimensions();
+        let (width, height) = (u32::from(width), u32::from(height));
+
+        if R::swaps_axes(self.properties.get_rotation()) || !y.is_multiple_of(8) || y > height {
+            return Err(Error::OutOfBounds);
+        }
+
+        Ok(self.split_pages(y, ((0, 0), (width, y)), ((0, y), (width, height - y))))
+    }
+
+    /// Split the framebuffer into the columns left of `x` and the columns from `x` on, as two
+    /// borrowed [`RegionMut`]s that can be drawn to side by side, see `split_rows_mut`.
+    ///
+    /// Returns `Error::OutOfBounds` if `x` is not a multiple of 8 within the display, or if the
+    /// display is not rotated by 90 or 270 degrees, where columns of the display cross the pages
+    /// of the panel. The [`FramebufferLayout::DisplayRows`] layout cannot be split this way.
+    pub fn split_columns_mut(&mut self, x: u32) -> Result<(RegionMut<'_>, RegionMut<'_>), Error> {
+        let (width, height) = self.get_dimensions();
+        let (width, height) = (u32::from(width), u32::from(height));
+
+        if !R::swaps_axes(self.properties.get_rotation())
+            || self.layout == FramebufferLayout::DisplayRows
+            || !x.is_multiple_of(8)
+            || x > width
+        {
+            return Err(Error::OutOfBounds);
+        }
+
+        Ok(self.split_pages(x, ((0, 0), (x, height)), ((x, 0), (width - x, height))))
+    }
+
+    /// Split the framebuffer and its change tracking before panel row `row`, a multiple of 8,
+    /// into regions covering `first` and `second` as `(origin, size)`
+    fn split_pages(
+        &mut self,
+        row: u32,
+        first: ((u32, u32), (u32, u32)),
+        second: ((u32, u32), (u32, u32)),
+    ) -> (RegionMut<'_>, RegionMut<'_>) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let panel_width = u32::from(display_width);
+        let length = display_width as usize * display_height as usize / 8;
+        let pages = usize::from(display_height / 8);
+
+        let offset = match self.layout {
+            FramebufferLayout::VerticalPages => row / 8 * panel_width,
+            FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => row * panel_width / 8,
+        };
+        let sideways = R::swaps_axes(self.properties.get_rotation());
+        let (first_buffer, second_buffer) =
+            self.buffer.bytes_mut()[..length].split_at_mut(offset as usize);
+        let (first_dirty, second_dirty) = self.dirty[..pages].split_at_mut(row as usize / 8);
+        let shared = Shared {
+            layout: self.layout,
+            panel_width,
+            sideways,
+            clip: self.clip,
+            pixels: &self.region_pixels,
+        };
+
+        (
+            RegionMut::new(first_buffer, first_dirty, shared, 0, first.0, first.1),
+            RegionMut::new(second_buffer, second_dirty, shared, row, second.0, second.1),
+        )
+    }
+
+    /// Count the lit pixels in the framebuffer and find their bounding box, in a single pass over
+    /// the buffer. This helps with checking what is being drawn, e.g. against a power budget.
+    pub fn framebuffer_stats(&self) -> FbStats {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let (width, height) = (u32::from(display_width), u32::from(display_height));
+        let length = (width * height / 8) as usize;
+
+        let mut lit_pixels = 0;
+        // Bounds of the lit pixels in panel (column, row) coordinates, both inclusive
+        let mut min = (u32::MAX, u32::MAX);
+        let mut max = (0, 0);
+
+        for (idx, &byte) in self.buffer.bytes()[..length].iter().enumerate() {
+            if byte == 0 {
+                continue;
+            }
+            lit_pixels += byte.count_ones();
+
+            let idx = idx as u32;
+            let (first, last) = match self.layout {
+                FramebufferLayout::DisplayRows if R::swaps_axes(self.properties.get_rotation()) => {
+                    // A row of the display is a column of the panel
+                    let (column, row) = (idx / (height / 8), idx % (height / 8) * 8);
+                    (
+                        (column, row + byte.leading_zeros()),
+                        (column, row + 7 - byte.trailing_zeros()),
+                    )
+                }
+                FramebufferLayout::VerticalPages => {
+                    let (column, row) = (idx % width, idx / width * 8);
+                    (
+                        (column, row + byte.trailing_zeros()),
+                        (column, row + 7 - byte.leading_zeros()),
+                    )
+                }
+                FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => {
+                    let (column, row) = (idx % (width / 8) * 8, idx / (width / 8));
+                    (
+                        (column + byte.leading_zeros(), row),
+                        (column + 7 - byte.trailing_zeros(), row),
+                    )
+                }
+            };
+
+            min = (min.0.min(first.0), min.1.min(first.1));
+            max = (max.0.max(last.0), max.1.max(last.1));
+        }
+
+        let bounding_box = (lit_pixels > 0).then(|| {
+            let (top_left, bottom_right) = (min, (max.0 + 1, max.1 + 1));
+
+            if R::swaps_axes(self.properties.get_rotation()) {
+                ((top_left.1, top_left.0), (bottom_right.1, bottom_right.0))
+            } else {
+                (top_left, bottom_right)
+            }
+        });
+
+        FbStats {
+            lit_pixels,
+            bounding_box,
+            coverage_percent: (lit_pixels * 100 / (width * height)) as u8,
+        }
     }
 
     /// Reset display. This is very important on the SH1108!
@@ -73,148 +567,4616 @@ where
         delay.delay_ms(1);
         rst.set_low()?;
         delay.delay_ms(1);
+        self.properties.mark_uninitialized();
         rst.set_high()?;
         delay.delay_ms(1);
         Ok(())
     }
 
+    /// Reset the display with `rst`, see [`GraphicsMode::reset`], then `init` it. This holds the
+    /// reset pin low for longer than the 10µs the datasheet requires and leaves the controller
+    /// time to come out of reset before the first command.
+    pub fn init_with_reset<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.reset(rst, delay).map_err(InitError::Pin)?;
+        self.init()?;
+
+        Ok(())
+    }
+
+    /// Reset and initialise the display with the delays of the datasheet power-on sequence, see
+    /// [`DisplayProperties::init_with_timing`] and [`Builder::with_power_timing`]
+    ///
+    /// [`Builder::with_power_timing`]: crate::Builder::with_power_timing
+    pub fn init_with_timing<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayUs<u32> + DelayMs<u16>,
+    {
+        self.properties.init_with_timing(rst, delay)?;
+
+        Ok(())
+    }
+
     /// Write out data to display
-    pub fn flush(&mut self) -> Result<(), DisplayError> {
+    ///
+    /// If a power budget is set with `Builder::with_power_budget`, the contrast is adjusted to the
+    /// number of lit pixels first, see [`DisplayProperties::apply_power_budget`].
+    ///
+    /// Every flush starts by setting the full draw area, so a flush that failed partway leaves at
+    /// most a partially updated frame on the screen and the next successful flush recovers it
+    /// completely.
+    ///
+    /// The controller has to be given a new page address for every page, so each page goes out
+    /// as one `send_data` call of the full display width. With the
+    /// [`FramebufferLayout::VerticalPages`] layout and no pixel shift, those are slices straight
+    /// out of the framebuffer, see `framebuffer_page`, so an interface that sends data with DMA
+    /// can transfer every page in one go without copying it first.
+    ///
+    /// Returns the number of pages and bytes sent, which for a full flush is always the whole
+    /// display.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush(&mut self) -> Result<FlushStats, DisplayError> {
+        self.flush_pages(&mut || {})
+    }
+
+    /// Like `flush`, but call `kick` before every page is sent, e.g. to feed a watchdog that a
+    /// full frame over a slow I2C bus would otherwise trip. The transfers are the same as with
+    /// `flush`.
+    pub fn flush_with_kick<F>(&mut self, mut kick: F) -> Result<FlushStats, DisplayError>
+    where
+        F: FnMut(),
+    {
+        self.flush_pages(&mut kick)
+    }
+
+    fn flush_pages(&mut self, kick: &mut dyn FnMut()) -> Result<FlushStats, DisplayError> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
         let display_size = self.properties.get_size();
 
         // Ensure the display buffer is at the origin of the display before we send the full frame
         // to prevent accidental offsets
         let (display_width, display_height) = display_size.dimensions();
         let column_offset = display_size.column_offset();
-        self.properties.set_draw_area(
+        self.properties.set_draw_area_unchecked(
             (column_offset, 0),
-            (display_width + column_offset, display_height),
+            (display_width + column_offset, display_height / 8),
         )?;
 
         let length = (display_width as usize) * (display_height as usize) / 8;
 
-        self.properties.draw(&self.buffer[..length])
-    }
+        let lit_pixels = self.buffer.bytes()[..length]
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum();
+        self.properties.apply_power_budget(lit_pixels)?;
 
-    /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
-    /// coordinates are out of the bounds of the display, this method call is a noop.
-    pub fn set_pixel(&mut self, x: u32, y: u32, value: u8) {
-        let (display_width, _) = self.properties.get_size().dimensions();
-        let display_rotation = self.properties.get_rotation();
+        self.dirty = [CLEAN; MAX_PAGES];
+        self.pending = [CLEAN; MAX_PAGES];
 
-        let idx = match display_rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => {
-                if x >= display_width as u32 {
-                    return;
+        self.properties.begin_flush();
+        match self.layout {
+            FramebufferLayout::VerticalPages if self.properties.get_pixel_shift() == (0, 0) => {
+                for page in self.buffer.bytes()[..length].chunks(display_width as usize) {
+                    kick();
+                    self.properties.draw(page)?;
                 }
-                ((y as usize) / 8 * display_width as usize) + (x as usize)
             }
+            _ => {
+                let mut page = [0u8; 160];
+                let page = &mut page[..display_width as usize];
 
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
-                if y >= display_width as u32 {
-                    return;
+                for index in 0..display_height as usize / 8 {
+                    self.page_bytes(index, 0, page);
+                    kick();
+                    self.properties.draw(page)?;
                 }
-                ((x as usize) / 8 * display_width as usize) + (y as usize)
             }
-        };
+        }
+        self.properties.end_flush();
 
-        if idx >= self.buffer.len() {
-            return;
+        Ok(FlushStats {
+            pages: usize::from(display_height / 8),
+            bytes: length,
+        })
+    }
+
+    /// Write out only the parts of the framebuffer that changed since the last flush.
+    ///
+    /// Changes are tracked as one range of columns per page, from the leftmost to the rightmost
+    /// changed column, so only the changed pages are sent and each of them only as wide as
+    /// needed. Methods that replace the whole framebuffer, like `clear` or `crossfade`, mark all
+    /// of it as changed. Use `flush` to send the complete frame regardless, e.g. after the display
+    /// RAM was lost.
+    ///
+    /// Returns the number of pages and bytes sent, see [`FlushStats::changed`] to tell whether
+    /// anything changed at all.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush_dirty(&mut self) -> Result<FlushStats, DisplayError> {
+        self.flush_dirty_pages(&mut || {})
+    }
+
+    /// Like `flush_dirty`, but call `kick` before every page is sent, see `flush_with_kick`
+    pub fn flush_dirty_with_kick<F>(&mut self, mut kick: F) -> Result<FlushStats, DisplayError>
+    where
+        F: FnMut(),
+    {
+        self.flush_dirty_pages(&mut kick)
+    }
+
+    fn flush_dirty_pages(&mut self, kick: &mut dyn FnMut()) -> Result<FlushStats, DisplayError> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let length = (display_width as usize) * (display_height as usize) / 8;
+
+        let lit_pixels = self.buffer.bytes()[..length]
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum();
+        self.properties.apply_power_budget(lit_pixels)?;
+
+        let dirty = self.unsent_spans();
+
+        let mut stats = FlushStats::default();
+        self.properties.begin_flush();
+        for index in 0..display_height / 8 {
+            let (start, end) = dirty[index as usize];
+            if start >= end {
+                continue;
+            }
+
+            kick();
+            self.send_page_span(index, start, end)?;
+            stats.pages += 1;
+            stats.bytes += usize::from(end - start);
         }
+        self.dirty = [CLEAN; MAX_PAGES];
+        self.pending = [CLEAN; MAX_PAGES];
+        self.properties.end_flush();
+
+        Ok(stats)
+    }
 
-        let (byte, bit) = match display_rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => {
-                let byte =
-                    &mut self.buffer[((y as usize) / 8 * display_width as usize) + (x as usize)];
-                let bit = 1 << (y % 8);
+    /// Like `flush_dirty`, but send at most `chunk_bytes` bytes of pixel data per call, so that a
+    /// screen update can be spread over several calls with other work in between. Returns
+    /// `Ok(true)` once everything that changed has been sent, and `Ok(false)` if it has to be
+    /// called again.
+    ///
+    /// The first call takes a snapshot of the changed parts of the framebuffer, the following
+    /// calls work through it. Drawing may continue in between: pixels that change in a part that
+    /// was not sent yet go out with the current round, and all changes since the snapshot are
+    /// sent by the next round. A `chunk_bytes` of 0 is treated as 1.
+    ///
+    /// ```rust,ignore
+    /// while !display.flush_chunked(128)? {
+    ///     yield_now().await;
+    /// }
+    /// ```
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush_chunked(&mut self, chunk_bytes: usize) -> Result<bool, DisplayError> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let pages = usize::from(display_height / 8);
+
+        let is_clean = |spans: &[(u8, u8)]| spans.iter().all(|&(start, end)| start >= end);
+        if is_clean(&self.pending[..pages]) {
+            let length = (display_width as usize) * (display_height as usize) / 8;
+            let lit_pixels = self.buffer.bytes()[..length]
+                .iter()
+                .map(|byte| byte.count_ones())
+                .sum();
+            self.properties.apply_power_budget(lit_pixels)?;
 
-                (byte, bit)
+            self.pending = self.shifted_dirty();
+            for (_, end) in &mut self.pending {
+                *end = (*end).min(display_width);
             }
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
-                let byte =
-                    &mut self.buffer[((x as usize) / 8 * display_width as usize) + (y as usize)];
-                let bit = 1 << (x % 8);
+            self.dirty = [CLEAN; MAX_PAGES];
 
-                (byte, bit)
+            if !is_clean(&self.pending[..pages]) {
+                self.properties.begin_flush();
             }
-        };
+        }
 
-        if value == 0 {
-            *byte &= !bit;
-        } else {
-            *byte |= bit;
+        let mut budget = chunk_bytes.max(1);
+        for index in 0..pages {
+            let (start, end) = self.pending[index];
+            if start >= end {
+                continue;
+            }
+            if budget == 0 {
+                break;
+            }
+
+            let chunk_end = end.min(start.saturating_add(budget.min(255) as u8));
+            self.send_page_span(index as u8, start, chunk_end)?;
+            self.pending[index] = if chunk_end < end {
+                (chunk_end, end)
+            } else {
+                CLEAN
+            };
+            budget -= usize::from(chunk_end - start);
         }
-    }
 
-    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
-    /// column 0 on the left, to column _n_ on the right
-    pub fn init(&mut self) -> Result<(), DisplayError> {
-        self.properties.init_column_mode()
-    }
+        let done = is_clean(&self.pending[..pages]);
+        if done {
+            self.properties.end_flush();
+        }
 
-    /// Get display dimensions, taking into account the current rotation of the display
-    pub fn get_dimensions(&self) -> (u8, u8) {
-        self.properties.get_dimensions()
+        Ok(done)
     }
 
-    /// Set the display rotation
-    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
-        self.properties.set_rotation(rot)
+    /// The columns of each page of the display RAM `flush_dirty` sends, i.e. the changed ones
+    /// and whatever an unfinished `flush_chunked` did not get to yet
+    fn unsent_spans(&self) -> [(u8, u8); MAX_PAGES] {
+        let (display_width, _) = self.properties.get_size().dimensions();
+
+        let mut dirty = self.shifted_dirty();
+        for ((start, end), &(pending_start, pending_end)) in dirty.iter_mut().zip(&self.pending) {
+            *start = (*start).min(pending_start);
+            *end = (*end).max(pending_end).min(display_width);
+        }
+
+        dirty
     }
 
-    /// Turn the display on or off. The display can be drawn to and retains all
-    /// of its memory even while off.
-    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
-        self.properties.display_on(on)
+    /// The changed columns of each page of the display RAM, i.e. the dirty spans of the
+    /// framebuffer moved by the pixel shift
+    fn shifted_dirty(&self) -> [(u8, u8); MAX_PAGES] {
+        let (dx, dy) = self.properties.get_pixel_shift();
+        if (dx, dy) == (0, 0) {
+            return self.dirty;
+        }
+
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let (width, pages) = (i32::from(display_width), i32::from(display_height / 8));
+        let mut shifted = [CLEAN; MAX_PAGES];
+
+        for (page, &(start, end)) in (0..pages).zip(self.dirty.iter()) {
+            let start = (i32::from(start) + i32::from(dx)).clamp(0, width);
+            let end = (i32::from(end.min(display_width)) + i32::from(dx)).clamp(0, width);
+            if start >= end {
+                continue;
+            }
+
+            // The 8 rows of a page end up in one or two pages once shifted
+            let top = (page * 8 + i32::from(dy)).div_euclid(8);
+            let bottom = (page * 8 + 7 + i32::from(dy)).div_euclid(8);
+            for target in top.max(0)..=bottom.min(pages - 1) {
+                let (target_start, target_end) = &mut shifted[target as usize];
+                *target_start = (*target_start).min(start as u8);
+                *target_end = (*target_end).max(end as u8);
+            }
+        }
+
+        shifted
     }
 
-    /// Set the display contrast
-    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
-        self.properties.set_contrast(contrast)
+    /// Send columns `start..end` of page `index` to the display
+    fn send_page_span(&mut self, index: u8, start: u8, end: u8) -> Result<(), DisplayError> {
+        let column_offset = self.properties.get_size().column_offset();
+
+        let mut page = [0u8; 160];
+        let page = &mut page[..(end - start) as usize];
+        self.page_bytes(index.into(), start.into(), page);
+
+        self.properties.set_draw_area_unchecked(
+            (column_offset + start, index),
+            (column_offset + end, index + 1),
+        )?;
+        self.properties.draw(page)
     }
-}
 
-#[cfg(feature = "graphics")]
-use embedded_graphics_core::{
-    draw_target::DrawTarget,
-    geometry::{Dimensions, OriginDimensions, Size},
-    pixelcolor::BinaryColor,
-    Pixel,
-};
+    /// Write out only the bytes that differ from `shadow`, a copy of what the display RAM holds,
+    /// and update `shadow` to match.
+    ///
+    /// Unlike `flush_dirty`, which sends every column between the leftmost and rightmost change
+    /// of a page, this compares the frame byte by byte with [`frame_diff`], so pixels that were
+    /// drawn over with the same value are not sent again at all. This suits interfaces that
+    /// redraw the whole frame but only change a few digits of it. `shadow` must be in the display
+    /// RAM layout regardless of the framebuffer layout, i.e. hold `width * height / 8` bytes. To
+    /// start with a known state, `clear` and `flush` the display and fill `shadow` with zeros.
+    ///
+    /// Returns `Error::BufferTooSmall` if `shadow` is shorter than the frame.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush_with_shadow(&mut self, shadow: &mut [u8]) -> Result<(), Error> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+        let display_size = self.properties.get_size();
+        let (display_width, display_height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+        let width = display_width as usize;
+        let length = width * (display_height as usize) / 8;
 
-#[cfg(feature = "graphics")]
-impl<DI> DrawTarget for GraphicsMode<DI>
-where
-    DI: WriteOnlyDataCommand,
-{
-    type Color = BinaryColor;
-    type Error = DisplayError;
+        if shadow.len() < length {
+            return Err(Error::BufferTooSmall);
+        }
 
-    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
-    where
-        I: IntoIterator<Item = Pixel<Self::Color>>,
-    {
-        let bb = self.bounding_box();
+        let lit_pixels = self.buffer.bytes()[..length]
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum();
+        self.properties.apply_power_budget(lit_pixels)?;
 
-        pixels
-            .into_iter()
-            .filter(|Pixel(pos, _color)| bb.contains(*pos))
-            .for_each(|Pixel(pos, color)| {
-                self.set_pixel(pos.x as u32, pos.y as u32, color.is_on().into())
-            });
+        self.properties.begin_flush();
+        for index in 0..display_height / 8 {
+            let mut page = [0u8; 160];
+            let page = &mut page[..width];
+            self.page_bytes(index.into(), 0, page);
+
+            let sent = &mut shadow[index as usize * width..][..width];
+            for span in frame_diff(sent, page, display_width) {
+                self.properties.set_draw_area_unchecked(
+                    (column_offset + span.start, index),
+                    (column_offset + span.end, index + 1),
+                )?;
+                self.properties
+                    .draw(&page[span.start as usize..span.end as usize])?;
+            }
+            sent.copy_from_slice(page);
+        }
+
+        self.dirty = [CLEAN; MAX_PAGES];
+        self.pending = [CLEAN; MAX_PAGES];
+        self.properties.end_flush();
 
         Ok(())
     }
-}
 
-#[cfg(feature = "graphics")]
-impl<DI> OriginDimensions for GraphicsMode<DI>
-where
-    DI: WriteOnlyDataCommand,
-{
+    /// Fill `out` with the display RAM bytes of page `index`, starting at column `start`
+    fn page_bytes(&self, index: usize, start: usize, out: &mut [u8]) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        let (dx, dy) = self.properties.get_pixel_shift();
+        if (dx, dy) != (0, 0) {
+            for (column, byte) in (start..).zip(out.iter_mut()) {
+                *byte = 0;
+                for bit in 0..8 {
+                    let src_column = column as i32 - i32::from(dx);
+                    let src_row = (index * 8 + bit) as i32 - i32::from(dy);
+
+                    if (0..i32::from(display_width)).contains(&src_column)
+                        && (0..i32::from(display_height)).contains(&src_row)
+                    {
+                        let (idx, mask) = self.panel_bit(src_column as u32, src_row as u32);
+                        if self.buffer.bytes()[idx] & mask != 0 {
+                            *byte |= 1 << bit;
+                        }
+                    }
+                }
+            }
+            return;
+        }
+
+        let display_width = display_width as usize;
+
+        match self.layout {
+            FramebufferLayout::VerticalPages => {
+                let offset = index * display_width + start;
+                out.copy_from_slice(&self.buffer.bytes()[offset..offset + out.len()]);
+            }
+            FramebufferLayout::DisplayRows if R::swaps_axes(self.properties.get_rotation()) => {
+                // Each column of the page is a row of the display, and its byte at `index` covers
+                // the 8 rows of the page with the top one in the most significant bit
+                let row_bytes = display_height as usize / 8;
+
+                for (column, byte) in (start..).zip(out.iter_mut()) {
+                    *byte = self.buffer.bytes()[column * row_bytes + index].reverse_bits();
+                }
+            }
+            FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => {
+                let row_bytes = display_width / 8;
+                let rows = &self.buffer.bytes()[index * 8 * row_bytes..];
+
+                for (column, byte) in (start..).zip(out.iter_mut()) {
+                    let mask = 0x80 >> (column % 8);
+                    *byte = 0;
+                    for bit in 0..8 {
+                        if rows[bit * row_bytes + column / 8] & mask != 0 {
+                            *byte |= 1 << bit;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Dissolve the current frame into `other` over `steps` flushed steps, `step_ms` apart.
+    ///
+    /// A 1bpp panel cannot blend pixels, so this is a dissolve rather than an alpha blend: every
+    /// step switches a larger, evenly spread share of the pixels over to `other`, following a 4x4
+    /// ordered dither pattern. After the last step the framebuffer holds exactly `other`.
+    ///
+    /// `other` must be in the same layout as the framebuffer, see [`FramebufferLayout`]. Bytes
+    /// beyond the end of `other` are left as they are.
+    pub fn crossfade<DELAY>(
+        &mut self,
+        other: &[u8],
+        steps: u8,
+        step_ms: u8,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u8>,
+    {
+        let steps = steps.max(1);
+
+        for step in 1..=steps {
+            let threshold = (16 * u16::from(step) / u16::from(steps)) as u8;
+            self.dissolve_step(other, threshold);
+            self.flush()?;
+
+            if step < steps {
+                delay.delay_ms(step_ms);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Take every pixel whose dither matrix entry is below `threshold` (0 to 16) from `other`
+    fn dissolve_step(&mut self, other: &[u8], threshold: u8) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let (display_width, display_height) = (display_width as usize, display_height as usize);
+        let sideways = R::swaps_axes(self.properties.get_rotation());
+
+        for (idx, (byte, other)) in self.buffer.bytes_mut().iter_mut().zip(other).enumerate() {
+            let mut mask = 0u8;
+
+            for bit in 0..8 {
+                let (column, row) = match self.layout {
+                    FramebufferLayout::VerticalPages => {
+                        (idx % display_width, idx / display_width * 8 + bit)
+                    }
+                    FramebufferLayout::DisplayRows if sideways => (
+                        idx / (display_height / 8),
+                        idx % (display_height / 8) * 8 + (7 - bit),
+                    ),
+                    FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => (
+                        idx % (display_width / 8) * 8 + (7 - bit),
+                        idx / (display_width / 8),
+                    ),
+                };
+
+                if BAYER_4X4[row % 4][column % 4] < threshold {
+                    mask |= 1 << bit;
+                }
+            }
+
+            *byte = (*byte & !mask) | (other & mask);
+        }
+
+        self.mark_all_dirty();
+    }
+
+    /// Fill the whole framebuffer with a top to bottom gradient from brightness `top_level` to
+    /// `bottom_level`, where `0` is off and `255` is fully lit. The gradient is approximated with
+    /// a 4x4 ordered dither pattern, which is anchored to display coordinates so it continues
+    /// seamlessly across page boundaries. "Top" follows the current rotation.
+    pub fn fill_gradient_vertical(&mut self, top_level: u8, bottom_level: u8) {
+        let (width, height) = self.get_dimensions();
+        let (top_level, bottom_level) = (i32::from(top_level), i32::from(bottom_level));
+
+        for y in 0..u32::from(height) {
+            let level =
+                top_level + (bottom_level - top_level) * y as i32 / (i32::from(height) - 1).max(1);
+
+            for x in 0..u32::from(width) {
+                let threshold = i32::from(BAYER_4X4[y as usize % 4][x as usize % 4]) * 16 + 8;
+                self.set_pixel(x, y, (level > threshold) as u8);
+            }
+        }
+    }
+
+    /// Draw a dashed or dotted line from `p0` to `p1`, both inclusive. `pattern` is an on/off mask
+    /// that repeats every 8 pixels along the line, starting with the most significant bit at
+    /// `p0`: `0b1100_1100` gives short dashes, `0b1010_1010` dots and `0xFF` a solid line. Pixels
+    /// for clear bits are left untouched, as are pixels outside the display.
+    pub fn draw_line_styled(&mut self, p0: (i32, i32), p1: (i32, i32), pattern: u8) {
+        let (width, height) = self.get_dimensions();
+        let (x0, y0) = (i64::from(p0.0), i64::from(p0.1));
+        let (dx, dy) = (i64::from(p1.0) - x0, i64::from(p1.1) - y0);
+
+        // Step along the longer axis and round the other coordinate, halfway values towards `p0`,
+        // which gives the same pixels as the Bresenham lines of embedded-graphics but can start at
+        // any step
+        let x_major = dx.abs() >= dy.abs();
+        let (major0, major_delta, major_size, minor0, minor_delta) = if x_major {
+            (x0, dx, width, y0, dy)
+        } else {
+            (y0, dy, height, x0, dx)
+        };
+        let steps = major_delta.abs();
+        let step = major_delta.signum();
+
+        // Only the steps that are on the display along the longer axis can draw anything, so
+        // lines reaching far off-screen take no longer than ones ending at the edge
+        let last_on_display = i64::from(major_size) - 1;
+        let (first, last) = if step >= 0 {
+            (-major0, last_on_display - major0)
+        } else {
+            (major0 - last_on_display, major0)
+        };
+
+        for i in first.max(0)..=last.min(steps) {
+            if pattern & (0x80 >> (i % 8)) == 0 {
+                continue;
+            }
+
+            let major = major0 + step * i;
+            let minor = if steps == 0 {
+                minor0
+            } else {
+                let offset =
+                    (2 * i128::from(minor_delta.abs()) * i128::from(i) + i128::from(steps) - 1)
+                        / (2 * i128::from(steps));
+                minor0 + minor_delta.signum() * offset as i64
+            };
+            let (x, y) = if x_major {
+                (major, minor)
+            } else {
+                (minor, major)
+            };
+
+            if let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) {
+                self.set_pixel(x, y, 1);
+            }
+        }
+    }
+
+    /// Turn a pixel on or off. A non-zero `value` is treated as on, `0` as off. If the X and Y
+    /// coordinates are out of the bounds of the display or the clipping area, this method call is
+    /// a noop. Pixels outside the display are counted, see `out_of_bounds_pixels`.
+    pub fn set_pixel(&mut self, x: u32, y: u32, value: u8) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        // The framebuffer is always laid out like the panel; rotation only swaps the axes and
+        // leaves the mirroring to the display controller
+        let (column, row) = if R::swaps_axes(self.properties.get_rotation()) {
+            (y, x)
+        } else {
+            (x, y)
+        };
+
+        if column >= display_width as u32 || row >= display_height as u32 {
+            self.count_out_of_bounds(1);
+            return;
+        }
+
+        if let Some(((x0, y0), (x1, y1))) = self.clip {
+            if !(x0..x1).contains(&x) || !(y0..y1).contains(&y) {
+                return;
+            }
+        }
+
+        self.write_panel_pixel(column, row, value != 0);
+        self.mark_dirty(row / 8, column, column + 1);
+        self.properties.count_pixels(1);
+    }
+
+    /// Get the number of pixels drawn outside the display since the display was created or
+    /// `reset_out_of_bounds` was called. Such pixels are skipped, which makes layout bugs easy to
+    /// overlook on the hardware; a test or debug build can check this stays 0 instead. Pixels
+    /// outside the clipping area but on the display are not counted.
+    pub fn out_of_bounds_pixels(&self) -> u32 {
+        self.out_of_bounds
+    }
+
+    /// Start counting pixels drawn outside the display from 0 again
+    pub fn reset_out_of_bounds(&mut self) {
+        self.out_of_bounds = 0;
+    }
+
+    /// Make drawing outside the display through `DrawTarget` an error. The pixels on the display
+    /// are still drawn, but `draw_iter`, `fill_contiguous` and `fill_solid` return
+    /// `DisplayError::OutOfBoundsError` if any pixel was outside, so layout bugs fail in CI
+    /// instead of going unnoticed. Methods that do not return a `Result`, like `set_pixel` and
+    /// `draw_bitmap`, only count them, see `out_of_bounds_pixels`. Defaults to `false`.
+    pub fn set_strict_bounds(&mut self, strict: bool) {
+        self.strict_bounds = strict;
+    }
+
+    fn count_out_of_bounds(&mut self, pixels: u32) {
+        self.out_of_bounds = self.out_of_bounds.saturating_add(pixels);
+    }
+
+    /// Turn the pixel at panel column `x` and row `y` on or off without any of the checks of
+    /// `set_pixel`, for custom rasterizers whose inner loops have already validated their
+    /// coordinates. The coordinates are before rotation, so a `FixedRotate0` display uses the
+    /// same ones as `set_pixel`, and neither the clipping area nor change tracking apply, so the
+    /// result has to be sent with `flush` or `flush_region` rather than `flush_dirty`.
+    ///
+    /// This is not an `unsafe fn` because a pixel outside the display is only a logic error: it
+    /// sets some other pixel or panics on the framebuffer index, but never touches other memory.
+    pub fn set_pixel_unchecked(&mut self, x: u32, y: u32, on: bool) {
+        self.write_panel_pixel(x, y, on);
+        self.properties.count_pixels(1);
+    }
+
+    /// Read back whether the pixel at `x` and `y` is lit in the framebuffer, in display
+    /// coordinates taking rotation into account. This reflects what the next flush sends, not
+    /// what is currently on the screen. Pixels outside the display read as off, and the clipping
+    /// area only affects drawing, not reading.
+    pub fn get_pixel(&self, x: u32, y: u32) -> bool {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        let (column, row) = if R::swaps_axes(self.properties.get_rotation()) {
+            (y, x)
+        } else {
+            (x, y)
+        };
+
+        if column >= display_width as u32 || row >= display_height as u32 {
+            return false;
+        }
+
+        let (idx, bit) = self.panel_bit(column, row);
+        self.buffer.bytes()[idx] & bit != 0
+    }
+
+    /// Iterate over all pixels of the framebuffer as `(x, y, on)`, in display coordinates taking
+    /// rotation into account, row by row from the top left. Like `get_pixel`, this reflects what
+    /// the next flush sends, e.g. to assert on a rendered frame in a test or mirror it to
+    /// another output.
+    pub fn pixels(&self) -> impl Iterator<Item = (u32, u32, bool)> + '_ {
+        let (width, height) = self.get_dimensions();
+
+        (0..u32::from(height))
+            .flat_map(move |y| (0..u32::from(width)).map(move |x| (x, y, self.get_pixel(x, y))))
+    }
+
+    /// Write the framebuffer to `writer` as text, e.g. to send what is on screen over a debug
+    /// UART. The dump starts with a `SH1108 <width>x<height>` line, in display coordinates
+    /// taking rotation into account, followed by one line of hex per pixel row from the top.
+    /// Every byte holds 8 pixels with the leftmost one in the most significant bit, and the last
+    /// byte of a row is padded with off pixels. Like `get_pixel`, this reflects what the next
+    /// flush sends.
+    #[cfg(feature = "frame-dump")]
+    pub fn dump_frame<W: core::fmt::Write>(&self, writer: &mut W) -> core::fmt::Result {
+        let (width, height) = self.get_dimensions();
+        writeln!(writer, "SH1108 {}x{}", width, height)?;
+
+        for y in 0..u32::from(height) {
+            for x in (0..u32::from(width)).step_by(8) {
+                let byte = (0..8).fold(0u8, |byte, bit| {
+                    byte | (u8::from(self.get_pixel(x + bit, y)) << (7 - bit))
+                });
+                write!(writer, "{:02x}", byte)?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Write the framebuffer to `writer` as a picture of `#` for on and `.` for off pixels, one
+    /// line per row, to see the screen of a headless test rig or a failing test in the log.
+    /// With a `scale` above 1, every `scale` x `scale` block of pixels becomes one character,
+    /// shown as on if any of its pixels is, so a 128x160 frame fits a terminal at a scale of 2
+    /// or 4. A `scale` of 0 is treated as 1.
+    #[cfg(feature = "frame-dump")]
+    pub fn dump_ascii<W: core::fmt::Write>(&self, writer: &mut W, scale: u32) -> core::fmt::Result {
+        let scale = scale.max(1);
+        let (width, height) = self.get_dimensions();
+        let (width, height) = (u32::from(width), u32::from(height));
+
+        for y in (0..height).step_by(scale as usize) {
+            for x in (0..width).step_by(scale as usize) {
+                let on = (y..(y + scale).min(height))
+                    .any(|y| (x..(x + scale).min(width)).any(|x| self.get_pixel(x, y)));
+                writer.write_char(if on { '#' } else { '.' })?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Framebuffer index and bit mask of the pixel at `column` and `row` of the panel
+    fn panel_bit(&self, column: u32, row: u32) -> (usize, u8) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        match self.layout {
+            FramebufferLayout::VerticalPages => (
+                (row as usize) / 8 * display_width as usize + (column as usize),
+                1 << (row % 8),
+            ),
+            FramebufferLayout::DisplayRows if R::swaps_axes(self.properties.get_rotation()) => (
+                (column as usize) * (display_height as usize / 8) + (row as usize) / 8,
+                0x80 >> (row % 8),
+            ),
+            FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => (
+                (row as usize) * (display_width as usize / 8) + (column as usize) / 8,
+                0x80 >> (column % 8),
+            ),
+        }
+    }
+
+    /// Whether the framebuffer holds the rows of the display in display coordinates, like an
+    /// embedded-graphics `Framebuffer`
+    fn holds_display_rows(&self) -> bool {
+        match self.layout {
+            FramebufferLayout::VerticalPages => false,
+            FramebufferLayout::RowMajor => !R::swaps_axes(self.properties.get_rotation()),
+            FramebufferLayout::DisplayRows => true,
+        }
+    }
+
+    /// Set the pixel at `column` and `row` of the panel, which must be on the display, without
+    /// marking it dirty
+    fn write_panel_pixel(&mut self, column: u32, row: u32, on: bool) {
+        let (idx, bit) = self.panel_bit(column, row);
+        let byte = &mut self.buffer.bytes_mut()[idx];
+
+        if on {
+            *byte |= bit;
+        } else {
+            *byte &= !bit;
+        }
+    }
+
+    /// Extend the changed columns of `page` to include `start..end`
+    fn mark_dirty(&mut self, page: u32, start: u32, end: u32) {
+        let (dirty_start, dirty_end) = &mut self.dirty[page as usize];
+        *dirty_start = (*dirty_start).min(start as u8);
+        *dirty_end = (*dirty_end).max(end as u8);
+    }
+
+    /// Draw a packed 1bpp bitmap rotated clockwise by `rotation`, with the top left corner of the
+    /// rotated bitmap at `dest`. This allows e.g. one arrow icon to be drawn pointing in all four
+    /// directions.
+    ///
+    /// `src` holds `src_dims.1` rows of `src_dims.0` pixels each, unrotated. Each row starts on a
+    /// new byte, with the leftmost pixel in the most significant bit. Missing bytes are drawn as
+    /// off and pixels falling outside the display are skipped.
+    pub fn draw_region_rotated(
+        &mut self,
+        src: &[u8],
+        src_dims: (u32, u32),
+        dest: (u32, u32),
+        rotation: DisplayRotation,
+    ) {
+        let row_bytes = (src_dims.0 as usize).div_ceil(8);
+
+        self.blit(src, row_bytes, (0, 0), src_dims, dest, rotation);
+    }
+
+    /// Like `draw_region_rotated`, but mirror the bitmap first: `flip_horizontal` swaps its left
+    /// and right, `flip_vertical` its top and bottom. Together with the rotation this covers
+    /// every orientation of an icon, so a single copy of it has to be stored.
+    pub fn draw_region_transformed(
+        &mut self,
+        src: &[u8],
+        src_dims: (u32, u32),
+        dest: (u32, u32),
+        rotation: DisplayRotation,
+        flip_horizontal: bool,
+        flip_vertical: bool,
+    ) {
+        let row_bytes = (src_dims.0 as usize).div_ceil(8);
+
+        self.blit_flipped(
+            src,
+            row_bytes,
+            (0, 0),
+            src_dims,
+            dest,
+            rotation,
+            (flip_horizontal, flip_vertical),
+        );
+    }
+
+    /// Draw a packed 1bpp bitmap of `w` by `h` pixels with its top left corner at `x` and `y`.
+    /// `data` holds `h` rows, each starting on a new byte, with the leftmost pixel in the most
+    /// significant bit. Both on and off pixels are drawn, missing bytes are drawn as off and
+    /// pixels falling outside the display are skipped.
+    ///
+    /// With the [`FramebufferLayout::RowMajor`] layout and no rotation by 90 or 270 degrees, or
+    /// with [`FramebufferLayout::DisplayRows`] in any rotation, and `x` a multiple of 8, each row
+    /// of a bitmap that fits on the display (and inside the clipping area) is copied byte by byte
+    /// instead of pixel by pixel. With the default [`FramebufferLayout::VerticalPages`] layout
+    /// and no rotation by 90 or 270 degrees, a bitmap starting at a `y` that is a multiple of 8 is
+    /// written a framebuffer byte, 8 rows of a column, at a time.
+    pub fn draw_bitmap(&mut self, x: u32, y: u32, w: u32, h: u32, data: &[u8]) {
+        self.draw_bitmap_with(x, y, w, h, data, BlitOp::Copy);
+    }
+
+    /// Like `draw_bitmap`, but combine the bitmap with what is already in the framebuffer as
+    /// selected by `op`, e.g. `BlitOp::And` with a mask and then `BlitOp::Or` with the sprite to
+    /// draw a sprite with a mask, or `BlitOp::Xor` for a cursor. Missing bytes count as off
+    /// pixels. Where `draw_bitmap` copies whole bytes, the bytes are combined at once as well.
+    pub fn draw_bitmap_with(&mut self, x: u32, y: u32, w: u32, h: u32, data: &[u8], op: BlitOp) {
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        let row_bytes = (w as usize).div_ceil(8);
+        let (display_width, display_height) = self.get_dimensions();
+        let (display_width, display_height) = (u32::from(display_width), u32::from(display_height));
+        // Bottom right corner, exclusive, unless it lies beyond the coordinate range
+        let end = x.checked_add(w).zip(y.checked_add(h));
+
+        let inside_clip = self.clip.is_none_or(|((x0, y0), (x1, y1))| {
+            end.is_some_and(|(x_end, y_end)| x >= x0 && y >= y0 && x_end <= x1 && y_end <= y1)
+        });
+        let on_display =
+            end.is_some_and(|(x_end, y_end)| x_end <= display_width && y_end <= display_height);
+
+        let bytewise = self.holds_display_rows() && x.is_multiple_of(8);
+        let pagewise = self.layout == FramebufferLayout::VerticalPages
+            && !R::swaps_axes(self.properties.get_rotation())
+            && y.is_multiple_of(8);
+
+        if !(bytewise || pagewise)
+            || !inside_clip
+            || !on_display
+            || data.len() < row_bytes * h as usize
+        {
+            if op == BlitOp::Copy {
+                self.blit(
+                    data,
+                    row_bytes,
+                    (0, 0),
+                    (w, h),
+                    (x, y),
+                    DisplayRotation::Rotate0,
+                );
+            } else {
+                self.blit_op(data, row_bytes, (w, h), (x, y), op);
+            }
+            return;
+        }
+
+        if bytewise {
+            let stride = display_width as usize / 8;
+            // Bits of the last byte of each row that belong to the bitmap
+            let last_mask = 0xFFu8 << ((8 - w % 8) % 8);
+
+            for (row, src) in data.chunks(row_bytes).take(h as usize).enumerate() {
+                let offset = (y as usize + row) * stride + x as usize / 8;
+                let dest = &mut self.buffer.bytes_mut()[offset..offset + row_bytes];
+
+                if op == BlitOp::Copy {
+                    dest[..row_bytes - 1].copy_from_slice(&src[..row_bytes - 1]);
+                } else {
+                    for (dest, &src) in dest[..row_bytes - 1].iter_mut().zip(src) {
+                        *dest = op.apply(*dest, src, 0xFF);
+                    }
+                }
+                dest[row_bytes - 1] = op.apply(dest[row_bytes - 1], src[row_bytes - 1], last_mask);
+            }
+        } else {
+            self.blit_pages(data, row_bytes, (w, h), (x, y), op);
+        }
+
+        let ((column0, row0), (column1, row1)) = if R::swaps_axes(self.properties.get_rotation()) {
+            ((y, x), (y + h, x + w))
+        } else {
+            ((x, y), (x + w, y + h))
+        };
+        for page in row0 / 8..row1.div_ceil(8) {
+            self.mark_dirty(page, column0, column1);
+        }
+        self.properties.count_pixels(w * h);
+    }
+
+    /// Draw frame `index` of a sprite sheet with its top left corner at `dest`. Pixels falling
+    /// outside the display are skipped.
+    ///
+    /// Returns `Error::OutOfBounds` if the sheet has no frame `index`.
+    pub fn draw_sprite(
+        &mut self,
+        sheet: &SpriteSheet<'_>,
+        index: usize,
+        dest: (u32, u32),
+    ) -> Result<(), Error> {
+        if index >= sheet.frame_count() {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.blit(
+            sheet.data(),
+            sheet.row_bytes(),
+            sheet.frame_origin(index),
+            sheet.frame_size(),
+            dest,
+            DisplayRotation::Rotate0,
+        );
+
+        Ok(())
+    }
+
+    /// Composite a [`Sprite`] with its top left corner at (`x`, `y`). Opaque sprites overwrite
+    /// everything they cover, transparent ones only turn pixels on. Pixels falling outside the
+    /// display are skipped.
+    pub fn blit_sprite<const M: usize>(&mut self, sprite: &Sprite<M>, x: u32, y: u32) {
+        let (width, height) = sprite.dimensions();
+
+        if !sprite.is_transparent() {
+            self.blit(
+                sprite.data(),
+                sprite.row_bytes(),
+                (0, 0),
+                (width, height),
+                (x, y),
+                DisplayRotation::Rotate0,
+            );
+            return;
+        }
+
+        for sy in 0..height {
+            for sx in 0..width {
+                if sprite.get_pixel(sx, sy) {
+                    self.set_pixel(x.saturating_add(sx), y.saturating_add(sy), 1);
+                }
+            }
+        }
+    }
+
+    /// Print `text` with the built-in [`Font5x8`] with the top left corner of the first
+    /// character at (`x`, `y`), for labels and numbers without the embedded-graphics dependency.
+    /// Characters take 6x8 pixel cells and a `'\n'` starts a new line below `x`. Only the lit
+    /// pixels of the glyphs are drawn; pixels falling outside the display are skipped.
+    #[cfg(feature = "tiny-text")]
+    pub fn draw_text(&mut self, x: u32, y: u32, text: &str) {
+        let (width, height) = Font5x8.cell_size();
+        let (mut cx, mut cy) = (x, y);
+
+        for c in text.chars() {
+            if c == '\n' {
+                cx = x;
+                cy = cy.saturating_add(height.into());
+                continue;
+            }
+
+            for gx in 0..width {
+                for gy in 0..height {
+                    if Font5x8.pixel(c, gx, gy) {
+                        self.set_pixel(
+                            cx.saturating_add(gx.into()),
+                            cy.saturating_add(gy.into()),
+                            1,
+                        );
+                    }
+                }
+            }
+
+            cx = cx.saturating_add(width.into());
+        }
+    }
+
+    /// Decode a run-length encoded image of `dims` (width, height) pixels into the framebuffer
+    /// with its top left corner at `pos`. See the [`rle`](../../rle/index.html) module for the
+    /// encoding.
+    ///
+    /// Both on and off pixels are drawn. If `rle` runs out before the image is complete, the
+    /// remaining pixels are left untouched; excess runs are ignored.
+    pub fn draw_rle_image(&mut self, pos: (u32, u32), rle: &[u8], dims: (u32, u32)) {
+        let (width, height) = dims;
+        let coords = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)));
+
+        for ((x, y), on) in coords.zip(RleDecoder::new(rle)) {
+            self.set_pixel(pos.0.saturating_add(x), pos.1.saturating_add(y), on as u8);
+        }
+    }
+
+    /// Move the framebuffer contents up by `pixels` rows, in display coordinates taking rotation
+    /// into account, and clear the rows that become free at the bottom, e.g. to make room for a
+    /// new line of a log console. The whole display moves regardless of the clipping area, and
+    /// everything is marked as changed.
+    ///
+    /// With the default [`FramebufferLayout::VerticalPages`] layout this works on whole bytes,
+    /// shifting the bits of neighbouring pages into each other; other layouts move every pixel.
+    pub fn scroll_up(&mut self, pixels: u32) {
+        self.scroll(pixels, true);
+    }
+
+    /// Move the framebuffer contents down by `pixels` rows and clear the rows that become free at
+    /// the top, see `scroll_up`
+    pub fn scroll_down(&mut self, pixels: u32) {
+        self.scroll(pixels, false);
+    }
+
+    fn scroll(&mut self, pixels: u32, up: bool) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let swap = R::swaps_axes(self.properties.get_rotation());
+        self.mark_all_dirty();
+
+        if self.layout == FramebufferLayout::VerticalPages {
+            let width = usize::from(display_width);
+            let pages = usize::from(display_height / 8);
+            let buffer = &mut self.buffer.bytes_mut()[..width * pages];
+
+            if swap {
+                // Rows of the display run along the columns of every page
+                let shift = usize::try_from(pixels).unwrap_or(usize::MAX).min(width);
+                for page in buffer.chunks_exact_mut(width) {
+                    if up {
+                        page.copy_within(shift.., 0);
+                        page[width - shift..].fill(0);
+                    } else {
+                        page.copy_within(..width - shift, shift);
+                        page[..shift].fill(0);
+                    }
+                }
+                return;
+            }
+
+            let whole = usize::try_from(pixels / 8).unwrap_or(usize::MAX);
+            let bits = pixels % 8;
+            let byte = |buffer: &[u8], page: Option<usize>, column: usize| {
+                page.filter(|&page| page < pages)
+                    .map_or(0, |page| buffer[page * width + column])
+            };
+
+            // Every byte only depends on bytes that are moved after it, so this works in place
+            for page in 0..pages {
+                let page = if up { page } else { pages - 1 - page };
+                for column in 0..width {
+                    buffer[page * width + column] = if up {
+                        let low = byte(buffer, page.checked_add(whole), column);
+                        let high = byte(buffer, page.checked_add(whole + 1), column);
+                        if bits == 0 {
+                            low
+                        } else {
+                            (low >> bits) | (high << (8 - bits))
+                        }
+                    } else {
+                        let high = byte(buffer, page.checked_sub(whole), column);
+                        let low = byte(buffer, page.checked_sub(whole + 1), column);
+                        if bits == 0 {
+                            high
+                        } else {
+                            (high << bits) | (low >> (8 - bits))
+                        }
+                    };
+                }
+            }
+            return;
+        }
+
+        let (width, height) = self.get_dimensions();
+        let (width, height) = (u32::from(width), u32::from(height));
+        for row in 0..height {
+            let y = if up { row } else { height - 1 - row };
+            let source = if up {
+                y.checked_add(pixels)
+            } else {
+                y.checked_sub(pixels)
+            };
+
+            for x in 0..width {
+                let on = source.is_some_and(|source| self.get_pixel(x, source));
+                let (column, row) = if swap { (y, x) } else { (x, y) };
+                self.write_panel_pixel(column, row, on);
+            }
+        }
+    }
+
+    /// Copy a `dims` sized area starting at `origin` out of a packed 1bpp bitmap with `row_bytes`
+    /// bytes per row, rotating it clockwise by `rotation`, to `dest` in the framebuffer.
+    fn blit(
+        &mut self,
+        src: &[u8],
+        row_bytes: usize,
+        origin: (u32, u32),
+        dims: (u32, u32),
+        dest: (u32, u32),
+        rotation: DisplayRotation,
+    ) {
+        self.blit_flipped(src, row_bytes, origin, dims, dest, rotation, (false, false));
+    }
+
+    /// Combine a `dims` sized packed 1bpp bitmap with `row_bytes` bytes per row with the
+    /// framebuffer at `dest` pixel by pixel, see `draw_bitmap_with`
+    fn blit_op(
+        &mut self,
+        src: &[u8],
+        row_bytes: usize,
+        dims: (u32, u32),
+        dest: (u32, u32),
+        op: BlitOp,
+    ) {
+        for y in 0..dims.1 {
+            for x in 0..dims.0 {
+                let on = src
+                    .get(y as usize * row_bytes + x as usize / 8)
+                    .is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0);
+                let (x, y) = (dest.0.saturating_add(x), dest.1.saturating_add(y));
+                let current = u8::from(self.get_pixel(x, y));
+
+                self.set_pixel(x, y, op.apply(current, u8::from(on), 1));
+            }
+        }
+    }
+
+    /// Combine a `dims` sized packed 1bpp bitmap with `row_bytes` bytes per row with a
+    /// [`FramebufferLayout::VerticalPages`] framebuffer at `dest` a byte at a time, gathering
+    /// each byte from 8 rows of the bitmap. The bitmap has to be on the display, in panel
+    /// coordinates and start on a page boundary, see `draw_bitmap_with`.
+    fn blit_pages(
+        &mut self,
+        src: &[u8],
+        row_bytes: usize,
+        dims: (u32, u32),
+        dest: (u32, u32),
+        op: BlitOp,
+    ) {
+        let stride = usize::from(self.properties.get_size().dimensions().0);
+        let (width, height) = dims;
+
+        for page in 0..height.div_ceil(8) {
+            let rows = (height - page * 8).min(8);
+            // Bits of the byte that belong to the bitmap, fewer in a last partial page
+            let mask = 0xFFu8 >> (8 - rows);
+            let offset = (dest.1 / 8 + page) as usize * stride + dest.0 as usize;
+
+            for x in 0..width as usize {
+                let byte = (0..rows).fold(0u8, |byte, bit| {
+                    let row = (page * 8 + bit) as usize;
+                    let on = src[row * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0;
+                    byte | u8::from(on) << bit
+                });
+
+                let dest = &mut self.buffer.bytes_mut()[offset + x];
+                *dest = op.apply(*dest, byte, mask);
+            }
+        }
+    }
+
+    /// `blit`, mirroring the area horizontally and/or vertically as given by `flip` before it is
+    /// rotated
+    #[allow(clippy::too_many_arguments)]
+    fn blit_flipped(
+        &mut self,
+        src: &[u8],
+        row_bytes: usize,
+        origin: (u32, u32),
+        dims: (u32, u32),
+        dest: (u32, u32),
+        rotation: DisplayRotation,
+        flip: (bool, bool),
+    ) {
+        let (width, height) = dims;
+
+        for y in 0..height {
+            for x in 0..width {
+                let flipped_x = if flip.0 { width - 1 - x } else { x };
+                let flipped_y = if flip.1 { height - 1 - y } else { y };
+                let (src_x, src_y) = (origin.0 + flipped_x, origin.1 + flipped_y);
+                let on = src
+                    .get(src_y as usize * row_bytes + src_x as usize / 8)
+                    .is_some_and(|byte| byte & (0x80 >> (src_x % 8)) != 0);
+
+                let (dx, dy) = match rotation {
+                    DisplayRotation::Rotate0 => (x, y),
+                    DisplayRotation::Rotate90 => (height - 1 - y, x),
+                    DisplayRotation::Rotate180 => (width - 1 - x, height - 1 - y),
+                    DisplayRotation::Rotate270 => (y, width - 1 - x),
+                };
+
+                self.set_pixel(
+                    dest.0.saturating_add(dx),
+                    dest.1.saturating_add(dy),
+                    on as u8,
+                );
+            }
+        }
+    }
+
+    /// Display is set up in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 on the left, to column _n_ on the right
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.properties.init_column_mode()
+    }
+
+    /// Initialise the display again after it lost its settings and RAM contents, e.g. to a
+    /// brown-out on its supply, see [`DisplayProperties::reinit`]. The framebuffer is kept and
+    /// sent again right away if `reflush` is set, or marked as changed for the next
+    /// `flush_dirty` otherwise.
+    pub fn reinit(&mut self, reflush: bool) -> Result<(), DisplayError> {
+        self.properties.reinit()?;
+
+        if reflush {
+            self.flush().map(|_| ())
+        } else {
+            self.mark_all_dirty();
+
+            Ok(())
+        }
+    }
+
+    /// Whether `init` completed since the driver was created or the display was last reset
+    pub fn is_initialized(&self) -> bool {
+        self.properties.is_initialized()
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.properties.get_dimensions()
+    }
+
+    /// Estimate the number of bytes sent to update an area of the screen. See
+    /// [`DisplayProperties::estimated_transfer_bytes`].
+    pub fn estimated_transfer_bytes(&self, start: (u8, u8), end: (u8, u8)) -> usize {
+        self.properties.estimated_transfer_bytes(start, end)
+    }
+
+    /// Estimate the time in microseconds needed to update an area of the screen. See
+    /// [`DisplayProperties::estimated_transfer_us`].
+    pub fn estimated_transfer_us(&self, start: (u8, u8), end: (u8, u8)) -> u32 {
+        self.properties.estimated_transfer_us(start, end)
+    }
+
+    /// Swap the panel columns and rows of the framebuffer within the largest square that fits,
+    /// and clear everything else
+    fn transpose(&mut self) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let (width, height) = (u32::from(display_width), u32::from(display_height));
+        let side = width.min(height);
+
+        for row in 0..height {
+            for column in 0..width {
+                if column >= side || row >= side {
+                    self.write_panel_pixel(column, row, false);
+                } else if column > row {
+                    let (idx, bit) = self.panel_bit(column, row);
+                    let (other_idx, other_bit) = self.panel_bit(row, column);
+                    let on = self.buffer.bytes()[idx] & bit != 0;
+                    let other_on = self.buffer.bytes()[other_idx] & other_bit != 0;
+
+                    self.write_panel_pixel(column, row, other_on);
+                    self.write_panel_pixel(row, column, on);
+                }
+            }
+        }
+    }
+
+    /// Change the rows of a [`FramebufferLayout::DisplayRows`] framebuffer to the length they
+    /// have in the other orientation, keeping the square in the top left corner. The pixels stay
+    /// in place in display coordinates, no transposition needed.
+    fn restride(&mut self) {
+        let (width, height) = self.get_dimensions();
+        let (old_row_bytes, new_row_bytes) = (usize::from(width) / 8, usize::from(height) / 8);
+        let side = usize::from(width.min(height));
+        let square_bytes = side / 8;
+
+        // Move the rows towards the end of the buffer last to first, and towards the start
+        // first to last, so no row is overwritten before it has been moved
+        let mut move_row = |row: usize| {
+            self.buffer.bytes_mut().copy_within(
+                row * old_row_bytes..row * old_row_bytes + square_bytes,
+                row * new_row_bytes,
+            );
+        };
+        if new_row_bytes > old_row_bytes {
+            (0..side).rev().for_each(&mut move_row);
+        } else {
+            (0..side).for_each(&mut move_row);
+        }
+
+        let length = usize::from(width) * usize::from(height) / 8;
+        for (idx, byte) in self.buffer.bytes_mut()[..length].iter_mut().enumerate() {
+            if idx / new_row_bytes >= side || idx % new_row_bytes >= square_bytes {
+                *byte = 0;
+            }
+        }
+    }
+
+    /// Mirror the displayed image independent of the rotation. See
+    /// [`DisplayProperties::set_mirror`].
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) -> Result<(), DisplayError> {
+        self.properties.set_mirror(horizontal, vertical)
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.display_on(on)
+    }
+
+    /// Put the display into its lowest power state. See [`DisplayProperties::sleep`].
+    pub fn sleep(&mut self) -> Result<(), DisplayError> {
+        self.properties.sleep()
+    }
+
+    /// Wake the display from `sleep`. See [`DisplayProperties::wake`].
+    pub fn wake<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.wake(delay)
+    }
+
+    /// Invert the display in hardware without changing the framebuffer. See
+    /// [`DisplayProperties::set_invert`].
+    pub fn set_invert(&mut self, invert: bool) -> Result<(), DisplayError> {
+        self.properties.set_invert(invert)
+    }
+
+    /// Whether the display was last turned on. See [`DisplayProperties::is_on`].
+    pub fn is_on(&self) -> bool {
+        self.properties.is_on()
+    }
+
+    /// Whether the display is inverted with `set_invert`
+    pub fn is_inverted(&self) -> bool {
+        self.properties.is_inverted()
+    }
+
+    /// Get the mirroring set with `set_mirror` as `(horizontal, vertical)`
+    pub fn get_mirror(&self) -> (bool, bool) {
+        self.properties.get_mirror()
+    }
+
+    /// Whether a display answers on the interface. See [`DisplayProperties::is_connected`].
+    pub fn is_connected(&mut self) -> bool {
+        self.properties.is_connected()
+    }
+
+    /// Send `command` to the controller without leaving the mode. See
+    /// [`DisplayProperties::send_command`].
+    pub fn send_command(&mut self, command: Command) -> Result<(), DisplayError> {
+        self.properties.send_command(command)
+    }
+
+    /// Send `bytes` to the controller as commands, e.g. for module specific commands. See
+    /// [`DisplayProperties::send_raw_command`].
+    pub fn send_raw_command(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.properties.send_raw_command(bytes)
+    }
+
+    /// Send `data` to the display RAM at the current address of the controller. The framebuffer
+    /// does not change, so the next `flush` overwrites what the data drew. See
+    /// [`DisplayProperties::send_raw_data`].
+    pub fn send_raw_data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        self.properties.send_raw_data(data)
+    }
+
+    /// Change the display size, see [`DisplayProperties::set_size`]. The framebuffer is cleared,
+    /// as its layout follows the size, and sent in full with the next flush. The clipping area
+    /// is kept.
+    ///
+    /// Returns `Error::BufferTooSmall` without changing anything if the framebuffer cannot hold
+    /// the new size.
+    pub fn set_size(&mut self, display_size: DisplaySize) -> Result<(), Error> {
+        if self.buffer.bytes().len() < display_size.framebuffer_size() {
+            return Err(Error::BufferTooSmall);
+        }
+
+        self.properties.set_size(display_size)?;
+        self.clear();
+
+        Ok(())
+    }
+
+    /// Dim the display after a period without drawing. See [`DisplayProperties::set_auto_dim`].
+    pub fn set_auto_dim(&mut self, auto_dim: Option<(u32, u8)>) -> Result<(), DisplayError> {
+        self.properties.set_auto_dim(auto_dim)
+    }
+
+    /// Turn the display off after a period without drawing, and back on with the next flush.
+    /// See [`DisplayProperties::set_auto_off`].
+    pub fn set_auto_off(&mut self, timeout_ms: Option<u32>) -> Result<(), DisplayError> {
+        self.properties.set_auto_off(timeout_ms)
+    }
+
+    /// Count idle time for `set_auto_dim` and `set_auto_off`. See
+    /// [`DisplayProperties::idle_tick`].
+    pub fn idle_tick(&mut self, elapsed_ms: u32) -> Result<(), DisplayError> {
+        self.properties.idle_tick(elapsed_ms)
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.properties.set_contrast(contrast)
+    }
+
+    /// Fade the display out to zero contrast. See [`DisplayProperties::fade_out`].
+    pub fn fade_out<DELAY>(
+        &mut self,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.properties.fade_out(duration_ms, delay)
+    }
+
+    /// Fade the display back in to its contrast. See [`DisplayProperties::fade_in`].
+    pub fn fade_in<DELAY>(
+        &mut self,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.properties.fade_in(duration_ms, delay)
+    }
+
+    /// Call `hook` with the progress of every flush. See [`DisplayProperties::set_flush_hook`].
+    pub fn set_flush_hook(&mut self, hook: Option<fn(FlushEvent)>) {
+        self.properties.set_flush_hook(hook);
+    }
+
+    /// Get the counters of what the driver did, see the [`metrics`](crate::metrics) module
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> Metrics {
+        let mut metrics = self.properties.metrics();
+        metrics.pixels_set = metrics.pixels_set.wrapping_add(self.region_pixels.get());
+
+        metrics
+    }
+
+    /// Start all counters of `metrics` from 0 again
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&mut self) {
+        self.properties.reset_metrics();
+        self.region_pixels.set(0);
+    }
+
+    /// Move the image on the panel by `dx` columns and `dy` rows from the next flush on. See
+    /// [`DisplayProperties::set_pixel_shift`].
+    pub fn set_pixel_shift(&mut self, dx: i8, dy: i8) {
+        self.properties.set_pixel_shift(dx, dy);
+        self.mark_all_dirty();
+    }
+
+    /// Get the current pixel shift as `(dx, dy)` in panel columns and rows
+    pub fn get_pixel_shift(&self) -> (i8, i8) {
+        self.properties.get_pixel_shift()
+    }
+
+    /// Move the image one pixel further along its burn-in prevention path from the next flush on,
+    /// and return the new shift. See [`DisplayProperties::advance_pixel_shift`] and
+    /// `Builder::with_pixel_shift_range`.
+    pub fn advance_pixel_shift(&mut self) -> (i8, i8) {
+        let shift = self.properties.advance_pixel_shift();
+        self.mark_all_dirty();
+        shift
+    }
+
+    /// Set the contrast so the display looks `percent` bright, from 0 to 100. See
+    /// [`DisplayProperties::set_brightness_percent`].
+    pub fn set_brightness_percent(&mut self, percent: u8) -> Result<(), DisplayError> {
+        self.properties.set_brightness_percent(percent)
+    }
+
+    /// Set the contrast and precharge period to a brightness preset. See
+    /// [`DisplayProperties::set_brightness`].
+    pub fn set_brightness(&mut self, brightness: Brightness) -> Result<(), DisplayError> {
+        self.properties.set_brightness(brightness)
+    }
+
+    /// Switch between the normal and the dimmed contrast and precharge, e.g. for a night mode.
+    /// See [`DisplayProperties::dim`].
+    pub fn dim(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.dim(on)
+    }
+
+    /// Set the contrast and precharge periods `dim` switches to. See
+    /// [`DisplayProperties::set_dim_level`].
+    pub fn set_dim_level(
+        &mut self,
+        contrast: u8,
+        phase1: u8,
+        phase2: u8,
+    ) -> Result<(), DisplayError> {
+        self.properties.set_dim_level(contrast, phase1, phase2)
+    }
+
+    /// Get the display contrast as last set by `set_contrast`
+    pub fn get_contrast(&self) -> u8 {
+        self.properties.get_contrast()
+    }
+
+    /// Pulse the contrast like a heartbeat. See [`DisplayProperties::heartbeat`].
+    pub fn heartbeat<DELAY>(&mut self, bpm: u16, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.properties.heartbeat(bpm, delay)
+    }
+
+    /// Briefly boost the contrast. See [`DisplayProperties::flash_contrast`].
+    pub fn flash_contrast<DELAY>(
+        &mut self,
+        boost: u8,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.properties.flash_contrast(boost, duration_ms, delay)
+    }
+
+    /// Sweep all contrast levels with every pixel lit. See [`DisplayProperties::contrast_sweep`].
+    pub fn contrast_sweep<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.contrast_sweep(delay)
+    }
+
+    /// Show a test pattern. See [`DisplayProperties::test_pattern`]. The framebuffer is left
+    /// as it is and the whole of it is marked dirty, so the next flush brings the image back.
+    pub fn test_pattern(&mut self, pattern: TestPattern) -> Result<(), DisplayError> {
+        self.properties.test_pattern(pattern)?;
+        self.mark_all_dirty();
+
+        Ok(())
+    }
+
+    /// Show the display RAM again after [`TestPattern::AllOn`]
+    pub fn end_test_pattern(&mut self) -> Result<(), DisplayError> {
+        self.properties.end_test_pattern()
+    }
+}
+
+impl<DI, const N: usize, S: FrameBufferStorage> GraphicsMode<DI, N, RuntimeRotation, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Set the display rotation. The framebuffer is laid out like the panel, so the image that
+    /// was drawn for the old rotation turns along with the display until it is redrawn. See
+    /// `set_rotation_remap` to keep it upright instead.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.properties.set_rotation(rot)
+    }
+
+    /// Set the display rotation and rearrange the framebuffer so every pixel stays at the same
+    /// `(x, y)` position in the new orientation. The next flush then shows the same image upright
+    /// in the new orientation.
+    ///
+    /// When switching between a landscape and a portrait orientation on a non-square display,
+    /// only the square in the top left corner fits both, and everything outside of it is
+    /// cleared. The rearrangement happens in place, without a second buffer. Nothing is sent to
+    /// the display until the next flush, except for the new scan direction.
+    pub fn set_rotation_remap(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        let sideways = RuntimeRotation::swaps_axes;
+
+        if sideways(rot) != sideways(self.properties.get_rotation()) {
+            if self.layout == FramebufferLayout::DisplayRows {
+                self.restride();
+            } else {
+                self.transpose();
+            }
+            self.mark_all_dirty();
+        }
+
+        self.properties.set_rotation(rot)
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode> GraphicsMode<DI, N, R, &'static mut [u8]>
+where
+    DI: DmaInterface,
+{
+    /// Start sending the whole framebuffer in the background, see the [`dma`](crate::dma)
+    /// module. The returned [`DmaFlush`] owns the display until it is done, and nothing is sent
+    /// before its first `poll`.
+    ///
+    /// The pages are handed to the interface straight out of the framebuffer with the
+    /// [`FramebufferLayout::VerticalPages`] layout and no pixel shift. Otherwise the first
+    /// `poll` does a blocking `flush` and the flush is done.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush_dma(self) -> DmaFlush<DI, N, R> {
+        DmaFlush::new(self)
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: DmaInterface,
+{
+    /// The number of lit pixels of a frame that can be sent through DMA, or `None` if the
+    /// layout or a pixel shift rule that out
+    pub(crate) fn dma_lit_pixels(&self) -> Option<u32> {
+        self.framebuffer_page(0)?;
+
+        Some(
+            self.framebuffer()
+                .iter()
+                .map(|byte| byte.count_ones())
+                .sum(),
+        )
+    }
+
+    /// Get ready to send a frame with `lit_pixels` pixels on through DMA, and return the number
+    /// of pages to send. The framebuffer counts as sent from here on.
+    pub(crate) fn begin_dma_flush(&mut self, lit_pixels: u32) -> Result<u8, DisplayError> {
+        let (_, display_height) = self.properties.get_size().dimensions();
+
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+        self.properties.apply_power_budget(lit_pixels)?;
+
+        self.dirty = [CLEAN; MAX_PAGES];
+        self.pending = [CLEAN; MAX_PAGES];
+        self.properties.begin_flush();
+
+        Ok(display_height / 8)
+    }
+
+    /// Point the display at page `index` and start sending that page of `frame`, a framebuffer
+    /// with the [`FramebufferLayout::VerticalPages`] layout
+    ///
+    /// # Safety
+    ///
+    /// `frame` must be `'static` and must not change until `poll_dma_page` gave the page back.
+    #[allow(unsafe_code)]
+    pub(crate) unsafe fn start_dma_page_of(
+        &mut self,
+        frame: &[u8],
+        index: u8,
+    ) -> Result<(), DisplayError> {
+        let width = usize::from(self.properties.get_size().dimensions().0);
+        let page = &frame[usize::from(index) * width..][..width];
+
+        self.properties
+            .start_dma_page(DmaPage::new(page.as_ptr(), page.len()), index)
+    }
+
+    /// Whether the page started with `start_dma_page_of` has been sent, see
+    /// [`DmaInterface::poll_data`]
+    pub(crate) fn poll_dma_page(&mut self) -> Option<(DmaPage, Result<(), DisplayError>)> {
+        self.properties.poll_dma_data()
+    }
+
+    /// Report the end of a flush through DMA
+    pub(crate) fn end_dma_flush(&mut self) {
+        self.properties.end_flush();
+    }
+}
+
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: ReadInterface,
+{
+    /// `flush`, then read the display RAM back and compare it to the framebuffer, see
+    /// [`verify`](Self::verify), e.g. for indicators that must not show a corrupted frame
+    pub fn flush_verified(&mut self) -> Result<FlushStats, VerifyError> {
+        let stats = self.flush().map_err(VerifyError::Display)?;
+        self.verify()?;
+
+        Ok(stats)
+    }
+
+    /// Read the whole display RAM back and compare it to what the framebuffer last sent, e.g.
+    /// to check a safety relevant screen periodically. Returns `VerifyError::Mismatch` with
+    /// where the two differ if the RAM was corrupted, e.g. by noise on the bus. The pages that
+    /// differ are marked as changed, so a `flush_dirty` repairs them.
+    ///
+    /// Changes to the framebuffer that were not flushed yet are reported as mismatches too.
+    pub fn verify(&mut self) -> Result<(), VerifyError> {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let width = usize::from(display_width);
+
+        let mut mismatch = Mismatch {
+            pages: 0,
+            bytes: 0,
+            first: (0, 0),
+        };
+        let mut expected = [0; 160];
+        let mut read = [0; 160];
+        for page in 0..display_height / 8 {
+            self.page_bytes(page.into(), 0, &mut expected[..width]);
+            self.properties
+                .read_ram(0, page, &mut read[..width])
+                .map_err(|error| match error {
+                    Error::Interface(error) => VerifyError::Display(error),
+                    // Pages of the display are always in range
+                    _ => VerifyError::Display(DisplayError::OutOfBoundsError),
+                })?;
+
+            let mut differing = expected[..width]
+                .iter()
+                .zip(&read[..width])
+                .enumerate()
+                .filter(|(_, (expected, read))| expected != read)
+                .map(|(column, _)| column as u32);
+            let Some(first) = differing.next() else {
+                continue;
+            };
+            let (last, count) =
+                differing.fold((first, 1), |(_, count), column| (column, count + 1));
+
+            if mismatch.pages == 0 {
+                mismatch.first = (page, first as u8);
+            }
+            mismatch.pages += 1;
+            mismatch.bytes += count;
+            self.mark_dirty(page.into(), first, last + 1);
+        }
+
+        if mismatch.pages > 0 {
+            return Err(VerifyError::Mismatch(mismatch));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Write the framebuffer as a binary PBM (`P4`) image, in display coordinates taking rotation
+    /// into account. Set bits are lit pixels, so image viewers show them in black on white.
+    pub fn write_pbm<W: std::io::Write>(&self, mut out: W) -> std::io::Result<()> {
+        let (width, height) = self.get_dimensions();
+        write!(out, "P4\n{} {}\n", width, height)?;
+
+        for y in 0..u32::from(height) {
+            for byte_x in (0..u32::from(width)).step_by(8) {
+                let byte = (0..8)
+                    .filter(|bit| self.get_pixel(byte_x + bit, y))
+                    .fold(0u8, |byte, bit| byte | 0x80 >> bit);
+                out.write_all(&[byte])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the framebuffer as an XBM image declaring `name_width`, `name_height` and
+    /// `name_bits`, in display coordinates taking rotation into account. Set bits are lit pixels.
+    pub fn write_xbm<W: std::io::Write>(&self, mut out: W, name: &str) -> std::io::Result<()> {
+        let (width, height) = self.get_dimensions();
+        writeln!(out, "#define {}_width {}", name, width)?;
+        writeln!(out, "#define {}_height {}", name, height)?;
+        write!(out, "static unsigned char {}_bits[] = {{", name)?;
+
+        let mut count = 0;
+        for y in 0..u32::from(height) {
+            for byte_x in (0..u32::from(width)).step_by(8) {
+                // XBM stores the leftmost pixel of each byte in the least significant bit
+                let byte = (0..8)
+                    .filter(|bit| self.get_pixel(byte_x + bit, y))
+                    .fold(0u8, |byte, bit| byte | 1 << bit);
+
+                let separator = if count == 0 { "" } else { "," };
+                let indent = if count % 12 == 0 { "\n   " } else { "" };
+                write!(out, "{}{} 0x{:02x}", separator, indent, byte)?;
+                count += 1;
+            }
+        }
+
+        writeln!(out, " }};")
+    }
+}
+
+#[cfg(feature = "framebuffer")]
+use embedded_graphics::{framebuffer::Framebuffer, pixelcolor::raw::RawU1};
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{Dimensions, OriginDimensions, Point, Size},
+    image::{GetPixel, ImageDrawable},
+    pixelcolor::{BinaryColor, GrayColor},
+    primitives::{PointsIter, Rectangle},
+    Pixel,
+};
+#[cfg(feature = "bmp")]
+use tinybmp::{Bmp, Bpp, RowOrder};
+
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+#[cfg(feature = "graphics")]
+use crate::mode::inverted::Inverted;
+#[cfg(feature = "mirror")]
+use crate::{error::MirrorError, mirror};
+
+/// How `modify_area` changes the pixels of an area
+#[cfg(feature = "graphics")]
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum AreaOp {
+    Set,
+    Clear,
+    Invert,
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> DrawTarget
+    for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        // Work out the clipping and rotation once instead of for every pixel like `set_pixel`
+        let drawable = self.drawable_area(&self.bounding_box());
+        let swap = self.is_rotated_sideways();
+
+        let bounds = self.bounding_box();
+        let before = self.out_of_bounds;
+        let mut drawn = 0;
+
+        for Pixel(pos, color) in pixels {
+            if drawable.contains(pos) {
+                let (x, y) = (pos.x as u32, pos.y as u32);
+                let (column, row) = if swap { (y, x) } else { (x, y) };
+                self.write_panel_pixel(column, row, color.is_on());
+                self.mark_dirty(row / 8, column, column + 1);
+                drawn += 1;
+            } else if !bounds.contains(pos) {
+                self.count_out_of_bounds(1);
+            }
+        }
+        self.properties.count_pixels(drawn);
+
+        self.check_bounds(before)
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        let before = self.out_of_bounds;
+        self.count_outside(area);
+        let drawable = self.drawable_area(area);
+        let Some(bottom_right) = drawable.bottom_right() else {
+            return self.check_bounds(before);
+        };
+        let swap = self.is_rotated_sideways();
+
+        for (point, color) in area.points().zip(colors) {
+            if drawable.contains(point) {
+                let (x, y) = (point.x as u32, point.y as u32);
+                let (column, row) = if swap { (y, x) } else { (x, y) };
+                self.write_panel_pixel(column, row, color.is_on());
+            }
+
+            // Everything after the last drawable point is outside of it
+            if point == bottom_right {
+                break;
+            }
+        }
+
+        self.mark_dirty_area(drawable);
+        self.properties
+            .count_pixels(drawable.size.width * drawable.size.height);
+
+        self.check_bounds(before)
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        let before = self.out_of_bounds;
+        self.fill_area(area, color.is_on());
+
+        self.check_bounds(before)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Borrow the display as a draw target that swaps on and off pixels, e.g. to draw content
+    /// made for dark on light. See the [`inverted`](crate::mode::inverted) module.
+    pub fn inverted(&mut self) -> Inverted<'_, Self> {
+        Inverted::new(self)
+    }
+
+    /// Turn off all pixels within `area`, given in display coordinates taking rotation into
+    /// account. Whole bytes of the framebuffer are cleared at once and only the rows at the edges
+    /// of a page are masked, so this is much faster than drawing the area pixel by pixel. Like
+    /// drawing, it leaves everything outside the display and the clip rectangle alone.
+    pub fn clear_region(&mut self, area: Rectangle) {
+        self.fill_area(&area, false);
+    }
+
+    /// Swap on and off pixels within `area`, given in display coordinates taking rotation into
+    /// account, e.g. to highlight the selected row of a menu. Like `clear_region`, whole bytes are
+    /// flipped at once and only the edges are masked, so highlighting a row costs far less than
+    /// drawing its text again in inverse colors. Inverting the same area again restores it.
+    pub fn invert_region(&mut self, area: Rectangle) {
+        self.modify_area(&area, AreaOp::Invert);
+    }
+
+    /// Turn `len` pixels on or off from `x`, `y` to the right, in display coordinates taking
+    /// rotation into account. Like `clear_region`, this fills whole framebuffer bytes where it
+    /// can and masks the ones at the ends, so grids and separators are much faster than with
+    /// `set_pixel`. Pixels outside the display and the clipping area are skipped.
+    pub fn hline(&mut self, x: u32, y: u32, len: u32, on: bool) {
+        if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+            self.fill_area(&Rectangle::new(Point::new(x, y), Size::new(len, 1)), on);
+        }
+    }
+
+    /// Turn `len` pixels on or off from `x`, `y` downwards, like `hline`
+    pub fn vline(&mut self, x: u32, y: u32, len: u32, on: bool) {
+        if let (Ok(x), Ok(y)) = (i32::try_from(x), i32::try_from(y)) {
+            self.fill_area(&Rectangle::new(Point::new(x, y), Size::new(1, len)), on);
+        }
+    }
+
+    /// Write out only the part of the framebuffer within `area`, given in display coordinates
+    /// taking rotation into account. The display RAM is written in whole pages of 8 rows, so
+    /// this sends the columns of `area` for every page it touches, i.e. a 32x16 area aligned to
+    /// the pages takes two transfers of 32 bytes. Parts of `area` outside the display are
+    /// ignored.
+    ///
+    /// The area is sent whether it changed or not, and the changes tracked for `flush_dirty` are
+    /// left as they are. Like the other flushes, this applies the power budget to the whole frame.
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush_region(&mut self, area: Rectangle) -> Result<(), DisplayError> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+        let area = area.intersection(&self.bounding_box());
+        if area.is_zero_sized() {
+            return Ok(());
+        }
+
+        let length = self.properties.get_size().framebuffer_size();
+        let lit_pixels = self.buffer.bytes()[..length]
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum();
+        self.properties.apply_power_budget(lit_pixels)?;
+
+        let ((column0, row0), (column1, row1)) = self.panel_span(area);
+        self.properties.begin_flush();
+        for index in row0 / 8..row1.div_ceil(8) {
+            self.send_page_span(index as u8, column0 as u8, column1 as u8)?;
+        }
+        self.properties.end_flush();
+
+        Ok(())
+    }
+
+    /// Set or clear all pixels of `area` that are visible, a byte at a time with the edges
+    /// masked
+    fn fill_area(&mut self, area: &Rectangle, on: bool) {
+        self.modify_area(area, if on { AreaOp::Set } else { AreaOp::Clear });
+    }
+
+    /// Apply `op` to all pixels of `area` that are visible, like `fill_area`
+    fn modify_area(&mut self, area: &Rectangle, op: AreaOp) {
+        self.count_outside(area);
+        let drawable = self.drawable_area(area);
+        if drawable.is_zero_sized() {
+            return;
+        }
+
+        let ((column0, row0), (column1, row1)) = self.panel_span(drawable);
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let width = display_width as usize;
+
+        let apply = |byte: &mut u8, mask: u8| match op {
+            AreaOp::Set => *byte |= mask,
+            AreaOp::Clear => *byte &= !mask,
+            AreaOp::Invert => *byte ^= mask,
+        };
+
+        match self.layout {
+            FramebufferLayout::VerticalPages => {
+                for page in row0 / 8..row1.div_ceil(8) {
+                    let top = row0.max(page * 8) - page * 8;
+                    let bottom = row1.min(page * 8 + 8) - page * 8;
+                    let mask = (0xFF << top) & (0xFF >> (8 - bottom));
+
+                    let offset = page as usize * width;
+                    let bytes = &mut self.buffer.bytes_mut()
+                        [offset + column0 as usize..offset + column1 as usize];
+                    if mask == 0xFF && op != AreaOp::Invert {
+                        bytes.fill(if op == AreaOp::Set { 0xFF } else { 0x00 });
+                    } else {
+                        bytes.iter_mut().for_each(|byte| apply(byte, mask));
+                    }
+                }
+            }
+            FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => {
+                // Rows of the panel, or of the rotated display with `DisplayRows` sideways
+                let ((x0, y0), (x1, y1), row_bytes) = if self.layout
+                    == FramebufferLayout::DisplayRows
+                    && self.is_rotated_sideways()
+                {
+                    (
+                        (row0, column0),
+                        (row1, column1),
+                        display_height as usize / 8,
+                    )
+                } else {
+                    ((column0, row0), (column1, row1), width / 8)
+                };
+
+                for row in y0..y1 {
+                    for index in x0 / 8..x1.div_ceil(8) {
+                        let left = x0.max(index * 8) - index * 8;
+                        let right = x1.min(index * 8 + 8) - index * 8;
+                        let mask = (0xFF >> left) & !0xFFu8.checked_shr(right).unwrap_or(0);
+
+                        apply(
+                            &mut self.buffer.bytes_mut()[row as usize * row_bytes + index as usize],
+                            mask,
+                        );
+                    }
+                }
+            }
+        }
+
+        self.mark_dirty_area(drawable);
+        self.properties
+            .count_pixels(drawable.size.width * drawable.size.height);
+    }
+
+    /// Count the pixels of `area` that lie outside the display
+    fn count_outside(&mut self, area: &Rectangle) {
+        let pixels = |area: &Rectangle| u64::from(area.size.width) * u64::from(area.size.height);
+        let outside = pixels(area) - pixels(&area.intersection(&self.bounding_box()));
+
+        self.count_out_of_bounds(u32::try_from(outside).unwrap_or(u32::MAX));
+    }
+
+    /// In strict mode, fail if pixels were drawn outside the display since the count was `before`
+    fn check_bounds(&self, before: u32) -> Result<(), DisplayError> {
+        if self.strict_bounds && self.out_of_bounds != before {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        Ok(())
+    }
+
+    /// Part of `area`, in display coordinates, that lies on the display and inside the clipping
+    /// area
+    fn drawable_area(&self, area: &Rectangle) -> Rectangle {
+        let area = area.intersection(&self.bounding_box());
+
+        match self.get_clip() {
+            Some(clip) => area.intersection(&clip),
+            None => area,
+        }
+    }
+
+    fn is_rotated_sideways(&self) -> bool {
+        R::swaps_axes(self.properties.get_rotation())
+    }
+
+    /// Panel `(column, row)` start (inclusive) and end (exclusive) of a non-empty `area` on the
+    /// display
+    fn panel_span(&self, area: Rectangle) -> ((u32, u32), (u32, u32)) {
+        let (x0, y0) = (area.top_left.x as u32, area.top_left.y as u32);
+        let (x1, y1) = (x0 + area.size.width, y0 + area.size.height);
+
+        if self.is_rotated_sideways() {
+            ((y0, x0), (y1, x1))
+        } else {
+            ((x0, y0), (x1, y1))
+        }
+    }
+
+    fn mark_dirty_area(&mut self, area: Rectangle) {
+        if area.is_zero_sized() {
+            return;
+        }
+
+        let ((column0, row0), (column1, row1)) = self.panel_span(area);
+        for page in row0 / 8..row1.div_ceil(8) {
+            self.mark_dirty(page, column0, column1);
+        }
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> OriginDimensions
+    for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
     fn size(&self) -> Size {
         let (w, h) = self.get_dimensions();
 
-        Size::new(w.into(), h.into())
+        Size::new(w.into(), h.into())
+    }
+}
+
+/// Reads the framebuffer, not the screen, so unflushed changes are included
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GetPixel
+    for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = BinaryColor;
+
+    fn pixel(&self, p: Point) -> Option<BinaryColor> {
+        if !self.bounding_box().contains(p) {
+            return None;
+        }
+
+        Some(self.get_pixel(p.x as u32, p.y as u32).into())
+    }
+}
+
+/// Draws the framebuffer onto another target, e.g. a simulator window or a larger composite
+/// display, through `embedded_graphics::image::Image`
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> ImageDrawable
+    for GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = BinaryColor;
+
+    fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.draw_sub_image(target, &self.bounding_box())
+    }
+
+    fn draw_sub_image<D>(&self, target: &mut D, area: &Rectangle) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let area = area.intersection(&self.bounding_box());
+        let colors = area
+            .points()
+            .map(|p| BinaryColor::from(self.get_pixel(p.x as u32, p.y as u32)));
+
+        target.fill_contiguous(&Rectangle::new(Point::zero(), area.size), colors)
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Restrict all further drawing to `clip`, given in display coordinates taking rotation into
+    /// account. Pixels outside of it are left untouched by `set_pixel`, the blitting methods and
+    /// the embedded-graphics `DrawTarget`. `None` allows drawing to the whole display again.
+    ///
+    /// The clipping area is not cleared by `clear`, and it is not transformed when the rotation
+    /// changes.
+    pub fn set_clip(&mut self, clip: Option<Rectangle>) {
+        self.clip = clip.map(|rect| {
+            let start = rect.top_left;
+            let end = start + rect.size;
+
+            (
+                (start.x.max(0) as u32, start.y.max(0) as u32),
+                (end.x.max(0) as u32, end.y.max(0) as u32),
+            )
+        });
+    }
+
+    /// Get the drawable area of the display. See [`DisplayProperties::visible_bounds`].
+    pub fn visible_bounds(&self) -> Rectangle {
+        self.properties.visible_bounds()
+    }
+
+    /// Copy a 1bpp framebuffer rendered elsewhere into the display buffer and flush it, without
+    /// drawing it pixel by pixel through the `DrawTarget`.
+    ///
+    /// `data` holds `size.height` rows of `size.width` pixels in display coordinates, taking
+    /// rotation into account. Each row starts on a new byte, with the leftmost pixel in the most
+    /// significant bit and set bits lit. This is the layout of an embedded-graphics
+    /// `Framebuffer<BinaryColor, RawU1, _, W, H, N>`, so one can be pushed with
+    /// `display.flush_framebuffer(fb.data(), fb.size())`. Pixels outside `size` are left as they
+    /// are.
+    ///
+    /// Returns `Error::OutOfBounds` if `size` is larger than the display and
+    /// `Error::BufferTooSmall` if `data` is too short for it.
+    pub fn flush_framebuffer(&mut self, data: &[u8], size: Size) -> Result<(), Error> {
+        let (width, height) = self.get_dimensions();
+        let row_bytes = (size.width as usize).div_ceil(8);
+
+        if size.width > width.into() || size.height > height.into() {
+            return Err(Error::OutOfBounds);
+        }
+        if data.len() < row_bytes * size.height as usize {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let full_frame = size == Size::new(width.into(), height.into());
+
+        if self.holds_display_rows() && full_frame && self.clip.is_none() {
+            // Same layout as our own buffer, so no need for the per pixel transform
+            let length = row_bytes * size.height as usize;
+            self.buffer.bytes_mut()[..length].copy_from_slice(&data[..length]);
+            self.mark_all_dirty();
+        } else {
+            self.blit(
+                data,
+                row_bytes,
+                (0, 0),
+                (size.width, size.height),
+                (0, 0),
+                DisplayRotation::Rotate0,
+            );
+        }
+
+        self.flush()?;
+
+        Ok(())
+    }
+
+    /// Allow drawing to the whole display again, the same as `set_clip(None)`
+    pub fn clear_clip(&mut self) {
+        self.clip = None;
+    }
+
+    /// Get the clipping area set with `set_clip`
+    pub fn get_clip(&self) -> Option<Rectangle> {
+        self.clip.map(|((x0, y0), (x1, y1))| {
+            Rectangle::new(
+                (x0 as i32, y0 as i32).into(),
+                Size::new(x1.saturating_sub(x0), y1.saturating_sub(y0)),
+            )
+        })
+    }
+
+    /// Draw a grayscale image, e.g. an `ImageRaw<Gray8>` or `ImageRaw<Gray4>`, with its top left
+    /// corner at `pos`, dithering it to on and off pixels with `dither`. Both on and off pixels
+    /// are drawn, and pixels falling outside the display are skipped.
+    pub fn draw_gray_image<I, C>(&mut self, image: &I, pos: (u32, u32), dither: Dither)
+    where
+        I: GetPixel<Color = C> + OriginDimensions,
+        C: GrayColor,
+    {
+        let Size { width, height } = image.size();
+        // Columns past the widest display can never be visible
+        let width = width.min(160);
+        let max_luma = i32::from(C::WHITE.luma());
+
+        // Floyd-Steinberg errors for the current and the next row, offset by one column so the
+        // neighbours of the first and last columns need no special case
+        let mut errors = [[0i32; 162]; 2];
+
+        for y in 0..height {
+            for x in 0..width {
+                let luma = image
+                    .pixel(Point::new(x as i32, y as i32))
+                    .map_or(0, |color| i32::from(color.luma()));
+                let level = luma * 255 / max_luma;
+                let (dx, dy) = (pos.0.saturating_add(x), pos.1.saturating_add(y));
+
+                let on = match dither {
+                    Dither::Ordered => {
+                        let threshold = i32::from(BAYER_4X4[dy as usize % 4][dx as usize % 4]);
+                        level > threshold * 16 + 8
+                    }
+                    Dither::FloydSteinberg => {
+                        let column = x as usize + 1;
+                        let level = level + errors[0][column];
+                        let on = level >= 128;
+                        let error = level - if on { 255 } else { 0 };
+
+                        errors[0][column + 1] += error * 7 / 16;
+                        errors[1][column - 1] += error * 3 / 16;
+                        errors[1][column] += error * 5 / 16;
+                        errors[1][column + 1] += error / 16;
+                        on
+                    }
+                };
+
+                self.set_pixel(dx, dy, on as u8);
+            }
+
+            errors = [errors[1], [0; 162]];
+        }
+    }
+}
+
+/// Conversions from and to the embedded-graphics `Framebuffer`, behind the `framebuffer` feature
+#[cfg(feature = "framebuffer")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Copy a `Framebuffer` rendered elsewhere into the display buffer and flush it. This is
+    /// [`flush_framebuffer`](Self::flush_framebuffer) with the size taken from the framebuffer
+    /// type, so the same errors apply.
+    pub fn flush_eg_framebuffer<BO, const W: usize, const H: usize, const M: usize>(
+        &mut self,
+        framebuffer: &Framebuffer<BinaryColor, RawU1, BO, W, H, M>,
+    ) -> Result<(), Error> {
+        self.flush_framebuffer(framebuffer.data(), Size::new(W as u32, H as u32))
+    }
+
+    /// Copy the display buffer into a `Framebuffer`, in display coordinates taking rotation into
+    /// account, e.g. to hand the current frame to code that works on embedded-graphics
+    /// framebuffers. Parts of `framebuffer` beyond the display are left as they are.
+    pub fn copy_to_framebuffer<BO, const W: usize, const H: usize, const M: usize>(
+        &self,
+        framebuffer: &mut Framebuffer<BinaryColor, RawU1, BO, W, H, M>,
+    ) {
+        let (width, height) = self.get_dimensions();
+        let full_frame = (W, H) == (width.into(), height.into());
+
+        if self.holds_display_rows() && full_frame {
+            // Both buffers hold the same rows, so they can be copied as they are
+            let length = W.div_ceil(8) * H;
+            framebuffer.data_mut()[..length].copy_from_slice(&self.buffer.bytes()[..length]);
+        } else {
+            for (x, y, on) in self.pixels() {
+                framebuffer.set_pixel(Point::new(x as i32, y as i32), on.into());
+            }
+        }
+    }
+}
+
+/// Drawing `tinybmp` images, behind the `bmp` feature
+#[cfg(feature = "bmp")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Draw a BMP image with its top left corner at `x` and `y`. Pixels falling outside the
+    /// display are skipped.
+    ///
+    /// Uncompressed 1 bit per pixel images, the usual format of splash screens, are drawn row by
+    /// row with [`draw_bitmap`](Self::draw_bitmap), so its byte by byte copies apply. Images of
+    /// up to 160 pixels wide are mapped through their color table a byte at a time. Everything
+    /// else is drawn pixel by pixel like with `Image::draw`.
+    pub fn draw_bmp(&mut self, bmp: &Bmp<'_, BinaryColor>, x: u32, y: u32) {
+        let raw = bmp.as_raw();
+        let header = raw.header();
+        let (width, height) = (header.image_size.width, header.image_size.height);
+        // BMP rows are padded to a multiple of 4 bytes
+        let stride = (width as usize).div_ceil(32) * 4;
+        let row_bytes = (width as usize).div_ceil(8);
+
+        let index_on = |index| {
+            raw.color_table()
+                .and_then(|table| table.get(index))
+                .map(|color| BinaryColor::from(color).is_on())
+        };
+
+        match (index_on(0), index_on(1)) {
+            (Some(zero_on), Some(one_on))
+                if header.bpp == Bpp::Bits1
+                    && width > 0
+                    && row_bytes <= MAX_ROW_BYTES
+                    && raw.image_data().len() >= stride * height as usize =>
+            {
+                let zero_mask = if zero_on { 0xFF } else { 0x00 };
+                let one_mask = if one_on { 0xFF } else { 0x00 };
+                let mut row = [0; MAX_ROW_BYTES];
+
+                for (index, src) in raw
+                    .image_data()
+                    .chunks(stride)
+                    .take(height as usize)
+                    .enumerate()
+                {
+                    for (dest, byte) in row.iter_mut().zip(&src[..row_bytes]) {
+                        *dest = (byte & one_mask) | (!byte & zero_mask);
+                    }
+
+                    let row_y = match header.row_order {
+                        RowOrder::TopDown => index as u32,
+                        _ => height - 1 - index as u32,
+                    };
+                    self.draw_bitmap(x, y.saturating_add(row_y), width, 1, &row[..row_bytes]);
+                }
+            }
+            _ => {
+                for Pixel(point, color) in bmp.pixels() {
+                    self.set_pixel(
+                        x.saturating_add(point.x as u32),
+                        y.saturating_add(point.y as u32),
+                        color.is_on().into(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Mirroring flushes to a host, behind the `mirror` feature
+#[cfg(feature = "mirror")]
+impl<DI, const N: usize, R: RotationMode, S: FrameBufferStorage> GraphicsMode<DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Write out only what changed like `flush_dirty`, then write the same changes to `sink` as
+    /// one frame of the format described in the [`mirror`] module, so a host can show a live
+    /// copy of the screen.
+    ///
+    /// Returns `MirrorError::Display` if the flush failed, in which case nothing is written to
+    /// `sink`, and `MirrorError::Sink` if writing to `sink` failed after the flush succeeded.
+    pub fn flush_dirty_mirrored<W>(
+        &mut self,
+        sink: &mut W,
+    ) -> Result<FlushStats, MirrorError<W::Error>>
+    where
+        W: embedded_io::Write,
+    {
+        let spans = self.unsent_spans();
+        let stats = self.flush_dirty().map_err(MirrorError::Display)?;
+
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+        let pages = display_height / 8;
+        mirror::write_header(sink, display_width, pages).map_err(MirrorError::Sink)?;
+
+        let mut page = [0u8; 160];
+        for (index, &(start, end)) in (0..pages).zip(&spans) {
+            if start >= end {
+                continue;
+            }
+
+            let page = &mut page[..usize::from(end - start)];
+            self.page_bytes(index.into(), start.into(), page);
+            mirror::write_span(sink, index, start, page).map_err(MirrorError::Sink)?;
+        }
+        mirror::write_end(sink).map_err(MirrorError::Sink)?;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        properties::PowerTiming,
+        test_helpers::{
+            static_framebuffer, CaptureInterface, DelayRecorder, PinStub, RamInterface, Sent,
+            StubInterface,
+        },
+        Builder,
+    };
+    use display_interface::DataFormat;
+    use std::vec;
+
+    /// Run `f` once for each framebuffer layout, with a function creating new displays in that
+    /// layout. They are set up by `builder` and connected to a clone of `iface`, so a
+    /// `CaptureInterface` collects what all of them send.
+    fn each_layout_with<DI>(
+        builder: Builder,
+        iface: &DI,
+        mut f: impl FnMut(&dyn Fn() -> GraphicsMode<DI>),
+    ) where
+        DI: WriteOnlyDataCommand + Clone,
+    {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            f(&|| {
+                let mut display: GraphicsMode<_> = builder.connect(iface.clone()).into();
+                display.set_framebuffer_layout(layout);
+                display
+            });
+        }
+    }
+
+    /// Run `f` on a display with the default settings in each framebuffer layout
+    fn each_layout(mut f: impl FnMut(GraphicsMode<StubInterface>)) {
+        each_layout_with(Builder::new(), &StubInterface, |new_display| {
+            f(new_display())
+        });
+    }
+
+    fn draw_pattern<DI: WriteOnlyDataCommand, R: RotationMode>(
+        display: &mut GraphicsMode<DI, MAX_BUFFER_SIZE, R>,
+    ) {
+        for i in 0..160 {
+            display.set_pixel(i, i, 1);
+            display.set_pixel(i, 159 - i, 1);
+            display.set_pixel(i / 2, 3, 1);
+        }
+        display.set_pixel(127, 0, 1);
+        display.set_pixel(0, 159, 1);
+    }
+
+    #[test]
+    fn layouts_produce_identical_output() {
+        for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate90] {
+            let pages = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new()
+                .with_rotation(rotation)
+                .connect(pages.clone())
+                .into();
+            display.init().unwrap();
+            pages.clear();
+            draw_pattern(&mut display);
+            display.flush().unwrap();
+
+            assert_eq!(pages.data().len(), 128 * 160 / 8);
+
+            for layout in [FramebufferLayout::RowMajor, FramebufferLayout::DisplayRows] {
+                let rows = CaptureInterface::new();
+                let mut display: GraphicsMode<_> = Builder::new()
+                    .with_rotation(rotation)
+                    .connect(rows.clone())
+                    .into();
+                display.init().unwrap();
+                rows.clear();
+                display.set_framebuffer_layout(layout);
+                draw_pattern(&mut display);
+                display.flush().unwrap();
+
+                assert_eq!(pages.sent(), rows.sent(), "{:?}", layout);
+            }
+        }
+    }
+
+    fn sent_with_rotation<R: RotationMode>(rotation: DisplayRotation) -> vec::Vec<Sent> {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_, MAX_BUFFER_SIZE, R> = Builder::new()
+            .with_rotation(rotation)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        draw_pattern(&mut display);
+        display.flush().unwrap();
+        iface.sent()
+    }
+
+    #[test]
+    fn fixed_rotation_matches_runtime_rotation() {
+        use crate::displayrotation::{FixedRotate0, FixedRotate180, FixedRotate270, FixedRotate90};
+
+        // The fixed rotation wins over the one given to the builder
+        let fixed = [
+            sent_with_rotation::<FixedRotate0>(DisplayRotation::Rotate90),
+            sent_with_rotation::<FixedRotate90>(DisplayRotation::Rotate0),
+            sent_with_rotation::<FixedRotate180>(DisplayRotation::Rotate0),
+            sent_with_rotation::<FixedRotate270>(DisplayRotation::Rotate0),
+        ];
+        let runtime = [
+            DisplayRotation::Rotate0,
+            DisplayRotation::Rotate90,
+            DisplayRotation::Rotate180,
+            DisplayRotation::Rotate270,
+        ]
+        .map(sent_with_rotation::<RuntimeRotation>);
+
+        assert_eq!(fixed, runtime);
+    }
+
+    fn pixel<DI: WriteOnlyDataCommand>(display: &GraphicsMode<DI>, x: u32, y: u32) -> bool {
+        let (width, _) = display.properties.get_size().dimensions();
+        display.buffer[(y / 8 * width as u32 + x) as usize] & (1 << (y % 8)) != 0
+    }
+
+    #[test]
+    fn rotation_remap_keeps_pixels_in_place() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let mut display: GraphicsMode<_> =
+                Builder::new().connect(CaptureInterface::new()).into();
+            display.set_framebuffer_layout(layout);
+            display.set_pixel(3, 100, 1);
+            display.set_pixel(127, 0, 1);
+            display.set_pixel(5, 140, 1);
+
+            display
+                .set_rotation_remap(DisplayRotation::Rotate180)
+                .unwrap();
+            assert!(display.get_pixel(3, 100));
+
+            display
+                .set_rotation_remap(DisplayRotation::Rotate90)
+                .unwrap();
+            assert_eq!(display.get_dimensions(), (160, 128));
+            assert!(display.get_pixel(3, 100), "{:?}", layout);
+            assert!(display.get_pixel(127, 0), "{:?}", layout);
+            assert!(!display.get_pixel(100, 3));
+
+            // Rows below the 128x128 square do not fit on the rotated display
+            let lit = display.buffer.iter().map(|b| b.count_ones()).sum::<u32>();
+            assert_eq!(lit, 2, "{:?}", layout);
+
+            display
+                .set_rotation_remap(DisplayRotation::Rotate0)
+                .unwrap();
+            assert!(display.get_pixel(3, 100));
+            assert!(display.get_pixel(127, 0));
+        }
+    }
+
+    #[test]
+    fn get_pixel_reads_back_set_pixel() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate90] {
+                let mut display: GraphicsMode<_> = Builder::new()
+                    .with_rotation(rotation)
+                    .connect(CaptureInterface::new())
+                    .into();
+                display.set_framebuffer_layout(layout);
+                let (width, height) = display.get_dimensions();
+
+                display.set_pixel(3, 17, 1);
+                display.set_pixel(u32::from(width) - 1, u32::from(height) - 1, 1);
+
+                for (x, y) in [(3, 17), (u32::from(width) - 1, u32::from(height) - 1)] {
+                    assert!(display.get_pixel(x, y), "{:?} ({}, {})", layout, x, y);
+                }
+                assert!(!display.get_pixel(17, 3));
+                assert!(!display.get_pixel(width.into(), 0));
+
+                display.set_pixel(3, 17, 0);
+                assert!(!display.get_pixel(3, 17));
+            }
+        }
+    }
+
+    #[test]
+    fn draw_bitmap_fast_path_matches_blit() {
+        // A 13x5 bitmap, so the last byte of each row is partial
+        let bitmap = [0xFF, 0xFF, 0x81, 0x08, 0xAA, 0x50, 0x81, 0x08, 0xFF, 0xF8u8];
+
+        for dest in [(8, 3), (5, 3), (120, 0)] {
+            let new_display = |layout| {
+                let mut display: GraphicsMode<_> =
+                    Builder::new().connect(CaptureInterface::new()).into();
+                display.set_framebuffer_layout(layout);
+                display.fill_gradient_vertical(0, 255);
+                display.dirty = [CLEAN; MAX_PAGES];
+                display
+            };
+
+            let mut display = new_display(FramebufferLayout::RowMajor);
+            display.draw_bitmap(dest.0, dest.1, 13, 5, &bitmap);
+
+            for layout in [
+                FramebufferLayout::VerticalPages,
+                FramebufferLayout::RowMajor,
+                FramebufferLayout::DisplayRows,
+            ] {
+                let mut expected = new_display(layout);
+                expected.draw_region_rotated(&bitmap, (13, 5), dest, DisplayRotation::Rotate0);
+
+                for x in 0..128 {
+                    for y in 0..160 {
+                        assert_eq!(
+                            display.get_pixel(x, y),
+                            expected.get_pixel(x, y),
+                            "{:?} ({}, {})",
+                            dest,
+                            x,
+                            y
+                        );
+                    }
+                }
+                assert_eq!(display.dirty, expected.dirty, "{:?}", dest);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_and_far_away_bitmaps_draw_nothing() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.set_framebuffer_layout(FramebufferLayout::RowMajor);
+        display.dirty = [CLEAN; MAX_PAGES];
+
+        display.draw_bitmap(8, 0, 0, 4, &[0xFF; 4]);
+        display.draw_bitmap(8, 0, 8, 0, &[0xFF; 4]);
+        display.draw_bitmap(u32::MAX - 7, 0, 16, 2, &[0xFF; 4]);
+        display.draw_bitmap(0, u32::MAX - 1, 8, 4, &[0xFF; 4]);
+
+        assert!(display.framebuffer().iter().all(|&byte| byte == 0));
+        assert_eq!(display.dirty, [CLEAN; MAX_PAGES]);
+    }
+
+    #[test]
+    fn display_rows_copy_bitmaps_when_rotated() {
+        let bitmap = [0xF0, 0x0F, 0x81, 0x18u8];
+
+        for rotation in [DisplayRotation::Rotate90, DisplayRotation::Rotate270] {
+            let new_display = |iface: CaptureInterface, layout| {
+                let mut display: GraphicsMode<_> =
+                    Builder::new().with_rotation(rotation).connect(iface).into();
+                display.init().unwrap();
+                display.set_framebuffer_layout(layout);
+                display.flush().unwrap();
+                display
+            };
+
+            let rows = CaptureInterface::new();
+            let mut display = new_display(rows.clone(), FramebufferLayout::DisplayRows);
+            display.draw_bitmap(16, 3, 16, 2, &bitmap);
+            // Rows of the rotated view, 160 pixels wide
+            assert_eq!(display.framebuffer()[3 * 20 + 2..][..2], bitmap[..2]);
+            assert_eq!(display.framebuffer()[4 * 20 + 2..][..2], bitmap[2..]);
+
+            let pages = CaptureInterface::new();
+            let mut expected = new_display(pages.clone(), FramebufferLayout::VerticalPages);
+            expected.draw_region_rotated(&bitmap, (16, 2), (16, 3), DisplayRotation::Rotate0);
+            assert_eq!(display.dirty, expected.dirty);
+
+            rows.clear();
+            pages.clear();
+            display.flush_dirty().unwrap();
+            expected.flush_dirty().unwrap();
+            assert_eq!(rows.sent(), pages.sent());
+
+            display
+                .set_rotation_remap(DisplayRotation::Rotate0)
+                .unwrap();
+            assert!(display.get_pixel(16, 3) && display.get_pixel(31, 3));
+            assert!(!display.get_pixel(20, 3) && display.get_pixel(23, 4));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn exports_pbm_and_xbm() {
+        use crate::displaysize::DisplaySize;
+
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_size(DisplaySize::Display64x160)
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(CaptureInterface::new())
+            .into();
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(9, 0, 1);
+        display.set_pixel(159, 63, 1);
+
+        let mut pbm = vec![];
+        display.write_pbm(&mut pbm).unwrap();
+        assert_eq!(pbm[..10], *b"P4\n160 64\n");
+        assert_eq!(pbm.len(), 10 + 160 * 64 / 8);
+        assert_eq!(pbm[10..12], [0x80, 0x40]);
+        assert_eq!(pbm[pbm.len() - 1], 0x01);
+
+        let mut xbm = vec![];
+        display.write_xbm(&mut xbm, "screen").unwrap();
+        let xbm = std::string::String::from_utf8(xbm).unwrap();
+        assert!(xbm.starts_with(
+            "#define screen_width 160\n#define screen_height 64\n\
+             static unsigned char screen_bits[] = {\n    0x01, 0x02, 0x00,"
+        ));
+        assert!(xbm.ends_with(", 0x80 };\n"));
+        assert_eq!(xbm.matches("0x").count(), 160 * 64 / 8);
+    }
+
+    #[test]
+    fn draw_region_rotated_90() {
+        // An 8x16 "L": a full-height left edge and a 4 pixel foot along the bottom
+        let mut sprite = [0b1000_0000u8; 16];
+        sprite[15] = 0b1111_0000;
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.draw_region_rotated(&sprite, (8, 16), (10, 20), DisplayRotation::Rotate90);
+
+        // Rotated clockwise, the left edge becomes the top edge and the foot runs down the left
+        for x in 0..16 {
+            for y in 0..8 {
+                let expected = y == 0 || (x == 0 && y < 4);
+                assert_eq!(pixel(&display, 10 + x, 20 + y), expected, "({}, {})", x, y);
+            }
+        }
+        assert!(!pixel(&display, 10, 28));
+        assert!(!pixel(&display, 26, 20));
+    }
+
+    #[test]
+    fn draw_region_rotated_clips() {
+        let sprite = [0xFFu8; 16];
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.draw_region_rotated(&sprite, (8, 16), (120, 156), DisplayRotation::Rotate270);
+
+        assert!(pixel(&display, 127, 159));
+        assert_eq!(display.buffer[128 * 160 / 8..].iter().max(), Some(&0));
+    }
+
+    #[test]
+    fn draw_region_transformed_flips_before_rotating() {
+        // 3x2 pixels with only the top left one lit
+        let icon = [0x80, 0x00];
+        let cases = [
+            (DisplayRotation::Rotate0, false, false, (0, 0)),
+            (DisplayRotation::Rotate0, true, false, (2, 0)),
+            (DisplayRotation::Rotate0, false, true, (0, 1)),
+            (DisplayRotation::Rotate0, true, true, (2, 1)),
+            (DisplayRotation::Rotate90, true, false, (1, 2)),
+        ];
+
+        for (rotation, flip_horizontal, flip_vertical, lit) in cases {
+            let mut display: GraphicsMode<_> =
+                Builder::new().connect(CaptureInterface::new()).into();
+            display.draw_region_transformed(
+                &icon,
+                (3, 2),
+                (10, 10),
+                rotation,
+                flip_horizontal,
+                flip_vertical,
+            );
+
+            let lit_pixels: vec::Vec<_> = display
+                .pixels()
+                .filter(|&(_, _, on)| on)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            assert_eq!(
+                lit_pixels,
+                [(10 + lit.0, 10 + lit.1)],
+                "{:?} {} {}",
+                rotation,
+                flip_horizontal,
+                flip_vertical
+            );
+        }
+    }
+
+    #[test]
+    fn draw_sprite_frame() {
+        // Four 8x8 frames side by side; frame n is a filled square of n + 1 pixels
+        let mut sheet = [0u8; 4 * 8];
+        for frame in 0..4 {
+            for row in 0..=frame {
+                sheet[row * 4 + frame] = 0xFF << (7 - frame);
+            }
+        }
+        let sheet = SpriteSheet::new(&sheet, (8, 8), 4);
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.draw_sprite(&sheet, 2, (4, 4)).unwrap();
+
+        for x in 0..8 {
+            for y in 0..8 {
+                assert_eq!(
+                    pixel(&display, 4 + x, 4 + y),
+                    x < 3 && y < 3,
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+
+        assert!(matches!(
+            display.draw_sprite(&sheet, 4, (0, 0)),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "tiny-text")]
+    fn draw_text_prints_the_built_in_font() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.draw_text(10, 20, "1\n-");
+
+        // '1' has its stem in the third column, '-' is a line through row 4
+        assert!((21..27).all(|y| pixel(&display, 12, y)));
+        assert!((10..14).all(|x| pixel(&display, x, 32)));
+        assert!(!pixel(&display, 14, 32) && !pixel(&display, 16, 22));
+    }
+
+    #[test]
+    #[cfg(feature = "frame-dump")]
+    fn dump_frame_writes_hex_rows() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(9, 1, 1);
+
+        let mut dump = std::string::String::new();
+        display.dump_frame(&mut dump).unwrap();
+
+        let lines: vec::Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 161);
+        assert_eq!(lines[0], "SH1108 128x160");
+        assert_eq!(lines[1], std::format!("80{}", "0".repeat(30)));
+        assert_eq!(lines[2], std::format!("0040{}", "0".repeat(28)));
+    }
+
+    #[test]
+    #[cfg(feature = "frame-dump")]
+    fn dump_ascii_draws_the_frame() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(5, 1, 1);
+
+        let mut dump = std::string::String::new();
+        display.dump_ascii(&mut dump, 1).unwrap();
+        let lines: vec::Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 160);
+        assert_eq!(lines[1], std::format!(".....#{}", ".".repeat(122)));
+
+        // Blocks with any pixel on are on
+        dump.clear();
+        display.dump_ascii(&mut dump, 4).unwrap();
+        let lines: vec::Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 40);
+        assert_eq!(lines[0], std::format!("##{}", ".".repeat(30)));
+        assert_eq!(lines[1], ".".repeat(32));
+    }
+
+    /// A bottom-up 1bpp BMP file with the color table `palette` (as 0xRRGGBB) and `rows` of
+    /// packed pixels, top row first
+    #[cfg(feature = "bmp")]
+    fn bmp_1bpp(width: u32, palette: [u32; 2], rows: &[&[u8]]) -> vec::Vec<u8> {
+        let stride = (width as usize).div_ceil(32) * 4;
+        let data_start = 14 + 40 + 8;
+        let data_len = stride * rows.len();
+
+        let mut file = vec::Vec::new();
+        file.extend_from_slice(b"BM");
+        file.extend_from_slice(&((data_start + data_len) as u32).to_le_bytes());
+        file.extend_from_slice(&[0; 4]);
+        file.extend_from_slice(&(data_start as u32).to_le_bytes());
+        file.extend_from_slice(&40u32.to_le_bytes());
+        file.extend_from_slice(&width.to_le_bytes());
+        file.extend_from_slice(&(rows.len() as u32).to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&1u16.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&(data_len as u32).to_le_bytes());
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&2u32.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        for color in palette {
+            file.extend_from_slice(&color.to_le_bytes());
+        }
+        for row in rows.iter().rev() {
+            file.extend_from_slice(row);
+            file.resize(file.len() + stride - row.len(), 0);
+        }
+
+        file
+    }
+
+    #[test]
+    #[cfg(feature = "bmp")]
+    fn draw_bmp_copies_rows() {
+        let rows: [&[u8]; 2] = [&[0b1000_0000, 0b0000_0001], &[0b0100_0000, 0x00]];
+
+        for layout in [
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::DisplayRows,
+        ] {
+            for (palette, inverted) in [([0x000000, 0xFFFFFF], false), ([0xFFFFFF, 0], true)] {
+                let file = bmp_1bpp(16, palette, &rows);
+                let bmp = Bmp::from_slice(&file).unwrap();
+
+                let mut display: GraphicsMode<_> =
+                    Builder::new().connect(CaptureInterface::new()).into();
+                display.set_framebuffer_layout(layout);
+                display.draw_bmp(&bmp, 8, 4);
+                assert_eq!(display.get_pixel(8, 4), !inverted);
+                assert_eq!(display.get_pixel(23, 4), !inverted);
+                assert_eq!(display.get_pixel(9, 5), !inverted);
+                assert_eq!(display.get_pixel(9, 4), inverted);
+                assert!(!display.get_pixel(24, 4) && !display.get_pixel(8, 6));
+            }
+        }
+    }
+
+    #[test]
+    fn split_regions_draw_independently() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+        ] {
+            let iface = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+            display.init().unwrap();
+            display.set_framebuffer_layout(layout);
+            display.flush().unwrap();
+
+            assert!(display.split_rows_mut(12).is_err());
+            assert!(display.split_columns_mut(16).is_err());
+
+            let (mut top, mut bottom) = display.split_rows_mut(16).unwrap();
+            assert_eq!(top.dimensions(), (128, 16));
+            assert_eq!(
+                (bottom.origin(), bottom.dimensions()),
+                ((0, 16), (128, 144))
+            );
+            top.set_pixel(3, 15, true);
+            top.set_pixel(3, 16, true);
+            bottom.set_pixel(100, 0, true);
+            assert!(top.get_pixel(3, 15) && bottom.get_pixel(100, 0));
+
+            assert!(display.get_pixel(3, 15) && display.get_pixel(100, 16));
+            assert!(!display.get_pixel(3, 16));
+
+            iface.clear();
+            let stats = display.flush_dirty().unwrap();
+            assert_eq!((stats.pages, stats.bytes), (2, 2));
+        }
+
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(CaptureInterface::new())
+            .into();
+        assert!(display.split_rows_mut(16).is_err());
+
+        let (mut left, mut right) = display.split_columns_mut(40).unwrap();
+        assert_eq!(
+            (left.dimensions(), right.dimensions()),
+            ((40, 128), (120, 128))
+        );
+        left.set_pixel(39, 5, true);
+        right.set_pixel(0, 127, true);
+        assert!(display.get_pixel(39, 5) && display.get_pixel(40, 127));
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn split_regions_follow_the_display_clip() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.set_clip(Some(Rectangle::new(Point::new(0, 8), Size::new(64, 16))));
+
+        let (mut top, mut bottom) = display.split_rows_mut(16).unwrap();
+        top.set_pixel(3, 3, true);
+        top.set_pixel(3, 12, true);
+        bottom.set_pixel(63, 7, true);
+        bottom.set_pixel(64, 7, true);
+
+        assert!(!display.get_pixel(3, 3) && display.get_pixel(3, 12));
+        assert!(display.get_pixel(63, 23) && !display.get_pixel(64, 23));
+        #[cfg(feature = "metrics")]
+        assert_eq!(display.metrics().pixels_set, 2);
+    }
+
+    #[test]
+    fn set_size_resends_the_resolution() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.set_pixel(100, 0, 1);
+        iface.clear();
+
+        display.set_size(DisplaySize::Display64x160).unwrap();
+        assert_eq!(iface.commands(), [0xA9, 0x00]);
+        assert_eq!(display.get_dimensions(), (64, 160));
+        assert!(!pixel(&display, 100, 0));
+
+        iface.clear();
+        display.flush().unwrap();
+        assert_eq!(iface.data().len(), 64 * 20);
+
+        let mut small: GraphicsMode<_, { DisplaySize::Display64x160.framebuffer_size() }> =
+            Builder::new()
+                .with_size(DisplaySize::Display64x160)
+                .connect(CaptureInterface::new())
+                .into();
+        assert!(matches!(
+            small.set_size(DisplaySize::Display128x160),
+            Err(Error::BufferTooSmall)
+        ));
+        assert_eq!(small.get_dimensions(), (64, 160));
+    }
+
+    #[test]
+    fn blit_sprite_with_and_without_transparency() {
+        let mut sprite = Sprite::<8>::new(4, 4);
+        for i in 0..4 {
+            sprite.set_pixel(i, i, true);
+        }
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        for x in 10..14 {
+            display.set_pixel(x, 20, 1);
+            display.set_pixel(x, 30, 1);
+        }
+
+        display.blit_sprite(&sprite, 10, 20);
+        sprite.set_transparent(true);
+        display.blit_sprite(&sprite, 10, 30);
+
+        for x in 0..4 {
+            assert_eq!(pixel(&display, 10 + x, 20), x == 0, "opaque {}", x);
+            assert!(pixel(&display, 10 + x, 30), "transparent {}", x);
+            assert!(pixel(&display, 10 + x, 30 + x));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn draw_iter_matches_set_pixel() {
+        use embedded_graphics::prelude::*;
+
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate270] {
+                let new_display = || {
+                    let mut display: GraphicsMode<_> = Builder::new()
+                        .with_rotation(rotation)
+                        .connect(CaptureInterface::new())
+                        .into();
+                    display.set_framebuffer_layout(layout);
+                    display.set_clip(Some(Rectangle::new(Point::new(5, 0), Size::new(100, 90))));
+                    display.dirty = [CLEAN; MAX_PAGES];
+                    display
+                };
+                let points = (0..400).map(|i| Point::new(i * 7 % 170 - 5, i * 13 % 170 - 5));
+
+                let mut expected = new_display();
+                for point in points.clone().filter(|p| p.x >= 0 && p.y >= 0) {
+                    expected.set_pixel(point.x as u32, point.y as u32, 1);
+                }
+
+                let mut display = new_display();
+                display
+                    .draw_iter(points.map(|p| Pixel(p, BinaryColor::On)))
+                    .unwrap();
+
+                assert_eq!(display.buffer, expected.buffer, "{:?}", layout);
+                assert_eq!(display.dirty, expected.dirty, "{:?}", layout);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn draws_onto_another_target() {
+        use embedded_graphics::{
+            framebuffer::{buffer_size, Framebuffer},
+            image::{GetPixel, Image, ImageDrawableExt},
+            pixelcolor::raw::{BigEndian, RawU1},
+            prelude::*,
+        };
+
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(CaptureInterface::new())
+            .into();
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(159, 127, 1);
+        display.set_pixel(30, 40, 1);
+        assert_eq!(display.pixel(Point::new(30, 40)), Some(BinaryColor::On));
+        assert_eq!(display.pixel(Point::new(31, 40)), Some(BinaryColor::Off));
+        assert_eq!(display.pixel(Point::new(160, 0)), None);
+
+        let mut fb = Framebuffer::<
+            BinaryColor,
+            RawU1,
+            BigEndian,
+            200,
+            200,
+            { buffer_size::<BinaryColor>(200, 200) },
+        >::new();
+        Image::new(&display, Point::new(10, 20))
+            .draw(&mut fb)
+            .unwrap();
+        Image::new(
+            &display.sub_image(&Rectangle::new(Point::new(25, 35), Size::new(10, 10))),
+            Point::new(180, 180),
+        )
+        .draw(&mut fb)
+        .unwrap();
+
+        let lit: vec::Vec<_> = fb
+            .bounding_box()
+            .points()
+            .filter(|&p| fb.pixel(p) == Some(BinaryColor::On))
+            .collect();
+        assert_eq!(
+            lit,
+            [
+                Point::new(10, 20),
+                Point::new(40, 60),
+                Point::new(169, 147),
+                Point::new(185, 185),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn fills_match_pixel_drawing() {
+        use embedded_graphics::prelude::*;
+
+        let areas = [
+            Rectangle::new(Point::new(3, 5), Size::new(21, 13)),
+            Rectangle::new(Point::new(-4, 150), Size::new(200, 30)),
+            Rectangle::new(Point::new(8, 8), Size::new(8, 8)),
+            Rectangle::new(Point::new(40, 40), Size::zero()),
+        ];
+
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate90] {
+                for clip in [
+                    None,
+                    Some(Rectangle::new(Point::new(6, 0), Size::new(9, 12))),
+                ] {
+                    let new_display = || {
+                        let mut display: GraphicsMode<_> = Builder::new()
+                            .with_rotation(rotation)
+                            .connect(CaptureInterface::new())
+                            .into();
+                        display.set_framebuffer_layout(layout);
+                        display.fill_gradient_vertical(0, 255);
+                        display.set_clip(clip);
+                        display.dirty = [CLEAN; MAX_PAGES];
+                        display
+                    };
+
+                    for area in areas {
+                        for color in [BinaryColor::On, BinaryColor::Off] {
+                            let mut expected = new_display();
+                            expected
+                                .draw_iter(area.points().map(|p| Pixel(p, color)))
+                                .unwrap();
+
+                            let mut solid = new_display();
+                            solid.fill_solid(&area, color).unwrap();
+                            assert_eq!(solid.buffer, expected.buffer, "{:?} {:?}", area, layout);
+                            assert_eq!(solid.dirty, expected.dirty, "{:?} {:?}", area, layout);
+
+                            let mut contiguous = new_display();
+                            let colors = area.points().map(|p| {
+                                if (p.x + p.y) % 3 == 0 {
+                                    color
+                                } else {
+                                    color.invert()
+                                }
+                            });
+                            contiguous.fill_contiguous(&area, colors.clone()).unwrap();
+                            let mut expected = new_display();
+                            expected
+                                .draw_iter(area.points().zip(colors).map(|(p, c)| Pixel(p, c)))
+                                .unwrap();
+                            assert_eq!(contiguous.buffer, expected.buffer, "{:?}", area);
+                            assert_eq!(contiguous.dirty, expected.dirty, "{:?}", area);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn flush_region_sends_touched_pages() {
+        use embedded_graphics::prelude::*;
+
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        display.set_pixel(12, 20, 1);
+        iface.clear();
+
+        // Rotated, display x 10..14 and y 20..22 are panel rows 10..14 and columns 20..22
+        display
+            .flush_region(Rectangle::new(Point::new(10, 20), Size::new(4, 2)))
+            .unwrap();
+
+        assert_eq!(
+            iface.sent(),
+            [
+                Sent::Commands(vec![0xB0, 0x01, 0x04, 0x12]),
+                Sent::Data(vec![0x10, 0x00]),
+                Sent::Commands(vec![0xB0, 0x01, 0x04, 0x12]),
+            ]
+        );
+        // A new framebuffer counts as changed everywhere, and stays so
+        assert_eq!(display.dirty[1], (0, u8::MAX));
+
+        iface.clear();
+        display
+            .flush_region(Rectangle::new(Point::new(200, 0), Size::new(4, 4)))
+            .unwrap();
+        assert!(iface.sent().is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn clip_truncates_line() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{Line, PrimitiveStyle},
+        };
+
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(CaptureInterface::new())
+            .into();
+        let clip = Rectangle::new(Point::new(10, 10), Size::new(20, 20));
+        display.set_clip(Some(clip));
+        assert_eq!(display.get_clip(), Some(clip));
+
+        Line::new(Point::new(0, 15), Point::new(159, 15))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+            .draw(&mut display)
+            .unwrap();
+
+        // Rotated by 90 degrees, the logical row 15 is stored in panel column 15
+        for x in 0..160 {
+            assert_eq!(pixel(&display, 15, x), (10..30).contains(&x), "{}", x);
+        }
+
+        display.clear_clip();
+        assert_eq!(display.get_clip(), None);
+        display.set_pixel(0, 0, 1);
+        assert!(pixel(&display, 0, 0));
+    }
+
+    #[test]
+    fn draw_line_styled_dashes() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+
+        display.draw_line_styled((-4, 5), (20, 5), 0b1100_1100);
+
+        for x in 0..160 {
+            let on = x <= 20 && (x + 4) % 4 < 2;
+            assert_eq!(pixel(&display, x, 5), on, "{}", x);
+            assert!(!pixel(&display, x, 4) && !pixel(&display, x, 6));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn draw_line_styled_matches_line() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{Line, PrimitiveStyle},
+        };
+
+        // Steep, shallow, negative and with the minor axis exactly halfway at some steps
+        for (p0, p1) in [
+            ((3, 2), (9, 40)),
+            ((2, 3), (90, 17)),
+            ((100, 50), (10, 20)),
+            ((40, 90), (55, 10)),
+            ((0, 0), (4, 1)),
+            ((4, 1), (0, 0)),
+            ((10, 10), (16, 13)),
+            ((16, 20), (10, 23)),
+        ] {
+            let mut styled: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+            styled.draw_line_styled(p0, p1, 0xFF);
+
+            let mut line: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+            Line::new(Point::new(p0.0, p0.1), Point::new(p1.0, p1.1))
+                .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+                .draw(&mut line)
+                .unwrap();
+
+            assert_eq!(
+                styled.framebuffer(),
+                line.framebuffer(),
+                "{:?}..{:?}",
+                p0,
+                p1
+            );
+        }
+    }
+
+    #[test]
+    fn draw_line_styled_far_off_screen() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+
+        // Only the steps on the display are walked, with the pattern still counted from `p0`
+        display.draw_line_styled((i32::MIN, 3), (i32::MAX, 3), 0b1000_0000);
+        display.draw_line_styled((7, i32::MAX), (7, i32::MIN + 1), 0xFF);
+        display.draw_line_styled((i32::MIN, i32::MIN), (-1, -1), 0xFF);
+
+        for x in 0..128 {
+            assert_eq!(pixel(&display, x, 3), x == 7 || x % 8 == 0, "{}", x);
+        }
+        assert!((0..160).all(|y| pixel(&display, 7, y)));
+        assert_eq!(display.out_of_bounds_pixels(), 0);
+    }
+
+    #[test]
+    fn power_budget_dims_bright_frames() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_power_budget(25)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        iface.clear();
+
+        // One pixel row in 8 is 12.5% lit, within the budget
+        for x in 0..128 {
+            display.set_pixel(x, 0, 1);
+        }
+        display.flush().unwrap();
+        assert!(!iface.commands().contains(&0x81));
+
+        // Fully lit is 4 times the budget, so the default contrast of 0x80 drops to a quarter
+        iface.clear();
+        display.fill_gradient_vertical(255, 255);
+        display.flush().unwrap();
+        assert!(iface.commands().windows(2).any(|c| c == [0x81, 0x20]));
+        assert_eq!(display.get_contrast(), 0x80);
+
+        iface.clear();
+        display.clear();
+        display.flush().unwrap();
+        assert!(iface.commands().windows(2).any(|c| c == [0x81, 0x80]));
+    }
+
+    #[test]
+    fn framebuffer_stats_counts_pixels() {
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(CaptureInterface::new())
+            .into();
+        assert_eq!(
+            display.framebuffer_stats(),
+            FbStats {
+                lit_pixels: 0,
+                bounding_box: None,
+                coverage_percent: 0,
+            }
+        );
+
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            display.set_framebuffer_layout(layout);
+            for x in 10..40 {
+                for y in 3..100 {
+                    display.set_pixel(x, y, 1);
+                }
+            }
+            display.set_pixel(150, 127, 1);
+
+            assert_eq!(
+                display.framebuffer_stats(),
+                FbStats {
+                    lit_pixels: 30 * 97 + 1,
+                    bounding_box: Some(((10, 3), (151, 128))),
+                    coverage_percent: 14,
+                },
+                "{:?}",
+                layout
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn flush_framebuffer_matches_draw_target() {
+        use embedded_graphics::{
+            framebuffer::{buffer_size, Framebuffer},
+            pixelcolor::raw::{BigEndian, RawU1},
+            prelude::*,
+            primitives::{Circle, PrimitiveStyle},
+        };
+
+        let circle = Circle::new(Point::new(20, 30), 50)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 3));
+        let mut fb = Framebuffer::<
+            BinaryColor,
+            RawU1,
+            BigEndian,
+            128,
+            160,
+            { buffer_size::<BinaryColor>(128, 160) },
+        >::new();
+        circle.draw(&mut fb).unwrap();
+
+        let expected = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(expected.clone()).into();
+        display.init().unwrap();
+        expected.clear();
+        circle.draw(&mut display).unwrap();
+        display.flush().unwrap();
+
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let iface = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+            display.init().unwrap();
+            iface.clear();
+            display.set_framebuffer_layout(layout);
+            display.flush_framebuffer(fb.data(), fb.size()).unwrap();
+
+            assert_eq!(iface.data(), expected.data(), "{:?}", layout);
+        }
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        assert!(matches!(
+            display.flush_framebuffer(&fb.data()[..100], fb.size()),
+            Err(Error::BufferTooSmall)
+        ));
+        assert!(matches!(
+            display.flush_framebuffer(fb.data(), Size::new(129, 160)),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "framebuffer")]
+    fn framebuffer_round_trips() {
+        use embedded_graphics::{
+            framebuffer::buffer_size,
+            pixelcolor::raw::BigEndian,
+            prelude::*,
+            primitives::{Circle, PrimitiveStyle},
+        };
+
+        type Fb = Framebuffer<
+            BinaryColor,
+            RawU1,
+            BigEndian,
+            128,
+            160,
+            { buffer_size::<BinaryColor>(128, 160) },
+        >;
+
+        let mut scene = Fb::new();
+        Circle::new(Point::new(20, 30), 50)
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 3))
+            .draw(&mut scene)
+            .unwrap();
+
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let mut display: GraphicsMode<_> =
+                Builder::new().connect(CaptureInterface::new()).into();
+            display.init().unwrap();
+            display.set_framebuffer_layout(layout);
+            display.flush_eg_framebuffer(&scene).unwrap();
+
+            let mut copy = Fb::new();
+            display.copy_to_framebuffer(&mut copy);
+            assert_eq!(copy.data(), scene.data(), "{:?}", layout);
+        }
+    }
+
+    #[test]
+    fn flush_dma_matches_flush() {
+        let expected = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(expected.clone()).into();
+        display.init().unwrap();
+        display.set_pixel(3, 100, 1);
+        expected.clear();
+        display.flush().unwrap();
+
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let iface = CaptureInterface::new();
+            let mut display = dma_display(iface.clone());
+            display.init().unwrap();
+            display.set_framebuffer_layout(layout);
+            display.set_pixel(3, 100, 1);
+            iface.clear();
+
+            let mut flush = display.flush_dma();
+            assert!(iface.sent().is_empty());
+            let mut polls = 1;
+            while !flush.poll().unwrap() {
+                polls += 1;
+            }
+            assert!(flush.is_done());
+            let mut display = flush.into_display();
+
+            assert_eq!(iface.data(), expected.data(), "{:?}", layout);
+            // One poll to start each page and one to see the last one finish
+            if layout == FramebufferLayout::VerticalPages {
+                assert_eq!(polls, 21);
+            }
+            assert!(!display.flush_dirty().unwrap().changed());
+        }
+    }
+
+    /// A display connected to `iface` with its framebuffer in `'static` memory, for flushes
+    /// through DMA
+    fn dma_display<DI: DmaInterface>(
+        iface: DI,
+    ) -> GraphicsMode<DI, 0, RuntimeRotation, &'static mut [u8]> {
+        let properties =
+            DisplayProperties::new(iface, DisplaySize::Display128x160, DisplayRotation::Rotate0);
+
+        GraphicsMode::with_storage(properties, static_framebuffer(MAX_BUFFER_SIZE))
+    }
+
+    /// A DMA interface that keeps every transfer running until it is polled, and fails the test
+    /// if a command is sent in the meantime
+    struct BackgroundDma {
+        iface: CaptureInterface,
+        running: Option<DmaPage>,
+    }
+
+    impl WriteOnlyDataCommand for BackgroundDma {
+        fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+            assert!(self.running.is_none(), "command sent during a DMA transfer");
+            self.iface.send_commands(cmd)
+        }
+
+        fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+            self.iface.send_data(buf)
+        }
+    }
+
+    impl DmaInterface for BackgroundDma {
+        fn start_data(&mut self, page: DmaPage) -> Result<(), (DmaPage, DisplayError)> {
+            self.iface
+                .send_data(DataFormat::U8(page.as_slice()))
+                .unwrap();
+            self.running = Some(page);
+            Ok(())
+        }
+
+        fn poll_data(&mut self) -> Option<(DmaPage, Result<(), DisplayError>)> {
+            self.running.take().map(|page| (page, Ok(())))
+        }
+    }
+
+    #[test]
+    fn flush_dma_wakes_the_display_before_a_transfer() {
+        let iface = CaptureInterface::new();
+        let mut display = dma_display(BackgroundDma {
+            iface: iface.clone(),
+            running: None,
+        });
+        display.init().unwrap();
+        display.set_auto_dim(Some((10, 0x10))).unwrap();
+        display.set_auto_off(Some(20)).unwrap();
+        display.idle_tick(20).unwrap();
+        iface.clear();
+
+        let mut flush = display.flush_dma();
+        while !flush.poll().unwrap() {}
+        drop(flush);
+
+        // Restored with the first page, before its transfer started
+        let first_data = iface
+            .sent()
+            .iter()
+            .position(|sent| matches!(sent, Sent::Data(_)))
+            .unwrap();
+        assert!(iface.sent()[..first_data]
+            .iter()
+            .any(|sent| *sent == Sent::Commands(vec![0x81, 0x80])));
+        assert!(iface.sent()[..first_data]
+            .iter()
+            .any(|sent| *sent == Sent::Commands(vec![0xAF])));
+        assert_eq!(iface.data().len(), 128 * 160 / 8);
+    }
+
+    #[test]
+    fn flush_dirty_sends_changed_spans() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let iface = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+            display.init().unwrap();
+            iface.clear();
+            display.set_framebuffer_layout(layout);
+
+            // A new framebuffer has never been sent, so all of it is dirty
+            display.flush_dirty().unwrap();
+            assert_eq!(iface.data().len(), 128 * 160 / 8);
+
+            iface.clear();
+            assert!(!display.flush_dirty().unwrap().changed());
+            assert!(iface.sent().is_empty());
+
+            display.set_pixel(5, 0, 1);
+            display.set_pixel(10, 25, 1);
+            display.set_pixel(12, 30, 1);
+            assert_eq!(
+                display.flush_dirty().unwrap(),
+                FlushStats { pages: 2, bytes: 4 }
+            );
+
+            assert_eq!(
+                iface.sent(),
+                [
+                    Sent::Commands(vec![0xB0, 0x00, 0x05, 0x11]),
+                    Sent::Data(vec![0x01]),
+                    Sent::Commands(vec![0xB0, 0x00, 0x05, 0x11]),
+                    Sent::Commands(vec![0xB0, 0x03, 0x0A, 0x11]),
+                    Sent::Data(vec![0x02, 0x00, 0x40]),
+                    Sent::Commands(vec![0xB0, 0x03, 0x0A, 0x11]),
+                ],
+                "{:?}",
+                layout
+            );
+        }
+    }
+
+    #[test]
+    fn flush_chunked_spreads_the_update() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.flush().unwrap();
+        iface.clear();
+
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(99, 0, 1);
+        display.set_pixel(3, 8, 1);
+
+        assert!(!display.flush_chunked(64).unwrap());
+        assert_eq!(iface.data().len(), 64);
+
+        // Drawn after the snapshot, but in a part of it that was not sent yet
+        display.set_pixel(98, 0, 1);
+        assert!(display.flush_chunked(64).unwrap());
+        assert_eq!(iface.data().len(), 100 + 1);
+        assert_eq!(iface.data()[98..], [0x01, 0x01, 0x01]);
+
+        // The next round sends what changed since the snapshot
+        assert!(display.flush_chunked(64).unwrap());
+        assert_eq!(iface.data()[101..], [0x01]);
+        iface.clear();
+        assert!(display.flush_chunked(64).unwrap());
+        assert!(iface.sent().is_empty());
+    }
+
+    #[test]
+    fn flush_sends_one_transfer_per_page() {
+        use crate::displaysize::DisplaySize;
+
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_size(DisplaySize::Display64x160)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        iface.clear();
+        display.set_pixel(1, 9, 1);
+        display.flush().unwrap();
+
+        let transfers: vec::Vec<_> = iface
+            .sent()
+            .into_iter()
+            .filter_map(|sent| match sent {
+                Sent::Data(data) => Some(data),
+                Sent::Commands(_) => None,
+            })
+            .collect();
+        assert_eq!(transfers.len(), 20);
+        assert!(transfers.iter().all(|page| page.len() == 64));
+        assert_eq!(display.framebuffer().len(), 64 * 160 / 8);
+        assert_eq!(display.framebuffer_page(1), Some(&transfers[1][..]));
+        assert_eq!(display.framebuffer_page(1).unwrap()[1], 0x02);
+        assert_eq!(display.framebuffer_page(20), None);
+
+        display.set_framebuffer_layout(FramebufferLayout::RowMajor);
+        assert_eq!(display.framebuffer_page(0), None);
+    }
+
+    #[test]
+    fn flush_hook_reports_progress() {
+        use core::cell::RefCell;
+
+        std::thread_local! {
+            static EVENTS: RefCell<vec::Vec<FlushEvent>> = const { RefCell::new(vec::Vec::new()) };
+        }
+        fn record(event: FlushEvent) {
+            EVENTS.with(|events| events.borrow_mut().push(event));
+        }
+        let take = || EVENTS.with(|events| events.take());
+
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_flush_hook(record)
+            .connect(StubInterface)
+            .into();
+        display.init().unwrap();
+        take();
+
+        display.flush().unwrap();
+        let events = take();
+        assert_eq!(events.len(), 22);
+        assert_eq!(events[0], FlushEvent::Start);
+        assert!(events[1..21].iter().all(|&e| e == FlushEvent::Chunk(128)));
+        assert_eq!(events[21], FlushEvent::End(128 * 20));
+
+        // A chunked flush starts with its first call and ends with its last one
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(0, 8, 1);
+        assert!(!display.flush_chunked(1).unwrap());
+        assert_eq!(take(), [FlushEvent::Start, FlushEvent::Chunk(1)]);
+        assert!(display.flush_chunked(1).unwrap());
+        assert_eq!(take(), [FlushEvent::Chunk(1), FlushEvent::End(2)]);
+
+        display.set_flush_hook(None);
+        display.flush().unwrap();
+        assert!(take().is_empty());
+    }
+
+    #[test]
+    fn kick_runs_before_every_page() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+        display.init().unwrap();
+
+        let mut kicks = 0;
+        display.flush_with_kick(|| kicks += 1).unwrap();
+        assert_eq!(kicks, 20);
+
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(5, 100, 1);
+        kicks = 0;
+        let stats = display.flush_dirty_with_kick(|| kicks += 1).unwrap();
+        assert_eq!((kicks, stats.pages), (2, 2));
+    }
+
+    #[test]
+    fn init_with_reset_pulses_before_init() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        let mut delay = DelayRecorder::new();
+
+        display.init_with_reset(&mut PinStub, &mut delay).unwrap();
+
+        assert_eq!(delay.delays(), [1, 1, 1]);
+        assert_eq!(iface.commands()[..2], [0xD5, 0x60]);
+    }
+
+    #[test]
+    fn init_with_timing_waits_as_configured() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_power_timing(PowerTiming {
+                charge_pump_ms: 30,
+                ..PowerTiming::default()
+            })
+            .connect(iface.clone())
+            .into();
+        let mut delay = DelayRecorder::new();
+
+        display.init_with_timing(&mut PinStub, &mut delay).unwrap();
+
+        // Reset low and release, then around display on
+        assert_eq!(delay.delays_us(), [10, 10]);
+        assert_eq!(delay.delays(), [30, 100]);
+        assert!(display.is_initialized());
+        assert_eq!(iface.commands()[..2], [0xD5, 0x60]);
+        assert_eq!(iface.commands().last(), Some(&0xAF));
+    }
+
+    #[test]
+    #[should_panic(expected = "display flushed before init()")]
+    fn flush_before_init_panics() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.flush().unwrap();
+    }
+
+    #[test]
+    fn reset_requires_init_again() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        let mut delay = DelayRecorder::new();
+        assert!(!display.is_initialized());
+
+        display.init_with_reset(&mut PinStub, &mut delay).unwrap();
+        assert!(display.is_initialized());
+
+        display.reset(&mut PinStub, &mut delay).unwrap();
+        assert!(!display.is_initialized());
+    }
+
+    #[test]
+    fn flush_with_shadow_sends_only_changed_bytes() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let iface = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+            display.init().unwrap();
+            display.set_framebuffer_layout(layout);
+            let mut shadow = [0u8; 128 * 160 / 8];
+
+            // Drawing the same pixels again sends nothing
+            display.set_pixel(2, 0, 1);
+            display.flush_with_shadow(&mut shadow).unwrap();
+            iface.clear();
+            display.clear();
+            display.set_pixel(2, 0, 1);
+            display.flush_with_shadow(&mut shadow).unwrap();
+            assert!(iface.sent().is_empty(), "{:?}", layout);
+
+            display.set_pixel(20, 9, 1);
+            display.set_pixel(100, 9, 1);
+            display.flush_with_shadow(&mut shadow).unwrap();
+
+            assert_eq!(
+                iface.sent(),
+                [
+                    Sent::Commands(vec![0xB0, 0x01, 0x04, 0x12]),
+                    Sent::Data(vec![0x02]),
+                    Sent::Commands(vec![0xB0, 0x01, 0x04, 0x12]),
+                    Sent::Commands(vec![0xB0, 0x01, 0x04, 0x17]),
+                    Sent::Data(vec![0x02]),
+                    Sent::Commands(vec![0xB0, 0x01, 0x04, 0x17]),
+                ],
+                "{:?}",
+                layout
+            );
+            assert_eq!(shadow[128 + 20], 0x02);
+        }
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.init().unwrap();
+        assert!(matches!(
+            display.flush_with_shadow(&mut [0; 16]),
+            Err(Error::BufferTooSmall)
+        ));
+    }
+
+    #[test]
+    fn pixel_shift_moves_flushed_image() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let shifted = CaptureInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new().connect(shifted.clone()).into();
+            display.init().unwrap();
+            display.set_framebuffer_layout(layout);
+            draw_pattern(&mut display);
+            display.set_pixel_shift(-2, 3);
+            shifted.clear();
+            display.flush().unwrap();
+
+            // The same pattern drawn 2 columns left and 3 rows down, cut off at the edges
+            let expected = CaptureInterface::new();
+            let mut reference: GraphicsMode<_> = Builder::new().connect(expected.clone()).into();
+            reference.init().unwrap();
+            let mut pattern: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+            draw_pattern(&mut pattern);
+            for x in 2..128 {
+                for y in 0..157 {
+                    if pattern.get_pixel(x, y) {
+                        reference.set_pixel(x - 2, y + 3, 1);
+                    }
+                }
+            }
+            expected.clear();
+            reference.flush().unwrap();
+
+            assert_eq!(shifted.data(), expected.data(), "{:?}", layout);
+
+            // A change to one page reaches both pages its rows are shifted into. Column 10 also
+            // holds pattern pixels in rows 3 and 10, which end up in bits 6 and 5.
+            shifted.clear();
+            display.set_pixel(10, 6, 1);
+            display.flush_dirty().unwrap();
+            assert_eq!(shifted.data(), [0x40, 0x22], "{:?}", layout);
+            assert!(shifted.commands().windows(2).any(|c| c == [0xB0, 0x01]));
+        }
+    }
+
+    #[test]
+    fn builder_mirror_applies_on_init() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_mirror(true, false)
+            .connect(iface.clone())
+            .into();
+        assert!(iface.sent().is_empty());
+
+        display.init().unwrap();
+
+        // Horizontal mirroring flips the segment remap of the unrotated orientation
+        assert!(iface.commands().windows(2).any(|c| c == [0xA1, 0xC0]));
+    }
+
+    #[test]
+    fn modes_are_built_from_hand_made_properties() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.assume_initialized();
+
+        let mut display: GraphicsMode<_> = GraphicsMode::from_properties(properties);
+        display.set_pixel(1, 0, 1);
+        display.flush_dirty().unwrap();
+        assert_eq!(iface.data()[..2], [0x00, 0x01]);
+
+        let properties = display.into_properties();
+        assert!(properties.is_initialized());
+        let terminal: TerminalMode<_> = TerminalMode::from_properties(properties);
+        assert_eq!(terminal.into_properties().get_dimensions(), (128, 160));
+    }
+
+    #[test]
+    fn verify_finds_and_repairs_corrupted_ram() {
+        let iface = RamInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.set_pixel(5, 17, 1);
+        display.flush_verified().unwrap();
+
+        iface.set_ram(16 + 5, 2, &[0x00, 0xFF]);
+        iface.set_ram(16 + 100, 7, &[0x10]);
+        let Err(VerifyError::Mismatch(mismatch)) = display.verify() else {
+            panic!("corruption not detected");
+        };
+        assert_eq!(
+            mismatch,
+            Mismatch {
+                pages: 2,
+                bytes: 3,
+                first: (2, 5),
+            }
+        );
+
+        let stats = display.flush_dirty().unwrap();
+        assert_eq!((stats.pages, stats.bytes), (2, 3));
+        assert_eq!(iface.page(2)[16 + 5..][..2], [0x02, 0x00]);
+        display.verify().unwrap();
+    }
+
+    #[test]
+    fn framebuffer_can_live_in_a_borrowed_slice() {
+        let iface = CaptureInterface::new();
+        let properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut frame = [0xAA; MAX_BUFFER_SIZE];
+
+        let mut display: GraphicsMode<_, 0, RuntimeRotation, &mut [u8]> =
+            GraphicsMode::with_storage(properties, &mut frame[..]);
+        display.init().unwrap();
+        display.set_pixel(2, 9, 1);
+        display.flush_dirty().unwrap();
+        assert_eq!(iface.data()[128..131], [0x00, 0x00, 0x02]);
+
+        let (_, storage) = display.into_parts();
+        assert_eq!(storage[128 + 2], 0x02);
+        assert_eq!(frame.iter().filter(|&&byte| byte != 0).count(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "framebuffer too small")]
+    fn too_small_storage_is_rejected() {
+        let properties = DisplayProperties::new(
+            StubInterface,
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut frame = [0; 64];
+
+        let _: GraphicsMode<_, 0, RuntimeRotation, &mut [u8]> =
+            GraphicsMode::with_storage(properties, &mut frame[..]);
+    }
+
+    #[test]
+    fn release_interface_returns_the_bus() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+
+        // The released interface can be handed to a new driver with different settings
+        let released = display.release_interface();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate180)
+            .connect(released)
+            .into();
+        iface.clear();
+        display.init().unwrap();
+
+        assert!(!iface.commands().is_empty());
+    }
+
+    #[test]
+    fn tuning_is_sent_from_the_mode() {
+        use crate::mode::{displaymode::DisplayTuning, TerminalMode};
+
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        iface.clear();
+        display.apply_precharge(0x3, 0x4).unwrap();
+        display.apply_clock_divider(0x8, 0x1).unwrap();
+        assert_eq!(iface.commands(), [0xD9, 0x43, 0xD5, 0x81]);
+
+        let mut terminal: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        iface.clear();
+        terminal.apply_vcom_level(0x40).unwrap();
+        assert_eq!(iface.commands(), [0xDB, 0x40]);
+    }
+
+    #[test]
+    fn builder_tuning_flows_into_init() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_clock_divider(0xF, 0x1)
+            .with_precharge(0x2, 0xA)
+            .with_vcom_level(0x40)
+            .connect(iface.clone())
+            .into();
+
+        display.init().unwrap();
+
+        let commands = iface.commands();
+        assert_eq!(commands[..2], [0xD5, 0xF1]);
+        assert!(commands.windows(2).any(|w| w == [0xD9, 0xA2]));
+        assert!(commands.windows(2).any(|w| w == [0xDB, 0x40]));
+    }
+
+    #[test]
+    fn exactly_sized_framebuffer() {
+        use crate::displaysize::DisplaySize;
+
+        const SIZE: usize = DisplaySize::Display64x160.framebuffer_size();
+        assert_eq!(SIZE, 1280);
+
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_, SIZE> = Builder::new()
+            .with_size(DisplaySize::Display64x160)
+            .connect(iface.clone())
+            .into();
+        display.init().unwrap();
+        iface.clear();
+        display.set_pixel(63, 159, 1);
+        display.flush().unwrap();
+
+        assert_eq!(iface.data().len(), SIZE);
+        assert_eq!(iface.data()[SIZE - 1], 0x80);
+    }
+
+    #[test]
+    #[should_panic(expected = "framebuffer too small")]
+    fn undersized_framebuffer_panics() {
+        let _: GraphicsMode<_, 1280> = Builder::new().connect(CaptureInterface::new()).into();
+    }
+
+    #[test]
+    fn crossfade_ends_at_other() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        let mut delay = DelayRecorder::new();
+        let other = [0xFFu8; 128 * 160 / 8];
+        display.init().unwrap();
+        iface.clear();
+
+        display.dissolve_step(&other, 8);
+        let lit: u32 = display.buffer.iter().map(|b| b.count_ones()).sum();
+        assert_eq!(lit, 128 * 160 / 2);
+
+        display.clear();
+        display.crossfade(&other, 4, 20, &mut delay).unwrap();
+
+        assert_eq!(display.buffer[..other.len()], other);
+        assert_eq!(iface.data().len(), 4 * other.len());
+        assert_eq!(delay.delays(), [20, 20, 20]);
+    }
+
+    #[test]
+    fn gradient_dither() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+
+        // A flat 50% level lights alternate pixels of every column
+        display.fill_gradient_vertical(128, 128);
+        assert_eq!(display.buffer[..4], [0x55, 0xAA, 0x55, 0xAA]);
+        assert_eq!(display.buffer[128 * 19..][..4], [0x55, 0xAA, 0x55, 0xAA]);
+
+        display.fill_gradient_vertical(0, 255);
+        assert_eq!(display.buffer[..128].iter().max(), Some(&0));
+        assert_eq!(display.buffer[128 * 10..][..4], [0x55, 0xAA, 0x55, 0xAA]);
+        assert_eq!(display.buffer[128 * 19..][..4], [0xF7, 0xFF, 0xFF, 0xFF]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn gray_images_are_dithered() {
+        use embedded_graphics::{image::ImageRaw, pixelcolor::Gray4};
+
+        // An 8x8 image at level 8 of 15, just over half brightness
+        let data = [0x88; 32];
+        let image = ImageRaw::<Gray4>::new(&data, 8);
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+
+        display.draw_gray_image(&image, (0, 0), Dither::Ordered);
+        assert_eq!(display.buffer[..4], [0x55, 0xAA, 0x55, 0xAA]);
+        assert_eq!(display.buffer[8], 0);
+
+        display.draw_gray_image(&image, (0, 0), Dither::FloydSteinberg);
+        let lit: u32 = display.buffer[..8]
+            .iter()
+            .map(|byte| byte.count_ones())
+            .sum();
+        assert!((32..=36).contains(&lit), "{} pixels lit", lit);
+
+        // Full levels need no dithering
+        let white = ImageRaw::<Gray4>::new(&[0xFF; 32], 8);
+        display.draw_gray_image(&white, (4, 0), Dither::FloydSteinberg);
+        assert_eq!(display.buffer[4..12], [0xFF; 8]);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn bright_gray8_images_are_dithered() {
+        use embedded_graphics::{image::ImageRaw, pixelcolor::Gray8};
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+
+        for dither in [Dither::Ordered, Dither::FloydSteinberg] {
+            // An 8x8 image at 200 of 255 lights most pixels
+            let image = ImageRaw::<Gray8>::new(&[200; 64], 8);
+            display.draw_gray_image(&image, (0, 0), dither);
+            let lit: u32 = display.buffer[..8]
+                .iter()
+                .map(|byte| byte.count_ones())
+                .sum();
+            assert!((44..=56).contains(&lit), "{:?}: {} pixels lit", dither, lit);
+
+            let white = ImageRaw::<Gray8>::new(&[255; 64], 8);
+            display.draw_gray_image(&white, (0, 0), dither);
+            assert_eq!(display.buffer[..8], [0xFF; 8]);
+        }
+    }
+
+    #[test]
+    fn draw_rle_image_decodes_runs() {
+        // 10x3 image with runs wrapping across rows, and the data ending one pixel short:
+        //   ..######..
+        //   ##......##
+        //   #########
+        let rle = [2, 6, 2, 2, 6, 11];
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.set_pixel(9, 2, 1);
+        display.set_pixel(0, 3, 1);
+        display.draw_rle_image((0, 0), &rle, (10, 3));
+
+        let expected = ["..######..", "##......##", "#########"];
+        for (y, row) in expected.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                assert_eq!(
+                    pixel(&display, x as u32, y as u32),
+                    c == '#',
+                    "({}, {})",
+                    x,
+                    y
+                );
+            }
+        }
+        assert!(pixel(&display, 9, 2));
+        assert!(pixel(&display, 0, 3));
+    }
+
+    #[test]
+    fn row_major_layout_packs_rows() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(CaptureInterface::new()).into();
+        display.set_framebuffer_layout(FramebufferLayout::RowMajor);
+
+        display.set_pixel(0, 0, 1);
+        display.set_pixel(9, 1, 1);
+
+        assert_eq!(display.buffer[0], 0b1000_0000);
+        assert_eq!(display.buffer[16 + 1], 0b0100_0000);
+    }
+
+    #[test]
+    fn reinit_resends_the_framebuffer() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.set_pixel(3, 3, 1);
+        display.flush().unwrap();
+
+        display.reinit(false).unwrap();
+        iface.clear();
+        display.flush_dirty().unwrap();
+        assert_eq!(iface.data().len(), 128 * 160 / 8);
+        assert_eq!(iface.data()[3], 0x08);
+
+        iface.clear();
+        display.reinit(true).unwrap();
+        assert_eq!(iface.data().len(), 128 * 160 / 8);
+        display.flush_dirty().unwrap();
+        assert_eq!(iface.data().len(), 128 * 160 / 8);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn clear_region_masks_partial_pages() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+            display.set_framebuffer_layout(layout);
+            display
+                .fill_solid(&display.bounding_box(), BinaryColor::On)
+                .unwrap();
+
+            display.clear_region(Rectangle::new(Point::new(10, 4), Size::new(20, 20)));
+            assert!(display.get_pixel(9, 4) && display.get_pixel(30, 4));
+            assert!(display.get_pixel(10, 3) && display.get_pixel(10, 24));
+            assert!(!display.get_pixel(10, 4) && !display.get_pixel(29, 23));
+            assert!(!display.get_pixel(20, 8) && !display.get_pixel(20, 15));
+        }
+    }
+
+    #[test]
+    fn blit_ops_combine_bitmaps_with_the_framebuffer() {
+        each_layout(|mut display| {
+            // Pixels 8..12 on in both rows
+            display.draw_bitmap(8, 0, 12, 2, &[0xF0, 0x00, 0xF0, 0x00]);
+
+            let bitmap = [0b1100_1100, 0b1100_0000];
+            let row = |display: &GraphicsMode<_>| -> u16 {
+                (0..12).fold(0, |row, x| {
+                    row << 1 | u16::from(display.get_pixel(8 + x, 1))
+                })
+            };
+
+            display.draw_bitmap_with(8, 1, 12, 1, &bitmap, BlitOp::Or);
+            assert_eq!(row(&display), 0b1111_1100_1100);
+            display.draw_bitmap_with(8, 1, 12, 1, &bitmap, BlitOp::Xor);
+            assert_eq!(row(&display), 0b0011_0000_0000);
+            display.draw_bitmap_with(8, 1, 12, 1, &[0b0110_0000], BlitOp::And);
+            assert_eq!(row(&display), 0b0010_0000_0000);
+            display.draw_bitmap_with(8, 1, 12, 1, &bitmap, BlitOp::Copy);
+            assert_eq!(row(&display), 0b1100_1100_1100);
+
+            // Pixels right of the bitmap and in other rows are left alone
+            assert!(!display.get_pixel(20, 1) && display.get_pixel(11, 0));
+        });
+    }
+
+    #[test]
+    fn page_aligned_bitmaps_match_the_row_major_layout() {
+        // 11 rows, so the second page is partial
+        let bitmap: [u8; 22] = core::array::from_fn(|i| (i as u8).wrapping_mul(37) ^ 0x5A);
+        let draw = |layout| {
+            let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+            display.set_framebuffer_layout(layout);
+            display.fill_gradient_vertical(128, 128);
+            display.dirty = [CLEAN; MAX_PAGES];
+            for op in [BlitOp::Xor, BlitOp::Copy, BlitOp::Or, BlitOp::And] {
+                display.draw_bitmap_with(3, 16, 13, 11, &bitmap, op);
+                display.draw_bitmap_with(3, 40, 13, 11, &bitmap, op);
+            }
+            display
+        };
+
+        let pages = draw(FramebufferLayout::VerticalPages);
+        let rows = draw(FramebufferLayout::RowMajor);
+        for y in 0..64 {
+            for x in 0..32 {
+                assert_eq!(pages.get_pixel(x, y), rows.get_pixel(x, y), "{:?}", (x, y));
+            }
+        }
+        assert_eq!(
+            pages.dirty[..7],
+            [CLEAN, CLEAN, (3, 16), (3, 16), CLEAN, (3, 16), (3, 16)]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn drawing_outside_the_display_is_counted() {
+        use embedded_graphics::{
+            prelude::*,
+            primitives::{Line, PrimitiveStyle},
+        };
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+        display.set_clip(Some(Rectangle::new(Point::zero(), Size::new(10, 10))));
+        display.set_pixel(20, 20, 1);
+        display.set_pixel(128, 0, 1);
+        display.draw_bitmap(124, 0, 8, 1, &[0xFF]);
+        assert_eq!(display.out_of_bounds_pixels(), 5);
+
+        // Only strict mode turns it into an error, after drawing what is on the display
+        display.reset_out_of_bounds();
+        display.clear_clip();
+        let line = Line::new(Point::new(120, 5), Point::new(130, 5))
+            .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1));
+        line.draw(&mut display).unwrap();
+        display.set_strict_bounds(true);
+        assert!(matches!(
+            line.draw(&mut display),
+            Err(DisplayError::OutOfBoundsError)
+        ));
+        assert!(matches!(
+            display.fill_solid(
+                &Rectangle::new(Point::new(-2, 0), Size::new(4, 4)),
+                BinaryColor::On
+            ),
+            Err(DisplayError::OutOfBoundsError)
+        ));
+        assert!(display.get_pixel(127, 5) && display.get_pixel(1, 3));
+        assert_eq!(display.out_of_bounds_pixels(), 3 + 3 + 8);
+
+        display.reset_out_of_bounds();
+        display
+            .fill_solid(&display.bounding_box(), BinaryColor::Off)
+            .unwrap();
+        assert_eq!(display.out_of_bounds_pixels(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn invert_region_flips_pixels_and_restores_them() {
+        each_layout(|mut display| {
+            display.set_pixel(12, 6, 1);
+            let area = Rectangle::new(Point::new(10, 4), Size::new(20, 20));
+
+            display.invert_region(area);
+            assert!(!display.get_pixel(12, 6) && display.get_pixel(10, 4));
+            assert!(display.get_pixel(29, 23) && display.get_pixel(20, 8));
+            assert!(!display.get_pixel(9, 4) && !display.get_pixel(10, 24));
+            assert_eq!(display.framebuffer_stats().lit_pixels, 20 * 20 - 1);
+
+            display.invert_region(area);
+            assert!(display.get_pixel(12, 6));
+            assert_eq!(display.framebuffer_stats().lit_pixels, 1);
+        });
+    }
+
+    #[test]
+    fn scrolling_moves_rows_across_pages() {
+        for rotation in [DisplayRotation::Rotate0, DisplayRotation::Rotate90] {
+            let builder = Builder::new().with_rotation(rotation);
+            each_layout_with(builder, &StubInterface, |new_display| {
+                let mut display = new_display();
+                display.set_pixel(3, 10, 1);
+                display.set_pixel(5, 21, 1);
+                display.set_pixel(7, 1, 1);
+
+                display.scroll_up(3);
+                assert!(display.get_pixel(3, 7) && display.get_pixel(5, 18));
+                assert!(!display.get_pixel(3, 10) && !display.get_pixel(5, 21));
+                assert_eq!(display.framebuffer_stats().lit_pixels, 2);
+
+                display.scroll_down(12);
+                assert!(display.get_pixel(3, 19) && display.get_pixel(5, 30));
+                assert_eq!(display.framebuffer_stats().lit_pixels, 2);
+
+                display.scroll_up(200);
+                assert_eq!(display.framebuffer_stats().lit_pixels, 0);
+            });
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn lines_fill_whole_bytes_with_masked_ends() {
+        for layout in [
+            FramebufferLayout::VerticalPages,
+            FramebufferLayout::RowMajor,
+            FramebufferLayout::DisplayRows,
+        ] {
+            let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+            display.set_framebuffer_layout(layout);
+
+            display.hline(3, 5, 20, true);
+            display.vline(40, 6, 12, true);
+            display.hline(120, 159, 20, true);
+            let lit: vec::Vec<_> = display
+                .pixels()
+                .filter(|&(_, _, on)| on)
+                .map(|(x, y, _)| (x, y))
+                .collect();
+            let mut expected: vec::Vec<_> = (3..23)
+                .map(|x| (x, 5))
+                .chain((6..18).map(|y| (40, y)))
+                .chain((120..128).map(|x| (x, 159)))
+                .collect();
+            expected.sort_by_key(|&(x, y)| (y, x));
+            assert_eq!(lit, expected, "{:?}", layout);
+
+            display.hline(10, 5, 4, false);
+            assert!(display.get_pixel(9, 5) && !display.get_pixel(10, 5));
+            assert!(!display.get_pixel(13, 5) && display.get_pixel(14, 5));
+        }
+    }
+
+    #[test]
+    fn unchecked_pixels_use_panel_coordinates() {
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(StubInterface)
+            .into();
+
+        display.set_pixel_unchecked(10, 3, true);
+        assert!(display.get_pixel(3, 10));
+        assert_eq!(display.framebuffer()[10], 0x08);
+
+        display.set_pixel_unchecked(10, 3, false);
+        assert!(!display.get_pixel(3, 10));
+    }
+
+    #[test]
+    fn pixels_iterate_in_display_coordinates() {
+        let mut display: GraphicsMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(StubInterface)
+            .into();
+        display.set_pixel(150, 2, 1);
+        display.set_pixel(0, 127, 1);
+
+        let lit: vec::Vec<_> = display
+            .pixels()
+            .filter(|&(_, _, on)| on)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        assert_eq!(lit, [(150, 2), (0, 127)]);
+        assert_eq!(display.pixels().count(), 160 * 128);
+        assert_eq!(display.pixels().nth(161), Some((1, 1, false)));
+    }
+
+    #[test]
+    fn framebuffer_can_be_written_directly() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.flush().unwrap();
+
+        // Column 5, row 9 is bit 1 of byte 128 + 5
+        display.framebuffer_mut()[128 + 5] = 0x02;
+        assert!(display.get_pixel(5, 9));
+
+        iface.clear();
+        display.flush_dirty().unwrap();
+        assert_eq!(iface.data().len(), 128 * 160 / 8);
+        assert_eq!(iface.data()[128 + 5], 0x02);
     }
 }
@@ -0,0 +1,324 @@
+//! Buffered 2 bit grayscale mode using temporal dithering
+//!
+//! ```rust,ignore
+//! let interface = /* your preferred `display-interface` implementor */;
+//! let mut display: GrayscaleMode<_> = Builder::new().connect(interface).into();
+//!
+//! display.init().unwrap();
+//! Circle::new(Point::new(20, 20), 40)
+//!     .into_styled(PrimitiveStyle::with_fill(Gray2::new(1)))
+//!     .draw(&mut display)
+//!     .unwrap();
+//!
+//! loop {
+//!     display.flush_subframe().unwrap();
+//!     delay.delay_ms(5u8);
+//! }
+//! ```
+//!
+//! The SH1108 only knows pixels that are on or off. This mode fakes four gray levels by cycling
+//! through three subframes: a pixel of level `n` is on in `n` of them, so level 1 is lit a third
+//! of the time and level 3 all of the time. The 2 bits per pixel take a framebuffer of 6400
+//! bytes, regardless of the display size.
+//!
+//! The display interfaces of this crate are blocking, so instead of a background task the mode
+//! has [`GrayscaleMode::flush_subframe`], which writes the next subframe to the display. It has to
+//! be called at a steady rate, from a timer interrupt, a main loop or a task of an async executor,
+//! for the gray levels to look even. Every call writes the whole display, which takes about 3ms
+//! at 10MHz SPI but about 70ms at 400kHz I2C, so the mode flickers visibly over I2C.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::{blocking::delay::DelayMs, digital::v2::OutputPin};
+
+use crate::{
+    displayrotation::DisplayRotation,
+    error::InitError,
+    mode::{
+        displaymode::{DisplayModeTrait, DisplayTuning},
+        raw::RawMode,
+    },
+    properties::DisplayProperties,
+};
+
+/// Number of subframes the gray levels are spread over
+const SUBFRAMES: u8 = 3;
+
+/// Grayscale mode handler
+pub struct GrayscaleMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    properties: DisplayProperties<DI>,
+    /// Gray levels of 8 vertically stacked pixels per entry, 2 bits each with the top pixel in
+    /// the least significant bits, page by page
+    buffer: [u16; 160 * 160 / 8],
+    /// Subframe written by the next `flush_subframe`
+    subframe: u8,
+}
+
+impl<DI> DisplayModeTrait<DI> for GrayscaleMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Create new GrayscaleMode instance
+    fn new(properties: DisplayProperties<DI>) -> Self {
+        GrayscaleMode {
+            properties,
+            buffer: [0; 160 * 160 / 8],
+            subframe: 0,
+        }
+    }
+
+    /// Release all resources used by GrayscaleMode
+    fn release(self) -> DisplayProperties<DI> {
+        self.properties
+    }
+}
+
+impl<DI> DisplayTuning<DI> for GrayscaleMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn properties_mut(&mut self) -> &mut DisplayProperties<DI> {
+        &mut self.properties
+    }
+}
+
+impl<DI> GrayscaleMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Switch back to [`RawMode`]. The display keeps showing the last subframe.
+    pub fn into_raw_mode(self) -> RawMode<DI> {
+        RawMode::new(self.properties)
+    }
+
+    /// Destroy the driver and return the interface
+    pub fn release_interface(self) -> DI {
+        self.properties.release()
+    }
+
+    /// Reset display. This is very important on the SH1108!
+    ///
+    /// This should be called before `init` or any other methods.
+    pub fn reset<RST, DELAY, PinE>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), PinE>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.mark_uninitialized();
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(1);
+        rst.set_high()?;
+        delay.delay_ms(1);
+        Ok(())
+    }
+
+    /// Initialise the display in column mode, i.e. a byte walks down a column of 8 pixels from
+    /// column 0 (left) to column 127 (right)
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.properties.init_column_mode()
+    }
+
+    /// Reset the display with `rst`, see [`GrayscaleMode::reset`], then `init` it
+    pub fn init_with_reset<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.reset(rst, delay).map_err(InitError::Pin)?;
+        self.init()?;
+
+        Ok(())
+    }
+
+    /// Clear the framebuffer. The display changes with the next `flush_subframe`.
+    pub fn clear(&mut self) {
+        self.buffer = [0; 160 * 160 / 8];
+    }
+
+    /// Get the display dimensions in pixels, taking rotation into account
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        self.properties.get_dimensions()
+    }
+
+    /// Set the gray level of the pixel at `x` and `y`, from 0 (off) to 3 (fully on), in display
+    /// coordinates taking rotation into account. Levels above 3 are treated as 3, and pixels
+    /// outside the display are ignored.
+    pub fn set_pixel(&mut self, x: u32, y: u32, level: u8) {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        let (column, row) = match self.properties.get_rotation() {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+        };
+
+        if column >= display_width as u32 || row >= display_height as u32 {
+            return;
+        }
+
+        let entry = &mut self.buffer[(row / 8) as usize * 160 + column as usize];
+        let shift = (row % 8) * 2;
+        *entry = (*entry & !(0b11 << shift)) | (u16::from(level.min(3)) << shift);
+    }
+
+    /// Get the gray level of the pixel at `x` and `y`, or `None` outside the display
+    pub fn get_pixel(&self, x: u32, y: u32) -> Option<u8> {
+        let (display_width, display_height) = self.properties.get_size().dimensions();
+
+        let (column, row) = match self.properties.get_rotation() {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+        };
+
+        if column >= display_width as u32 || row >= display_height as u32 {
+            return None;
+        }
+
+        let entry = self.buffer[(row / 8) as usize * 160 + column as usize];
+        Some((entry >> ((row % 8) * 2)) as u8 & 0b11)
+    }
+
+    /// Write the next of the three subframes to the display. Call this at a steady rate, see the
+    /// [module documentation](self).
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn flush_subframe(&mut self) -> Result<(), DisplayError> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "display flushed before init()"
+        );
+
+        let display_size = self.properties.get_size();
+        let (display_width, display_height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+
+        self.properties.begin_flush();
+        self.properties.set_draw_area_unchecked(
+            (column_offset, 0),
+            (display_width + column_offset, display_height / 8),
+        )?;
+
+        // A pixel is on in subframes below its level
+        let subframe = self.subframe;
+        for entries in self
+            .buffer
+            .chunks_exact(160)
+            .take(display_height as usize / 8)
+        {
+            let mut page = [0u8; 160];
+            for (byte, entry) in page.iter_mut().zip(entries) {
+                *byte = (0..8)
+                    .filter(|bit| (entry >> (bit * 2)) as u8 & 0b11 > subframe)
+                    .fold(0, |byte, bit| byte | 1 << bit);
+            }
+            self.properties.draw(&page[..display_width as usize])?;
+        }
+
+        self.subframe = (subframe + 1) % SUBFRAMES;
+        self.properties.end_flush();
+
+        Ok(())
+    }
+
+    /// Set the display rotation. This takes effect with the next `flush_subframe`, but does not
+    /// move what is already in the framebuffer.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.properties.set_rotation(rot)
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.display_on(on)
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.properties.set_contrast(contrast)
+    }
+}
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{Gray2, GrayColor},
+    Pixel,
+};
+
+#[cfg(feature = "graphics")]
+impl<DI> DrawTarget for GrayscaleMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = Gray2;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(pos.x), u32::try_from(pos.y)) {
+                self.set_pixel(x, y, color.luma());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<DI> OriginDimensions for GrayscaleMode<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        let (w, h) = self.properties.get_dimensions();
+
+        Size::new(w.into(), h.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_helpers::CaptureInterface, Builder};
+    use std::vec;
+
+    #[test]
+    fn levels_are_spread_over_subframes() {
+        let iface = CaptureInterface::new();
+        let mut display: GrayscaleMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+
+        for level in 0..4 {
+            display.set_pixel(u32::from(level), 9, level);
+        }
+        display.set_pixel(200, 0, 3);
+        assert_eq!(display.get_pixel(2, 9), Some(2));
+        assert_eq!(display.get_pixel(200, 0), None);
+
+        let mut subframes = vec::Vec::new();
+        for _ in 0..4 {
+            iface.clear();
+            display.flush_subframe().unwrap();
+            let data = iface.data();
+            assert_eq!(data.len(), 128 * 20);
+            subframes.push(data[128..132].to_vec());
+        }
+
+        // Row 9 is bit 1 of page 1
+        assert_eq!(subframes[0], [0x00, 0x02, 0x02, 0x02]);
+        assert_eq!(subframes[1], [0x00, 0x00, 0x02, 0x02]);
+        assert_eq!(subframes[2], [0x00, 0x00, 0x00, 0x02]);
+        assert_eq!(subframes[3], subframes[0]);
+    }
+}
@@ -0,0 +1,116 @@
+//! Drawing with inverted colors
+//!
+//! ```rust,ignore
+//! let mut display: GraphicsMode<_> = Builder::new().connect(interface).into();
+//! display.init().unwrap();
+//!
+//! // Rendering code written for dark on light
+//! draw_dialog(&mut display.inverted())?;
+//! display.flush().unwrap();
+//! ```
+//!
+//! [`Inverted`] borrows a draw target and swaps `BinaryColor::On` and `BinaryColor::Off` of
+//! everything drawn through it, including fills and `clear`, so rendering code can be reused for
+//! the opposite theme as is. Unlike [`GraphicsMode::set_invert`], which inverts the whole panel in
+//! hardware, only what is drawn through the wrapper is affected, so inverted and normal content
+//! can share one frame.
+//!
+//! [`GraphicsMode::set_invert`]: crate::mode::GraphicsMode::set_invert
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget, geometry::Dimensions, pixelcolor::BinaryColor, primitives::Rectangle,
+    Pixel,
+};
+
+/// A draw target that draws with inverted colors, see the [module documentation](self)
+#[derive(Debug)]
+pub struct Inverted<'a, T> {
+    target: &'a mut T,
+}
+
+impl<'a, T> Inverted<'a, T>
+where
+    T: DrawTarget<Color = BinaryColor>,
+{
+    /// Invert everything drawn to `target` through the wrapper
+    pub fn new(target: &'a mut T) -> Self {
+        Self { target }
+    }
+
+    /// Get the wrapped draw target, to draw to it without inverting
+    pub fn inner_mut(&mut self) -> &mut T {
+        self.target
+    }
+}
+
+impl<T> DrawTarget for Inverted<'_, T>
+where
+    T: DrawTarget<Color = BinaryColor>,
+{
+    type Color = BinaryColor;
+    type Error = T::Error;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        self.target.draw_iter(
+            pixels
+                .into_iter()
+                .map(|Pixel(point, color)| Pixel(point, color.invert())),
+        )
+    }
+
+    fn fill_contiguous<I>(&mut self, area: &Rectangle, colors: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Self::Color>,
+    {
+        self.target
+            .fill_contiguous(area, colors.into_iter().map(BinaryColor::invert))
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.fill_solid(area, color.invert())
+    }
+
+    fn clear(&mut self, color: Self::Color) -> Result<(), Self::Error> {
+        self.target.clear(color.invert())
+    }
+}
+
+impl<T> Dimensions for Inverted<'_, T>
+where
+    T: DrawTarget<Color = BinaryColor>,
+{
+    fn bounding_box(&self) -> Rectangle {
+        self.target.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mode::GraphicsMode, test_helpers::StubInterface, Builder};
+    use embedded_graphics_core::geometry::{Point, Size};
+
+    #[test]
+    fn colors_are_swapped() {
+        let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+
+        let mut inverted = display.inverted();
+        inverted.clear(BinaryColor::Off).unwrap();
+        inverted
+            .fill_solid(
+                &Rectangle::new(Point::new(10, 10), Size::new(4, 4)),
+                BinaryColor::On,
+            )
+            .unwrap();
+        inverted
+            .draw_iter([Pixel(Point::new(0, 0), BinaryColor::On)])
+            .unwrap();
+
+        assert!(!display.get_pixel(0, 0) && display.get_pixel(1, 0));
+        assert!(!display.get_pixel(10, 10) && !display.get_pixel(13, 13));
+        assert!(display.get_pixel(14, 13) && display.get_pixel(127, 159));
+    }
+}
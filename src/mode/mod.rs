@@ -3,8 +3,41 @@
 //! This driver can be used in different modes. A mode defines how the driver will behave, and what
 //! methods it exposes. Look at the modes below for more information on what they expose.
 
+#[cfg(feature = "graphics")]
+pub mod banded;
+pub mod direct;
 pub mod displaymode;
+pub mod double;
+#[cfg(feature = "graphics")]
+pub mod external;
 pub mod graphics;
+pub mod grayscale;
+#[cfg(feature = "graphics")]
+pub mod inverted;
+pub mod pacer;
 pub mod raw;
+pub mod split;
+pub mod storage;
+pub mod terminal;
+#[cfg(feature = "graphics")]
+pub mod tiled;
 
-pub use self::{graphics::GraphicsMode, raw::RawMode};
+#[cfg(feature = "graphics")]
+pub use self::{
+    banded::BandedMode,
+    external::{ExternalMode, ExternalRam, SpiRam},
+    graphics::Dither,
+    inverted::Inverted,
+    tiled::TiledDisplay,
+};
+pub use self::{
+    direct::DirectMode,
+    double::DoubleBuffered,
+    graphics::{BlitOp, FbStats, FlushStats, FramebufferLayout, GraphicsMode, Mismatch},
+    grayscale::GrayscaleMode,
+    pacer::FramePacer,
+    raw::RawMode,
+    split::RegionMut,
+    storage::FrameBufferStorage,
+    terminal::{TerminalMode, TextOverflow},
+};
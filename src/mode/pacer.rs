@@ -0,0 +1,170 @@
+//! Capping the flush rate of a [`GraphicsMode`]
+//!
+//! ```rust,ignore
+//! // At most 20 frames per second
+//! let mut pacer = FramePacer::new(50);
+//!
+//! loop {
+//!     if let Some(event) = poll_input() {
+//!         draw(&mut display, event);
+//!         pacer.request();
+//!     }
+//!
+//!     pacer.tick(elapsed_ms_since_last_loop());
+//!     pacer.poll(&mut display)?;
+//! }
+//! ```
+//!
+//! Flushing after every change easily sends frames back to back and keeps a slow I2C bus busy
+//! all the time. A [`FramePacer`] instead collects flush requests and sends at most one
+//! `flush_dirty` per interval. Requests that come in while the interval runs are coalesced into
+//! the next flush, which then sends everything that changed in the meantime at once.
+//!
+//! The pacer has no clock of its own. Like
+//! [`GraphicsMode::idle_tick`](crate::mode::GraphicsMode::idle_tick), it is told how much time
+//! passed with [`FramePacer::tick`], or waits out the rest of the interval with a delay in
+//! [`FramePacer::flush`].
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::blocking::delay::DelayMs;
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::graphics::{FlushStats, GraphicsMode},
+};
+
+/// Limits how often a display is flushed, see the [module documentation](self)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FramePacer {
+    interval_ms: u16,
+    since_flush_ms: u16,
+    requested: bool,
+}
+
+impl FramePacer {
+    /// Create a pacer that flushes at most once every `interval_ms` milliseconds. The first flush
+    /// may go out right away.
+    pub fn new(interval_ms: u16) -> Self {
+        Self {
+            interval_ms,
+            since_flush_ms: interval_ms,
+            requested: false,
+        }
+    }
+
+    /// Get the minimum time between two flushes in milliseconds
+    pub fn interval_ms(&self) -> u16 {
+        self.interval_ms
+    }
+
+    /// Ask for a flush. Several requests before the next flush result in only one.
+    pub fn request(&mut self) {
+        self.requested = true;
+    }
+
+    /// Whether a flush was requested and not sent yet
+    pub fn is_pending(&self) -> bool {
+        self.requested
+    }
+
+    /// Tell the pacer that `elapsed_ms` milliseconds passed
+    pub fn tick(&mut self, elapsed_ms: u32) {
+        let elapsed_ms = u16::try_from(elapsed_ms).unwrap_or(u16::MAX);
+        self.since_flush_ms = self.since_flush_ms.saturating_add(elapsed_ms);
+    }
+
+    /// Flush the changes of `display` with `flush_dirty` if a flush was requested and the
+    /// interval since the last one passed. Returns what was sent, or `None` if nothing was flushed
+    /// yet because no flush was requested or it is too early.
+    pub fn poll<DI, const N: usize, R>(
+        &mut self,
+        display: &mut GraphicsMode<DI, N, R>,
+    ) -> Result<Option<FlushStats>, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+    {
+        if !self.requested || self.since_flush_ms < self.interval_ms {
+            return Ok(None);
+        }
+
+        self.flush_now(display).map(Some)
+    }
+
+    /// Flush the changes of `display` with `flush_dirty`, first waiting with `delay` for what is
+    /// left of the interval since the last flush. Time spent elsewhere only counts if it was
+    /// passed to [`tick`](Self::tick).
+    pub fn flush<DI, const N: usize, R, DELAY>(
+        &mut self,
+        display: &mut GraphicsMode<DI, N, R>,
+        delay: &mut DELAY,
+    ) -> Result<FlushStats, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+        DELAY: DelayMs<u16>,
+    {
+        if self.since_flush_ms < self.interval_ms {
+            delay.delay_ms(self.interval_ms - self.since_flush_ms);
+        }
+
+        self.flush_now(display)
+    }
+
+    fn flush_now<DI, const N: usize, R>(
+        &mut self,
+        display: &mut GraphicsMode<DI, N, R>,
+    ) -> Result<FlushStats, DisplayError>
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+    {
+        let stats = display.flush_dirty()?;
+        self.requested = false;
+        self.since_flush_ms = 0;
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        test_helpers::{CaptureInterface, DelayRecorder},
+        Builder,
+    };
+
+    #[test]
+    fn requests_are_coalesced_until_the_interval_passed() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        let mut pacer = FramePacer::new(50);
+
+        assert_eq!(pacer.poll(&mut display).unwrap(), None);
+        pacer.request();
+        assert!(pacer.poll(&mut display).unwrap().is_some());
+        assert!(!pacer.is_pending());
+
+        display.set_pixel(0, 0, 1);
+        pacer.request();
+        pacer.tick(20);
+        display.set_pixel(1, 0, 1);
+        pacer.request();
+        assert_eq!(pacer.poll(&mut display).unwrap(), None);
+
+        iface.clear();
+        pacer.tick(30);
+        let stats = pacer.poll(&mut display).unwrap().unwrap();
+        assert_eq!((stats.pages, stats.bytes), (1, 2));
+        assert_eq!(iface.data(), [0x01, 0x01]);
+        assert_eq!(pacer.poll(&mut display).unwrap(), None);
+
+        let mut delay = DelayRecorder::new();
+        pacer.tick(10);
+        pacer.flush(&mut display, &mut delay).unwrap();
+        assert_eq!(delay.delays(), [40]);
+    }
+}
@@ -4,9 +4,15 @@
 //! builder. Used as a source to coerce the driver into richer modes like
 //! [`GraphicsMode`](../graphics/index.html).
 
-use display_interface::WriteOnlyDataCommand;
+use display_interface::{DisplayError, WriteOnlyDataCommand};
 
-use crate::{mode::displaymode::DisplayModeTrait, properties::DisplayProperties};
+use crate::{
+    displaysize::DisplaySize,
+    error::Error,
+    mode::{displaymode::DisplayModeTrait, graphics::GraphicsMode, terminal::TerminalMode},
+    properties::{DisplayProperties, TestPattern},
+    read::ReadInterface,
+};
 
 /// Raw display mode
 pub struct RawMode<DI>
@@ -36,4 +42,51 @@ impl<DI: WriteOnlyDataCommand> RawMode<DI> {
     pub fn new(properties: DisplayProperties<DI>) -> Self {
         RawMode { properties }
     }
+
+    /// Switch to the buffered [`GraphicsMode`], keeping the interface and display settings
+    pub fn into_graphics_mode(self) -> GraphicsMode<DI> {
+        GraphicsMode::new(self.properties)
+    }
+
+    /// Switch to the unbuffered [`TerminalMode`], keeping the interface and display settings
+    pub fn into_terminal_mode(self) -> TerminalMode<DI> {
+        TerminalMode::new(self.properties)
+    }
+
+    /// Whether a display answers on the interface, e.g. to decide at boot whether to switch to a
+    /// drawing mode at all. See [`DisplayProperties::probe`].
+    pub fn is_connected(&mut self) -> bool {
+        self.properties.is_connected()
+    }
+
+    /// Change the display size before switching to a drawing mode, e.g. after detecting which
+    /// module is fitted. See [`DisplayProperties::set_size`].
+    pub fn set_size(&mut self, display_size: DisplaySize) -> Result<(), DisplayError> {
+        self.properties.set_size(display_size)
+    }
+
+    /// Show a test pattern while bringing up the hardware, before any drawing mode is set up.
+    /// See [`DisplayProperties::test_pattern`].
+    pub fn test_pattern(&mut self, pattern: TestPattern) -> Result<(), DisplayError> {
+        self.properties.test_pattern(pattern)
+    }
+
+    /// Show a run-length encoded boot logo right after power-on, before any drawing mode is set
+    /// up. See [`DisplayProperties::show_splash`].
+    pub fn show_splash(&mut self, rle: &[u8]) -> Result<(), DisplayError> {
+        self.properties.show_splash(rle)
+    }
+
+    /// Destroy the driver and return the interface
+    pub fn release_interface(self) -> DI {
+        self.properties.release()
+    }
+}
+
+impl<DI: ReadInterface> RawMode<DI> {
+    /// Turn a single pixel on or off in display RAM without any framebuffer, on an interface
+    /// that can read. See [`DisplayProperties::set_pixel_direct`].
+    pub fn set_pixel_direct(&mut self, x: u32, y: u32, on: bool) -> Result<(), Error> {
+        self.properties.set_pixel_direct(x, y, on)
+    }
 }
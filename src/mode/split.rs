@@ -0,0 +1,200 @@
+//! Two regions of one [`GraphicsMode`] framebuffer, borrowed side by side
+//!
+//! ```rust,ignore
+//! let mut display: GraphicsMode<_> = Builder::new().connect(interface).into();
+//! display.init().unwrap();
+//!
+//! let (mut status_bar, mut main_area) = display.split_rows_mut(16).unwrap();
+//! join(draw_status(&mut status_bar), draw_main(&mut main_area)).await;
+//!
+//! display.flush_dirty().unwrap();
+//! ```
+//!
+//! [`GraphicsMode::split_rows_mut`] and [`GraphicsMode::split_columns_mut`] cut the framebuffer
+//! in two at a page boundary of the panel, like `split_at_mut` on a slice. Each half is a
+//! [`RegionMut`] that borrows its own part of the framebuffer and of the change tracking, so two
+//! pieces of code, e.g. two futures joined in one task, can draw to them at the same time
+//! without passing the display back and forth. Coordinates are relative to the top left corner
+//! of the region, and drawing is clipped to the region and to the clipping area of the display.
+//! Pixels drawn count towards `GraphicsMode::metrics` as usual.
+//!
+//! The regions borrow the display, so they cannot outlive it or be moved into tasks of their
+//! own, and they do not send anything. Once both are dropped, `flush_dirty` on the display sends
+//! what changed in either of them in one go.
+//!
+//! [`GraphicsMode`]: crate::mode::GraphicsMode
+//! [`GraphicsMode::split_rows_mut`]: crate::mode::GraphicsMode::split_rows_mut
+//! [`GraphicsMode::split_columns_mut`]: crate::mode::GraphicsMode::split_columns_mut
+
+use core::cell::Cell;
+#[cfg(feature = "graphics")]
+use core::convert::Infallible;
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+
+use crate::mode::graphics::FramebufferLayout;
+
+/// What both regions of a split share with the display
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Shared<'a> {
+    pub(crate) layout: FramebufferLayout,
+    pub(crate) panel_width: u32,
+    /// Whether display `x` runs along the panel rows, i.e. the display is rotated by 90 or 270
+    /// degrees
+    pub(crate) sideways: bool,
+    /// Clipping area of the display, in display coordinates
+    pub(crate) clip: Option<((u32, u32), (u32, u32))>,
+    /// Counter of the pixels set, added to the metrics of the display
+    pub(crate) pixels: &'a Cell<u32>,
+}
+
+/// One part of a framebuffer split with [`GraphicsMode::split_rows_mut`] or
+/// [`GraphicsMode::split_columns_mut`], see the [module documentation](self)
+///
+/// [`GraphicsMode::split_rows_mut`]: crate::mode::GraphicsMode::split_rows_mut
+/// [`GraphicsMode::split_columns_mut`]: crate::mode::GraphicsMode::split_columns_mut
+#[derive(Debug)]
+pub struct RegionMut<'a> {
+    /// The framebuffer bytes of the pages of the region
+    buffer: &'a mut [u8],
+    /// Changed columns of the pages of the region
+    dirty: &'a mut [(u8, u8)],
+    shared: Shared<'a>,
+    /// Panel row of the first page of the region
+    first_row: u32,
+    /// Top left corner of the region on the display
+    origin: (u32, u32),
+    /// Width and height of the region
+    size: (u32, u32),
+}
+
+impl<'a> RegionMut<'a> {
+    pub(crate) fn new(
+        buffer: &'a mut [u8],
+        dirty: &'a mut [(u8, u8)],
+        shared: Shared<'a>,
+        first_row: u32,
+        origin: (u32, u32),
+        size: (u32, u32),
+    ) -> Self {
+        Self {
+            buffer,
+            dirty,
+            shared,
+            first_row,
+            origin,
+            size,
+        }
+    }
+
+    /// Get the width and height of the region
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Get the position of the top left corner of the region on the display
+    pub fn origin(&self) -> (u32, u32) {
+        self.origin
+    }
+
+    /// Turn a pixel of the region on or off. Pixels outside the region or the clipping area of
+    /// the display are ignored.
+    pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        if x >= self.size.0 || y >= self.size.1 {
+            return;
+        }
+
+        let (x, y) = (self.origin.0 + x, self.origin.1 + y);
+        if let Some(((x0, y0), (x1, y1))) = self.shared.clip {
+            if !(x0..x1).contains(&x) || !(y0..y1).contains(&y) {
+                return;
+            }
+        }
+
+        let (column, row) = if self.shared.sideways { (y, x) } else { (x, y) };
+        let row = row - self.first_row;
+        let panel_width = self.shared.panel_width;
+
+        let (idx, bit) = match self.shared.layout {
+            FramebufferLayout::VerticalPages => {
+                ((row / 8 * panel_width + column) as usize, 1 << (row % 8))
+            }
+            // Split regions only exist where `DisplayRows` holds the rows of the panel
+            FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => (
+                (row * panel_width / 8 + column / 8) as usize,
+                0x80 >> (column % 8),
+            ),
+        };
+        if on {
+            self.buffer[idx] |= bit;
+        } else {
+            self.buffer[idx] &= !bit;
+        }
+
+        let (start, end) = &mut self.dirty[(row / 8) as usize];
+        *start = (*start).min(column as u8);
+        *end = (*end).max(column as u8 + 1);
+        self.shared
+            .pixels
+            .set(self.shared.pixels.get().wrapping_add(1));
+    }
+
+    /// Read back whether a pixel of the region is lit. Pixels outside the region read as off.
+    pub fn get_pixel(&self, x: u32, y: u32) -> bool {
+        if x >= self.size.0 || y >= self.size.1 {
+            return false;
+        }
+
+        let (x, y) = (self.origin.0 + x, self.origin.1 + y);
+        let (column, row) = if self.shared.sideways { (y, x) } else { (x, y) };
+        let row = row - self.first_row;
+        let panel_width = self.shared.panel_width;
+
+        match self.shared.layout {
+            FramebufferLayout::VerticalPages => {
+                self.buffer[(row / 8 * panel_width + column) as usize] & (1 << (row % 8)) != 0
+            }
+            FramebufferLayout::RowMajor | FramebufferLayout::DisplayRows => {
+                self.buffer[(row * panel_width / 8 + column / 8) as usize] & (0x80 >> (column % 8))
+                    != 0
+            }
+        }
+    }
+
+    /// Turn every pixel of the region off
+    pub fn clear(&mut self) {
+        self.buffer.fill(0);
+        self.dirty.fill((0, u8::MAX));
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl DrawTarget for RegionMut<'_> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(point, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(point.x), u32::try_from(point.y)) {
+                self.set_pixel(x, y, color.is_on());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl OriginDimensions for RegionMut<'_> {
+    fn size(&self) -> Size {
+        Size::new(self.size.0, self.size.1)
+    }
+}
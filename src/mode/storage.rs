@@ -0,0 +1,58 @@
+//! Where the framebuffer of a [`GraphicsMode`] is kept
+//!
+//! A `GraphicsMode` stores its framebuffer inline as a `[u8; N]` by default. Its fourth type
+//! parameter takes any [`FrameBufferStorage`] instead, e.g. a buffer that is shared with other
+//! code or placed by the application:
+//!
+//! ```rust,ignore
+//! let mut frame = [0; 2560];
+//!
+//! let properties =
+//!     DisplayProperties::new(interface, DisplaySize::Display128x160, DisplayRotation::Rotate0);
+//! let mut display: GraphicsMode<_, 0, RuntimeRotation, &mut [u8]> =
+//!     GraphicsMode::with_storage(properties, &mut frame[..]);
+//! ```
+//!
+//! With a storage other than the array, the `N` parameter is not used and can be 0. The storage
+//! has to hold at least [`DisplaySize::framebuffer_size`] bytes, laid out as described for
+//! [`GraphicsMode::framebuffer`], and the drawing code works on it through [`bytes`] and
+//! [`bytes_mut`], so a storage can e.g. keep a checksum of the frame up to date or count
+//! accesses for a profiler.
+//!
+//! Both return the whole frame as one slice, so the storage has to keep it in contiguous
+//! memory. Compressed or chunked backings cannot implement the trait.
+//!
+//! [`DisplaySize::framebuffer_size`]: crate::prelude::DisplaySize::framebuffer_size
+//! [`GraphicsMode`]: crate::mode::GraphicsMode
+//! [`GraphicsMode::framebuffer`]: crate::mode::GraphicsMode::framebuffer
+//! [`bytes`]: FrameBufferStorage::bytes
+//! [`bytes_mut`]: FrameBufferStorage::bytes_mut
+
+/// Contiguous backing memory of a framebuffer, see the [module documentation](self)
+pub trait FrameBufferStorage {
+    /// Get all bytes of the storage
+    fn bytes(&self) -> &[u8];
+
+    /// Get all bytes of the storage mutably
+    fn bytes_mut(&mut self) -> &mut [u8];
+}
+
+impl<const N: usize> FrameBufferStorage for [u8; N] {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
+
+impl FrameBufferStorage for &mut [u8] {
+    fn bytes(&self) -> &[u8] {
+        self
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        self
+    }
+}
@@ -0,0 +1,1266 @@
+//! Unbuffered text mode with a built-in font
+//!
+//! Characters are written straight to the display RAM as they are printed, so this mode needs no
+//! framebuffer and no embedded-graphics. It implements [`core::fmt::Write`]:
+//!
+//! ```rust,ignore
+//! use core::fmt::Write;
+//!
+//! let interface = /* your preferred `display-interface` implementor */;
+//! let mut display: TerminalMode<_> = Builder::new().connect(interface).into();
+//!
+//! display.init().unwrap();
+//! write!(display, "T={}C", 21).unwrap();
+//! ```
+//!
+//! With the `ufmt` feature it also implements `ufmt::uWrite`, so `uwrite!` can print formatted
+//! values on targets where `core::fmt` is too large.
+//!
+//! The built-in font is 5x8 pixels with one column of spacing, so each character takes a 6x8
+//! cell and a 128x160 display fits 21 columns by 20 rows. Other fonts can be used with
+//! `with_font`, see [`font`](crate::font). The controller writes whole pages of 8 pixel rows, so
+//! the cells are rounded up to a multiple of 8 pixels along the pages: a 6x10 font takes 6x16
+//! cells, and with the display rotated by 90 or 270 degrees the built-in font takes 8x8 cells.
+//! The number of cells is limited to 520, which only matters for cells smaller than 6x8 pixels.
+//!
+//! Text wraps onto the next row at the right edge of the display. After the last row it either
+//! continues at the top row or scrolls everything up by one row, see [`TextOverflow`]. Control
+//! characters move the cursor without printing anything:
+//!
+//! - `\n` moves to the start of the next row and `\r` to the start of the current one. A `\n`
+//!   right after text wrapped at the edge, or after `\r` following it, is absorbed by the wrap,
+//!   so lines exactly as wide as the display are not followed by an empty row.
+//! - `\t` moves to the next tab stop, every 4 columns by default, see `set_tab_width`, or to
+//!   the start of the next row after the last stop.
+//! - Backspace, `\x08`, moves one column to the left, stopping at the start of the row. Like on
+//!   a serial terminal, it does not erase the character, so print `"\x08 \x08"` for that.
+//!
+//! Other control characters, and characters above U+7FFF, are printed as `?`.
+//!
+//! A cursor can be shown with `set_cursor_visible`, which inverts the character cell at the
+//! cursor position, and made to blink by calling `blink_cursor` periodically. To redraw the cell
+//! under the cursor, the mode remembers the printed characters in a buffer of 1040 bytes.
+//!
+//! A small subset of ANSI escape sequences is understood, so code written for a serial terminal
+//! can print to the display unchanged. Positions are counted from 1 and clamped to the display,
+//! and a missing or zero count means 1:
+//!
+//! - `ESC[row;columnH` and `ESC[row;columnf` move the cursor
+//! - `ESC[nA`, `ESC[nB`, `ESC[nC` and `ESC[nD` move the cursor up, down, right and left
+//! - `ESC[J` clears from the cursor to the end of the display, `ESC[1J` from the start of the
+//!   display to the cursor and `ESC[2J` the whole display, without moving the cursor
+//! - `ESC[K`, `ESC[1K` and `ESC[2K` do the same for the row of the cursor
+//! - `ESC[7m` prints the following characters in reverse video and `ESC[0m` or `ESC[27m`
+//!   switches it off again. Other attributes are ignored.
+//!
+//! Any other escape sequence is dropped without printing anything.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use hal::{
+    blocking::delay::{DelayMs, DelayUs},
+    digital::v2::OutputPin,
+};
+
+use crate::{
+    brightness::Brightness,
+    command::Command,
+    displayrotation::DisplayRotation,
+    displaysize::DisplaySize,
+    error::{Error, InitError},
+    font::{Font5x8, TerminalFont},
+    mode::{
+        displaymode::{DisplayModeTrait, DisplayTuning},
+        graphics::GraphicsMode,
+        raw::RawMode,
+    },
+    properties::DisplayProperties,
+};
+
+/// Most character cells that fit on a display, 26 columns by 20 rows of the built-in font on a
+/// 160x160 display
+const MAX_CELLS: usize = 160 / 6 * 160 / 8;
+
+/// An empty character cell
+const SPACE: u16 = b' ' as u16;
+
+/// What happens when text goes past the last row of the display
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TextOverflow {
+    /// Continue at the top row, overwriting the oldest text. This is the default.
+    Wrap,
+    /// Move all text up by one row and continue on the emptied last row. The SH1108 cannot
+    /// scroll in hardware, so this redraws every row.
+    Scroll,
+}
+
+/// Most parameters kept for one escape sequence
+const MAX_PARAMS: usize = 4;
+
+/// Progress of parsing an escape sequence
+#[derive(Clone, Copy, PartialEq)]
+enum Escape {
+    /// Not in an escape sequence
+    None,
+    /// `ESC` was received
+    Started,
+    /// Receiving the parameters of an `ESC[` sequence. `count` is the index of the parameter
+    /// being received.
+    Csi {
+        params: [u16; MAX_PARAMS],
+        count: usize,
+    },
+}
+
+/// Set in `TerminalMode::text` for characters printed in reverse video
+const REVERSE: u16 = 0x8000;
+
+/// Terminal mode handler
+///
+/// `F` is the font to print with, see [`TerminalMode::with_font`].
+pub struct TerminalMode<DI, F = Font5x8>
+where
+    DI: WriteOnlyDataCommand,
+{
+    properties: DisplayProperties<DI>,
+    font: F,
+    cursor: (u8, u8),
+    /// Character printed in every cell, row by row, to redraw the cell under the cursor. Bit 15
+    /// is set for characters in reverse video.
+    text: [u16; MAX_CELLS],
+    cursor_visible: bool,
+    /// Whether the cell under the cursor is currently drawn inverted
+    cursor_shown: bool,
+    overflow: TextOverflow,
+    escape: Escape,
+    /// Whether characters are printed in reverse video
+    reverse: bool,
+    /// Columns between two tab stops
+    tab_width: u8,
+    /// Whether the cursor just wrapped onto a new row, so a `\n` has nothing left to do
+    wrapped: bool,
+}
+
+impl<DI, F> DisplayModeTrait<DI> for TerminalMode<DI, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: TerminalFont + Default,
+{
+    /// Create new TerminalMode instance
+    fn new(properties: DisplayProperties<DI>) -> Self {
+        TerminalMode {
+            properties,
+            font: F::default(),
+            cursor: (0, 0),
+            text: [SPACE; MAX_CELLS],
+            cursor_visible: false,
+            cursor_shown: false,
+            overflow: TextOverflow::Wrap,
+            escape: Escape::None,
+            reverse: false,
+            tab_width: 4,
+            wrapped: false,
+        }
+    }
+
+    /// Release all resources used by TerminalMode
+    fn release(self) -> DisplayProperties<DI> {
+        self.properties
+    }
+}
+
+impl<DI, F> DisplayTuning<DI> for TerminalMode<DI, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: TerminalFont,
+{
+    fn properties_mut(&mut self) -> &mut DisplayProperties<DI> {
+        &mut self.properties
+    }
+}
+
+impl<DI, F> TerminalMode<DI, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: TerminalFont,
+{
+    /// Print with `font` from now on. This does not change text that is already on the display,
+    /// and forgets it for redrawing the cell under the cursor, so `clear` the display first when
+    /// switching fonts after `init`. The cursor moves to the top left corner.
+    ///
+    /// Panics if the cells of `font` do not fit on the display in every rotation.
+    pub fn with_font<G: TerminalFont>(self, font: G) -> TerminalMode<DI, G> {
+        let (panel_width, panel_height) = self.properties.get_size().dimensions();
+        let (panel_width, panel_height) = (u16::from(panel_width), u16::from(panel_height));
+        let (width, height) = cell_size(font.cell_size(), DisplayRotation::Rotate0);
+        let (rotated_width, rotated_height) =
+            cell_size(font.cell_size(), DisplayRotation::Rotate90);
+        assert!(
+            width > 0
+                && height > 0
+                && width <= panel_width
+                && height <= panel_height
+                && rotated_width <= panel_height
+                && rotated_height <= panel_width,
+            "font cells do not fit on the display"
+        );
+
+        TerminalMode {
+            properties: self.properties,
+            font,
+            cursor: (0, 0),
+            text: [SPACE; MAX_CELLS],
+            cursor_visible: self.cursor_visible,
+            cursor_shown: false,
+            overflow: self.overflow,
+            escape: Escape::None,
+            reverse: self.reverse,
+            tab_width: self.tab_width,
+            wrapped: false,
+        }
+    }
+
+    /// Create the mode from `properties` set up by hand, e.g. after a custom init sequence, to
+    /// print on top. If the display was initialised from outside the driver, record that with
+    /// [`DisplayProperties::assume_initialized`]. The cursor starts in the top left corner.
+    pub fn from_properties(properties: DisplayProperties<DI>) -> Self
+    where
+        F: Default,
+    {
+        <Self as DisplayModeTrait<DI>>::new(properties)
+    }
+
+    /// Return the display properties, forgetting the printed text. The display keeps showing
+    /// it.
+    pub fn into_properties(self) -> DisplayProperties<DI> {
+        self.properties
+    }
+
+    /// Switch back to [`RawMode`]. The display keeps showing the printed text.
+    pub fn into_raw_mode(self) -> RawMode<DI> {
+        RawMode::new(self.properties)
+    }
+
+    /// Switch to the buffered [`GraphicsMode`], keeping the interface and display settings. The
+    /// new framebuffer starts out cleared, so the next flush overwrites the printed text.
+    pub fn into_graphics_mode(self) -> GraphicsMode<DI> {
+        GraphicsMode::new(self.properties)
+    }
+
+    /// Destroy the driver and return the interface. The display keeps showing the printed text.
+    pub fn release_interface(self) -> DI {
+        self.properties.release()
+    }
+
+    /// Reset display. This is very important on the SH1108!
+    ///
+    /// This should be called before `init` or any other methods.
+    pub fn reset<RST, DELAY, PinE>(&mut self, rst: &mut RST, delay: &mut DELAY) -> Result<(), PinE>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.mark_uninitialized();
+        rst.set_high()?;
+        delay.delay_ms(1);
+        rst.set_low()?;
+        delay.delay_ms(1);
+        rst.set_high()?;
+        delay.delay_ms(1);
+        Ok(())
+    }
+
+    /// Initialise the display and clear it
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.properties.init_column_mode()?;
+        self.clear()
+    }
+
+    /// Initialise the display again after it lost its settings and RAM contents, e.g. to a
+    /// brown-out on its supply, see [`DisplayProperties::reinit`]. The text and cursor are kept
+    /// and drawn again.
+    pub fn reinit(&mut self) -> Result<(), DisplayError> {
+        self.properties.reinit()?;
+        self.clear_ram()?;
+
+        let (_, rows) = self.get_dimensions();
+        for row in 0..rows {
+            self.draw_row(row)?;
+        }
+
+        self.cursor_shown = false;
+        self.show_cursor()
+    }
+
+    /// Reset the display with `rst`, see [`TerminalMode::reset`], then `init` it
+    pub fn init_with_reset<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayMs<u8>,
+    {
+        self.reset(rst, delay).map_err(InitError::Pin)?;
+        self.init()?;
+
+        Ok(())
+    }
+
+    /// Reset and initialise the display with the delays of the datasheet power-on sequence, see
+    /// [`DisplayProperties::init_with_timing`] and [`Builder::with_power_timing`], then clear it
+    ///
+    /// [`Builder::with_power_timing`]: crate::Builder::with_power_timing
+    pub fn init_with_timing<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayUs<u32> + DelayMs<u16>,
+    {
+        self.properties.init_with_timing(rst, delay)?;
+        self.clear()?;
+
+        Ok(())
+    }
+
+    /// Clear the display and move the cursor to the top left corner
+    pub fn clear(&mut self) -> Result<(), DisplayError> {
+        self.clear_ram()?;
+
+        self.text = [SPACE; MAX_CELLS];
+        self.cursor = (0, 0);
+        self.cursor_shown = false;
+        self.wrapped = false;
+
+        self.show_cursor()
+    }
+
+    /// Clear the display RAM without touching the stored text
+    fn clear_ram(&mut self) -> Result<(), DisplayError> {
+        let display_size = self.properties.get_size();
+        let (display_width, display_height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+
+        self.properties.set_draw_area_unchecked(
+            (column_offset, 0),
+            (display_width + column_offset, display_height / 8),
+        )?;
+        for _ in 0..display_height / 8 {
+            self.properties.draw(&[0; 160][..display_width as usize])?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the number of character columns and rows that fit on the display, taking rotation
+    /// and the font into account
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        let (width, height) = self.properties.get_dimensions();
+        let (cell_width, cell_height) = self.cell_size();
+        let rows = height / cell_height;
+        let max_columns = u8::try_from(MAX_CELLS / usize::from(rows)).unwrap_or(u8::MAX);
+
+        ((width / cell_width).min(max_columns), rows)
+    }
+
+    /// Get the `(column, row)` position of the cursor in characters
+    pub fn get_position(&self) -> (u8, u8) {
+        self.cursor
+    }
+
+    /// Move the cursor to `column` and `row`, counted in characters from the top left.
+    ///
+    /// Returns `Error::OutOfBounds` if the position is outside the display.
+    pub fn set_position(&mut self, column: u8, row: u8) -> Result<(), Error> {
+        let (columns, rows) = self.get_dimensions();
+        if column >= columns || row >= rows {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.hide_cursor()?;
+        self.cursor = (column, row);
+        self.wrapped = false;
+        self.show_cursor()?;
+
+        Ok(())
+    }
+
+    /// Show or hide the cursor, drawn by inverting the character cell at the cursor position.
+    /// The cursor is hidden by default. See `blink_cursor` to make it blink.
+    pub fn set_cursor_visible(&mut self, visible: bool) -> Result<(), DisplayError> {
+        self.hide_cursor()?;
+        self.cursor_visible = visible;
+
+        self.show_cursor()
+    }
+
+    /// Whether the cursor was turned on with `set_cursor_visible`
+    pub fn is_cursor_visible(&self) -> bool {
+        self.cursor_visible
+    }
+
+    /// Toggle the inverted cell of a visible cursor. Calling this periodically, e.g. every 500ms,
+    /// makes the cursor blink. Printing or moving the cursor shows it straight away again. Does
+    /// nothing while the cursor is hidden.
+    pub fn blink_cursor(&mut self) -> Result<(), DisplayError> {
+        if !self.cursor_visible {
+            return Ok(());
+        }
+
+        if self.cursor_shown {
+            self.hide_cursor()
+        } else {
+            self.show_cursor()
+        }
+    }
+
+    /// Print a single character at the cursor and advance it, wrapping at the edges of the
+    /// display. Characters that are part of an escape sequence are not printed, see the
+    /// [module documentation](self).
+    ///
+    /// Panics in debug builds if the display was not initialised with `init` first.
+    pub fn print_char(&mut self, c: char) -> Result<(), DisplayError> {
+        debug_assert!(
+            self.properties.is_initialized(),
+            "character printed before init()"
+        );
+        if c == '\x1b' || self.escape != Escape::None {
+            return self.parse_escape(c);
+        }
+
+        let (columns, rows) = self.get_dimensions();
+        self.hide_cursor()?;
+
+        let (column, row) = self.cursor;
+        let wrapped = core::mem::take(&mut self.wrapped);
+        self.cursor = match c {
+            '\n' if wrapped => (column, row),
+            '\n' => (0, self.next_row(row, rows)?),
+            '\r' => {
+                self.wrapped = wrapped;
+                (0, row)
+            }
+            '\t' => {
+                let stop = (column / self.tab_width + 1).saturating_mul(self.tab_width);
+                if stop < columns {
+                    (stop, row)
+                } else {
+                    self.wrapped = true;
+                    (0, self.next_row(row, rows)?)
+                }
+            }
+            '\x08' => (column.saturating_sub(1), row),
+            _ => {
+                let c = match u16::try_from(u32::from(c)) {
+                    Ok(code) if code < REVERSE && !c.is_control() => code,
+                    _ => u16::from(b'?'),
+                };
+                let attributes = if self.reverse { REVERSE } else { 0 };
+                self.text[usize::from(row) * usize::from(columns) + usize::from(column)] =
+                    c | attributes;
+                self.draw_cell(column, row, false)?;
+
+                if column + 1 < columns {
+                    (column + 1, row)
+                } else {
+                    self.wrapped = true;
+                    (0, self.next_row(row, rows)?)
+                }
+            }
+        };
+
+        self.show_cursor()
+    }
+
+    /// Feed `c` to the escape sequence parser and run the sequence once it is complete
+    fn parse_escape(&mut self, c: char) -> Result<(), DisplayError> {
+        self.escape = match (self.escape, c) {
+            (_, '\x1b') => Escape::Started,
+            (Escape::Started, '[') => Escape::Csi {
+                params: [0; MAX_PARAMS],
+                count: 0,
+            },
+            (Escape::Csi { mut params, count }, '0'..='9') => {
+                if let Some(param) = params.get_mut(count) {
+                    let digit = c as u16 - '0' as u16;
+                    *param = param.saturating_mul(10).saturating_add(digit);
+                }
+                Escape::Csi { params, count }
+            }
+            (Escape::Csi { params, count }, ';') => Escape::Csi {
+                params,
+                count: count + 1,
+            },
+            (Escape::Csi { params, count }, '@'..='~') => {
+                self.escape = Escape::None;
+                let params = &params[..(count + 1).min(MAX_PARAMS)];
+                return self.run_escape(c, params);
+            }
+            // Intermediate bytes like the `?` of private sequences are skipped
+            (Escape::Csi { params, count }, ' '..='?') => Escape::Csi { params, count },
+            _ => Escape::None,
+        };
+
+        Ok(())
+    }
+
+    /// Run the `ESC[` sequence ending in `command` with `params`
+    fn run_escape(&mut self, command: char, params: &[u16]) -> Result<(), DisplayError> {
+        let (columns, rows) = self.get_dimensions();
+        let (column, row) = self.cursor;
+        // Counts and positions saturate at the display size, and 0 counts as 1
+        let count = |param: u16| u8::try_from(param).unwrap_or(u8::MAX).max(1);
+        let cells = usize::from(columns) * usize::from(rows);
+        let cursor = usize::from(row) * usize::from(columns) + usize::from(column);
+        let row_start = usize::from(row) * usize::from(columns);
+
+        self.wrapped = false;
+        self.hide_cursor()?;
+        match command {
+            'H' | 'f' => {
+                let row = count(params[0]).min(rows) - 1;
+                let column = count(params.get(1).copied().unwrap_or(0)).min(columns) - 1;
+                self.cursor = (column, row);
+            }
+            'A' => self.cursor.1 = row.saturating_sub(count(params[0])),
+            'B' => self.cursor.1 = row.saturating_add(count(params[0])).min(rows - 1),
+            'C' => self.cursor.0 = column.saturating_add(count(params[0])).min(columns - 1),
+            'D' => self.cursor.0 = column.saturating_sub(count(params[0])),
+            'J' => match params[0] {
+                0 => self.erase(cursor, cells)?,
+                1 => self.erase(0, cursor + 1)?,
+                2 => self.erase(0, cells)?,
+                _ => {}
+            },
+            'K' => match params[0] {
+                0 => self.erase(cursor, row_start + usize::from(columns))?,
+                1 => self.erase(row_start, cursor + 1)?,
+                2 => self.erase(row_start, row_start + usize::from(columns))?,
+                _ => {}
+            },
+            'm' => {
+                for param in params {
+                    match param {
+                        0 | 27 => self.reverse = false,
+                        7 => self.reverse = true,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        self.show_cursor()
+    }
+
+    /// Clear the cells `start..end`, counted row by row from the top left, and redraw their rows
+    fn erase(&mut self, start: usize, end: usize) -> Result<(), DisplayError> {
+        let (columns, _) = self.get_dimensions();
+        let columns = usize::from(columns);
+
+        self.text[start..end].fill(SPACE);
+        for row in start / columns..end.div_ceil(columns) {
+            self.draw_row(row as u8)?;
+        }
+
+        Ok(())
+    }
+
+    /// Choose whether text continues at the top or scrolls up after the last row. See
+    /// [`TextOverflow`].
+    pub fn set_overflow(&mut self, overflow: TextOverflow) {
+        self.overflow = overflow;
+    }
+
+    /// Get the behaviour after the last row set with `set_overflow`
+    pub fn get_overflow(&self) -> TextOverflow {
+        self.overflow
+    }
+
+    /// Set the number of columns between two tab stops for `\t`. Defaults to 4, and 0 is
+    /// treated as 1.
+    pub fn set_tab_width(&mut self, tab_width: u8) {
+        self.tab_width = tab_width.max(1);
+    }
+
+    /// The row that follows `row`, scrolling the text up if needed
+    fn next_row(&mut self, row: u8, rows: u8) -> Result<u8, DisplayError> {
+        if row + 1 < rows {
+            return Ok(row + 1);
+        }
+
+        match self.overflow {
+            TextOverflow::Wrap => Ok(0),
+            TextOverflow::Scroll => {
+                self.scroll_up()?;
+                Ok(rows - 1)
+            }
+        }
+    }
+
+    /// Move all text up by one row, clear the last row and redraw the display
+    fn scroll_up(&mut self) -> Result<(), DisplayError> {
+        let (columns, rows) = self.get_dimensions();
+        let (columns, rows) = (usize::from(columns), usize::from(rows));
+
+        self.text.copy_within(columns..columns * rows, 0);
+        self.text[columns * (rows - 1)..columns * rows].fill(SPACE);
+
+        for row in 0..rows as u8 {
+            self.draw_row(row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Draw all characters stored for text row `row`
+    fn draw_row(&mut self, row: u8) -> Result<(), DisplayError> {
+        let (columns, _) = self.get_dimensions();
+        let (cell_width, cell_height) = self.cell_size();
+
+        match self.properties.get_rotation() {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => {
+                // The whole row is in the same pages, so it goes out in one transfer per page
+                let start = usize::from(row) * usize::from(columns);
+                let first_page = row * cell_height / 8;
+                let pages = cell_height / 8;
+                let length = columns * cell_width;
+
+                let column_offset = self.properties.get_size().column_offset();
+                self.properties.set_draw_area_unchecked(
+                    (column_offset, first_page),
+                    (column_offset + length, first_page + pages),
+                )?;
+                for page in 0..pages {
+                    let mut buffer = [0u8; 160];
+                    for (cell, &c) in buffer
+                        .chunks_exact_mut(cell_width.into())
+                        .zip(&self.text[start..start + usize::from(columns)])
+                    {
+                        self.render_cell_page(c, page, c & REVERSE != 0, cell);
+                    }
+                    self.properties.draw(&buffer[..usize::from(length)])?;
+                }
+
+                Ok(())
+            }
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                (0..columns).try_for_each(|column| self.draw_cell(column, row, false))
+            }
+        }
+    }
+
+    /// Draw the character stored for the cell at `column` and `row`, inverted if `invert` is set
+    fn draw_cell(&mut self, column: u8, row: u8, invert: bool) -> Result<(), DisplayError> {
+        let (columns, _) = self.get_dimensions();
+        let (cell_width, cell_height) = self.cell_size();
+        let c = self.text[usize::from(row) * usize::from(columns) + usize::from(column)];
+        let invert = invert != (c & REVERSE != 0);
+        let column_offset = self.properties.get_size().column_offset();
+
+        // Work out the panel columns and the pages the cell is stored in
+        let (start, width, first_page, pages) = match self.properties.get_rotation() {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (
+                column * cell_width,
+                cell_width,
+                row * cell_height / 8,
+                cell_height / 8,
+            ),
+            // Display rows run along the panel columns
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (
+                row * cell_height,
+                cell_height,
+                column * cell_width / 8,
+                cell_width / 8,
+            ),
+        };
+
+        self.properties.set_draw_area_unchecked(
+            (column_offset + start, first_page),
+            (column_offset + start + width, first_page + pages),
+        )?;
+        for page in 0..pages {
+            let mut buffer = [0u8; 160];
+            let cell = &mut buffer[..usize::from(width)];
+            self.render_cell_page(c, page, invert, cell);
+            self.properties.draw(cell)?;
+        }
+
+        Ok(())
+    }
+
+    /// Render page `page` of the cell holding `c` to `out`, one byte per panel column
+    fn render_cell_page(&self, c: u16, page: u8, invert: bool, out: &mut [u8]) {
+        let (font_width, font_height) = self.font.cell_size();
+        let c = char::from_u32(u32::from(c & !REVERSE)).unwrap_or('?');
+        let swap = matches!(
+            self.properties.get_rotation(),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270
+        );
+
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = 0;
+            for bit in 0..8 {
+                // With the display rotated the glyph has to be transposed
+                let (x, y) = if swap {
+                    (page * 8 + bit, i as u8)
+                } else {
+                    (i as u8, page * 8 + bit)
+                };
+                if x < font_width && y < font_height && self.font.pixel(c, x, y) {
+                    *byte |= 1 << bit;
+                }
+            }
+            if invert {
+                *byte = !*byte;
+            }
+        }
+    }
+
+    /// Draw the cursor if it is visible and not drawn yet
+    fn show_cursor(&mut self) -> Result<(), DisplayError> {
+        if self.cursor_visible && !self.cursor_shown {
+            let (column, row) = self.cursor;
+            self.draw_cell(column, row, true)?;
+            self.cursor_shown = true;
+        }
+
+        Ok(())
+    }
+
+    /// Restore the cell under the cursor if the cursor is drawn
+    fn hide_cursor(&mut self) -> Result<(), DisplayError> {
+        if self.cursor_shown {
+            let (column, row) = self.cursor;
+            self.draw_cell(column, row, false)?;
+            self.cursor_shown = false;
+        }
+
+        Ok(())
+    }
+
+    /// Set the display rotation. This does not move text that is already on the display, and
+    /// forgets it for redrawing the cell under the cursor.
+    pub fn set_rotation(&mut self, rot: DisplayRotation) -> Result<(), DisplayError> {
+        self.hide_cursor()?;
+        self.properties.set_rotation(rot)?;
+        self.text = [SPACE; MAX_CELLS];
+        self.cursor = (0, 0);
+        self.wrapped = false;
+
+        self.show_cursor()
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.display_on(on)
+    }
+
+    /// Put the display into its lowest power state. See [`DisplayProperties::sleep`].
+    pub fn sleep(&mut self) -> Result<(), DisplayError> {
+        self.properties.sleep()
+    }
+
+    /// Wake the display from `sleep`. See [`DisplayProperties::wake`].
+    pub fn wake<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u8>,
+    {
+        self.properties.wake(delay)
+    }
+
+    /// Mirror the displayed image independent of the rotation. See
+    /// [`DisplayProperties::set_mirror`]. This does not move text that is already on the display.
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) -> Result<(), DisplayError> {
+        self.properties.set_mirror(horizontal, vertical)
+    }
+
+    /// Invert the display in hardware. See [`DisplayProperties::set_invert`].
+    pub fn set_invert(&mut self, invert: bool) -> Result<(), DisplayError> {
+        self.properties.set_invert(invert)
+    }
+
+    /// Whether the display was last turned on. See [`DisplayProperties::is_on`].
+    pub fn is_on(&self) -> bool {
+        self.properties.is_on()
+    }
+
+    /// Whether the display is inverted with `set_invert`
+    pub fn is_inverted(&self) -> bool {
+        self.properties.is_inverted()
+    }
+
+    /// Get the mirroring set with `set_mirror` as `(horizontal, vertical)`
+    pub fn get_mirror(&self) -> (bool, bool) {
+        self.properties.get_mirror()
+    }
+
+    /// Get the display contrast as last set by `set_contrast`
+    pub fn get_contrast(&self) -> u8 {
+        self.properties.get_contrast()
+    }
+
+    /// Whether a display answers on the interface. See [`DisplayProperties::is_connected`].
+    pub fn is_connected(&mut self) -> bool {
+        self.properties.is_connected()
+    }
+
+    /// Send `command` to the controller without leaving the mode. See
+    /// [`DisplayProperties::send_command`].
+    pub fn send_command(&mut self, command: Command) -> Result<(), DisplayError> {
+        self.properties.send_command(command)
+    }
+
+    /// Send `bytes` to the controller as commands, e.g. for module specific commands. See
+    /// [`DisplayProperties::send_raw_command`].
+    pub fn send_raw_command(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.properties.send_raw_command(bytes)
+    }
+
+    /// Send `data` to the display RAM at the current address of the controller. The printed text
+    /// is not updated, so redrawing the cursor may draw over it. See
+    /// [`DisplayProperties::send_raw_data`].
+    pub fn send_raw_data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        self.properties.send_raw_data(data)
+    }
+
+    /// Change the display size, see [`DisplayProperties::set_size`]. The number of rows and
+    /// columns follows the size, so the terminal is cleared.
+    pub fn set_size(&mut self, display_size: DisplaySize) -> Result<(), DisplayError> {
+        self.properties.set_size(display_size)?;
+
+        self.clear()
+    }
+
+    /// Dim the display after a period without drawing. See [`DisplayProperties::set_auto_dim`].
+    pub fn set_auto_dim(&mut self, auto_dim: Option<(u32, u8)>) -> Result<(), DisplayError> {
+        self.properties.set_auto_dim(auto_dim)
+    }
+
+    /// Turn the display off after a period without drawing, and back on with the next flush.
+    /// See [`DisplayProperties::set_auto_off`].
+    pub fn set_auto_off(&mut self, timeout_ms: Option<u32>) -> Result<(), DisplayError> {
+        self.properties.set_auto_off(timeout_ms)
+    }
+
+    /// Get the counters of what the driver did, see the [`metrics`](crate::metrics) module
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.properties.metrics()
+    }
+
+    /// Start all counters of `metrics` from 0 again
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&mut self) {
+        self.properties.reset_metrics();
+    }
+
+    /// Count idle time for `set_auto_dim` and `set_auto_off`. See
+    /// [`DisplayProperties::idle_tick`].
+    pub fn idle_tick(&mut self, elapsed_ms: u32) -> Result<(), DisplayError> {
+        self.properties.idle_tick(elapsed_ms)
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.properties.set_contrast(contrast)
+    }
+
+    /// Fade the display out to zero contrast. See [`DisplayProperties::fade_out`].
+    pub fn fade_out<DELAY>(
+        &mut self,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.properties.fade_out(duration_ms, delay)
+    }
+
+    /// Fade the display back in to its contrast. See [`DisplayProperties::fade_in`].
+    pub fn fade_in<DELAY>(
+        &mut self,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.properties.fade_in(duration_ms, delay)
+    }
+
+    /// Set the contrast so the display looks `percent` bright, from 0 to 100. See
+    /// [`DisplayProperties::set_brightness_percent`].
+    pub fn set_brightness_percent(&mut self, percent: u8) -> Result<(), DisplayError> {
+        self.properties.set_brightness_percent(percent)
+    }
+
+    /// Set the contrast and precharge period to a brightness preset. See
+    /// [`DisplayProperties::set_brightness`].
+    pub fn set_brightness(&mut self, brightness: Brightness) -> Result<(), DisplayError> {
+        self.properties.set_brightness(brightness)
+    }
+
+    /// Switch between the normal and the dimmed contrast and precharge, e.g. for a night mode.
+    /// See [`DisplayProperties::dim`].
+    pub fn dim(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.properties.dim(on)
+    }
+
+    /// Set the contrast and precharge periods `dim` switches to. See
+    /// [`DisplayProperties::set_dim_level`].
+    pub fn set_dim_level(
+        &mut self,
+        contrast: u8,
+        phase1: u8,
+        phase2: u8,
+    ) -> Result<(), DisplayError> {
+        self.properties.set_dim_level(contrast, phase1, phase2)
+    }
+
+    /// Size of a character cell in display pixels
+    fn cell_size(&self) -> (u8, u8) {
+        let (width, height) = cell_size(self.font.cell_size(), self.properties.get_rotation());
+
+        // `with_font` made sure the cells fit on the display
+        (width as u8, height as u8)
+    }
+}
+
+/// Size of the display cells for a font with `font_size` cells. The controller writes whole pages
+/// of 8 pixel rows, so the side of the cell along the pages is rounded up to a multiple of 8.
+fn cell_size(font_size: (u8, u8), rotation: DisplayRotation) -> (u16, u16) {
+    let (width, height) = (u16::from(font_size.0), u16::from(font_size.1));
+
+    match rotation {
+        DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (width, height.div_ceil(8) * 8),
+        DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (width.div_ceil(8) * 8, height),
+    }
+}
+
+impl<DI, F> core::fmt::Write for TerminalMode<DI, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: TerminalFont,
+{
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        s.chars()
+            .try_for_each(|c| self.print_char(c))
+            .map_err(|_| core::fmt::Error)
+    }
+}
+
+/// Formatting through `ufmt`, behind the `ufmt` feature, for targets where the code size of
+/// `core::fmt` does not fit
+#[cfg(feature = "ufmt")]
+impl<DI, F> ufmt_write::uWrite for TerminalMode<DI, F>
+where
+    DI: WriteOnlyDataCommand,
+    F: TerminalFont,
+{
+    type Error = DisplayError;
+
+    fn write_str(&mut self, s: &str) -> Result<(), DisplayError> {
+        s.chars().try_for_each(|c| self.print_char(c))
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), DisplayError> {
+        self.print_char(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{displaysize::DisplaySize, test_helpers::CaptureInterface, Builder};
+    use core::fmt::Write;
+
+    #[test]
+    fn write_renders_glyphs() {
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        assert_eq!(display.get_dimensions(), (21, 20));
+
+        display.init().unwrap();
+        display.set_position(20, 3).unwrap();
+        iface.clear();
+        write!(display, "!T").unwrap();
+
+        // The second character wraps onto the next row
+        assert_eq!(display.get_position(), (1, 4));
+        assert_eq!(iface.commands()[..4], [0xB0, 0x03, 0x08, 0x18]);
+        assert_eq!(iface.data()[..6], [0x00, 0x00, 0x5E, 0x00, 0x00, 0x00]);
+        assert_eq!(iface.data()[6..], [0x00, 0x02, 0x7E, 0x02, 0x00, 0x00]);
+        assert!(iface
+            .commands()
+            .windows(4)
+            .any(|c| c == [0xB0, 0x04, 0x00, 0x11]));
+
+        display.print_char('\n').unwrap();
+        assert_eq!(display.get_position(), (0, 5));
+        assert!(matches!(
+            display.set_position(21, 0),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn control_characters_move_the_cursor() {
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+
+        write!(display, "!\t!").unwrap();
+        assert_eq!(display.get_position(), (5, 0));
+        display.set_tab_width(8);
+        write!(display, "\t\t\t").unwrap();
+        assert_eq!(display.get_position(), (0, 1));
+
+        // Backspace moves back without erasing and stops at the start of the row
+        iface.clear();
+        write!(display, "!\x08\x08\x08").unwrap();
+        assert_eq!(display.get_position(), (0, 1));
+        assert_eq!(iface.data()[..6], [0x00, 0x00, 0x5E, 0x00, 0x00, 0x00]);
+
+        // A full row is not followed by an empty one
+        display.set_position(0, 2).unwrap();
+        write!(display, "{:21}\r\n!", "").unwrap();
+        assert_eq!(display.get_position(), (1, 3));
+        write!(display, "\n\n").unwrap();
+        assert_eq!(display.get_position(), (0, 5));
+    }
+
+    #[test]
+    fn mode_switching_keeps_settings() {
+        let iface = CaptureInterface::new();
+        let mut terminal = Builder::new()
+            .with_rotation(DisplayRotation::Rotate270)
+            .connect(iface.clone())
+            .into_terminal_mode();
+        terminal.set_contrast(0x42).unwrap();
+
+        let mut graphics = terminal.into_graphics_mode();
+        assert_eq!(graphics.get_dimensions(), (160, 128));
+        assert_eq!(graphics.get_contrast(), 0x42);
+        graphics.set_pixel(0, 0, 1);
+
+        let terminal = graphics.into_raw_mode().into_terminal_mode();
+        assert_eq!(terminal.get_dimensions(), (20, 16));
+        assert_eq!(terminal.get_position(), (0, 0));
+    }
+
+    #[test]
+    fn cursor_inverts_its_cell() {
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        write!(display, "!").unwrap();
+
+        iface.clear();
+        display.set_cursor_visible(true).unwrap();
+        assert!(display.is_cursor_visible());
+        assert_eq!(iface.data(), [0xFF; 6]);
+
+        iface.clear();
+        display.blink_cursor().unwrap();
+        assert_eq!(iface.data(), [0x00; 6]);
+        display.blink_cursor().unwrap();
+        assert_eq!(iface.data()[6..], [0xFF; 6]);
+
+        // Moving the cursor back restores the old cell and inverts the character under it
+        iface.clear();
+        display.set_position(0, 0).unwrap();
+        assert_eq!(iface.data()[..6], [0x00; 6]);
+        assert_eq!(iface.data()[6..], [0xFF, 0xFF, 0xA1, 0xFF, 0xFF, 0xFF]);
+
+        // Printing over it draws the new character and the cursor in the next cell
+        iface.clear();
+        write!(display, "T").unwrap();
+        assert_eq!(iface.data()[6..12], [0x00, 0x02, 0x7E, 0x02, 0x00, 0x00]);
+        assert_eq!(iface.data()[12..], [0xFF; 6]);
+        assert_eq!(display.get_position(), (1, 0));
+
+        iface.clear();
+        display.set_cursor_visible(false).unwrap();
+        display.blink_cursor().unwrap();
+        assert_eq!(iface.data(), [0x00; 6]);
+    }
+
+    #[test]
+    #[cfg(feature = "ufmt")]
+    fn uwrite_prints_like_fmt_write() {
+        use ufmt_write::uWrite;
+
+        let expected = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(expected.clone()).into();
+        display.init().unwrap();
+        expected.clear();
+        write!(display, "T=21").unwrap();
+
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        iface.clear();
+        uWrite::write_str(&mut display, "T=2").unwrap();
+        uWrite::write_char(&mut display, '1').unwrap();
+
+        assert_eq!(iface.data(), expected.data());
+        assert_eq!(display.get_position(), (4, 0));
+    }
+
+    #[test]
+    fn scroll_moves_text_up() {
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        assert_eq!(display.get_overflow(), TextOverflow::Wrap);
+        display.set_overflow(TextOverflow::Scroll);
+
+        display.set_position(0, 1).unwrap();
+        write!(display, "!").unwrap();
+        display.set_position(0, 19).unwrap();
+        iface.clear();
+        display.write_str("T\n").unwrap();
+
+        // Every row is redrawn in one transfer, one row higher up
+        assert_eq!(display.get_position(), (0, 19));
+        let data = iface.data();
+        assert_eq!(data.len(), 6 + 20 * 126);
+        assert_eq!(data[6..12], [0x00, 0x00, 0x5E, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            data[6 + 18 * 126..][..6],
+            [0x00, 0x02, 0x7E, 0x02, 0x00, 0x00]
+        );
+        assert!(data[6 + 19 * 126..].iter().all(|&byte| byte == 0));
+
+        // Wrapping continues at the top instead
+        display.set_overflow(TextOverflow::Wrap);
+        display.print_char('\n').unwrap();
+        assert_eq!(display.get_position(), (0, 0));
+    }
+
+    /// A 10x12 font with a filled 9x12 block for every glyph
+    struct BlockFont;
+
+    impl TerminalFont for BlockFont {
+        fn cell_size(&self) -> (u8, u8) {
+            (10, 12)
+        }
+
+        fn pixel(&self, _: char, x: u8, _: u8) -> bool {
+            x < 9
+        }
+    }
+
+    #[test]
+    fn custom_fonts_take_whole_pages() {
+        let iface = CaptureInterface::new();
+        let display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        let mut display = display.with_font(BlockFont);
+        assert_eq!(display.get_dimensions(), (12, 10));
+
+        display.init().unwrap();
+        display.set_position(1, 1).unwrap();
+        iface.clear();
+        display.print_char('x').unwrap();
+
+        // The cell covers pages 2 and 3, with the last 4 rows left empty
+        assert_eq!(iface.commands()[..4], [0xB0, 0x02, 0x0A, 0x11]);
+        assert_eq!(
+            iface.data()[..10],
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00]
+        );
+        assert_eq!(
+            iface.data()[10..],
+            [0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x0F, 0x00]
+        );
+
+        // Rotated, the cell is rounded up to 16 pixels wide instead
+        display.set_rotation(DisplayRotation::Rotate90).unwrap();
+        assert_eq!(display.get_dimensions(), (10, 10));
+    }
+
+    #[test]
+    #[should_panic(expected = "font cells do not fit on the display")]
+    fn fonts_must_fit_the_display() {
+        let display: TerminalMode<_> = Builder::new()
+            .with_size(DisplaySize::Display64x160)
+            .connect(CaptureInterface::new())
+            .into();
+
+        // 80 pixel wide cells do not fit across the panel
+        struct WideFont;
+        impl TerminalFont for WideFont {
+            fn cell_size(&self) -> (u8, u8) {
+                (80, 12)
+            }
+
+            fn pixel(&self, _: char, _: u8, _: u8) -> bool {
+                false
+            }
+        }
+        let _ = display.with_font(WideFont);
+    }
+
+    #[test]
+    fn rotated_glyphs_are_transposed() {
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new()
+            .with_rotation(DisplayRotation::Rotate90)
+            .connect(iface.clone())
+            .into();
+        assert_eq!(display.get_dimensions(), (20, 16));
+
+        display.init().unwrap();
+        iface.clear();
+        display.print_char('!').unwrap();
+
+        // A vertical bar in glyph column 2 becomes bit 2 of the panel columns it covers
+        assert_eq!(
+            iface.data(),
+            [0x00, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04, 0x00]
+        );
+    }
+
+    #[test]
+    fn escape_sequences_move_erase_and_reverse() {
+        let iface = CaptureInterface::new();
+        let mut display: TerminalMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+
+        write!(display, "\x1b[3;5H").unwrap();
+        assert_eq!(display.get_position(), (4, 2));
+        write!(display, "\x1b[2A\x1b[10C\x1b[B\x1b[99D").unwrap();
+        assert_eq!(display.get_position(), (0, 1));
+        write!(display, "\x1b[99;99f\x1b[?25l").unwrap();
+        assert_eq!(display.get_position(), (20, 19));
+
+        // Reverse video inverts the whole cell, and is remembered when the row is redrawn
+        display.set_position(0, 0).unwrap();
+        iface.clear();
+        write!(display, "\x1b[7m!\x1b[m!").unwrap();
+        assert_eq!(iface.data()[..6], [0xFF, 0xFF, 0xA1, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(iface.data()[6..], [0x00, 0x00, 0x5E, 0x00, 0x00, 0x00]);
+
+        iface.clear();
+        write!(display, "\x1b[K").unwrap();
+        assert_eq!(display.get_position(), (2, 0));
+        let data = iface.data();
+        assert_eq!(data.len(), 126);
+        assert_eq!(data[..6], [0xFF, 0xFF, 0xA1, 0xFF, 0xFF, 0xFF]);
+        assert!(data[12..].iter().all(|&byte| byte == 0));
+
+        // Clearing the display keeps the cursor where it is
+        iface.clear();
+        write!(display, "\x1b[2J").unwrap();
+        assert_eq!(display.get_position(), (2, 0));
+        assert_eq!(iface.data().len(), 20 * 126);
+        assert!(iface.data().iter().all(|&byte| byte == 0));
+    }
+}
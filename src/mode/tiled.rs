@@ -0,0 +1,237 @@
+//! Several displays side by side as one canvas
+//!
+//! ```rust,ignore
+//! let left: GraphicsMode<_> = Builder::new().connect(left_interface).into();
+//! let right: GraphicsMode<_> = Builder::new().connect(right_interface).into();
+//! let mut display = TiledDisplay::new([left, right]);
+//!
+//! display.init().unwrap();
+//! Line::new(Point::new(0, 80), Point::new(255, 80))
+//!     .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+//!     .draw(&mut display)
+//!     .unwrap();
+//! display.flush().unwrap();
+//! ```
+//!
+//! [`TiledDisplay`] places its [`GraphicsMode`] panels next to each other from left to right, top
+//! aligned, and draws to them like a single display as wide as all of them together. Every panel
+//! keeps its own framebuffer, rotation and clipping, and the tiling follows the sizes of the
+//! panels as they are at the time of drawing, so rotating a panel by 90 degrees through
+//! [`TiledDisplay::panels_mut`] makes its tile narrower or wider.
+//!
+//! All panels have the same interface type. Displays on separate chip select pins need pins
+//! of the same type for that, e.g. the type erased pins most HALs offer.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Point, Size},
+    pixelcolor::BinaryColor,
+    primitives::Rectangle,
+    Pixel,
+};
+
+use crate::{
+    displayrotation::{RotationMode, RuntimeRotation},
+    mode::graphics::{FlushStats, GraphicsMode, MAX_BUFFER_SIZE},
+};
+
+/// Several [`GraphicsMode`] panels drawn to as one display, see the
+/// [module documentation](self)
+pub struct TiledDisplay<
+    DI,
+    const PANELS: usize,
+    const N: usize = MAX_BUFFER_SIZE,
+    R = RuntimeRotation,
+> where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+{
+    panels: [GraphicsMode<DI, N, R>; PANELS],
+}
+
+impl<DI, const PANELS: usize, const N: usize, R: RotationMode> TiledDisplay<DI, PANELS, N, R>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Tile `panels` from left to right
+    pub fn new(panels: [GraphicsMode<DI, N, R>; PANELS]) -> Self {
+        Self { panels }
+    }
+
+    /// Get the panels, from left to right
+    pub fn panels(&self) -> &[GraphicsMode<DI, N, R>; PANELS] {
+        &self.panels
+    }
+
+    /// Get the panels mutably, e.g. to change the contrast of only one of them
+    pub fn panels_mut(&mut self) -> &mut [GraphicsMode<DI, N, R>; PANELS] {
+        &mut self.panels
+    }
+
+    /// Take the panels back out
+    pub fn release(self) -> [GraphicsMode<DI, N, R>; PANELS] {
+        self.panels
+    }
+
+    /// Initialise every panel, see [`GraphicsMode::init`]
+    pub fn init(&mut self) -> Result<(), DisplayError> {
+        self.panels.iter_mut().try_for_each(GraphicsMode::init)
+    }
+
+    /// Clear the framebuffers of all panels. The displays change with the next flush.
+    pub fn clear(&mut self) {
+        self.panels.iter_mut().for_each(GraphicsMode::clear);
+    }
+
+    /// Write out the whole framebuffer of every panel, see [`GraphicsMode::flush`]. Stops at the
+    /// first panel that fails, and returns what all panels sent together otherwise.
+    pub fn flush(&mut self) -> Result<FlushStats, DisplayError> {
+        self.flush_each(GraphicsMode::flush)
+    }
+
+    /// Write out only what changed on every panel, see [`GraphicsMode::flush_dirty`]. Panels
+    /// without changes send nothing. Stops at the first panel that fails, and returns what all
+    /// panels sent together otherwise.
+    pub fn flush_dirty(&mut self) -> Result<FlushStats, DisplayError> {
+        self.flush_each(GraphicsMode::flush_dirty)
+    }
+
+    /// Flush every panel with `flush` and add up the stats
+    fn flush_each(
+        &mut self,
+        flush: fn(&mut GraphicsMode<DI, N, R>) -> Result<FlushStats, DisplayError>,
+    ) -> Result<FlushStats, DisplayError> {
+        self.panels
+            .iter_mut()
+            .try_fold(FlushStats::default(), |total, panel| {
+                let stats = flush(panel)?;
+
+                Ok(FlushStats {
+                    pages: total.pages + stats.pages,
+                    bytes: total.bytes + stats.bytes,
+                })
+            })
+    }
+
+    /// Turn all displays on or off
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.panels
+            .iter_mut()
+            .try_for_each(|panel| panel.display_on(on))
+    }
+
+    /// Set the contrast of all displays
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.panels
+            .iter_mut()
+            .try_for_each(|panel| panel.set_contrast(contrast))
+    }
+
+    /// The panels with the area they cover on the canvas
+    fn tiles(&mut self) -> impl Iterator<Item = (Rectangle, &mut GraphicsMode<DI, N, R>)> {
+        let mut left = 0;
+
+        self.panels.iter_mut().map(move |panel| {
+            let tile = Rectangle::new(Point::new(left, 0), panel.size());
+            left += tile.size.width as i32;
+
+            (tile, panel)
+        })
+    }
+}
+
+impl<DI, const PANELS: usize, const N: usize, R: RotationMode> DrawTarget
+    for TiledDisplay<DI, PANELS, N, R>
+where
+    DI: WriteOnlyDataCommand,
+{
+    type Color = BinaryColor;
+    type Error = DisplayError;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if let Some((tile, panel)) = self.tiles().find(|(tile, _)| tile.contains(pos)) {
+                panel.draw_iter([Pixel(pos - tile.top_left, color)])?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fill_solid(&mut self, area: &Rectangle, color: Self::Color) -> Result<(), Self::Error> {
+        // Split the area at the panel borders so every panel gets one fill
+        for (tile, panel) in self.tiles() {
+            let part = tile.intersection(area);
+            if part.size != Size::zero() {
+                panel.fill_solid(
+                    &Rectangle::new(part.top_left - tile.top_left, part.size),
+                    color,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<DI, const PANELS: usize, const N: usize, R: RotationMode> OriginDimensions
+    for TiledDisplay<DI, PANELS, N, R>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn size(&self) -> Size {
+        self.panels.iter().fold(Size::zero(), |size, panel| {
+            let tile = panel.size();
+
+            Size::new(size.width + tile.width, size.height.max(tile.height))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_helpers::CaptureInterface, Builder};
+
+    #[test]
+    fn drawing_is_split_across_panels() {
+        let left_iface = CaptureInterface::new();
+        let right_iface = CaptureInterface::new();
+        let mut display: TiledDisplay<_, 2> = TiledDisplay::new([
+            Builder::new().connect(left_iface.clone()).into(),
+            Builder::new().connect(right_iface.clone()).into(),
+        ]);
+        display.init().unwrap();
+        assert_eq!(display.size(), Size::new(256, 160));
+
+        display
+            .fill_solid(
+                &Rectangle::new(Point::new(120, 10), Size::new(16, 2)),
+                BinaryColor::On,
+            )
+            .unwrap();
+        display
+            .draw_iter([
+                Pixel(Point::new(200, 100), BinaryColor::On),
+                Pixel(Point::new(256, 0), BinaryColor::On),
+            ])
+            .unwrap();
+
+        let [left, right] = display.panels();
+        assert!(left.get_pixel(120, 10) && left.get_pixel(127, 11));
+        assert!(right.get_pixel(0, 10) && right.get_pixel(7, 11));
+        assert!(!right.get_pixel(8, 10));
+        assert!(right.get_pixel(72, 100));
+
+        left_iface.clear();
+        right_iface.clear();
+        let stats = display.flush().unwrap();
+        assert_eq!(stats.bytes, 2 * 128 * 20);
+        assert_eq!(left_iface.data().len(), 128 * 20);
+        assert_eq!(right_iface.data().len(), 128 * 20);
+    }
+}
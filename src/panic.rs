@@ -0,0 +1,114 @@
+//! Showing panics on the display, behind the `panic-screen` feature
+//!
+//! [`draw_panic_screen`] initialises the display from scratch and prints the panic message and
+//! its location with the built-in font, so a device that stopped in the field shows why:
+//!
+//! ```rust,ignore
+//! #[panic_handler]
+//! fn panic(info: &PanicInfo) -> ! {
+//!     // The driver owned by the application is out of reach, so build a new interface
+//!     let interface = unsafe { steal_display_interface() };
+//!     let _ = draw_panic_screen(Builder::new(), interface, info);
+//!
+//!     loop {}
+//! }
+//! ```
+//!
+//! Everything is sent with blocking writes and no framebuffer is needed, only the 1040 bytes
+//! [`TerminalMode`] keeps of the printed text, on the stack. The display is initialised with the
+//! settings of the given [`Builder`], so a module that needs e.g. a column offset or an external
+//! VPP shows the message as usual. Text that does not fit wraps and continues at the top.
+
+use core::{
+    fmt::{self, Write},
+    panic::{Location, PanicInfo},
+};
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+use crate::{builder::Builder, mode::TerminalMode};
+
+/// Initialise the display on `interface` with the settings of `builder` and print the message
+/// and location of the panic `info`, see the [module documentation](self)
+pub fn draw_panic_screen<DI>(
+    builder: Builder,
+    interface: DI,
+    info: &PanicInfo<'_>,
+) -> Result<(), DisplayError>
+where
+    DI: WriteOnlyDataCommand,
+{
+    draw_message(builder, interface, info.message(), info.location())
+}
+
+fn draw_message<DI>(
+    builder: Builder,
+    interface: DI,
+    message: impl fmt::Display,
+    location: Option<&Location<'_>>,
+) -> Result<(), DisplayError>
+where
+    DI: WriteOnlyDataCommand,
+{
+    let mut terminal: TerminalMode<_> = builder.connect(interface).into();
+    terminal.init()?;
+
+    let mut printer = Printer {
+        terminal: &mut terminal,
+        error: None,
+    };
+    let _ = write!(printer, "PANIC\n{}\n", message);
+    if let Some(location) = location {
+        let _ = write!(printer, "{}:{}", location.file(), location.line());
+    }
+
+    printer.error.map_or(Ok(()), Err)
+}
+
+/// Prints to a terminal, keeping the display error that `fmt::Write` cannot return
+struct Printer<'a, DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    terminal: &'a mut TerminalMode<DI>,
+    error: Option<DisplayError>,
+}
+
+impl<DI> Write for Printer<'_, DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        s.chars().try_for_each(|c| {
+            self.terminal.print_char(c).map_err(|error| {
+                self.error = Some(error);
+                fmt::Error
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::RamInterface;
+
+    #[test]
+    fn message_and_location_are_printed() {
+        let iface = RamInterface::new();
+        let location = Location::caller();
+
+        draw_message(
+            Builder::new(),
+            iface.clone(),
+            "index out of bounds",
+            Some(location),
+        )
+        .unwrap();
+
+        // "PANIC" on the first row, the message on the second, the location below
+        let lit = |page: u8| iface.page(page).iter().filter(|&&byte| byte != 0).count();
+        assert!(lit(0) > 0 && lit(1) > 0 && lit(2) > 0);
+        assert_eq!(lit(19), 0);
+    }
+}
@@ -0,0 +1,407 @@
+//! 8-bit parallel interfaces for the SH1108, driven over GPIO pins
+//!
+//! Besides I2C and SPI, the SH1108 can be wired to an 8-bit parallel bus in either 8080 or 6800
+//! style, selected with its IM pins. Both put a whole byte on the D0-D7 lines and latch it with a
+//! strobe, with A0 (DC) low for commands and high for display data:
+//!
+//! * [`Parallel8080Interface`] latches on the rising edge of a low pulse on WR. RD must be held
+//!   high.
+//! * [`Parallel6800Interface`] latches on the falling edge of a high pulse on E. R/W must be held
+//!   low.
+//!
+//! ```rust,ignore
+//! let bus = GpioBus::new((d0, d1, d2, d3, d4, d5, d6, d7));
+//! let interface = Parallel8080Interface::new(bus, dc, wr);
+//! let mut disp: GraphicsMode<_> = Builder::new().connect(interface).into();
+//! ```
+//!
+//! Chip select is not handled, tie CS low or assert it around the use of the display. The
+//! interfaces only write, so reading back the display needs an interface of its own that can
+//! turn the data pins around.
+//!
+//! # Timing
+//!
+//! The strobe has to stay low and high for at least 100ns each, and a whole write cycle takes at
+//! least 300ns. The data lines are set before the strobe starts and only change again for the
+//! next byte, so the setup and hold times are met whenever the strobe is. Most microcontrollers
+//! need longer than that per pin change through `OutputPin` anyway. Faster ones can stretch every
+//! strobe level with `with_strobe_delay`, which spins for the given number of loop iterations.
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use hal::digital::v2::OutputPin;
+
+/// The 8 data lines of a parallel bus
+pub trait OutputBus {
+    /// Put `value` on the data lines, with bit 0 on D0
+    fn set_value(&mut self, value: u8) -> Result<(), DisplayError>;
+}
+
+/// An [`OutputBus`] made of 8 separate GPIO pins, D0 first. Only pins whose level changes are
+/// written.
+pub struct GpioBus<P0, P1, P2, P3, P4, P5, P6, P7> {
+    pins: (P0, P1, P2, P3, P4, P5, P6, P7),
+    last: Option<u8>,
+}
+
+impl<P0, P1, P2, P3, P4, P5, P6, P7> GpioBus<P0, P1, P2, P3, P4, P5, P6, P7>
+where
+    P0: OutputPin,
+    P1: OutputPin,
+    P2: OutputPin,
+    P3: OutputPin,
+    P4: OutputPin,
+    P5: OutputPin,
+    P6: OutputPin,
+    P7: OutputPin,
+{
+    /// Create a bus from the pins connected to D0 to D7
+    pub fn new(pins: (P0, P1, P2, P3, P4, P5, P6, P7)) -> Self {
+        Self { pins, last: None }
+    }
+
+    /// Release the pins
+    pub fn release(self) -> (P0, P1, P2, P3, P4, P5, P6, P7) {
+        self.pins
+    }
+}
+
+/// Set `pin` to bit `bit` of `value` if it differs from `last`
+fn set_bit<P: OutputPin>(
+    pin: &mut P,
+    bit: u8,
+    value: u8,
+    last: Option<u8>,
+) -> Result<(), DisplayError> {
+    let high = value & 1 << bit != 0;
+
+    if last.is_some_and(|last| (last & 1 << bit != 0) == high) {
+        return Ok(());
+    }
+
+    set_level(pin, high, DisplayError::BusWriteError)
+}
+
+/// Set `pin` high or low, mapping a failure to `error`
+fn set_level<P: OutputPin>(
+    pin: &mut P,
+    high: bool,
+    error: DisplayError,
+) -> Result<(), DisplayError> {
+    let result = if high { pin.set_high() } else { pin.set_low() };
+
+    result.map_err(|_| error)
+}
+
+impl<P0, P1, P2, P3, P4, P5, P6, P7> OutputBus for GpioBus<P0, P1, P2, P3, P4, P5, P6, P7>
+where
+    P0: OutputPin,
+    P1: OutputPin,
+    P2: OutputPin,
+    P3: OutputPin,
+    P4: OutputPin,
+    P5: OutputPin,
+    P6: OutputPin,
+    P7: OutputPin,
+{
+    fn set_value(&mut self, value: u8) -> Result<(), DisplayError> {
+        let last = self.last;
+        // Forget the levels until all pins are set, a failed write leaves them unknown
+        self.last = None;
+
+        set_bit(&mut self.pins.0, 0, value, last)?;
+        set_bit(&mut self.pins.1, 1, value, last)?;
+        set_bit(&mut self.pins.2, 2, value, last)?;
+        set_bit(&mut self.pins.3, 3, value, last)?;
+        set_bit(&mut self.pins.4, 4, value, last)?;
+        set_bit(&mut self.pins.5, 5, value, last)?;
+        set_bit(&mut self.pins.6, 6, value, last)?;
+        set_bit(&mut self.pins.7, 7, value, last)?;
+
+        self.last = Some(value);
+
+        Ok(())
+    }
+}
+
+/// Call `write` for every byte of `format`
+fn for_each_byte(
+    format: DataFormat<'_>,
+    mut write: impl FnMut(u8) -> Result<(), DisplayError>,
+) -> Result<(), DisplayError> {
+    match format {
+        DataFormat::U8(buf) => buf.iter().try_for_each(|&byte| write(byte)),
+        DataFormat::U8Iter(iter) => {
+            for byte in iter {
+                write(byte)?;
+            }
+
+            Ok(())
+        }
+        _ => Err(DisplayError::DataFormatNotImplemented),
+    }
+}
+
+/// Keep a strobe level for `loops` iterations
+fn stretch(loops: u32) {
+    for _ in 0..loops {
+        core::hint::spin_loop();
+    }
+}
+
+/// 8080 style parallel interface, see the [module documentation](self)
+pub struct Parallel8080Interface<BUS, DC, WR> {
+    bus: BUS,
+    dc: DC,
+    wr: WR,
+    strobe_delay: u32,
+}
+
+impl<BUS, DC, WR> Parallel8080Interface<BUS, DC, WR>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    WR: OutputPin,
+{
+    /// Create an interface from the data bus, the DC (A0) pin and the WR pin. WR should already
+    /// be high.
+    pub fn new(bus: BUS, dc: DC, wr: WR) -> Self {
+        Self {
+            bus,
+            dc,
+            wr,
+            strobe_delay: 0,
+        }
+    }
+
+    /// Spin for `loops` iterations after every edge of WR, for microcontrollers that would
+    /// otherwise pulse it faster than the SH1108 allows
+    pub fn with_strobe_delay(self, loops: u32) -> Self {
+        Self {
+            strobe_delay: loops,
+            ..self
+        }
+    }
+
+    /// Release the bus and pins
+    pub fn release(self) -> (BUS, DC, WR) {
+        (self.bus, self.dc, self.wr)
+    }
+
+    fn send(&mut self, data: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        set_level(&mut self.dc, data, DisplayError::DCError)?;
+
+        for_each_byte(format, |byte| {
+            self.bus.set_value(byte)?;
+            set_level(&mut self.wr, false, DisplayError::BusWriteError)?;
+            stretch(self.strobe_delay);
+            set_level(&mut self.wr, true, DisplayError::BusWriteError)?;
+            stretch(self.strobe_delay);
+
+            Ok(())
+        })
+    }
+}
+
+impl<BUS, DC, WR> WriteOnlyDataCommand for Parallel8080Interface<BUS, DC, WR>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    WR: OutputPin,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(true, buf)
+    }
+}
+
+/// 6800 style parallel interface, see the [module documentation](self)
+pub struct Parallel6800Interface<BUS, DC, E> {
+    bus: BUS,
+    dc: DC,
+    e: E,
+    strobe_delay: u32,
+}
+
+impl<BUS, DC, E> Parallel6800Interface<BUS, DC, E>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    E: OutputPin,
+{
+    /// Create an interface from the data bus, the DC (A0) pin and the E pin. E should already
+    /// be low.
+    pub fn new(bus: BUS, dc: DC, e: E) -> Self {
+        Self {
+            bus,
+            dc,
+            e,
+            strobe_delay: 0,
+        }
+    }
+
+    /// Spin for `loops` iterations after every edge of E, for microcontrollers that would
+    /// otherwise pulse it faster than the SH1108 allows
+    pub fn with_strobe_delay(self, loops: u32) -> Self {
+        Self {
+            strobe_delay: loops,
+            ..self
+        }
+    }
+
+    /// Release the bus and pins
+    pub fn release(self) -> (BUS, DC, E) {
+        (self.bus, self.dc, self.e)
+    }
+
+    fn send(&mut self, data: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        set_level(&mut self.dc, data, DisplayError::DCError)?;
+
+        for_each_byte(format, |byte| {
+            self.bus.set_value(byte)?;
+            set_level(&mut self.e, true, DisplayError::BusWriteError)?;
+            stretch(self.strobe_delay);
+            set_level(&mut self.e, false, DisplayError::BusWriteError)?;
+            stretch(self.strobe_delay);
+
+            Ok(())
+        })
+    }
+}
+
+impl<BUS, DC, E> WriteOnlyDataCommand for Parallel6800Interface<BUS, DC, E>
+where
+    BUS: OutputBus,
+    DC: OutputPin,
+    E: OutputPin,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(true, buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mode::GraphicsMode, Builder};
+    use std::{cell::RefCell, rc::Rc, vec::Vec};
+
+    /// Levels of the bus lines and the bytes latched by the simulated controller
+    #[derive(Default)]
+    struct Lines {
+        data: u8,
+        dc: bool,
+        strobe: bool,
+        pin_writes: usize,
+        latched: Vec<(bool, u8)>,
+    }
+
+    #[derive(Clone, Copy)]
+    enum Line {
+        Data(u8),
+        Dc,
+        /// A strobe latching on the given edge, true for rising
+        Strobe(bool),
+    }
+
+    struct Pin(Rc<RefCell<Lines>>, Line);
+
+    impl OutputPin for Pin {
+        type Error = ();
+
+        fn set_high(&mut self) -> Result<(), ()> {
+            self.set(true);
+            Ok(())
+        }
+
+        fn set_low(&mut self) -> Result<(), ()> {
+            self.set(false);
+            Ok(())
+        }
+    }
+
+    impl Pin {
+        fn set(&mut self, high: bool) {
+            let mut lines = self.0.borrow_mut();
+            lines.pin_writes += 1;
+            match self.1 {
+                Line::Data(bit) => lines.data = (lines.data & !(1 << bit)) | u8::from(high) << bit,
+                Line::Dc => lines.dc = high,
+                Line::Strobe(edge) => {
+                    if lines.strobe != edge && high == edge {
+                        let latch = (lines.dc, lines.data);
+                        lines.latched.push(latch);
+                    }
+                    lines.strobe = high;
+                }
+            }
+        }
+    }
+
+    fn bus(lines: &Rc<RefCell<Lines>>) -> impl OutputBus {
+        let pin = |bit| Pin(lines.clone(), Line::Data(bit));
+        GpioBus::new((
+            pin(0),
+            pin(1),
+            pin(2),
+            pin(3),
+            pin(4),
+            pin(5),
+            pin(6),
+            pin(7),
+        ))
+    }
+
+    #[test]
+    fn bytes_are_latched_with_the_dc_level() {
+        let lines = Rc::new(RefCell::new(Lines::default()));
+        lines.borrow_mut().strobe = true;
+        let mut iface = Parallel8080Interface::new(
+            bus(&lines),
+            Pin(lines.clone(), Line::Dc),
+            Pin(lines.clone(), Line::Strobe(true)),
+        );
+
+        iface.send_commands(DataFormat::U8(&[0xB0, 0x12])).unwrap();
+        iface
+            .send_data(DataFormat::U8Iter(&mut [0xFF, 0xFE].into_iter()))
+            .unwrap();
+        assert_eq!(
+            lines.borrow().latched,
+            [(false, 0xB0), (false, 0x12), (true, 0xFF), (true, 0xFE)]
+        );
+
+        // Only the changed data line is written for the second byte
+        lines.borrow_mut().pin_writes = 0;
+        iface.send_data(DataFormat::U8(&[0xFF])).unwrap();
+        assert_eq!(lines.borrow().pin_writes, 1 + 1 + 2);
+
+        let lines = Rc::new(RefCell::new(Lines::default()));
+        let iface = Parallel6800Interface::new(
+            bus(&lines),
+            Pin(lines.clone(), Line::Dc),
+            Pin(lines.clone(), Line::Strobe(false)),
+        )
+        .with_strobe_delay(2);
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface).into();
+        display.init().unwrap();
+        display.set_pixel(0, 0, 1);
+        display.flush().unwrap();
+
+        let latched = &lines.borrow().latched;
+        let data: Vec<u8> = latched
+            .iter()
+            .filter(|(dc, _)| *dc)
+            .map(|&(_, byte)| byte)
+            .collect();
+        assert_eq!(data.len(), 128 * 20);
+        assert_eq!(data[0], 0x01);
+        assert!(latched.iter().any(|(dc, _)| !dc));
+    }
+}
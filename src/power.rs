@@ -0,0 +1,248 @@
+//! Power saving and burn-in protection state of the display properties
+
+use crate::{brightness::Brightness, properties::FlushEvent};
+
+/// `scale` value for which the contrast is applied unchanged
+const SCALE_FULL: u16 = 256;
+
+/// The state behind the power budget, dimming, automatic dimming and turning off, the pixel
+/// shift and the flush hook of [`DisplayProperties`](crate::properties::DisplayProperties). It
+/// decides what has to change, and the display properties send the commands for it.
+pub(crate) struct PowerState {
+    /// Share of the pixels, in percent, that may be lit at the full contrast
+    budget: u8,
+    /// Factor the contrast is reduced by to stay within the budget, out of `SCALE_FULL`
+    scale: u16,
+    /// Milliseconds passed to `tick` since pixel data was last sent
+    idle_ms: u32,
+    /// Idle time after which the display is dimmed, and the contrast it is dimmed to
+    auto_dim: Option<(u32, u8)>,
+    /// Whether the display is currently dimmed for being idle
+    dimmed: bool,
+    /// Idle time after which the display is turned off
+    auto_off: Option<u32>,
+    /// Whether the display is currently turned off for being idle
+    blanked: bool,
+    /// Contrast and precharge periods used while `dim` is on
+    dim_level: (u8, (u8, u8)),
+    /// Whether the display is dimmed with `dim`
+    dim_on: bool,
+    shift: (i8, i8),
+    shift_range: u8,
+    shift_step: u16,
+    flush_hook: Option<fn(FlushEvent)>,
+    /// Pixel data bytes sent since the current flush started, if one is in progress
+    flush_bytes: Option<usize>,
+}
+
+impl PowerState {
+    pub(crate) fn new() -> Self {
+        Self {
+            budget: 100,
+            scale: SCALE_FULL,
+            idle_ms: 0,
+            auto_dim: None,
+            dimmed: false,
+            auto_off: None,
+            blanked: false,
+            dim_level: (Brightness::Dim.contrast(), Brightness::Dim.precharge()),
+            dim_on: false,
+            shift: (0, 0),
+            shift_range: 0,
+            shift_step: 0,
+            flush_hook: None,
+            flush_bytes: None,
+        }
+    }
+
+    /// The contrast to apply instead of `contrast`, i.e. the dimmed one while `dim` is on,
+    /// reduced to the power budget
+    pub(crate) fn resting_contrast(&self, contrast: u8) -> u8 {
+        let contrast = if self.dim_on {
+            self.dim_level.0
+        } else {
+            contrast
+        };
+
+        (u32::from(contrast) * u32::from(self.scale) / u32::from(SCALE_FULL)) as u8
+    }
+
+    /// The precharge periods to apply instead of `precharge`, i.e. the dimmed ones while `dim`
+    /// is on
+    pub(crate) fn active_precharge(&self, precharge: (u8, u8)) -> (u8, u8) {
+        if self.dim_on {
+            self.dim_level.1
+        } else {
+            precharge
+        }
+    }
+
+    pub(crate) fn set_budget(&mut self, max_on_pixels_percent: u8) {
+        self.budget = max_on_pixels_percent.min(100);
+    }
+
+    /// Scale the contrast down for a frame lighting `lit_pixels` of `pixels`, see
+    /// `DisplayProperties::apply_power_budget`. Returns whether the scale changed.
+    pub(crate) fn apply_budget(&mut self, lit_pixels: u32, pixels: u32) -> bool {
+        let allowed = pixels * u32::from(self.budget) / 100;
+
+        let scale = if self.budget < 100 && lit_pixels > allowed {
+            (allowed * u32::from(SCALE_FULL) / lit_pixels) as u16
+        } else {
+            SCALE_FULL
+        };
+
+        let changed = scale != self.scale;
+        self.scale = scale;
+        changed
+    }
+
+    pub(crate) fn set_dim_level(&mut self, contrast: u8, precharge: (u8, u8)) {
+        self.dim_level = (contrast, precharge);
+    }
+
+    pub(crate) fn set_dim(&mut self, on: bool) {
+        self.dim_on = on;
+    }
+
+    pub(crate) fn is_dim(&self) -> bool {
+        self.dim_on
+    }
+
+    /// Count `elapsed_ms` milliseconds of idle time
+    pub(crate) fn tick(&mut self, elapsed_ms: u32) {
+        self.idle_ms = self.idle_ms.saturating_add(elapsed_ms);
+    }
+
+    /// Start counting the idle time from 0 again, and stop counting the display as dimmed for
+    /// being idle. Used when the contrast is set by hand.
+    pub(crate) fn reset_idle(&mut self) {
+        self.idle_ms = 0;
+        self.dimmed = false;
+    }
+
+    pub(crate) fn set_auto_dim(&mut self, auto_dim: Option<(u32, u8)>) {
+        self.auto_dim = auto_dim;
+    }
+
+    pub(crate) fn set_auto_off(&mut self, timeout_ms: Option<u32>) {
+        self.auto_off = timeout_ms;
+    }
+
+    /// The contrast to dim to, if the display is due to be dimmed for being idle. The display
+    /// counts as dimmed from then on.
+    pub(crate) fn start_dim(&mut self) -> Option<u8> {
+        let (timeout_ms, dim_contrast) = self.auto_dim?;
+        if self.dimmed || self.idle_ms < timeout_ms {
+            return None;
+        }
+
+        self.dimmed = true;
+        Some(dim_contrast)
+    }
+
+    /// Whether the display is due to be turned off for being idle. It counts as turned off
+    /// from then on.
+    pub(crate) fn start_blank(&mut self) -> bool {
+        let Some(timeout_ms) = self.auto_off else {
+            return false;
+        };
+        if self.blanked || self.idle_ms < timeout_ms {
+            return false;
+        }
+
+        self.blanked = true;
+        true
+    }
+
+    /// Whether the display was dimmed for being idle and has to be restored. It no longer
+    /// counts as dimmed after this.
+    pub(crate) fn end_dim(&mut self) -> bool {
+        core::mem::take(&mut self.dimmed)
+    }
+
+    /// Whether the display was turned off for being idle and has to be turned on again. It no
+    /// longer counts as off after this.
+    pub(crate) fn end_blank(&mut self) -> bool {
+        core::mem::take(&mut self.blanked)
+    }
+
+    pub(crate) fn is_dimmed(&self) -> bool {
+        self.dimmed
+    }
+
+    pub(crate) fn is_blanked(&self) -> bool {
+        self.blanked
+    }
+
+    pub(crate) fn set_shift_range(&mut self, range: u8) {
+        self.shift_range = range.min(i8::MAX as u8);
+        self.shift_step = 0;
+        self.shift = (0, 0);
+    }
+
+    pub(crate) fn shift_range(&self) -> u8 {
+        self.shift_range
+    }
+
+    pub(crate) fn set_shift(&mut self, dx: i8, dy: i8) {
+        self.shift = (dx, dy);
+    }
+
+    pub(crate) fn shift(&self) -> (i8, i8) {
+        self.shift
+    }
+
+    /// Move the shift one step along the path of `DisplayProperties::advance_pixel_shift`
+    pub(crate) fn advance_shift(&mut self) -> (i8, i8) {
+        let range = i32::from(self.shift_range);
+        let side = 2 * range + 1;
+        let step = i32::from(self.shift_step);
+
+        let row = step / side;
+        let column = if row % 2 == 0 {
+            step % side
+        } else {
+            side - 1 - step % side
+        };
+
+        self.shift_step = ((step + 1) % (side * side)) as u16;
+        self.shift = ((column - range) as i8, (row - range) as i8);
+        self.shift
+    }
+
+    pub(crate) fn set_flush_hook(&mut self, hook: Option<fn(FlushEvent)>) {
+        self.flush_hook = hook;
+    }
+
+    pub(crate) fn begin_flush(&mut self) {
+        self.flush_bytes = Some(0);
+        if let Some(hook) = self.flush_hook {
+            hook(FlushEvent::Start);
+        }
+    }
+
+    /// Report the end of the flush in progress to the hook. Returns whether one was in progress.
+    pub(crate) fn end_flush(&mut self) -> bool {
+        let Some(bytes) = self.flush_bytes.take() else {
+            return false;
+        };
+        if let Some(hook) = self.flush_hook {
+            hook(FlushEvent::End(bytes));
+        }
+
+        true
+    }
+
+    /// Count `bytes` of sent pixel data for the flush hook, which also ends the idle time
+    pub(crate) fn chunk(&mut self, bytes: usize) {
+        if let Some(total) = &mut self.flush_bytes {
+            *total += bytes;
+        }
+        if let Some(hook) = self.flush_hook {
+            hook(FlushEvent::Chunk(bytes));
+        }
+
+        self.idle_ms = 0;
+    }
+}
@@ -1,3 +1,12 @@
 //! Crate prelude
 
-pub use super::{displayrotation::DisplayRotation, displaysize::DisplaySize, mode::GraphicsMode};
+pub use super::{
+    brightness::Brightness,
+    display::MonoDisplay,
+    displayrotation::DisplayRotation,
+    displaysize::DisplaySize,
+    mode::{
+        displaymode::DisplayTuning, BlitOp, DirectMode, FbStats, FlushStats, FramebufferLayout,
+        GraphicsMode, GrayscaleMode, TerminalMode, TextOverflow,
+    },
+};
@@ -1,18 +1,253 @@
 //! Container to store and set display properties
 
 use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use hal::{
+    blocking::delay::{DelayMs, DelayUs},
+    digital::v2::OutputPin,
+};
 
-use crate::{command::Command, displayrotation::DisplayRotation, displaysize::DisplaySize};
+use crate::{
+    brightness::{self, Brightness},
+    command::Command,
+    displayrotation::DisplayRotation,
+    displaysize::DisplaySize,
+    dma::{DmaInterface, DmaPage},
+    error::{Error, InitError},
+    power::PowerState,
+    read::{ReadInterface, Status},
+    rle::RleDecoder,
+};
+
+/// The interface as stored in [`DisplayProperties`], counting what is sent with the `metrics`
+/// feature
+#[cfg(feature = "metrics")]
+type Iface<DI> = crate::metrics::Counted<DI>;
+#[cfg(not(feature = "metrics"))]
+type Iface<DI> = DI;
+
+/// How far above the resting contrast a heartbeat peaks
+const HEARTBEAT_BOOST: u8 = 0x60;
+
+/// Time for the DC-DC converter and panel voltages to settle after waking, in milliseconds
+const WAKE_SETTLE_MS: u8 = 100;
+
+/// Time each contrast level is shown for during a contrast sweep, in milliseconds
+const CONTRAST_SWEEP_STEP_MS: u8 = 10;
+
+/// Time between contrast steps of a fade, in milliseconds
+const FADE_STEP_MS: u16 = 10;
+
+/// Columns of display RAM
+pub(crate) const RAM_COLUMNS: u8 = 160;
+
+/// Pages of 8 rows of display RAM
+pub(crate) const RAM_PAGES: u8 = 160 / 8;
+
+/// Command bytes sent to set the draw address of every page (`B0h`, page, column low and high)
+const PAGE_ADDRESS_OVERHEAD: usize = 4;
+
+/// Bus clock cycles per transferred byte used for transfer time estimates. This is the I2C cost
+/// of a byte plus its acknowledge bit; SPI needs 8.
+const CLOCKS_PER_BYTE: u64 = 9;
+
+/// Optional features supported by the display controller in its current configuration
+///
+/// This lets portable UI code check what the hardware can do instead of hard-coding knowledge of
+/// the SH1108, e.g. to fall back to a full redraw where a controller cannot scroll.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// Width of the display RAM window in pixels, before rotation
+    pub width: u8,
+    /// Height of the display RAM window in pixels, before rotation
+    pub height: u8,
+    /// Number of 8 pixel high pages the display RAM window is divided into
+    pub pages: u8,
+    /// Number of contrast steps
+    pub contrast_steps: u16,
+    /// The content can be scrolled or panned in hardware, without rewriting display RAM. The
+    /// SH1108 has no display start line or display offset commands, so this is always `false`.
+    pub hardware_scroll: bool,
+    /// Any column and page window of display RAM can be updated without sending the full frame
+    pub partial_update: bool,
+    /// The panel voltage can be generated by a built-in DC-DC converter (charge pump)
+    pub charge_pump: bool,
+    /// The display can be inverted in hardware
+    pub hardware_invert: bool,
+}
+
+impl Capabilities {
+    /// Get the capabilities of an SH1108 driving a display of the given size
+    pub const fn for_size(display_size: DisplaySize) -> Self {
+        let (width, height) = display_size.dimensions();
+
+        Self {
+            width,
+            height,
+            pages: height / 8,
+            contrast_steps: 256,
+            hardware_scroll: false,
+            partial_update: true,
+            charge_pump: true,
+            hardware_invert: true,
+        }
+    }
+}
+
+/// Where the panel drive voltage VPP comes from, see [`DisplayProperties::set_charge_pump`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChargePump {
+    /// The built-in DC-DC converter generates VPP, switching at the given frequency from 0
+    /// (0.6 x 500kHz) to 7 (1.3 x 500kHz). This is the power-on default, at frequency 0, so
+    /// the setting `init` sends for it is the one the controller has after a reset anyway.
+    Internal(u8),
+    /// VPP is supplied externally and the built-in converter stays off. Modules wired like this
+    /// stay blank or draw excess current with the converter enabled.
+    External,
+}
+
+/// Delays of the power-on sequence run by [`DisplayProperties::init_with_delay`] and the
+/// `init_with_timing` methods of the modes, see [`DisplayProperties::set_power_timing`]
+///
+/// The defaults follow the datasheet. Modules with slow supplies, large decoupling capacitors
+/// or an external VPP that comes up late may need longer delays.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerTiming {
+    /// How long the reset pin is held low, in microseconds. The datasheet requires at least 10.
+    pub reset_low_us: u32,
+    /// Time between releasing the reset pin and the first command, in microseconds
+    pub reset_release_us: u32,
+    /// Time between enabling the charge pump and turning the display on, in milliseconds, e.g.
+    /// for an external VPP switched on together with the panel
+    pub charge_pump_ms: u16,
+    /// Time after turning the display on for the DC-DC converter and panel voltages to settle
+    /// before the image is stable, in milliseconds. The datasheet gives 100.
+    pub display_on_ms: u16,
+}
+
+impl Default for PowerTiming {
+    fn default() -> Self {
+        Self {
+            reset_low_us: 10,
+            reset_release_us: 10,
+            charge_pump_ms: 0,
+            display_on_ms: WAKE_SETTLE_MS.into(),
+        }
+    }
+}
+
+/// A built-in image for factory tests and bring-up, see [`DisplayProperties::test_pattern`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TestPattern {
+    /// Every pixel lit by the entire display on command, without touching the display RAM
+    AllOn,
+    /// Alternating pixels, to spot shorted or open rows and columns
+    Checkerboard,
+    /// 8 pixel wide bars, one lit and one dark, across the width of the panel
+    VerticalBars,
+}
+
+impl TestPattern {
+    /// The display RAM byte of `column` in an 8 pixel high page
+    fn page_byte(self, column: u8) -> u8 {
+        match self {
+            TestPattern::AllOn => 0xFF,
+            TestPattern::Checkerboard if column.is_multiple_of(2) => 0x55,
+            TestPattern::Checkerboard => 0xAA,
+            TestPattern::VerticalBars if (column / 8).is_multiple_of(2) => 0xFF,
+            TestPattern::VerticalBars => 0x00,
+        }
+    }
+}
+
+/// How the pixels of an image passed to [`DisplayProperties::draw_layout`] are packed, so assets
+/// exported by common tools can be sent as they are
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RawLayout {
+    /// Every byte holds 8 pixels of a column with the top one in the least significant bit, for
+    /// pages of 8 rows from the top, which is how the controller stores them and what `draw`
+    /// takes. Also the "vertical" output of image2cpp and the usual SSD1306 and SH1106 format.
+    VerticalLsbFirst,
+    /// Like `VerticalLsbFirst`, but with the top pixel in the most significant bit
+    VerticalMsbFirst,
+    /// Every byte holds 8 pixels of a row with the leftmost one in the most significant bit, and
+    /// every row starts on a new byte. The "horizontal" output of image2cpp and the
+    /// format of `GraphicsMode::draw_bitmap`.
+    HorizontalMsbFirst,
+    /// Like `HorizontalMsbFirst`, but with the leftmost pixel in the least significant bit, as in
+    /// XBM files exported by GIMP
+    HorizontalLsbFirst,
+}
+
+impl RawLayout {
+    /// The display RAM byte of `column` in page `page` of a `width` pixel wide image in `data`
+    fn page_byte(self, data: &[u8], width: usize, page: usize, column: usize) -> u8 {
+        let byte = |index: usize| data.get(index).copied().unwrap_or(0);
+
+        match self {
+            RawLayout::VerticalLsbFirst => byte(page * width + column),
+            RawLayout::VerticalMsbFirst => byte(page * width + column).reverse_bits(),
+            RawLayout::HorizontalMsbFirst | RawLayout::HorizontalLsbFirst => {
+                let row_bytes = width.div_ceil(8);
+                let bit = match self {
+                    RawLayout::HorizontalMsbFirst => 0x80 >> (column % 8),
+                    _ => 1 << (column % 8),
+                };
+
+                (0..8).fold(0, |out, row| {
+                    let on = byte((page * 8 + row) * row_bytes + column / 8) & bit != 0;
+                    out | (u8::from(on) << row)
+                })
+            }
+        }
+    }
+}
+
+/// Progress of a flush, reported to the hook set with [`DisplayProperties::set_flush_hook`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlushEvent {
+    /// A flush of the framebuffer started
+    Start,
+    /// A transfer of this many bytes of pixel data was sent
+    Chunk(usize),
+    /// The flush completed after sending this many bytes of pixel data in total. A flush that
+    /// fails partway does not report an end.
+    End(usize),
+}
 
 /// Display properties struct
 pub struct DisplayProperties<DI> {
-    iface: DI,
+    iface: Iface<DI>,
     display_size: DisplaySize,
     display_rotation: DisplayRotation,
+    mirror: (bool, bool),
     draw_area_start: (u8, u8),
     draw_area_end: (u8, u8),
     draw_column: u8,
     draw_row: u8,
+    /// Whether the draw area was given in rotated coordinates, see `set_draw_area`
+    transposed_area: bool,
+    /// Bytes of the current 8x8 block of a transposed draw area
+    block: [u8; 8],
+    block_len: u8,
+    ram_offset: (u8, u8),
+    address_stale: bool,
+    contrast: u8,
+    bus_clock_hint: u32,
+    clock_divider: (u8, u8),
+    precharge: (u8, u8),
+    vcom_level: u8,
+    charge_pump: ChargePump,
+    power_timing: PowerTiming,
+    invert: bool,
+    /// Whether the display was last turned on or off
+    on: bool,
+    initialized: bool,
+    power: PowerState,
 }
 
 impl<DI> DisplayProperties<DI>
@@ -26,40 +261,338 @@ where
         display_rotation: DisplayRotation,
     ) -> DisplayProperties<DI> {
         DisplayProperties {
-            iface,
+            iface: Iface::from(iface),
             display_size,
             display_rotation,
+            mirror: (false, false),
             draw_area_start: (0, 0),
             draw_area_end: (0, 0),
             draw_column: 0,
             draw_row: 0,
+            transposed_area: false,
+            block: [0; 8],
+            block_len: 0,
+            ram_offset: (0, 0),
+            address_stale: true,
+            contrast: 0x80,
+            bus_clock_hint: 400_000,
+            clock_divider: (0x6, 0x0),
+            precharge: (0x8, 0x2),
+            vcom_level: 0x35,
+            charge_pump: ChargePump::Internal(0),
+            power_timing: PowerTiming::default(),
+            invert: false,
+            on: false,
+            initialized: false,
+            power: PowerState::new(),
         }
     }
 
     /// Initialise the display in column mode (i.e. a byte walks down a column of 8 pixels) with
     /// column 0 on the left and column _(display_width - 1)_ on the right.
     pub fn init_column_mode(&mut self) -> Result<(), DisplayError> {
+        self.send_init_settings()?;
+        self.finish_init()
+    }
+
+    /// Send everything `init_column_mode` sets up before turning the display on
+    fn send_init_settings(&mut self) -> Result<(), DisplayError> {
         let display_rotation = self.display_rotation;
 
-        Command::DisplayClockDiv(0x6, 0x0).send(&mut self.iface)?;
+        let (fosc, div) = self.clock_divider;
+        Command::DisplayClockDiv(fosc, div).send(&mut self.iface)?;
 
         Command::DisplayResolution(self.display_size).send(&mut self.iface)?;
         self.set_rotation(display_rotation)?;
 
-        Command::PreChargePeriod(0x8, 0x2).send(&mut self.iface)?;
+        let (phase1, phase2) = self.active_precharge();
+        Command::PreChargePeriod(phase1, phase2).send(&mut self.iface)?;
+        Command::VcomDeselectLevel(self.vcom_level).send(&mut self.iface)?;
+        // Only takes effect while the display is off, as it is after a reset
+        self.send_charge_pump(true)
+    }
+
+    /// Turn the display on at the end of an init
+    fn finish_init(&mut self) -> Result<(), DisplayError> {
         Command::DisplayOn(true).send(&mut self.iface)?;
+        self.on = true;
+        self.power.end_blank();
+        self.initialized = true;
+
+        Ok(())
+    }
+
+    /// Initialise the display like `init_column_mode`, waiting with `delay` as the datasheet
+    /// requires instead of relying on the code around it to be slow enough: for
+    /// [`PowerTiming::charge_pump_ms`] between enabling the charge pump and turning the display
+    /// on, and for [`PowerTiming::display_on_ms`] afterwards, see `set_power_timing`.
+    pub fn init_with_delay<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.send_init_settings()?;
+        delay.delay_ms(self.power_timing.charge_pump_ms);
+        self.finish_init()?;
+        delay.delay_ms(self.power_timing.display_on_ms);
+
+        Ok(())
+    }
+
+    /// Run the whole power-on sequence of the datasheet once the supply is stable: pulse the
+    /// reset pin `rst` for [`PowerTiming::reset_low_us`], wait for
+    /// [`PowerTiming::reset_release_us`], then `init_with_delay`
+    pub fn init_with_timing<RST, DELAY, PinE>(
+        &mut self,
+        rst: &mut RST,
+        delay: &mut DELAY,
+    ) -> Result<(), InitError<PinE>>
+    where
+        RST: OutputPin<Error = PinE>,
+        DELAY: DelayUs<u32> + DelayMs<u16>,
+    {
+        self.mark_uninitialized();
+        rst.set_low().map_err(InitError::Pin)?;
+        delay.delay_us(self.power_timing.reset_low_us);
+        rst.set_high().map_err(InitError::Pin)?;
+        delay.delay_us(self.power_timing.reset_release_us);
+        self.init_with_delay(delay)?;
+
+        Ok(())
+    }
+
+    /// Initialise the display like `init_column_mode`, and explicitly select the page addressing
+    /// mode of the controller, in which the column address advances with every byte and the page
+    /// only changes when it is set again.
+    ///
+    /// Page addressing is the power on default that all drawing in this driver relies on, so this
+    /// is only needed if something else, e.g. a bootloader, may have left the controller in
+    /// vertical addressing mode without resetting it. Data packed page-major, one page of 8 rows
+    /// after the other, can be drawn as is with `draw_pages`.
+    pub fn init_page_mode(&mut self) -> Result<(), DisplayError> {
+        self.init_column_mode()?;
+
+        Command::AddressMode(false).send(&mut self.iface)
+    }
+
+    /// Initialise the display again after it lost its settings, e.g. to a brown-out or an ESD
+    /// event on its supply, without creating the driver again. `init_column_mode` replays the
+    /// stored clock, size, rotation, mirroring, precharge, VCOM and charge pump settings, then
+    /// the contrast and inversion are restored and the RAM address is sent again before the next
+    /// draw. The display RAM itself is not restored, see the `reinit` methods of the modes.
+    pub fn reinit(&mut self) -> Result<(), DisplayError> {
+        self.address_stale = true;
+        self.init_column_mode()?;
+
+        Command::Contrast(self.resting_contrast()).send(&mut self.iface)?;
+        Command::Invert(self.invert).send(&mut self.iface)
+    }
+
+    /// Whether `init_column_mode` completed since the driver was created or the display was last
+    /// reset. Drawing to a display that is not initialised silently leaves the screen blank.
+    pub fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+
+    /// Record that the controller was initialised from outside the driver, e.g. with a custom
+    /// init sequence, so the drawing modes do not expect `init_column_mode` first. The driver
+    /// still assumes page addressing and the settings stored in the properties.
+    pub fn assume_initialized(&mut self) {
+        self.initialized = true;
+    }
+
+    /// Record that the controller was reset and needs to be initialised again
+    pub(crate) fn mark_uninitialized(&mut self) {
+        self.initialized = false;
+    }
+
+    /// Call `hook` with the progress of every flush, e.g. to time frames for an FPS counter or a
+    /// profiler. It is called when a flush starts, after every transfer of pixel data, and when
+    /// the flush completes, see [`FlushEvent`]. Pixel data drawn outside of a flush, like the
+    /// characters of `TerminalMode`, is reported as chunks as well. `None` removes the hook.
+    pub fn set_flush_hook(&mut self, hook: Option<fn(FlushEvent)>) {
+        self.power.set_flush_hook(hook);
+    }
+
+    /// Report the start of a flush to the flush hook
+    pub(crate) fn begin_flush(&mut self) {
+        self.power.begin_flush();
+    }
+
+    /// Report the end of the flush in progress, if any, to the flush hook
+    pub(crate) fn end_flush(&mut self) {
+        let ended = self.power.end_flush();
+        #[cfg(feature = "metrics")]
+        if ended {
+            self.iface.metrics.flushes = self.iface.metrics.flushes.wrapping_add(1);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = ended;
+    }
+
+    /// Count `pixels` drawn into a framebuffer, for `metrics`
+    pub(crate) fn count_pixels(&mut self, pixels: u32) {
+        #[cfg(feature = "metrics")]
+        {
+            self.iface.metrics.pixels_set = self.iface.metrics.pixels_set.wrapping_add(pixels);
+        }
+        #[cfg(not(feature = "metrics"))]
+        let _ = pixels;
+    }
+
+    /// Get the counters of what the driver did since it was created or `reset_metrics` was
+    /// called, see the [`metrics`](crate::metrics) module
+    #[cfg(feature = "metrics")]
+    pub fn metrics(&self) -> crate::metrics::Metrics {
+        self.iface.metrics
+    }
+
+    /// Start all counters of `metrics` from 0 again
+    #[cfg(feature = "metrics")]
+    pub fn reset_metrics(&mut self) {
+        self.iface.metrics = crate::metrics::Metrics::default();
+    }
+
+    /// Report sent pixel data to the flush hook, and end the idle time, see `set_auto_dim`
+    fn notify_chunk(&mut self, bytes: usize) -> Result<(), DisplayError> {
+        self.power.chunk(bytes);
+        self.end_idle()
+    }
+
+    /// Restore the contrast after `set_auto_dim` dimmed the display, and turn it back on after
+    /// `set_auto_off` turned it off
+    fn end_idle(&mut self) -> Result<(), DisplayError> {
+        if self.power.end_dim() {
+            Command::Contrast(self.resting_contrast()).send(&mut self.iface)?;
+        }
+        if self.power.end_blank() {
+            Command::DisplayOn(true).send(&mut self.iface)?;
+        }
+
+        Ok(())
+    }
+
+    /// Dim the display to `dim_contrast` once `timeout_ms` milliseconds passed without any
+    /// pixel data being sent, i.e. without flushes or other drawing, and restore the contrast
+    /// with the next data that is sent. A common way to save power on battery powered devices.
+    /// `None` turns automatic dimming off again, restoring the contrast if the display is
+    /// dimmed.
+    ///
+    /// The driver has no clock of its own, so the time is counted by calls to `idle_tick`.
+    pub fn set_auto_dim(&mut self, auto_dim: Option<(u32, u8)>) -> Result<(), DisplayError> {
+        self.power.set_auto_dim(auto_dim);
+
+        if auto_dim.is_none() && self.power.end_dim() {
+            Command::Contrast(self.resting_contrast()).send(&mut self.iface)?;
+        }
+
+        Ok(())
+    }
+
+    /// Turn the display off once `timeout_ms` milliseconds passed without any pixel data being
+    /// sent, like a screensaver, and turn it back on with the next data that is sent, e.g. by the
+    /// next `flush`. The display RAM keeps its contents while off, so nothing has to be redrawn.
+    /// A display turned off with `display_on` or `sleep` stays off. `None` disables this again,
+    /// turning the display back on if it is off for being idle.
+    ///
+    /// Like `set_auto_dim`, the time is counted by calls to `idle_tick`, and both can be used
+    /// together, e.g. to dim after 10 seconds and turn off after a minute.
+    pub fn set_auto_off(&mut self, timeout_ms: Option<u32>) -> Result<(), DisplayError> {
+        self.power.set_auto_off(timeout_ms);
+
+        if timeout_ms.is_none() && self.power.end_blank() {
+            Command::DisplayOn(true).send(&mut self.iface)?;
+        }
+
+        Ok(())
+    }
+
+    /// Count `elapsed_ms` milliseconds of idle time for `set_auto_dim` and `set_auto_off`, e.g.
+    /// from a timer tick or the main loop. Dims or turns off the display once the idle time
+    /// reaches the respective timeout.
+    pub fn idle_tick(&mut self, elapsed_ms: u32) -> Result<(), DisplayError> {
+        self.power.tick(elapsed_ms);
+
+        if let Some(dim_contrast) = self.power.start_dim() {
+            Command::Contrast(dim_contrast.min(self.resting_contrast())).send(&mut self.iface)?;
+        }
+
+        if self.on && self.power.start_blank() {
+            Command::DisplayOn(false).send(&mut self.iface)?;
+        }
 
         Ok(())
     }
 
+    /// Whether the display is currently turned off after being idle, see `set_auto_off`
+    pub fn is_blanked(&self) -> bool {
+        self.power.is_blanked()
+    }
+
+    /// Whether the display is currently dimmed after being idle, see `set_auto_dim`
+    pub fn is_dimmed(&self) -> bool {
+        self.power.is_dimmed()
+    }
+
     /// Set the position in the framebuffer of the display where any sent data should be
     /// drawn. This method can be used for changing the affected area on the screen as well
     /// as (re-)setting the start point of the next `draw` call.
+    ///
+    /// `start` and `end` are `(column, page)` pairs, with `end` exclusive. Returns
+    /// `DisplayError::OutOfBoundsError` without sending anything if the area is empty or
+    /// inverted, or reaches past the 160 columns and 20 pages of display RAM.
+    ///
+    /// The area and the data drawn to it follow the rotation at the time of the call, so raw
+    /// data looks the same in all four rotations. Rotated by 0 or 180 degrees the controller
+    /// writes the bytes as they are. Rotated by 90 or 270 degrees the columns and pages are those
+    /// of the rotated view of display RAM, and `draw` transposes every 8 bytes to the panel
+    /// layout before sending them, which takes an address command per 8 bytes. The controller
+    /// can only write whole bytes, which are 8 columns wide in this view, so the columns of the
+    /// area must then start and end at multiples of 8, or `DisplayError::OutOfBoundsError` is
+    /// returned.
     pub fn set_draw_area(&mut self, start: (u8, u8), end: (u8, u8)) -> Result<(), DisplayError> {
+        if start.0 >= end.0 || start.1 >= end.1 || end.0 > RAM_COLUMNS || end.1 > RAM_PAGES {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => {
+                self.set_draw_area_unchecked(start, end)
+            }
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                // 160 rows of RAM across and 20 pages of 8 columns down, like unrotated
+                if !start.0.is_multiple_of(8) || !end.0.is_multiple_of(8) {
+                    return Err(DisplayError::OutOfBoundsError);
+                }
+
+                self.draw_area_start = start;
+                self.draw_area_end = end;
+                self.draw_column = start.0;
+                self.draw_row = start.1;
+                self.transposed_area = true;
+                self.block_len = 0;
+
+                Ok(())
+            }
+        }
+    }
+
+    /// `set_draw_area` for areas the driver computed itself from the display size
+    pub(crate) fn set_draw_area_unchecked(
+        &mut self,
+        start: (u8, u8),
+        end: (u8, u8),
+    ) -> Result<(), DisplayError> {
+        debug_assert!(
+            start.0 < end.0 && start.1 < end.1,
+            "empty draw area {:?}..{:?}",
+            start,
+            end
+        );
         self.draw_area_start = start;
         self.draw_area_end = end;
         self.draw_column = start.0;
         self.draw_row = start.1;
+        self.transposed_area = false;
 
         self.send_draw_address()
     }
@@ -67,11 +600,41 @@ where
     /// Send the data to the display for drawing at the current position in the framebuffer
     /// and advance the position accordingly. Cf. `set_draw_area` to modify the affected area by
     /// this method.
+    ///
+    /// If a transfer fails partway, the controller may have stored any number of the bytes and
+    /// its RAM address no longer matches the tracked position, which only advances past data that
+    /// was sent successfully. The next `draw` call therefore re-sends the address first and
+    /// continues with the first row of data that did not go through, so retrying (or just drawing
+    /// the next frame) never shifts the output.
+    ///
+    /// The buffer may end anywhere within a row, and the next `draw` call continues right after
+    /// it. Returns `DisplayError::OutOfBoundsError` if no draw area was set yet.
+    ///
+    /// For an area set with the display rotated by 90 or 270 degrees, see `set_draw_area`, the
+    /// bytes are sent in blocks of 8 and a block that fails to send is dropped.
     pub fn draw(&mut self, mut buffer: &[u8]) -> Result<(), DisplayError> {
+        if self.draw_area_start.0 >= self.draw_area_end.0 {
+            return Err(DisplayError::OutOfBoundsError);
+        }
+
+        if self.transposed_area {
+            return self.draw_transposed(buffer);
+        }
+
+        if self.address_stale && !buffer.is_empty() {
+            self.send_draw_address()?;
+        }
+
         while !buffer.is_empty() {
-            let count = self.draw_area_end.0 - self.draw_column;
+            let remaining = self.draw_area_end.0 - self.draw_column;
+            let count = buffer.len().min(remaining.into()) as u8;
+            self.address_stale = true;
+            #[cfg(feature = "trace-commands")]
+            defmt::trace!("sh1108 data {=u8} bytes", count);
             self.iface
                 .send_data(DataFormat::U8(&buffer[..count as usize]))?;
+            self.address_stale = false;
+            self.notify_chunk(count.into())?;
             self.draw_column += count;
 
             if self.draw_column >= self.draw_area_end.0 {
@@ -91,79 +654,2211 @@ where
         Ok(())
     }
 
-    fn send_draw_address(&mut self) -> Result<(), DisplayError> {
-        Command::PageAddress(self.draw_row).send(&mut self.iface)?;
-        Command::ColumnAddressLow(0xF & self.draw_column).send(&mut self.iface)?;
-        Command::ColumnAddressHigh(0xF & (self.draw_column >> 4)).send(&mut self.iface)
+    /// Send a whole image for the draw area, packed as given by `layout`, converting it to the
+    /// layout of the display RAM a page at a time on the way. `data` holds the pixels of the draw
+    /// area, as wide as it and as high as its pages, e.g. 32x16 pixels for an area of 32 columns
+    /// and 2 pages. Missing bytes are drawn as off.
+    ///
+    /// With [`RawLayout::VerticalLsbFirst`] this is the same as `draw`. Draw areas set in
+    /// rotated coordinates only take that layout, and return
+    /// `DisplayError::DataFormatNotImplemented` for the others.
+    pub fn draw_layout(&mut self, data: &[u8], layout: RawLayout) -> Result<(), DisplayError> {
+        if layout == RawLayout::VerticalLsbFirst || self.draw_area_start.0 >= self.draw_area_end.0 {
+            return self.draw(data);
+        }
+        if self.transposed_area {
+            return Err(DisplayError::DataFormatNotImplemented);
+        }
+
+        let width = usize::from(self.draw_area_end.0.saturating_sub(self.draw_area_start.0));
+        let pages = usize::from(self.draw_area_end.1.saturating_sub(self.draw_area_start.1));
+        let mut buffer = [0; RAM_COLUMNS as usize];
+        let buffer = &mut buffer[..width];
+
+        for page in 0..pages {
+            for (column, byte) in buffer.iter_mut().enumerate() {
+                *byte = layout.page_byte(data, width, page, column);
+            }
+            self.draw(buffer)?;
+        }
+
+        Ok(())
     }
 
-    /// Get the configured display size
-    pub fn get_size(&self) -> DisplaySize {
-        self.display_size
+    /// Send `command` to the controller as is. The driver does not keep track of what it
+    /// changes, so settings it replays, like the contrast or rotation, are not updated, and the
+    /// RAM address is sent again before the next draw.
+    pub fn send_command(&mut self, command: Command) -> Result<(), DisplayError> {
+        self.address_stale = true;
+
+        command.send(&mut self.iface)
     }
 
-    /// Get display dimensions, taking into account the current rotation of the display
-    ///
-    /// ```rust
-    ///# #[path = "test_helpers.rs"]
-    ///# mod test_helpers;
-    ///# use test_helpers::StubInterface;
-    ///# let interface = StubInterface;
-    /// use sh1108::prelude::*;
+    /// Send `bytes` to the controller as commands, e.g. for undocumented or module specific
+    /// commands that [`Command`] has no variant for. Like `send_command`, nothing is tracked.
+    pub fn send_raw_command(&mut self, bytes: &[u8]) -> Result<(), DisplayError> {
+        self.address_stale = true;
+        #[cfg(feature = "trace-commands")]
+        defmt::trace!("sh1108 raw command {=[u8]:#04x}", bytes);
+
+        self.iface.send_commands(DataFormat::U8(bytes))
+    }
+
+    /// Send `data` to the display RAM at whatever address the controller is at, bypassing the
+    /// draw area. The RAM address is sent again before the next draw.
+    pub fn send_raw_data(&mut self, data: &[u8]) -> Result<(), DisplayError> {
+        self.address_stale = true;
+        #[cfg(feature = "trace-commands")]
+        defmt::trace!("sh1108 raw data {=usize} bytes", data.len());
+
+        self.iface.send_data(DataFormat::U8(data))
+    }
+
+    /// Start sending `page` at the start of the draw area through DMA, see
+    /// [`DmaInterface::start_data`]
+    pub(crate) fn start_dma_data(&mut self, page: DmaPage) -> Result<(), DisplayError>
+    where
+        DI: DmaInterface,
+    {
+        let len = page.as_slice().len();
+
+        // The tracked position does not follow background transfers
+        self.address_stale = true;
+        #[cfg(feature = "trace-commands")]
+        defmt::trace!("sh1108 dma data {=usize} bytes", len);
+        // No commands can be sent until the transfer completed
+        self.end_idle()?;
+        self.iface.start_data(page).map_err(|(_, error)| error)?;
+        self.notify_chunk(len)?;
+
+        Ok(())
+    }
+
+    /// Point the display at page `index` and start sending `page`, the bytes of that page,
+    /// through DMA
+    pub(crate) fn start_dma_page(&mut self, page: DmaPage, index: u8) -> Result<(), DisplayError>
+    where
+        DI: DmaInterface,
+    {
+        let (width, _) = self.display_size.dimensions();
+        let column_offset = self.display_size.column_offset();
+
+        self.set_draw_area_unchecked((column_offset, index), (column_offset + width, index + 1))?;
+
+        self.start_dma_data(page)
+    }
+
+    /// Whether the transfer started with `start_dma_data` completed
+    pub(crate) fn poll_dma_data(&mut self) -> Option<(DmaPage, Result<(), DisplayError>)>
+    where
+        DI: DmaInterface,
+    {
+        self.iface.poll_data()
+    }
+
+    /// Move the position at which the next `draw` call continues within the current draw area,
+    /// without changing the area itself. `column` and `row` are in the same units as the area
+    /// passed to `set_draw_area`.
     ///
-    /// let mut display: GraphicsMode<_> = sh1108::Builder::new().connect(interface).into();
-    /// assert_eq!(display.get_dimensions(), (128, 160));
+    /// Returns `Error::OutOfBounds` if the position is outside the draw area, or for a rotated
+    /// area, does not start a block of 8 columns.
+    pub fn set_cursor_raw(&mut self, column: u8, row: u8) -> Result<(), Error> {
+        if !(self.draw_area_start.0..self.draw_area_end.0).contains(&column)
+            || !(self.draw_area_start.1..self.draw_area_end.1).contains(&row)
+            || (self.transposed_area && !column.is_multiple_of(8))
+        {
+            return Err(Error::OutOfBounds);
+        }
+
+        self.draw_column = column;
+        self.draw_row = row;
+
+        if self.transposed_area {
+            self.block_len = 0;
+            return Ok(());
+        }
+
+        Ok(self.send_draw_address()?)
+    }
+
+    /// `draw` for an area in the rotated view of display RAM, one 8x8 block at a time
+    fn draw_transposed(&mut self, buffer: &[u8]) -> Result<(), DisplayError> {
+        for &byte in buffer {
+            self.block[usize::from(self.block_len)] = byte;
+            self.block_len += 1;
+            if self.block_len < 8 {
+                continue;
+            }
+            self.block_len = 0;
+
+            // Bit `j` of byte `i` is at rotated column `draw_column + i` and row `8 * draw_row +
+            // j`, which is panel row `draw_column + i` and panel column `8 * draw_row + j`
+            let mut panel = [0u8; 8];
+            for (i, byte) in self.block.iter().enumerate() {
+                for (j, out) in panel.iter_mut().enumerate() {
+                    *out |= (byte >> j & 1) << i;
+                }
+            }
+
+            // The controller is left somewhere the unrotated path does not expect
+            self.address_stale = true;
+            self.send_ram_address(8 * self.draw_row, self.draw_column / 8)?;
+            #[cfg(feature = "trace-commands")]
+            defmt::trace!("sh1108 data {=u8} bytes", 8u8);
+            self.iface.send_data(DataFormat::U8(&panel))?;
+            self.notify_chunk(panel.len())?;
+
+            self.draw_column += 8;
+            if self.draw_column >= self.draw_area_end.0 {
+                self.draw_column = self.draw_area_start.0;
+
+                self.draw_row += 1;
+                if self.draw_row >= self.draw_area_end.1 {
+                    self.draw_row = self.draw_area_start.1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send the window from `upper_left` to `lower_right` (exclusive) of a framebuffer of your own
+    /// to the same window of the display. `buffer` is laid out like the display RAM: pages of 8
+    /// rows, `disp_width` bytes each, with the least significant bit of a byte at the top.
+    /// Coordinates are `(column, row)` pixels of the panel, before rotation, and the rows are
+    /// rounded out to whole pages because the controller cannot write less than a page high.
     ///
-    /// # let interface = StubInterface;
-    /// let mut rotated_display: GraphicsMode<_> = sh1108::Builder::new().with_rotation(DisplayRotation::Rotate90).connect(interface).into();
-    /// assert_eq!(rotated_display.get_dimensions(), (160, 128));
-    /// ```
-    pub fn get_dimensions(&self) -> (u8, u8) {
-        let (w, h) = self.display_size.dimensions();
+    /// Returns `Error::OutOfBounds` if the window is empty or does not fit the display or
+    /// `disp_width`, and `Error::BufferTooSmall` if `buffer` does not reach the bottom of the
+    /// window.
+    pub fn bounded_draw(
+        &mut self,
+        buffer: &[u8],
+        disp_width: usize,
+        upper_left: (u8, u8),
+        lower_right: (u8, u8),
+    ) -> Result<(), Error> {
+        let (width, height) = self.display_size.dimensions();
+        let (column0, row0) = upper_left;
+        let (column1, row1) = lower_right;
 
-        match self.display_rotation {
-            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
-            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
+        if column0 >= column1
+            || row0 >= row1
+            || column1 > width
+            || row1 > height
+            || usize::from(column1) > disp_width
+        {
+            return Err(Error::OutOfBounds);
+        }
+
+        let (page0, page1) = (row0 / 8, row1.div_ceil(8));
+        if buffer.len() < usize::from(page1 - 1) * disp_width + usize::from(column1) {
+            return Err(Error::BufferTooSmall);
+        }
+
+        let column_offset = self.display_size.column_offset();
+        self.set_draw_area_unchecked(
+            (column_offset + column0, page0),
+            (column_offset + column1, page1),
+        )?;
+        for page in page0..page1 {
+            let start = usize::from(page) * disp_width;
+            self.draw(&buffer[start + usize::from(column0)..start + usize::from(column1)])?;
         }
+
+        Ok(())
     }
 
-    /// Get the display rotation
-    pub fn get_rotation(&self) -> DisplayRotation {
-        self.display_rotation
+    /// Draw an image packed page-major, i.e. `width` bytes for its first 8 rows followed by
+    /// `width` bytes for each further 8 rows, with its top left corner at panel column `column`
+    /// and page `page`, before rotation. This is the layout many font and asset converters
+    /// produce for SSD1306-style displays, so it needs no conversion on the device. The image is
+    /// as many pages high as `data` holds whole pages.
+    ///
+    /// Returns `Error::OutOfBounds` if the image is empty or does not fit the display.
+    pub fn draw_pages(
+        &mut self,
+        data: &[u8],
+        width: u8,
+        column: u8,
+        page: u8,
+    ) -> Result<(), Error> {
+        let (display_width, display_height) = self.display_size.dimensions();
+        let pages = data.len() / usize::from(width.max(1));
+
+        if width == 0
+            || pages == 0
+            || u16::from(column) + u16::from(width) > u16::from(display_width)
+            || usize::from(page) + pages > usize::from(display_height / 8)
+        {
+            return Err(Error::OutOfBounds);
+        }
+
+        let column_offset = self.display_size.column_offset();
+        self.set_draw_area_unchecked(
+            (column_offset + column, page),
+            (column_offset + column + width, page + pages as u8),
+        )?;
+
+        Ok(self.draw(&data[..pages * usize::from(width)])?)
     }
 
-    /// Set the display rotation
-    pub fn set_rotation(&mut self, display_rotation: DisplayRotation) -> Result<(), DisplayError> {
-        self.display_rotation = display_rotation;
+    /// Stream a whole frame straight to the display, e.g. an animation frame read from external
+    /// flash, without a framebuffer. `frame` yields the packed column mode data page by page,
+    /// i.e. the display width in bytes for each page before rotation, the same layout
+    /// `GraphicsMode` flushes. It is read one page at a time into a buffer of 160 bytes. Data
+    /// that comes in chunks can be passed as `chunks.into_iter().flatten().copied()`.
+    ///
+    /// Exactly one frame of bytes is taken from `frame`, so consecutive frames can be played by
+    /// passing the same iterator with `by_ref()`. Returns `Error::BufferTooSmall` if `frame`
+    /// ends early, after drawing the bytes it did yield.
+    pub fn stream_frame<I>(&mut self, frame: I) -> Result<(), Error>
+    where
+        I: IntoIterator<Item = u8>,
+    {
+        let (width, height) = self.display_size.dimensions();
+        let column_offset = self.display_size.column_offset();
+        let mut frame = frame.into_iter();
 
-        match display_rotation {
-            DisplayRotation::Rotate0 => {
-                Command::SegmentRemap(false).send(&mut self.iface)?;
-                Command::SetCommonScanDir(false).send(&mut self.iface)
-            }
-            DisplayRotation::Rotate90 => {
-                Command::SegmentRemap(false).send(&mut self.iface)?;
-                Command::SetCommonScanDir(true).send(&mut self.iface)
+        self.set_draw_area_unchecked((column_offset, 0), (column_offset + width, height / 8))?;
+        for _ in 0..height / 8 {
+            let mut page = [0u8; 160];
+            let mut length = 0;
+            // `page` comes first so no byte is taken from `frame` once the page is full
+            for (byte, data) in page[..usize::from(width)].iter_mut().zip(&mut frame) {
+                *byte = data;
+                length += 1;
             }
-            DisplayRotation::Rotate180 => {
-                Command::SegmentRemap(true).send(&mut self.iface)?;
-                Command::SetCommonScanDir(true).send(&mut self.iface)
+
+            if length > 0 {
+                self.draw(&page[..length])?;
             }
-            DisplayRotation::Rotate270 => {
-                Command::SegmentRemap(true).send(&mut self.iface)?;
-                Command::SetCommonScanDir(false).send(&mut self.iface)
+            if length < usize::from(width) {
+                return Err(Error::BufferTooSmall);
             }
         }
+
+        Ok(())
     }
 
-    /// Turn the display on or off. The display can be drawn to and retains all
-    /// of its memory even while off.
-    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
-        Command::DisplayOn(on).send(&mut self.iface)
+    /// Move the position at which the next `draw` call continues back to the start of the current
+    /// draw area. This is the same as calling `set_draw_area` again with the same bounds, which
+    /// is handy for repeatedly redrawing a fixed window.
+    pub fn reset_draw_pointer(&mut self) -> Result<(), DisplayError> {
+        self.draw_column = self.draw_area_start.0;
+        self.draw_row = self.draw_area_start.1;
+
+        self.send_draw_address()
     }
 
-    /// Set the display contrast
-    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
-        Command::Contrast(contrast).send(&mut self.iface)
+    /// Get the `(column, row)` position at which the next `draw` call continues
+    pub fn get_cursor_raw(&self) -> (u8, u8) {
+        (self.draw_column, self.draw_row)
+    }
+
+    /// Shift everything drawn by `column` columns and `page` pages of display RAM, for modules
+    /// whose visible area does not start where the display size expects it. The offset is added
+    /// to every RAM address sent to the controller, on top of the offset of the display size.
+    /// Defaults to `(0, 0)`.
+    pub fn set_ram_offset(&mut self, column: u8, page: u8) {
+        self.ram_offset = (column, page);
+    }
+
+    /// Get the RAM offset set with `set_ram_offset` as `(column, page)`
+    pub fn get_ram_offset(&self) -> (u8, u8) {
+        self.ram_offset
+    }
+
+    /// Set how far `advance_pixel_shift` moves the image from its original position, in pixels
+    /// in every direction. The SH1108 has no display offset or start line register, so the shift
+    /// is applied in software when `GraphicsMode` flushes its framebuffer. Defaults to 0, which
+    /// disables the shift.
+    pub fn set_pixel_shift_range(&mut self, range: u8) {
+        self.power.set_shift_range(range);
+    }
+
+    /// Get the range set with `set_pixel_shift_range`
+    pub fn get_pixel_shift_range(&self) -> u8 {
+        self.power.shift_range()
+    }
+
+    /// Move the image `dx` columns right and `dy` rows down of the panel when `GraphicsMode`
+    /// flushes it. Pixels shifted past an edge are not shown and the uncovered edge stays dark.
+    pub fn set_pixel_shift(&mut self, dx: i8, dy: i8) {
+        self.power.set_shift(dx, dy);
+    }
+
+    /// Get the current pixel shift as `(dx, dy)` in panel columns and rows
+    pub fn get_pixel_shift(&self) -> (i8, i8) {
+        self.power.shift()
+    }
+
+    /// Move the pixel shift one pixel further along a path visiting every offset within the
+    /// range set with `set_pixel_shift_range`, and return the new shift. Calling this every few
+    /// minutes keeps a static image from burning into the panel.
+    ///
+    /// The path sweeps the square of offsets row by row in alternating directions, starting at
+    /// the top left, so each step moves the image by a single pixel except for the jump back to
+    /// the start.
+    pub fn advance_pixel_shift(&mut self) -> (i8, i8) {
+        self.power.advance_shift()
+    }
+
+    fn send_draw_address(&mut self) -> Result<(), DisplayError> {
+        self.address_stale = true;
+        self.send_ram_address(self.draw_column, self.draw_row)?;
+        self.address_stale = false;
+
+        Ok(())
+    }
+
+    /// Point the RAM address of the controller at `column` and `page`, plus the RAM offset
+    fn send_ram_address(&mut self, column: u8, page: u8) -> Result<(), DisplayError> {
+        let page = page.wrapping_add(self.ram_offset.1);
+        let column = column.wrapping_add(self.ram_offset.0);
+
+        Command::send_batch(
+            [
+                Command::PageAddress(page),
+                Command::ColumnAddressLow(0xF & column),
+                Command::ColumnAddressHigh(0xF & (column >> 4)),
+            ],
+            &mut self.iface,
+        )
+    }
+
+    /// Set the oscillator frequency (0-15, higher is faster) and the clock divide ratio minus one
+    /// (0-15) used by `init_column_mode`. A faster frame rate reduces flicker at the cost of
+    /// current. Defaults to `(0x6, 0x0)`.
+    pub fn set_clock_divider(&mut self, fosc: u8, divide_ratio: u8) {
+        self.clock_divider = (fosc, divide_ratio);
+    }
+
+    /// Change the oscillator frequency and clock divide ratio of a running display, see
+    /// `set_clock_divider`, e.g. to lower the frame rate and current while a static image is
+    /// shown and raise it again for animations. The new setting is kept for later inits.
+    pub fn apply_clock_divider(&mut self, fosc: u8, divide_ratio: u8) -> Result<(), DisplayError> {
+        self.set_clock_divider(fosc, divide_ratio);
+
+        Command::DisplayClockDiv(fosc, divide_ratio).send(&mut self.iface)
+    }
+
+    /// Set the precharge (phase 1) and discharge (phase 2) periods in display clocks (1-15) used
+    /// by `init_column_mode`. Longer periods make slow panels brighter. Defaults to `(0x8, 0x2)`.
+    pub fn set_precharge(&mut self, phase1: u8, phase2: u8) {
+        self.precharge = (phase1, phase2);
+    }
+
+    /// Set the VCOM deselect level used by `init_column_mode`, see the datasheet for the voltage
+    /// this maps to. Defaults to the power-on value of 0x35.
+    pub fn set_vcom_level(&mut self, level: u8) {
+        self.vcom_level = level;
+    }
+
+    /// Change the precharge and discharge periods of a running display, see `set_precharge`,
+    /// e.g. to tune out flicker on a panel batch from a service menu. The new setting is kept for
+    /// later inits.
+    pub fn apply_precharge(&mut self, phase1: u8, phase2: u8) -> Result<(), DisplayError> {
+        self.set_precharge(phase1, phase2);
+
+        let (phase1, phase2) = self.active_precharge();
+        Command::PreChargePeriod(phase1, phase2).send(&mut self.iface)
+    }
+
+    /// Change the VCOM deselect level of a running display, see `set_vcom_level`, e.g. to reduce
+    /// crosstalk. The new setting is kept for later inits.
+    pub fn apply_vcom_level(&mut self, level: u8) -> Result<(), DisplayError> {
+        self.set_vcom_level(level);
+
+        Command::VcomDeselectLevel(level).send(&mut self.iface)
+    }
+
+    /// Set where the panel drive voltage comes from, used by `init_column_mode` and `wake`.
+    /// Defaults to the built-in DC-DC converter at the power-on switching frequency,
+    /// `ChargePump::Internal(0)`.
+    pub fn set_charge_pump(&mut self, charge_pump: ChargePump) {
+        self.charge_pump = charge_pump;
+    }
+
+    /// Get the panel drive voltage source set with `set_charge_pump`
+    pub fn get_charge_pump(&self) -> ChargePump {
+        self.charge_pump
+    }
+
+    /// Set the delays of the power-on sequence used by `init_with_delay` and the
+    /// `init_with_timing` methods of the modes. Defaults to `PowerTiming::default()`, which
+    /// follows the datasheet.
+    pub fn set_power_timing(&mut self, power_timing: PowerTiming) {
+        self.power_timing = power_timing;
+    }
+
+    /// Get the power-on delays set with `set_power_timing`
+    pub fn get_power_timing(&self) -> PowerTiming {
+        self.power_timing
+    }
+
+    /// Send the DC-DC setting, with the converter enabled if `on` and it is used at all
+    fn send_charge_pump(&mut self, on: bool) -> Result<(), DisplayError> {
+        match self.charge_pump {
+            ChargePump::Internal(frequency) => Command::DcDc(on, frequency),
+            ChargePump::External => Command::DcDc(false, 0),
+        }
+        .send(&mut self.iface)
+    }
+
+    /// Set the bus clock frequency in Hz used by `estimated_transfer_us`. Defaults to 400kHz.
+    ///
+    /// A parallel interface moves a whole byte per write cycle instead of one bit per clock, so
+    /// give it nine times the number of write cycles per second.
+    pub fn set_bus_clock_hint(&mut self, hz: u32) {
+        self.bus_clock_hint = hz;
+    }
+
+    /// Estimate the number of bytes that need to be sent to update the area of the screen from
+    /// `start` (inclusive) to `end` (exclusive), both given as `(x, y)` in display coordinates
+    /// taking rotation into account.
+    ///
+    /// The display RAM is written in whole pages of 8 rows, so the area is rounded out to page
+    /// boundaries. The estimate includes the 4 command bytes which set the draw address for each
+    /// page, but not any per-transfer overhead of the interface such as I2C addressing.
+    ///
+    /// ```rust
+    ///# #[path = "test_helpers.rs"]
+    ///# mod test_helpers;
+    ///# use test_helpers::StubInterface;
+    ///# let interface = StubInterface;
+    /// use sh1108::prelude::*;
+    ///
+    /// let display: GraphicsMode<_> = sh1108::Builder::new().connect(interface).into();
+    /// // A 32x16 area starting halfway down a page touches 3 pages
+    /// assert_eq!(display.estimated_transfer_bytes((0, 4), (32, 20)), 3 * (32 + 4));
+    /// ```
+    pub fn estimated_transfer_bytes(&self, start: (u8, u8), end: (u8, u8)) -> usize {
+        let ((columns_start, rows_start), (columns_end, rows_end)) = match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (start, end),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => {
+                ((start.1, start.0), (end.1, end.0))
+            }
+        };
+        let (width, height) = self.display_size.dimensions();
+        let columns_end = columns_end.min(width);
+        let rows_end = rows_end.min(height);
+
+        if columns_start >= columns_end || rows_start >= rows_end {
+            return 0;
+        }
+
+        let columns = (columns_end - columns_start) as usize;
+        let pages = ((rows_end - 1) / 8 - rows_start / 8 + 1) as usize;
+
+        pages * (columns + PAGE_ADDRESS_OVERHEAD)
+    }
+
+    /// Estimate the time in microseconds needed to update an area of the screen at the bus clock
+    /// set with `set_bus_clock_hint`. See `estimated_transfer_bytes` for how the area is given.
+    ///
+    /// Every byte is assumed to take 9 bus clocks, which matches I2C and slightly overestimates
+    /// SPI, see `set_bus_clock_hint` for parallel interfaces. Time spent by the interface
+    /// implementation itself is not accounted for.
+    pub fn estimated_transfer_us(&self, start: (u8, u8), end: (u8, u8)) -> u32 {
+        let clocks = self.estimated_transfer_bytes(start, end) as u64 * CLOCKS_PER_BYTE;
+
+        (clocks * 1_000_000 / u64::from(self.bus_clock_hint.max(1))) as u32
+    }
+
+    /// Consume the properties and return the interface, e.g. to share the bus with other devices
+    /// or to create the driver again with different settings
+    pub fn release(self) -> DI {
+        #[cfg(feature = "metrics")]
+        return self.iface.inner;
+        #[cfg(not(feature = "metrics"))]
+        return self.iface;
+    }
+
+    /// Get the configured display size
+    pub fn get_size(&self) -> DisplaySize {
+        self.display_size
+    }
+
+    /// Change the display size after the driver was created, e.g. for a product that detects
+    /// which module is fitted at boot. An initialised display gets the new resolution right
+    /// away, otherwise it is sent by the next `init_column_mode`. The draw area is not adapted
+    /// and has to be set again before drawing.
+    pub fn set_size(&mut self, display_size: DisplaySize) -> Result<(), DisplayError> {
+        self.display_size = display_size;
+        // The column offset of the visible area changes with the size
+        self.address_stale = true;
+
+        if self.initialized {
+            Command::DisplayResolution(display_size).send(&mut self.iface)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the optional features supported by the display controller. See [`Capabilities`].
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::for_size(self.display_size)
+    }
+
+    /// Get display dimensions, taking into account the current rotation of the display
+    ///
+    /// ```rust
+    ///# #[path = "test_helpers.rs"]
+    ///# mod test_helpers;
+    ///# use test_helpers::StubInterface;
+    ///# let interface = StubInterface;
+    /// use sh1108::prelude::*;
+    ///
+    /// let mut display: GraphicsMode<_> = sh1108::Builder::new().connect(interface).into();
+    /// assert_eq!(display.get_dimensions(), (128, 160));
+    ///
+    /// # let interface = StubInterface;
+    /// let mut rotated_display: GraphicsMode<_> = sh1108::Builder::new().with_rotation(DisplayRotation::Rotate90).connect(interface).into();
+    /// assert_eq!(rotated_display.get_dimensions(), (160, 128));
+    /// ```
+    pub fn get_dimensions(&self) -> (u8, u8) {
+        let (w, h) = self.display_size.dimensions();
+
+        match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (w, h),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (h, w),
+        }
+    }
+
+    /// Get the display rotation
+    pub fn get_rotation(&self) -> DisplayRotation {
+        self.display_rotation
+    }
+
+    /// Set the display rotation. Any mirroring set with `set_mirror` is kept and applied on top
+    /// of the new rotation.
+    pub fn set_rotation(&mut self, display_rotation: DisplayRotation) -> Result<(), DisplayError> {
+        self.display_rotation = display_rotation;
+
+        self.send_scan_direction()
+    }
+
+    /// Mirror the displayed image, independent of the rotation. `horizontal` swaps left and right
+    /// and `vertical` swaps top and bottom, both as seen in the current rotation.
+    ///
+    /// The rotation chooses a base orientation and the mirror flags are applied on top of it, so
+    /// the two can be set in any order: a panel mounted behind a mirror can use any rotation with
+    /// `set_mirror(true, false)`. Mirroring both axes is the same as rotating by 180 degrees. The
+    /// framebuffer is not affected, only how the controller scans it out.
+    pub fn set_mirror(&mut self, horizontal: bool, vertical: bool) -> Result<(), DisplayError> {
+        self.mirror = (horizontal, vertical);
+
+        self.send_scan_direction()
+    }
+
+    /// Store the mirroring without sending it, for the builder. It takes effect with the next
+    /// `init_column_mode`.
+    pub(crate) fn set_initial_mirror(&mut self, horizontal: bool, vertical: bool) {
+        self.mirror = (horizontal, vertical);
+    }
+
+    /// Store the rotation without sending it, for display modes with a rotation fixed at compile
+    /// time. It takes effect with the next `init_column_mode`.
+    pub(crate) fn set_initial_rotation(&mut self, display_rotation: DisplayRotation) {
+        self.display_rotation = display_rotation;
+    }
+
+    /// Get the mirroring set with `set_mirror` as `(horizontal, vertical)`
+    pub fn get_mirror(&self) -> (bool, bool) {
+        self.mirror
+    }
+
+    fn send_scan_direction(&mut self) -> Result<(), DisplayError> {
+        let (remap, reverse_scan) = match self.display_rotation {
+            DisplayRotation::Rotate0 => (false, false),
+            DisplayRotation::Rotate90 => (false, true),
+            DisplayRotation::Rotate180 => (true, true),
+            DisplayRotation::Rotate270 => (true, false),
+        };
+
+        // Segments run along the panel columns, which are the vertical axis when rotated by 90
+        // or 270 degrees
+        let (horizontal, vertical) = self.mirror;
+        let (flip_segments, flip_commons) = match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (horizontal, vertical),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (vertical, horizontal),
+        };
+
+        Command::SegmentRemap(remap != flip_segments).send(&mut self.iface)?;
+        Command::SetCommonScanDir(reverse_scan != flip_commons).send(&mut self.iface)
+    }
+
+    /// Turn the display on or off. The display can be drawn to and retains all
+    /// of its memory even while off.
+    pub fn display_on(&mut self, on: bool) -> Result<(), DisplayError> {
+        Command::DisplayOn(on).send(&mut self.iface)?;
+        self.on = on;
+        self.power.end_blank();
+
+        Ok(())
+    }
+
+    /// Whether the display was last turned on, by `init_column_mode`, `display_on` or `wake`,
+    /// rather than off by `display_on` or `sleep`. A new driver assumes the display is off, as
+    /// it is after a reset.
+    pub fn is_on(&self) -> bool {
+        self.on
+    }
+
+    /// Check whether a display answers on the interface by sending it a NOP command, which
+    /// changes nothing on the display. Safe to call before `init`.
+    ///
+    /// Over I2C a missing display does not acknowledge its address, so the write fails with the
+    /// error of the interface, usually `DisplayError::BusWriteError`. SPI and parallel
+    /// interfaces get no answer from the display and always succeed here; with a
+    /// [`ReadInterface`], [`DisplayProperties::read_status`] can be used instead.
+    pub fn probe(&mut self) -> Result<(), DisplayError> {
+        Command::Noop.send(&mut self.iface)
+    }
+
+    /// Whether a display answers on the interface, see [`DisplayProperties::probe`]. Lets
+    /// firmware skip all drawing when an optional display is not fitted.
+    pub fn is_connected(&mut self) -> bool {
+        self.probe().is_ok()
+    }
+
+    /// Put the display into its lowest power state: the display is turned off, which stops the
+    /// panel drive, and the built-in DC-DC converter is disabled. The display RAM is retained
+    /// and can still be written while asleep. Use `wake` to return to normal operation.
+    pub fn sleep(&mut self) -> Result<(), DisplayError> {
+        Command::DisplayOn(false).send(&mut self.iface)?;
+        self.on = false;
+        self.power.end_blank();
+        // The DC-DC converter may only be reconfigured while the display is off
+        self.send_charge_pump(false)
+    }
+
+    /// Wake the display from `sleep`: the DC-DC converter is enabled again, unless the panel
+    /// voltage is supplied externally, see `set_charge_pump`, and turns on together with the
+    /// display, then this waits the 100ms the datasheet gives the panel voltages to settle
+    /// before the display shows a stable image.
+    pub fn wake<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u8>,
+    {
+        self.send_charge_pump(true)?;
+        Command::DisplayOn(true).send(&mut self.iface)?;
+        self.on = true;
+        self.power.end_blank();
+        delay.delay_ms(WAKE_SETTLE_MS);
+
+        Ok(())
+    }
+
+    /// Invert the display in hardware, so lit pixels turn off and vice versa. The display RAM is
+    /// not changed, so this takes effect instantly without sending a new frame.
+    pub fn set_invert(&mut self, invert: bool) -> Result<(), DisplayError> {
+        self.invert = invert;
+        Command::Invert(invert).send(&mut self.iface)
+    }
+
+    /// Whether the display is inverted with `set_invert`
+    pub fn is_inverted(&self) -> bool {
+        self.invert
+    }
+
+    /// Set the display contrast
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), DisplayError> {
+        self.contrast = contrast;
+        // Changing the contrast by hand counts as activity for `set_auto_dim`
+        self.power.reset_idle();
+        Command::Contrast(self.resting_contrast()).send(&mut self.iface)
+    }
+
+    /// Set the contrast and precharge period to a [`Brightness`] preset. The precharge is also
+    /// used by later calls to `init_column_mode`.
+    pub fn set_brightness(&mut self, brightness: Brightness) -> Result<(), DisplayError> {
+        let (phase1, phase2) = brightness.precharge();
+        self.set_precharge(phase1, phase2);
+        let (phase1, phase2) = self.active_precharge();
+        Command::PreChargePeriod(phase1, phase2).send(&mut self.iface)?;
+
+        self.set_contrast(brightness.contrast())
+    }
+
+    /// Set the contrast so the display looks `percent` bright, from 0 to 100, through the
+    /// gamma curve of [`contrast_for_percent`](crate::brightness::contrast_for_percent), so 50
+    /// looks about half as bright as 100. Only the contrast changes; a longer precharge, see
+    /// `set_precharge` or the [`Brightness`] presets, extends the range further.
+    pub fn set_brightness_percent(&mut self, percent: u8) -> Result<(), DisplayError> {
+        self.set_contrast(brightness::contrast_for_percent(percent))
+    }
+
+    /// Limit the share of lit pixels, in percent, the display may draw full current for. Values
+    /// of 100 and above disable the limit, which is the default. See `apply_power_budget`.
+    pub fn set_power_budget(&mut self, max_on_pixels_percent: u8) {
+        self.power.set_budget(max_on_pixels_percent);
+    }
+
+    /// Adjust the contrast to the power budget for a frame with `lit_pixels` pixels turned on.
+    /// This is called by `GraphicsMode::flush` before sending each frame.
+    ///
+    /// The current drawn by an OLED panel is roughly proportional to the number of lit pixels
+    /// times the contrast setting. If more pixels are lit than the budget allows, the contrast is
+    /// scaled down by the same ratio so the product, and with it the current, stays at the level
+    /// of a frame lighting exactly the budgeted share at the contrast set with `set_contrast`.
+    /// Once a frame is back within the budget the contrast is restored. The contrast command is
+    /// only sent when the applied level changes, and `get_contrast` is never affected.
+    pub fn apply_power_budget(&mut self, lit_pixels: u32) -> Result<(), DisplayError> {
+        let (width, height) = self.display_size.dimensions();
+        let pixels = u32::from(width) * u32::from(height);
+
+        let previous = self.resting_contrast();
+        if !self.power.apply_budget(lit_pixels, pixels) || self.resting_contrast() == previous {
+            return Ok(());
+        }
+
+        Command::Contrast(self.resting_contrast()).send(&mut self.iface)
+    }
+
+    /// The contrast actually applied between effects, i.e. the one set by `set_contrast` or
+    /// the dimmed one of `dim`, reduced to the power budget
+    fn resting_contrast(&self) -> u8 {
+        self.power.resting_contrast(self.contrast)
+    }
+
+    /// The precharge periods actually applied, i.e. the ones set by `set_precharge` or the
+    /// dimmed ones of `dim`
+    fn active_precharge(&self) -> (u8, u8) {
+        self.power.active_precharge(self.precharge)
+    }
+
+    /// Set the contrast and precharge periods `dim` switches to. Defaults to those of
+    /// [`Brightness::Dim`]. Takes effect right away if the display is dimmed.
+    pub fn set_dim_level(
+        &mut self,
+        contrast: u8,
+        phase1: u8,
+        phase2: u8,
+    ) -> Result<(), DisplayError> {
+        self.power.set_dim_level(contrast, (phase1, phase2));
+
+        if self.power.is_dim() {
+            self.dim(true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Switch between the normal contrast and precharge, as set with `set_contrast` and
+    /// `set_precharge`, and the dimmed ones set with `set_dim_level`, e.g. for a night mode.
+    /// Changing the contrast or precharge while dimmed changes the normal values that
+    /// `dim(false)` returns to.
+    pub fn dim(&mut self, on: bool) -> Result<(), DisplayError> {
+        self.power.set_dim(on);
+
+        let (phase1, phase2) = self.active_precharge();
+        Command::PreChargePeriod(phase1, phase2).send(&mut self.iface)?;
+        Command::Contrast(self.resting_contrast()).send(&mut self.iface)
+    }
+
+    /// Whether the display is dimmed with `dim`
+    pub fn is_dim(&self) -> bool {
+        self.power.is_dim()
+    }
+
+    /// Get the display contrast as last set by `set_contrast`. Defaults to the controller's
+    /// power-on value of `0x80`.
+    pub fn get_contrast(&self) -> u8 {
+        self.contrast
+    }
+
+    /// Pulse the contrast in a double "lub-dub" beat, like a heartbeat, for ambient status
+    /// indication. One beat at `bpm` beats per minute is played per call, so the caller decides
+    /// how often to repeat it.
+    ///
+    /// The beat peaks `0x60` above the current contrast (saturating at `0xFF`), with
+    /// a second, weaker peak halfway up, and comes to rest at the current contrast for the
+    /// remainder of the period. The cached contrast is not changed.
+    pub fn heartbeat<DELAY>(&mut self, bpm: u16, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        let rest = self.resting_contrast();
+        let peak = rest.saturating_add(HEARTBEAT_BOOST);
+        let echo = rest + (peak - rest) / 2;
+
+        // Divide the beat period into eighths: peak, rest, echo, then rest for the rest of it
+        let eighth = (60_000 / u32::from(bpm.max(1)) / 8) as u16;
+
+        for (contrast, eighths) in [(peak, 1), (rest, 1), (echo, 1), (rest, 5)] {
+            Command::Contrast(contrast).send(&mut self.iface)?;
+            delay.delay_ms(eighth * eighths);
+        }
+
+        Ok(())
+    }
+
+    /// Briefly raise the contrast by `boost` (saturating at `0xFF`) for `duration_ms`
+    /// milliseconds, then restore the contrast set with `set_contrast`. This draws attention to
+    /// the display without changing what it shows.
+    pub fn flash_contrast<DELAY>(
+        &mut self,
+        boost: u8,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        Command::Contrast(self.resting_contrast().saturating_add(boost)).send(&mut self.iface)?;
+        delay.delay_ms(duration_ms);
+
+        Command::Contrast(self.resting_contrast()).send(&mut self.iface)
+    }
+
+    /// Fade the display out by lowering the contrast from its current value to `0x00` over
+    /// `duration_ms` milliseconds, in steps of about 10ms. The display stays on at zero contrast,
+    /// e.g. to be put to sleep afterwards. The contrast set with `set_contrast` is not changed, so
+    /// `fade_in` brings the display back to it.
+    pub fn fade_out<DELAY>(
+        &mut self,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.fade(self.resting_contrast(), 0, duration_ms, delay)
+    }
+
+    /// Fade the display in by raising the contrast from `0x00` to the one set with `set_contrast`
+    /// over `duration_ms` milliseconds, in steps of about 10ms
+    pub fn fade_in<DELAY>(
+        &mut self,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        self.fade(0, self.resting_contrast(), duration_ms, delay)
+    }
+
+    fn fade<DELAY>(
+        &mut self,
+        from: u8,
+        to: u8,
+        duration_ms: u16,
+        delay: &mut DELAY,
+    ) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u16>,
+    {
+        let steps = u32::from((duration_ms / FADE_STEP_MS).max(1));
+        let (from, to, duration) = (i32::from(from), i32::from(to), u32::from(duration_ms));
+
+        for step in 1..=steps {
+            // Spread the duration over the steps without losing the remainder
+            let elapsed = |step: u32| duration * step / steps;
+            delay.delay_ms((elapsed(step) - elapsed(step - 1)) as u16);
+
+            let contrast = from + (to - from) * step as i32 / steps as i32;
+            Command::Contrast(contrast as u8).send(&mut self.iface)?;
+        }
+
+        Ok(())
+    }
+
+    /// Light up every pixel and sweep the contrast from `0x00` up to `0xFF` and back down, showing
+    /// each level for 10ms. This lets a technician check the brightness uniformity of the panel
+    /// and its usable contrast range.
+    ///
+    /// The display RAM is left untouched and the contrast set before the sweep is restored
+    /// afterwards, so the previous image comes back once the sweep is done.
+    pub fn contrast_sweep<DELAY>(&mut self, delay: &mut DELAY) -> Result<(), DisplayError>
+    where
+        DELAY: DelayMs<u8>,
+    {
+        Command::AllOn(true).send(&mut self.iface)?;
+
+        for contrast in (0..=0xFF).chain((0..0xFF).rev()) {
+            Command::Contrast(contrast).send(&mut self.iface)?;
+            delay.delay_ms(CONTRAST_SWEEP_STEP_MS);
+        }
+
+        Command::Contrast(self.resting_contrast()).send(&mut self.iface)?;
+        Command::AllOn(false).send(&mut self.iface)
+    }
+
+    /// Show a test pattern over the whole panel, e.g. in a factory test or while bringing up new
+    /// hardware. [`TestPattern::AllOn`] uses the entire display on command of the controller and
+    /// leaves the display RAM untouched, the other patterns are written into the display RAM
+    /// and replace its contents.
+    ///
+    /// Call [`end_test_pattern`](Self::end_test_pattern) to show the display RAM again after
+    /// [`TestPattern::AllOn`].
+    pub fn test_pattern(&mut self, pattern: TestPattern) -> Result<(), DisplayError> {
+        if pattern == TestPattern::AllOn {
+            return Command::AllOn(true).send(&mut self.iface);
+        }
+
+        Command::AllOn(false).send(&mut self.iface)?;
+
+        let display_size = self.get_size();
+        let (display_width, display_height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+
+        let mut page = [0; 160];
+        for (column, byte) in page.iter_mut().enumerate() {
+            *byte = pattern.page_byte(column as u8);
+        }
+
+        self.set_draw_area_unchecked(
+            (column_offset, 0),
+            (display_width + column_offset, display_height / 8),
+        )?;
+        for _ in 0..display_height / 8 {
+            self.draw(&page[..display_width as usize])?;
+        }
+
+        Ok(())
+    }
+
+    /// Show the display RAM again after [`TestPattern::AllOn`]
+    pub fn end_test_pattern(&mut self) -> Result<(), DisplayError> {
+        Command::AllOn(false).send(&mut self.iface)
+    }
+
+    /// Show a run-length encoded full screen image, e.g. a boot logo, decoding it straight to
+    /// the display RAM one page at a time, so it can be shown right after power-on before a
+    /// drawing mode and its framebuffer are set up. See the [`rle`](crate::rle) module for the
+    /// encoding. Missing pixels at the end of `rle` are drawn as off.
+    ///
+    /// The image is as wide and high as the panel, before rotation, like the framebuffer of
+    /// `GraphicsMode`, so for a display rotated by 90 or 270 degrees it has to be stored
+    /// transposed. A display that was not initialised yet is initialised first, and only turned
+    /// on once the image is in its RAM, so nothing else flashes up before it.
+    pub fn show_splash(&mut self, rle: &[u8]) -> Result<(), DisplayError> {
+        let initialized = self.initialized;
+        if !initialized {
+            self.send_init_settings()?;
+        }
+
+        let display_size = self.get_size();
+        let (display_width, display_height) = display_size.dimensions();
+        let column_offset = display_size.column_offset();
+        let width = usize::from(display_width);
+
+        self.set_draw_area_unchecked(
+            (column_offset, 0),
+            (display_width + column_offset, display_height / 8),
+        )?;
+        let mut pixels = RleDecoder::new(rle);
+        for _ in 0..display_height / 8 {
+            let mut page = [0; 160];
+            for bit in 0..8 {
+                for byte in &mut page[..width] {
+                    if pixels.next().unwrap_or(false) {
+                        *byte |= 1 << bit;
+                    }
+                }
+            }
+            self.draw(&page[..width])?;
+        }
+
+        if !initialized {
+            self.finish_init()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<DI> DisplayProperties<DI>
+where
+    DI: ReadInterface,
+{
+    /// Read the status register of the controller, e.g. to wait for it to leave the busy state
+    /// after a reset or to check whether the display is on
+    pub fn read_status(&mut self) -> Result<Status, DisplayError> {
+        self.iface.read_status().map(Status::from_bits)
+    }
+
+    /// Read `buffer.len()` bytes of display RAM, starting at panel column `column` of page
+    /// `page`, before rotation. This shows what is actually on the display, e.g. to verify a
+    /// flush.
+    ///
+    /// Returns `Error::OutOfBounds` if the bytes do not fit in the page.
+    pub fn read_ram(&mut self, column: u8, page: u8, buffer: &mut [u8]) -> Result<(), Error> {
+        self.check_ram_range(column, page, buffer.len())?;
+
+        self.start_ram_read(column, page, false)?;
+        Ok(self.iface.read_data(buffer)?)
+    }
+
+    /// Change `count` bytes of display RAM in place, starting at panel column `column` of page
+    /// `page`, before rotation. `modify` is called with the index and current value of every
+    /// byte and returns its new value. Each byte is read and written back in the
+    /// read-modify-write mode of the controller, so nothing else is overwritten and no
+    /// framebuffer is needed.
+    ///
+    /// Returns `Error::OutOfBounds` if the bytes do not fit in the page.
+    pub fn modify_ram<F>(
+        &mut self,
+        column: u8,
+        page: u8,
+        count: u8,
+        mut modify: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(u8, u8) -> u8,
+    {
+        self.check_ram_range(column, page, count.into())?;
+
+        // Reads do not advance the column address in read-modify-write mode, writes do
+        self.start_ram_read(column, page, true)?;
+        for index in 0..count {
+            let mut byte = [0];
+            self.iface.read_data(&mut byte)?;
+            let byte = [modify(index, byte[0])];
+            self.iface.send_data(DataFormat::U8(&byte))?;
+        }
+
+        Ok(Command::End.send(&mut self.iface)?)
+    }
+
+    /// Turn the pixel at `x` and `y` on or off right in display RAM, in display coordinates
+    /// taking rotation into account. Pixels outside the display are ignored.
+    ///
+    /// The byte holding the pixel is changed with `modify_ram`, so the pixel combines with what
+    /// is already on the screen without any framebuffer or cache, at the cost of a read and a
+    /// write per pixel. Enough for a cursor or a dial on a microcontroller that cannot spare
+    /// the RAM for [`DirectMode`](crate::mode::DirectMode).
+    pub fn set_pixel_direct(&mut self, x: u32, y: u32, on: bool) -> Result<(), Error> {
+        let (width, height) = self.display_size.dimensions();
+
+        let (column, row) = match self.display_rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => (x, y),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => (y, x),
+        };
+
+        if column >= width.into() || row >= height.into() {
+            return Ok(());
+        }
+
+        let bit = 1 << (row % 8);
+        self.modify_ram(column as u8, (row / 8) as u8, 1, |_, byte| {
+            if on {
+                byte | bit
+            } else {
+                byte & !bit
+            }
+        })
+    }
+
+    /// Check that `len` bytes from panel column `column` of page `page` are on the display
+    fn check_ram_range(&self, column: u8, page: u8, len: usize) -> Result<(), Error> {
+        let (width, height) = self.display_size.dimensions();
+
+        if page >= height / 8 || usize::from(column) + len > usize::from(width) {
+            return Err(Error::OutOfBounds);
+        }
+
+        Ok(())
+    }
+
+    /// Set the RAM address to panel column `column` of page `page`, enter read-modify-write
+    /// mode if `modify` and do the dummy read the controller needs before returning display
+    /// data
+    fn start_ram_read(&mut self, column: u8, page: u8, modify: bool) -> Result<(), DisplayError> {
+        // The controller address no longer matches the position of `draw`
+        self.address_stale = true;
+        self.send_ram_address(self.display_size.column_offset() + column, page)?;
+        // Outside of read-modify-write mode the dummy read advances the column, so the mode
+        // has to start first for `End` to return to the right column
+        if modify {
+            Command::ReadModifyWrite.send(&mut self.iface)?;
+        }
+
+        self.iface.read_data(&mut [0])
+    }
+}
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    geometry::{Point, Size},
+    primitives::Rectangle,
+};
+
+#[cfg(feature = "graphics")]
+impl<DI> DisplayProperties<DI>
+where
+    DI: WriteOnlyDataCommand,
+{
+    /// Get the drawable area of the display in display coordinates, taking into account the
+    /// current rotation. This is the rectangle with its top left corner at the origin and the
+    /// size returned by `get_dimensions`.
+    pub fn visible_bounds(&self) -> Rectangle {
+        let (w, h) = self.get_dimensions();
+
+        Rectangle::new(Point::zero(), Size::new(w.into(), h.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::{CaptureInterface, DelayRecorder, RamInterface, Sent};
+    use std::vec;
+
+    #[test]
+    fn heartbeat_pulses_and_comes_to_rest() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut delay = DelayRecorder::new();
+
+        properties.set_contrast(0x40).unwrap();
+        iface.clear();
+        properties.heartbeat(60, &mut delay).unwrap();
+
+        assert_eq!(
+            iface.commands(),
+            [0x81, 0xA0, 0x81, 0x40, 0x81, 0x70, 0x81, 0x40]
+        );
+        assert_eq!(delay.delays(), [125, 125, 125, 625]);
+        assert_eq!(properties.get_contrast(), 0x40);
+    }
+
+    #[test]
+    fn draw_resumes_at_cursor() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.set_draw_area((16, 0), (20, 2)).unwrap();
+        assert!(matches!(
+            properties.set_cursor_raw(20, 0),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            properties.set_cursor_raw(16, 2),
+            Err(Error::OutOfBounds)
+        ));
+
+        iface.clear();
+        properties.set_cursor_raw(18, 1).unwrap();
+        assert_eq!(properties.get_cursor_raw(), (18, 1));
+
+        properties.draw(&[1, 2, 3, 4, 5, 6]).unwrap();
+
+        assert_eq!(
+            iface.sent(),
+            [
+                Sent::Commands(vec![0xB0, 0x01, 0x02, 0x11]),
+                Sent::Data(vec![1, 2]),
+                Sent::Commands(vec![0xB0, 0x00, 0x00, 0x11]),
+                Sent::Data(vec![3, 4, 5, 6]),
+                Sent::Commands(vec![0xB0, 0x01, 0x00, 0x11]),
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_layout_converts_to_display_ram() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        // A 10x16 image with the top left pixel, the right column and pixel (8, 9) on
+        let expected = [
+            0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0xFF, //
+            0, 0, 0, 0, 0, 0, 0, 0, 0x02, 0xFF,
+        ];
+        let mut horizontal = [0u8; 32];
+        for row in 0..16 {
+            horizontal[row * 2 + 1] = 0x40;
+        }
+        horizontal[0] |= 0x80;
+        horizontal[9 * 2 + 1] |= 0x80;
+
+        let vertical_msb = expected.map(u8::reverse_bits);
+        let horizontal_lsb = horizontal.map(u8::reverse_bits);
+        for (data, layout) in [
+            (&expected[..], RawLayout::VerticalLsbFirst),
+            (&vertical_msb[..], RawLayout::VerticalMsbFirst),
+            (&horizontal[..], RawLayout::HorizontalMsbFirst),
+            (&horizontal_lsb[..], RawLayout::HorizontalLsbFirst),
+        ] {
+            properties.set_draw_area((0, 0), (10, 2)).unwrap();
+            iface.clear();
+            properties.draw_layout(data, layout).unwrap();
+            assert_eq!(iface.data(), expected, "{:?}", layout);
+        }
+
+        // Missing bytes are off
+        iface.clear();
+        properties
+            .draw_layout(&horizontal[..2], RawLayout::HorizontalMsbFirst)
+            .unwrap();
+        let mut first_row = [0; 20];
+        first_row[0] = 0x01;
+        first_row[9] = 0x01;
+        assert_eq!(iface.data(), first_row);
+
+        properties.set_rotation(DisplayRotation::Rotate90).unwrap();
+        properties.set_draw_area((0, 0), (8, 1)).unwrap();
+        assert!(matches!(
+            properties.draw_layout(&[0; 8], RawLayout::HorizontalMsbFirst),
+            Err(DisplayError::DataFormatNotImplemented)
+        ));
+    }
+
+    #[test]
+    fn draw_area_is_validated() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        // No area set yet
+        assert!(matches!(
+            properties.draw(&[1]),
+            Err(DisplayError::OutOfBoundsError)
+        ));
+
+        for (start, end) in [
+            ((16, 0), (16, 2)),
+            ((20, 0), (16, 2)),
+            ((16, 2), (20, 1)),
+            ((0, 0), (161, 1)),
+            ((0, 0), (1, 21)),
+        ] {
+            assert!(matches!(
+                properties.set_draw_area(start, end),
+                Err(DisplayError::OutOfBoundsError)
+            ));
+        }
+        assert!(iface.sent().is_empty());
+
+        // A buffer ending partway through a row is picked up by the next draw
+        properties.set_draw_area((16, 0), (20, 2)).unwrap();
+        iface.clear();
+        properties.draw(&[1, 2, 3]).unwrap();
+        properties.draw(&[4, 5]).unwrap();
+
+        assert_eq!(
+            iface.sent(),
+            [
+                Sent::Data(vec![1, 2, 3]),
+                Sent::Data(vec![4]),
+                Sent::Commands(vec![0xB0, 0x01, 0x00, 0x11]),
+                Sent::Data(vec![5]),
+            ]
+        );
+    }
+
+    #[test]
+    fn bounded_draw_sends_the_window() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display64x160,
+            DisplayRotation::Rotate0,
+        );
+        let buffer: vec::Vec<u8> = (0..=255).cycle().take(64 * 20).collect();
+
+        properties
+            .bounded_draw(&buffer, 64, (2, 5), (4, 9))
+            .unwrap();
+
+        // Rows 5 to 8 touch pages 0 and 1, and the 64 column display starts at RAM column 48
+        assert_eq!(iface.commands()[..4], [0xB0, 0x00, 0x02, 0x13]);
+        assert_eq!(iface.data(), [2, 3, 66, 67]);
+
+        assert!(matches!(
+            properties.bounded_draw(&buffer, 64, (4, 0), (4, 8)),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            properties.bounded_draw(&buffer, 64, (0, 0), (65, 8)),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            properties.bounded_draw(&buffer[..100], 64, (0, 0), (40, 9)),
+            Err(Error::BufferTooSmall)
+        ));
+    }
+
+    #[test]
+    fn ram_reads_back_and_modifies_in_place() {
+        let iface = RamInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        assert!(!properties.read_status().unwrap().is_display_on());
+        properties.init_column_mode().unwrap();
+        assert!(properties.read_status().unwrap().is_display_on());
+
+        properties.set_draw_area((16, 2), (20, 3)).unwrap();
+        properties.draw(&[1, 2, 3, 4]).unwrap();
+
+        // The dummy byte after setting the address is skipped
+        let mut buffer = [0; 3];
+        properties.read_ram(1, 2, &mut buffer).unwrap();
+        assert_eq!(buffer, [2, 3, 4]);
+
+        properties
+            .modify_ram(0, 2, 3, |index, byte| byte | (0x10 << index))
+            .unwrap();
+        assert_eq!(iface.page(2)[16..20], [0x11, 0x22, 0x43, 4]);
+        assert!(matches!(
+            properties.read_ram(126, 2, &mut buffer),
+            Err(Error::OutOfBounds)
+        ));
+
+        // Drawing continues where it left off
+        properties.draw(&[5]).unwrap();
+        assert_eq!(iface.page(2)[16..20], [5, 0x22, 0x43, 4]);
+    }
+
+    #[test]
+    fn pixels_are_set_directly_in_ram() {
+        let iface = RamInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate90,
+        );
+        properties.init_column_mode().unwrap();
+
+        iface.set_ram(16 + 9, 0, &[0x81]);
+        properties.set_pixel_direct(2, 9, true).unwrap();
+        properties.set_pixel_direct(7, 9, false).unwrap();
+        properties.set_pixel_direct(0, 128, true).unwrap();
+
+        assert_eq!(iface.page(0)[16 + 9], 0x05);
+        assert!(iface.page(0)[..16 + 9].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn frames_stream_from_an_iterator() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display64x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+
+        // Two frames back to back, and half of a third one
+        let mut frames = (0..64 * 20 * 5 / 2).map(|i| (i / (64 * 20)) as u8);
+        iface.clear();
+        properties.stream_frame(frames.by_ref()).unwrap();
+        assert_eq!(iface.commands()[..4], [0xB0, 0x00, 0x00, 0x13]);
+        assert_eq!(iface.data(), [0; 64 * 20]);
+
+        iface.clear();
+        properties.stream_frame(frames.by_ref()).unwrap();
+        assert_eq!(iface.data(), [1; 64 * 20]);
+
+        iface.clear();
+        assert!(matches!(
+            properties.stream_frame(frames.by_ref()),
+            Err(Error::BufferTooSmall)
+        ));
+        assert_eq!(iface.data(), [2; 64 * 10]);
+    }
+
+    #[test]
+    fn page_mode_draws_page_major_data() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display160x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_page_mode().unwrap();
+        assert_eq!(iface.commands().last(), Some(&0x20));
+
+        // A 3 pixel wide, 16 pixel high glyph with a trailing partial page that is ignored
+        iface.clear();
+        properties
+            .draw_pages(&[1, 2, 3, 4, 5, 6, 7], 3, 10, 2)
+            .unwrap();
+        assert_eq!(iface.commands()[..4], [0xB0, 0x02, 0x0A, 0x10]);
+        assert_eq!(iface.data(), [1, 2, 3, 4, 5, 6]);
+
+        assert!(matches!(
+            properties.draw_pages(&[1, 2], 3, 10, 2),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            properties.draw_pages(&[1, 2, 3], 3, 158, 0),
+            Err(Error::OutOfBounds)
+        ));
+        assert!(matches!(
+            properties.draw_pages(&[1, 2, 3, 4, 5, 6], 3, 0, 19),
+            Err(Error::OutOfBounds)
+        ));
+    }
+
+    #[test]
+    fn mirror_composes_with_rotation() {
+        use DisplayRotation::*;
+
+        // (rotation, horizontal, vertical, segment remap, common scan)
+        let cases = [
+            (Rotate0, false, false, 0xA0, 0xC0),
+            (Rotate0, true, false, 0xA1, 0xC0),
+            (Rotate0, false, true, 0xA0, 0xC8),
+            (Rotate0, true, true, 0xA1, 0xC8),
+            (Rotate90, false, false, 0xA0, 0xC8),
+            (Rotate90, true, false, 0xA0, 0xC0),
+            (Rotate90, false, true, 0xA1, 0xC8),
+            (Rotate90, true, true, 0xA1, 0xC0),
+            (Rotate180, false, false, 0xA1, 0xC8),
+            (Rotate180, true, false, 0xA0, 0xC8),
+            (Rotate180, false, true, 0xA1, 0xC0),
+            (Rotate180, true, true, 0xA0, 0xC0),
+            (Rotate270, false, false, 0xA1, 0xC0),
+            (Rotate270, true, false, 0xA1, 0xC8),
+            (Rotate270, false, true, 0xA0, 0xC0),
+            (Rotate270, true, true, 0xA0, 0xC8),
+        ];
+
+        for (rotation, horizontal, vertical, remap, scan) in cases {
+            let iface = CaptureInterface::new();
+            let mut properties =
+                DisplayProperties::new(iface.clone(), DisplaySize::Display128x160, Rotate0);
+
+            // Both orders give the same result
+            properties.set_mirror(horizontal, vertical).unwrap();
+            iface.clear();
+            properties.set_rotation(rotation).unwrap();
+            assert_eq!(iface.commands(), [remap, scan], "{:x} {:x}", remap, scan);
+
+            properties.set_mirror(false, false).unwrap();
+            properties.set_rotation(Rotate0).unwrap();
+            properties.set_rotation(rotation).unwrap();
+            iface.clear();
+            properties.set_mirror(horizontal, vertical).unwrap();
+            assert_eq!(iface.commands(), [remap, scan], "{:x} {:x}", remap, scan);
+            assert_eq!(properties.get_mirror(), (horizontal, vertical));
+        }
+    }
+
+    #[test]
+    fn sleep_and_wake_sequences() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut delay = DelayRecorder::new();
+
+        properties.sleep().unwrap();
+        assert_eq!(iface.commands(), [0xAE, 0xAD, 0x80]);
+
+        iface.clear();
+        properties.wake(&mut delay).unwrap();
+        assert_eq!(iface.commands(), [0xAD, 0x81, 0xAF]);
+        assert_eq!(delay.delays(), [100]);
+    }
+
+    #[test]
+    fn brightness_percent_follows_the_gamma_curve() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.set_brightness_percent(50).unwrap();
+        assert_eq!(iface.commands(), [0x81, 55]);
+        properties.set_brightness_percent(150).unwrap();
+        assert_eq!(properties.get_contrast(), 0xFF);
+        properties.set_brightness_percent(0).unwrap();
+        assert_eq!(properties.get_contrast(), 0);
+    }
+
+    #[test]
+    fn auto_dim_after_idle_time() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+        properties.set_auto_dim(Some((1000, 0x10))).unwrap();
+        iface.clear();
+
+        properties.idle_tick(600).unwrap();
+        assert!(iface.sent().is_empty());
+        properties.idle_tick(600).unwrap();
+        assert_eq!(iface.commands(), [0x81, 0x10]);
+        assert!(properties.is_dimmed());
+
+        // Only once, and undone by the next data
+        properties.idle_tick(600).unwrap();
+        iface.clear();
+        properties.set_draw_area((16, 0), (144, 1)).unwrap();
+        properties.draw(&[0xFF]).unwrap();
+        assert_eq!(iface.commands()[iface.commands().len() - 2..], [0x81, 0x80]);
+        assert!(!properties.is_dimmed());
+
+        // The idle time starts over with the data
+        iface.clear();
+        properties.idle_tick(600).unwrap();
+        assert!(iface.sent().is_empty());
+    }
+
+    #[test]
+    fn auto_off_wakes_on_the_next_data() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+        properties.set_auto_off(Some(5000)).unwrap();
+        properties.set_draw_area((16, 0), (144, 1)).unwrap();
+        iface.clear();
+
+        properties.idle_tick(5000).unwrap();
+        assert_eq!(iface.commands(), [0xAE]);
+        assert!(properties.is_blanked() && properties.is_on());
+
+        iface.clear();
+        properties.draw(&[0xFF]).unwrap();
+        assert_eq!(iface.commands().last(), Some(&0xAF));
+        assert!(!properties.is_blanked());
+
+        // A display turned off on purpose is left alone
+        properties.display_on(false).unwrap();
+        iface.clear();
+        properties.idle_tick(5000).unwrap();
+        properties.draw(&[0xFF]).unwrap();
+        assert!(!iface.commands().contains(&0xAF));
+    }
+
+    #[test]
+    fn display_state_is_remembered() {
+        let mut properties = DisplayProperties::new(
+            CaptureInterface::new(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        assert!(!properties.is_on());
+
+        properties.init_column_mode().unwrap();
+        assert!(properties.is_on());
+        properties.display_on(false).unwrap();
+        assert!(!properties.is_on());
+        properties.wake(&mut DelayRecorder::new()).unwrap();
+        assert!(properties.is_on());
+        properties.sleep().unwrap();
+        assert!(!properties.is_on());
+
+        properties.set_invert(true).unwrap();
+        properties.set_mirror(true, false).unwrap();
+        properties.set_contrast(0x20).unwrap();
+        assert!(properties.is_inverted());
+        assert_eq!(properties.get_mirror(), (true, false));
+        assert_eq!(properties.get_contrast(), 0x20);
+    }
+
+    #[test]
+    fn clock_divider_changes_at_runtime() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+
+        iface.clear();
+        properties.apply_clock_divider(0x2, 0x1).unwrap();
+        assert_eq!(iface.commands(), [0xD5, 0x21]);
+
+        // Kept for the next init
+        iface.clear();
+        properties.init_column_mode().unwrap();
+        assert_eq!(iface.commands()[..2], [0xD5, 0x21]);
+    }
+
+    #[test]
+    fn raw_commands_and_data_pass_through() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+        properties.set_draw_area((0, 0), (128, 20)).unwrap();
+        properties.draw(&[0x01]).unwrap();
+
+        iface.clear();
+        properties.send_command(Command::Contrast(0x10)).unwrap();
+        properties.send_raw_command(&[0xE3, 0xE3]).unwrap();
+        properties.send_raw_data(&[0xAA, 0x55]).unwrap();
+        assert_eq!(iface.commands(), [0x81, 0x10, 0xE3, 0xE3]);
+        assert_eq!(iface.data(), [0xAA, 0x55]);
+
+        // The next draw sends the RAM address again
+        iface.clear();
+        properties.draw(&[0x02]).unwrap();
+        assert_eq!(iface.commands(), [0xB0, 0x00, 0x01, 0x10]);
+    }
+
+    #[test]
+    fn precharge_and_vcom_change_at_runtime() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+
+        iface.clear();
+        properties.apply_precharge(0x3, 0x4).unwrap();
+        properties.apply_vcom_level(0x40).unwrap();
+        assert_eq!(iface.commands(), [0xD9, 0x43, 0xDB, 0x40]);
+
+        // Kept for the next init
+        iface.clear();
+        properties.init_column_mode().unwrap();
+        let commands = iface.commands();
+        assert!(commands.windows(2).any(|w| w == [0xD9, 0x43]));
+        assert!(commands.windows(2).any(|w| w == [0xDB, 0x40]));
+    }
+
+    #[test]
+    fn reinit_restores_contrast_and_inversion() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.init_column_mode().unwrap();
+        properties.set_contrast(0x30).unwrap();
+        properties.set_invert(true).unwrap();
+        properties.set_draw_area((16, 0), (144, 20)).unwrap();
+
+        iface.clear();
+        properties.reinit().unwrap();
+        let commands = iface.commands();
+        assert_eq!(commands[..2], [0xD5, 0x60]);
+        assert!(commands.ends_with(&[0xAF, 0x81, 0x30, 0xA7]));
+
+        // The controller forgot the RAM address, so it is sent again before drawing
+        iface.clear();
+        properties.draw(&[0xFF]).unwrap();
+        assert_eq!(iface.commands(), [0xB0, 0x00, 0x00, 0x11]);
+    }
+
+    #[test]
+    fn rotated_draw_areas_are_transposed() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate90,
+        );
+
+        assert!(matches!(
+            properties.set_draw_area((4, 0), (16, 1)),
+            Err(DisplayError::OutOfBoundsError)
+        ));
+
+        // Two blocks of 8 rotated columns on rotated page 3, the first with its top row lit and
+        // the second with its left column lit
+        properties.set_draw_area((8, 3), (24, 4)).unwrap();
+        assert!(iface.sent().is_empty());
+        properties.draw(&[0x01; 3]).unwrap();
+        properties.draw(&[0x01; 5]).unwrap();
+        properties.draw(&[0xFF, 0, 0, 0, 0, 0, 0, 0]).unwrap();
+
+        assert_eq!(
+            iface.sent(),
+            [
+                Sent::Commands(vec![0xB0, 0x01, 0x08, 0x11]),
+                Sent::Data(vec![0xFF, 0, 0, 0, 0, 0, 0, 0]),
+                Sent::Commands(vec![0xB0, 0x02, 0x08, 0x11]),
+                Sent::Data(vec![0x01; 8]),
+            ]
+        );
+
+        // Unrotated areas are sent as they are again
+        properties.set_rotation(DisplayRotation::Rotate0).unwrap();
+        iface.clear();
+        properties.set_draw_area((16, 0), (18, 1)).unwrap();
+        properties.draw(&[0x0F, 0xF0]).unwrap();
+        assert_eq!(iface.data(), [0x0F, 0xF0]);
+    }
+
+    #[test]
+    fn charge_pump_setting_is_sent_on_init_and_wake() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut delay = DelayRecorder::new();
+
+        // The default is the power-on value of the DC-DC setting
+        properties.init_column_mode().unwrap();
+        assert!(iface.commands().ends_with(&[0xAD, 0x81, 0xAF]));
+
+        iface.clear();
+        properties.set_charge_pump(ChargePump::Internal(6));
+        properties.init_column_mode().unwrap();
+        assert!(iface.commands().ends_with(&[0xAD, 0x8D, 0xAF]));
+
+        iface.clear();
+        properties.set_charge_pump(ChargePump::External);
+        properties.init_column_mode().unwrap();
+        assert!(iface.commands().ends_with(&[0xAD, 0x80, 0xAF]));
+
+        iface.clear();
+        properties.sleep().unwrap();
+        properties.wake(&mut delay).unwrap();
+        assert_eq!(iface.commands(), [0xAE, 0xAD, 0x80, 0xAD, 0x80, 0xAF]);
+    }
+
+    #[test]
+    fn set_invert_sends_reverse_display() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.set_invert(true).unwrap();
+        properties.set_invert(false).unwrap();
+
+        assert_eq!(iface.commands(), [0xA7, 0xA6]);
+    }
+
+    #[test]
+    fn ram_offset_shifts_addresses() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.set_ram_offset(4, 2);
+
+        properties.set_draw_area((16, 0), (18, 2)).unwrap();
+        properties.draw(&[1, 2]).unwrap();
+
+        assert_eq!(
+            iface.sent(),
+            [
+                Sent::Commands(vec![0xB0, 0x02, 0x04, 0x11]),
+                Sent::Data(vec![1, 2]),
+                Sent::Commands(vec![0xB0, 0x03, 0x04, 0x11]),
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_resyncs_after_failed_transfer() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.set_draw_area((16, 0), (20, 2)).unwrap();
+        properties.draw(&[1, 2, 3, 4]).unwrap();
+
+        iface.set_fail_data(true);
+        assert!(properties.draw(&[5, 6, 7, 8]).is_err());
+        assert_eq!(properties.get_cursor_raw(), (16, 1));
+
+        iface.set_fail_data(false);
+        iface.clear();
+        properties.draw(&[5, 6, 7, 8]).unwrap();
+
+        assert_eq!(
+            iface.sent(),
+            [
+                Sent::Commands(vec![0xB0, 0x01, 0x00, 0x11]),
+                Sent::Data(vec![5, 6, 7, 8]),
+                Sent::Commands(vec![0xB0, 0x00, 0x00, 0x11]),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "graphics")]
+    fn visible_bounds_follow_rotation() {
+        let mut properties = DisplayProperties::new(
+            CaptureInterface::new(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        assert_eq!(
+            properties.visible_bounds(),
+            Rectangle::new(Point::zero(), Size::new(128, 160))
+        );
+
+        properties.set_rotation(DisplayRotation::Rotate90).unwrap();
+        assert_eq!(
+            properties.visible_bounds(),
+            Rectangle::new(Point::zero(), Size::new(160, 128))
+        );
+    }
+
+    #[test]
+    fn draw_after_reset_starts_at_origin() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.set_draw_area((32, 2), (34, 4)).unwrap();
+        properties.draw(&[1, 2]).unwrap();
+        assert_eq!(properties.get_cursor_raw(), (32, 3));
+
+        properties.reset_draw_pointer().unwrap();
+        assert_eq!(properties.get_cursor_raw(), (32, 2));
+
+        iface.clear();
+        properties.draw(&[3, 4]).unwrap();
+        assert_eq!(iface.sent()[0], Sent::Data(vec![3, 4]));
+        assert_eq!(properties.get_cursor_raw(), (32, 3));
+    }
+
+    #[test]
+    fn default_capabilities() {
+        let properties = DisplayProperties::new(
+            CaptureInterface::new(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        assert_eq!(
+            properties.capabilities(),
+            Capabilities {
+                width: 128,
+                height: 160,
+                pages: 20,
+                contrast_steps: 256,
+                hardware_scroll: false,
+                partial_update: true,
+                charge_pump: true,
+                hardware_invert: true,
+            }
+        );
+    }
+
+    #[test]
+    fn transfer_estimates() {
+        let mut properties = DisplayProperties::new(
+            CaptureInterface::new(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        assert_eq!(
+            properties.estimated_transfer_bytes((0, 0), (128, 160)),
+            20 * (128 + 4)
+        );
+        assert_eq!(properties.estimated_transfer_bytes((10, 8), (20, 16)), 14);
+        assert_eq!(properties.estimated_transfer_bytes((10, 8), (10, 16)), 0);
+        assert_eq!(properties.estimated_transfer_bytes((120, 0), (200, 1)), 12);
+
+        properties.set_bus_clock_hint(100_000);
+        assert_eq!(properties.estimated_transfer_us((10, 8), (20, 16)), 1260);
+
+        properties.set_rotation(DisplayRotation::Rotate90).unwrap();
+        assert_eq!(properties.estimated_transfer_bytes((8, 10), (16, 20)), 14);
+    }
+
+    #[test]
+    fn contrast_sweep_restores_contrast() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut delay = DelayRecorder::new();
+
+        properties.set_contrast(0x42).unwrap();
+        iface.clear();
+        properties.contrast_sweep(&mut delay).unwrap();
+
+        let commands = iface.commands();
+        assert_eq!(commands[..3], [0xA5, 0x81, 0x00]);
+        assert_eq!(commands[2 * 0xFF + 1..][..2], [0x81, 0xFF]);
+        assert_eq!(
+            commands[commands.len() - 5..],
+            [0x81, 0x00, 0x81, 0x42, 0xA4]
+        );
+        assert_eq!(delay.delays().len(), 2 * 0xFF + 1);
+        assert_eq!(properties.get_contrast(), 0x42);
+    }
+
+    #[test]
+    fn test_patterns() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.test_pattern(TestPattern::AllOn).unwrap();
+        assert_eq!(iface.commands(), [0xA5]);
+        assert!(iface.data().is_empty());
+        properties.end_test_pattern().unwrap();
+        assert_eq!(iface.commands(), [0xA5, 0xA4]);
+
+        iface.clear();
+        properties.test_pattern(TestPattern::Checkerboard).unwrap();
+        assert_eq!(iface.commands()[0], 0xA4);
+        let data = iface.data();
+        assert_eq!(data.len(), 128 * 20);
+        assert_eq!(data[..4], [0x55, 0xAA, 0x55, 0xAA]);
+
+        iface.clear();
+        properties.test_pattern(TestPattern::VerticalBars).unwrap();
+        let data = iface.data();
+        assert_eq!(data.len(), 128 * 20);
+        assert_eq!(data[7..9], [0xFF, 0x00]);
+        assert_eq!(data[15..17], [0x00, 0xFF]);
+    }
+
+    #[test]
+    fn splash_is_decoded_into_ram_before_display_on() {
+        let iface = RamInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        // Three pixels on in the first row, then one at the start of row 9 after 1152 off
+        properties
+            .show_splash(&[0, 3, 255, 0, 255, 0, 255, 0, 255, 0, 129, 1])
+            .unwrap();
+
+        assert!(properties.is_initialized() && properties.is_on());
+        assert_eq!(iface.page(0)[16..20], [0x01, 0x01, 0x01, 0x00]);
+        assert_eq!(iface.page(1)[16..18], [0x02, 0x00]);
+
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.show_splash(&[]).unwrap();
+        let sent = iface.sent();
+        let pages = sent[..sent.len() - 1]
+            .iter()
+            .filter(|sent| matches!(sent, Sent::Data(data) if data.len() == 128))
+            .count();
+        assert_eq!(pages, 20);
+        assert!(matches!(sent.last(), Some(Sent::Commands(commands)) if commands == &[0xAF]));
+    }
+
+    #[test]
+    fn flash_contrast_restores_contrast() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut delay = DelayRecorder::new();
+
+        properties.set_contrast(0x30).unwrap();
+        iface.clear();
+        properties.flash_contrast(0x50, 200, &mut delay).unwrap();
+        properties.flash_contrast(0xF0, 50, &mut delay).unwrap();
+
+        assert_eq!(
+            iface.commands(),
+            [0x81, 0x80, 0x81, 0x30, 0x81, 0xFF, 0x81, 0x30]
+        );
+        assert_eq!(delay.delays(), [200, 50]);
+        assert_eq!(properties.get_contrast(), 0x30);
+    }
+
+    #[test]
+    fn heartbeat_saturates() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.set_contrast(0xF0).unwrap();
+        iface.clear();
+        properties
+            .heartbeat(120, &mut DelayRecorder::new())
+            .unwrap();
+
+        assert_eq!(
+            iface.commands(),
+            [0x81, 0xFF, 0x81, 0xF0, 0x81, 0xF7, 0x81, 0xF0]
+        );
+    }
+
+    #[test]
+    fn brightness_sets_contrast_and_precharge() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+
+        properties.set_brightness(Brightness::Bright).unwrap();
+        assert_eq!(iface.commands(), [0xD9, 0x2C, 0x81, 0xC0]);
+        assert_eq!(properties.get_contrast(), 0xC0);
+
+        // The precharge sticks across a re-init
+        iface.clear();
+        properties.init_column_mode().unwrap();
+        assert!(iface.commands().windows(2).any(|c| c == [0xD9, 0x2C]));
+    }
+
+    #[test]
+    fn dim_switches_contrast_and_precharge() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        properties.set_contrast(0xC0).unwrap();
+        properties.set_dim_level(0x10, 0x2, 0x2).unwrap();
+
+        iface.clear();
+        properties.dim(true).unwrap();
+        assert_eq!(iface.commands(), [0xD9, 0x22, 0x81, 0x10]);
+        assert!(properties.is_dim());
+
+        // The normal contrast changes underneath, the dimmed one stays
+        iface.clear();
+        properties.set_contrast(0xFF).unwrap();
+        assert_eq!(iface.commands(), [0x81, 0x10]);
+
+        iface.clear();
+        properties.dim(false).unwrap();
+        assert_eq!(iface.commands(), [0xD9, 0x28, 0x81, 0xFF]);
+    }
+
+    #[test]
+    fn fades_step_the_contrast() {
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        let mut delay = DelayRecorder::new();
+
+        properties.fade_out(35, &mut delay).unwrap();
+        assert_eq!(delay.delays(), [11, 12, 12]);
+        assert_eq!(iface.commands(), [0x81, 0x56, 0x81, 0x2B, 0x81, 0x00]);
+        assert_eq!(properties.get_contrast(), 0x80);
+
+        iface.clear();
+        let mut delay = DelayRecorder::new();
+        properties.fade_in(5, &mut delay).unwrap();
+        assert_eq!(delay.total_ms(), 5);
+        assert_eq!(iface.commands(), [0x81, 0x80]);
+    }
+
+    #[test]
+    fn pixel_shift_visits_every_offset() {
+        let mut properties = DisplayProperties::new(
+            CaptureInterface::new(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        assert_eq!(properties.advance_pixel_shift(), (0, 0));
+
+        properties.set_pixel_shift_range(1);
+        let path: vec::Vec<_> = (0..10).map(|_| properties.advance_pixel_shift()).collect();
+        assert_eq!(
+            path,
+            [
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (0, 0),
+                (-1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+                (-1, -1),
+            ]
+        );
+    }
+
+    #[test]
+    fn probing_detects_a_missing_display() {
+        /// An I2C bus on which no device acknowledges
+        struct Unanswered;
+
+        impl WriteOnlyDataCommand for Unanswered {
+            fn send_commands(&mut self, _cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+                Err(DisplayError::BusWriteError)
+            }
+            fn send_data(&mut self, _buf: DataFormat<'_>) -> Result<(), DisplayError> {
+                Err(DisplayError::BusWriteError)
+            }
+        }
+
+        let iface = CaptureInterface::new();
+        let mut properties = DisplayProperties::new(
+            iface.clone(),
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        assert!(properties.is_connected());
+        assert_eq!(iface.commands(), [0xE3]);
+
+        let mut properties = DisplayProperties::new(
+            Unanswered,
+            DisplaySize::Display128x160,
+            DisplayRotation::Rotate0,
+        );
+        assert!(matches!(
+            properties.probe(),
+            Err(DisplayError::BusWriteError)
+        ));
+        assert!(!properties.is_connected());
     }
 }
@@ -0,0 +1,78 @@
+//! Reading back the display RAM and the status register
+//!
+//! `display-interface` only covers writing to a display, but the SH1108 can also be read over
+//! its parallel and I2C interfaces. SPI is write only. Interfaces that can read implement
+//! [`ReadInterface`] on top of `WriteOnlyDataCommand`. The crate ships one for I2C,
+//! `i2c_interface::I2CReadInterface` with the `i2c` feature. The other interfaces only write, so
+//! a parallel bus needs an implementation of its own that can turn the data pins around.
+//!
+//! An interface that can read enables
+//! [`DisplayProperties::read_status`](crate::properties::DisplayProperties::read_status),
+//! [`DisplayProperties::read_ram`](crate::properties::DisplayProperties::read_ram) and
+//! [`DisplayProperties::modify_ram`](crate::properties::DisplayProperties::modify_ram), which
+//! use the read-modify-write mode of the controller to change display RAM in place, e.g. for
+//! unbuffered pixel updates with
+//! [`DisplayProperties::set_pixel_direct`](crate::properties::DisplayProperties::set_pixel_direct)
+//! and [`DirectMode::modify_pixel`](crate::mode::DirectMode::modify_pixel), and checking that a
+//! flush arrived intact with [`GraphicsMode::verify`](crate::mode::GraphicsMode::verify).
+//!
+//! With the `test-util` feature, `test_helpers::RamInterface` simulates a readable display for
+//! tests.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+
+/// A display interface that can also read from the display
+pub trait ReadInterface: WriteOnlyDataCommand {
+    /// Read the status register, i.e. a read with the A0 (D/C) line low
+    fn read_status(&mut self) -> Result<u8, DisplayError>;
+
+    /// Read `buf.len()` bytes of display data, i.e. reads with the A0 (D/C) line high. The
+    /// controller returns a dummy byte on the first read after the RAM address is set, which the
+    /// driver reads and discards itself, so implementations pass every byte on unchanged.
+    ///
+    /// Implementations without a dedicated error return `DisplayError::BusWriteError` for
+    /// failed reads.
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError>;
+}
+
+/// Contents of the SH1108 status register, see
+/// [`DisplayProperties::read_status`](crate::properties::DisplayProperties::read_status)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status(u8);
+
+impl Status {
+    /// Interpret a raw status register value
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    /// Get the raw status register value
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Whether the controller is busy with an internal operation or a reset and rejects commands
+    pub fn is_busy(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
+    /// Whether the display is turned on. The status bit is the opposite of the Display ON/OFF
+    /// command, low while the display is on.
+    pub fn is_display_on(&self) -> bool {
+        self.0 & 0x40 == 0
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+impl ReadInterface for crate::test_helpers::RamInterface {
+    fn read_status(&mut self) -> Result<u8, DisplayError> {
+        Ok(self.status())
+    }
+
+    fn read_data(&mut self, buf: &mut [u8]) -> Result<(), DisplayError> {
+        self.read(buf);
+
+        Ok(())
+    }
+}
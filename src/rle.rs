@@ -0,0 +1,65 @@
+//! Run-length encoded 1bpp images
+//!
+//! Images with large solid areas, like most UI graphics, compress very well by storing only how
+//! long each run of identical pixels is. The encoding used here is deliberately simple:
+//!
+//! * Pixels are walked row by row, left to right, top to bottom. Runs carry on from the end of
+//!   one row to the start of the next.
+//! * Every byte is the length of a run of 0 to 255 pixels. Runs alternate between off and on,
+//!   starting with off.
+//! * A zero length run switches colour without drawing anything. It encodes images starting with
+//!   an on pixel (`0, n, ...`) and runs longer than 255 pixels (`255, 0, 45` is 300 off pixels).
+//!
+//! For example, this 8x2 image:
+//!
+//! ```text
+//! ..####..
+//! ##....##
+//! ```
+//!
+//! is encoded as `[2, 4, 2, 2, 4, 2]`.
+//!
+//! ```rust
+//! use sh1108::rle::RleDecoder;
+//!
+//! let pixels: Vec<bool> = RleDecoder::new(&[2, 4, 2, 2, 4, 2]).collect();
+//! assert_eq!(pixels.len(), 16);
+//! assert!(!pixels[1] && pixels[2] && pixels[8]);
+//! ```
+
+/// Iterator decoding run-length encoded data into pixel values, `true` being on
+#[derive(Clone, Debug)]
+pub struct RleDecoder<'a> {
+    runs: core::slice::Iter<'a, u8>,
+    remaining: u8,
+    on: bool,
+}
+
+impl<'a> RleDecoder<'a> {
+    /// Create a decoder over run-length encoded data
+    pub fn new(data: &'a [u8]) -> Self {
+        let mut runs = data.iter();
+        let remaining = runs.next().copied().unwrap_or(0);
+
+        Self {
+            runs,
+            remaining,
+            on: false,
+        }
+    }
+}
+
+impl Iterator for RleDecoder<'_> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        while self.remaining == 0 {
+            self.remaining = *self.runs.next()?;
+            self.on = !self.on;
+        }
+
+        self.remaining -= 1;
+
+        Some(self.on)
+    }
+}
@@ -0,0 +1,157 @@
+//! Sharing one display between tasks, behind the `shared` feature
+//!
+//! A [`SharedDisplay`] puts a display behind an async
+//! [`embassy_sync::mutex::Mutex`], so several embassy tasks, e.g. a status bar, the main UI and
+//! alert popups, can draw on it and flush it without stepping on each other:
+//!
+//! ```rust,ignore
+//! type Display = SharedDisplay<CriticalSectionRawMutex, GraphicsMode<Interface>>;
+//!
+//! #[embassy_executor::task]
+//! async fn status_bar(display: &'static Display) {
+//!     loop {
+//!         display.lock().await.clear_region(STATUS_BAR);
+//!         display.flush().await.unwrap();
+//!         Timer::after_secs(1).await;
+//!     }
+//! }
+//! ```
+//!
+//! Every task draws through a [`DrawGuard`], which gives access to the display until it is
+//! dropped, and the other tasks wait for it in `lock`. A flush takes the lock as well, so it
+//! never sends a frame another task is halfway through drawing, and `flush_dirty` sends whatever
+//! all tasks changed since the last one. The raw mutex `M` picks what the lock is safe against,
+//! e.g. `CriticalSectionRawMutex` for tasks on different executors or interrupt priorities, or
+//! `NoopRawMutex` for tasks of one executor.
+
+use core::ops::{Deref, DerefMut};
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use embassy_sync::{
+    blocking_mutex::raw::RawMutex,
+    mutex::{Mutex, MutexGuard},
+};
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::{storage::FrameBufferStorage, FlushStats, GraphicsMode},
+};
+
+/// A display shared between tasks, see the [module documentation](self)
+pub struct SharedDisplay<M, D>
+where
+    M: RawMutex,
+{
+    display: Mutex<M, D>,
+}
+
+impl<M, D> SharedDisplay<M, D>
+where
+    M: RawMutex,
+{
+    /// Share `display`, usually already initialised
+    pub const fn new(display: D) -> Self {
+        Self {
+            display: Mutex::new(display),
+        }
+    }
+
+    /// Wait until no other task uses the display and get access to it
+    pub async fn lock(&self) -> DrawGuard<'_, M, D> {
+        DrawGuard(self.display.lock().await)
+    }
+
+    /// Get access to the display if no other task uses it right now
+    pub fn try_lock(&self) -> Option<DrawGuard<'_, M, D>> {
+        self.display.try_lock().ok().map(DrawGuard)
+    }
+
+    /// Stop sharing the display and get it back
+    pub fn into_inner(self) -> D {
+        self.display.into_inner()
+    }
+}
+
+impl<M, DI, const N: usize, R, S> SharedDisplay<M, GraphicsMode<DI, N, R, S>>
+where
+    M: RawMutex,
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    /// Wait for the display and send what any task changed since the last flush, see
+    /// `GraphicsMode::flush_dirty`
+    pub async fn flush(&self) -> Result<FlushStats, DisplayError> {
+        self.lock().await.flush_dirty()
+    }
+}
+
+/// Access to a [`SharedDisplay`] for one task, until it is dropped
+pub struct DrawGuard<'a, M, D>(MutexGuard<'a, M, D>)
+where
+    M: RawMutex;
+
+impl<M, D> Deref for DrawGuard<'_, M, D>
+where
+    M: RawMutex,
+{
+    type Target = D;
+
+    fn deref(&self) -> &D {
+        &self.0
+    }
+}
+
+impl<M, D> DerefMut for DrawGuard<'_, M, D>
+where
+    M: RawMutex,
+{
+    fn deref_mut(&mut self) -> &mut D {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{
+        future::Future,
+        pin::pin,
+        task::{Context, Poll, Waker},
+    };
+
+    use embassy_sync::blocking_mutex::raw::NoopRawMutex;
+
+    use super::*;
+    use crate::{test_helpers::CaptureInterface, Builder};
+
+    /// Poll `future` once, the mutex never has to wait in these tests
+    fn poll_once<F: Future>(future: F) -> Poll<F::Output> {
+        pin!(future).poll(&mut Context::from_waker(Waker::noop()))
+    }
+
+    #[test]
+    fn tasks_take_turns() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.flush().unwrap();
+        let shared = SharedDisplay::<NoopRawMutex, _>::new(display);
+
+        let Poll::Ready(mut guard) = poll_once(shared.lock()) else {
+            panic!("display locked");
+        };
+        guard.set_pixel(4, 0, 1);
+        assert!(shared.try_lock().is_none());
+        assert!(poll_once(shared.flush()).is_pending());
+        drop(guard);
+
+        shared.try_lock().unwrap().set_pixel(5, 0, 1);
+        iface.clear();
+        let Poll::Ready(stats) = poll_once(shared.flush()) else {
+            panic!("display locked");
+        };
+        assert_eq!(stats.unwrap().bytes, 2);
+        assert_eq!(iface.data(), [0x01, 0x01]);
+        assert!(shared.into_inner().get_pixel(4, 0));
+    }
+}
@@ -0,0 +1,315 @@
+//! Desktop simulator backend, behind the `simulator` feature
+//!
+//! [`SimulatorInterface`] stands in for the display interface on a PC. It decodes the commands
+//! the driver sends into a model of the SH1108: the 160 columns by 20 pages of display RAM, the
+//! RAM address, the segment remap and common scan direction, the display resolution and the
+//! display on, entire display on and inverse display commands. [`SimulatorInterface::render`]
+//! draws what the panel shows into an
+//! [embedded-graphics-simulator](https://docs.rs/embedded-graphics-simulator)
+//! `SimulatorDisplay`, so a UI layout can be checked on a laptop before it is flashed. As the
+//! image goes through the same page memory and scan directions as on hardware, so do rotation
+//! and mirroring mistakes.
+//!
+//! The dependency is built without its SDL window. To open one, enable the `with-sdl` feature of
+//! `embedded-graphics-simulator` in the application:
+//!
+//! ```rust,ignore
+//! use embedded_graphics_simulator::{BinaryColorTheme, OutputSettingsBuilder, Window};
+//! use sh1108::{simulator::SimulatorInterface, prelude::*, Builder};
+//!
+//! let iface = SimulatorInterface::new();
+//! let mut display: GraphicsMode<_> = Builder::new()
+//!     .with_rotation(DisplayRotation::Rotate90)
+//!     .connect(iface.clone())
+//!     .into();
+//! display.init().unwrap();
+//!
+//! // Draw the UI and flush it like on the device
+//! display.flush().unwrap();
+//!
+//! let settings = OutputSettingsBuilder::new().theme(BinaryColorTheme::OledWhite).build();
+//! Window::new("SH1108", &settings).show_static(&iface.render(DisplayRotation::Rotate90));
+//! ```
+
+use std::{cell::RefCell, rc::Rc};
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+use embedded_graphics_core::{
+    draw_target::DrawTarget, geometry::Point, geometry::Size, pixelcolor::BinaryColor, Pixel,
+};
+use embedded_graphics_simulator::SimulatorDisplay;
+
+use crate::{displayrotation::DisplayRotation, displaysize::DisplaySize};
+
+/// Rows of the panel, the number of common outputs
+const PANEL_ROWS: u32 = 160;
+
+/// Display interface that simulates an SH1108 in memory, see the [module documentation](self).
+/// Clones share the same display, so a handle can be kept to render it after moving the
+/// interface into the driver.
+#[derive(Clone, Debug, Default)]
+pub struct SimulatorInterface {
+    state: Rc<RefCell<Controller>>,
+}
+
+/// Model of the controller state that affects the image
+#[derive(Debug)]
+struct Controller {
+    ram: [[u8; 160]; 20],
+    page: u8,
+    column: u8,
+    vertical_addressing: bool,
+    resolution: DisplaySize,
+    segment_remap: bool,
+    reverse_scan: bool,
+    display_on: bool,
+    all_on: bool,
+    inverted: bool,
+}
+
+impl Default for Controller {
+    /// The power on reset state, with the display off
+    fn default() -> Self {
+        Self {
+            ram: [[0; 160]; 20],
+            page: 0,
+            column: 0,
+            vertical_addressing: false,
+            resolution: DisplaySize::Display160x160,
+            segment_remap: false,
+            reverse_scan: false,
+            display_on: false,
+            all_on: false,
+            inverted: false,
+        }
+    }
+}
+
+impl Controller {
+    fn command(&mut self, byte: u8, argument: &mut dyn Iterator<Item = u8>) {
+        match byte {
+            0x00..=0x0F => self.column = (self.column & 0xF0) | byte,
+            0x10..=0x1F => self.column = (self.column & 0x0F) | ((byte & 0xF) << 4),
+            0x20 | 0x21 => self.vertical_addressing = byte == 0x21,
+            0xA0 | 0xA1 => self.segment_remap = byte == 0xA1,
+            0xA4 | 0xA5 => self.all_on = byte == 0xA5,
+            0xA6 | 0xA7 => self.inverted = byte == 0xA7,
+            0xAE | 0xAF => self.display_on = byte == 0xAF,
+            0xC0..=0xCF => self.reverse_scan = byte & 0x08 != 0,
+            0xB0 => self.page = argument.next().unwrap_or(0) % 20,
+            0xA9 => {
+                self.resolution = match argument.next().unwrap_or(0) & 0x3 {
+                    0x0 => DisplaySize::Display64x160,
+                    0x1 => DisplaySize::Display96x160,
+                    0x2 => DisplaySize::Display128x160,
+                    _ => DisplaySize::Display160x160,
+                };
+            }
+            // The argument of the other two byte commands does not change the image
+            0x81 | 0xAD | 0xD5 | 0xD9 | 0xDB | 0xDC => {
+                argument.next();
+            }
+            _ => {}
+        }
+    }
+
+    fn data(&mut self, byte: u8) {
+        // Column addresses past the RAM store nothing and stay where they are
+        let Some(cell) = self.ram[usize::from(self.page)].get_mut(usize::from(self.column)) else {
+            return;
+        };
+        *cell = byte;
+
+        if self.vertical_addressing {
+            self.page += 1;
+            if self.page == 20 {
+                self.page = 0;
+                self.column = (self.column + 1) % 160;
+            }
+        } else {
+            self.column = (self.column + 1) % 160;
+        }
+    }
+
+    /// Whether the panel lights the pixel of `segment` and `common`, before any rotation
+    fn panel_pixel(&self, segment: u32, common: u32) -> bool {
+        let (width, _) = self.resolution.dimensions();
+        let width = u32::from(width);
+
+        let column = if self.segment_remap {
+            width - 1 - segment
+        } else {
+            segment
+        } + u32::from(self.resolution.column_offset());
+        let row = if self.reverse_scan {
+            PANEL_ROWS - 1 - common
+        } else {
+            common
+        };
+
+        let lit = self.ram[row as usize / 8][column as usize] & (1 << (row % 8)) != 0;
+        self.display_on && (self.all_on || lit != self.inverted)
+    }
+}
+
+/// Pass the bytes of `format` to `f`. The driver only sends bytes, so other formats are
+/// rejected with `DisplayError::DataFormatNotImplemented`.
+fn bytes(
+    format: DataFormat<'_>,
+    mut f: impl FnMut(&mut dyn Iterator<Item = u8>),
+) -> Result<(), DisplayError> {
+    match format {
+        DataFormat::U8(buf) => f(&mut buf.iter().copied()),
+        DataFormat::U8Iter(iter) => f(iter),
+        _ => return Err(DisplayError::DataFormatNotImplemented),
+    }
+
+    Ok(())
+}
+
+impl SimulatorInterface {
+    /// Create a simulated display in its power on state, with cleared RAM and the display off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw what the panel shows into a `SimulatorDisplay`, as seen with the panel mounted for
+    /// `rotation`, i.e. the rotation the driver was set up with. The image is then upright, and
+    /// as wide and high as the driver reports with `get_dimensions`.
+    pub fn render(&self, rotation: DisplayRotation) -> SimulatorDisplay<BinaryColor> {
+        let state = self.state.borrow();
+        let state = &*state;
+        let (width, _) = state.resolution.dimensions();
+        let width = u32::from(width);
+
+        let size = match rotation {
+            DisplayRotation::Rotate0 | DisplayRotation::Rotate180 => Size::new(width, PANEL_ROWS),
+            DisplayRotation::Rotate90 | DisplayRotation::Rotate270 => Size::new(PANEL_ROWS, width),
+        };
+        let mut display = SimulatorDisplay::new(size);
+
+        let pixels = (0..PANEL_ROWS).flat_map(|common| {
+            (0..width).map(move |segment| {
+                let (x, y) = match rotation {
+                    DisplayRotation::Rotate0 => (segment, common),
+                    DisplayRotation::Rotate90 => (PANEL_ROWS - 1 - common, segment),
+                    DisplayRotation::Rotate180 => (width - 1 - segment, PANEL_ROWS - 1 - common),
+                    DisplayRotation::Rotate270 => (common, width - 1 - segment),
+                };
+
+                Pixel(
+                    Point::new(x as i32, y as i32),
+                    state.panel_pixel(segment, common).into(),
+                )
+            })
+        });
+        display.draw_iter(pixels).unwrap_or_else(|e| match e {});
+
+        display
+    }
+}
+
+impl WriteOnlyDataCommand for SimulatorInterface {
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut state = self.state.borrow_mut();
+
+        bytes(cmd, |bytes| {
+            while let Some(byte) = bytes.next() {
+                state.command(byte, bytes);
+            }
+        })
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut state = self.state.borrow_mut();
+
+        bytes(buf, |bytes| bytes.for_each(|byte| state.data(byte)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mode::GraphicsMode, Builder};
+    use embedded_graphics_core::geometry::OriginDimensions;
+
+    #[test]
+    fn renders_upright_in_every_rotation() {
+        for rotation in [
+            DisplayRotation::Rotate0,
+            DisplayRotation::Rotate90,
+            DisplayRotation::Rotate180,
+            DisplayRotation::Rotate270,
+        ] {
+            let iface = SimulatorInterface::new();
+            let mut display: GraphicsMode<_> = Builder::new()
+                .with_rotation(rotation)
+                .connect(iface.clone())
+                .into();
+            display.init().unwrap();
+            display.set_pixel(10, 3, 1);
+            display.set_pixel(0, 120, 1);
+            display.flush().unwrap();
+
+            let rendered = iface.render(rotation);
+            let (width, height) = display.get_dimensions();
+            assert_eq!(
+                rendered.size(),
+                Size::new(width.into(), height.into()),
+                "{:?}",
+                rotation
+            );
+
+            let lit: std::vec::Vec<_> = (0..height as i32)
+                .flat_map(|y| (0..width as i32).map(move |x| Point::new(x, y)))
+                .filter(|&point| rendered.get_pixel(point).is_on())
+                .collect();
+            assert_eq!(
+                lit,
+                [Point::new(10, 3), Point::new(0, 120)],
+                "{:?}",
+                rotation
+            );
+        }
+    }
+
+    #[test]
+    fn display_modes_change_the_image() {
+        let iface = SimulatorInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        let lit = |iface: &SimulatorInterface| {
+            let rendered = iface.render(DisplayRotation::Rotate0);
+            (0..160)
+                .flat_map(|y| (0..128).map(move |x| Point::new(x, y)))
+                .filter(|&point| rendered.get_pixel(point).is_on())
+                .count()
+        };
+
+        assert_eq!(lit(&iface), 0);
+        display.init().unwrap();
+        display.set_pixel(5, 5, 1);
+        display.flush().unwrap();
+        assert_eq!(lit(&iface), 1);
+
+        display.set_invert(true).unwrap();
+        assert_eq!(lit(&iface), 128 * 160 - 1);
+        display.display_on(false).unwrap();
+        assert_eq!(lit(&iface), 0);
+    }
+
+    #[test]
+    fn unusual_input_is_rejected_or_ignored() {
+        let mut iface = SimulatorInterface::new();
+
+        assert!(matches!(
+            iface.send_data(DataFormat::U16(&[0xFFFF])),
+            Err(DisplayError::DataFormatNotImplemented)
+        ));
+
+        // Column 0xA0 is one past the RAM
+        iface.send_commands(DataFormat::U8(&[0x1A, 0x00])).unwrap();
+        iface.send_data(DataFormat::U8(&[0xFF; 4])).unwrap();
+        let state = iface.state.borrow();
+        assert!(state.ram.iter().flatten().all(|&byte| byte == 0));
+    }
+}
@@ -0,0 +1,210 @@
+//! Rendering [Slint](https://slint.dev) UIs with its software renderer, behind the `slint` feature
+//!
+//! [`LineBufferAdapter`] implements Slint's `LineBufferProvider` on top of a
+//! [`GraphicsMode`], so the renderer can draw straight into its framebuffer:
+//!
+//! ```rust,ignore
+//! use slint::platform::software_renderer::{MinimalSoftwareWindow, RepaintBufferType};
+//! use sh1108::slint::{Dither, LineBufferAdapter};
+//!
+//! let window = MinimalSoftwareWindow::new(RepaintBufferType::ReusedBuffer);
+//! window.set_size(slint::PhysicalSize::new(128, 160));
+//!
+//! loop {
+//!     slint::platform::update_timers_and_animations();
+//!     window.draw_if_needed(|renderer| {
+//!         let mut lines = LineBufferAdapter::new(&mut display, Dither::Ordered);
+//!         renderer.render_by_line(&mut lines);
+//!         lines.finish().unwrap();
+//!     });
+//! }
+//! ```
+//!
+//! Slint renders one line of colour pixels at a time into a small buffer, which the adapter
+//! turns into on and off pixels by their brightness, with a fixed threshold or an ordered
+//! dither for grey areas and images. Every band of 8 rows is flushed with
+//! [`GraphicsMode::flush_dirty`] as soon as Slint moves on to the next one, so the transfer
+//! overlaps with rendering.
+//!
+//! Slint only renders the parts of the window that changed, and the framebuffer keeps the
+//! rest, so the window should be created with `RepaintBufferType::ReusedBuffer` and the size of
+//! the display in its current rotation.
+
+use display_interface::{DisplayError, WriteOnlyDataCommand};
+use slint::platform::software_renderer::{LineBufferProvider, Rgb565Pixel};
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::{storage::FrameBufferStorage, GraphicsMode},
+};
+
+/// How [`LineBufferAdapter`] turns the brightness of a pixel into on or off
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Dither {
+    /// Pixels at least as bright as the given level, out of 255, are on. Keeps text and lines
+    /// crisp.
+    Threshold(u8),
+    /// Compare every pixel against a 4x4 Bayer matrix, which renders grey as a regular pattern
+    /// of on pixels, e.g. for gradients and photos
+    Ordered,
+}
+
+/// 4x4 Bayer matrix for [`Dither::Ordered`]
+const BAYER: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+impl Dither {
+    /// Whether the pixel at `x` and `y` with brightness `luma` is on
+    fn is_on(self, x: usize, y: usize, luma: u8) -> bool {
+        match self {
+            Dither::Threshold(level) => luma >= level,
+            Dither::Ordered => luma > BAYER[y % 4][x % 4] * 16 + 8,
+        }
+    }
+}
+
+/// Brightness of a Slint pixel from 0 to 255
+fn luma(pixel: Rgb565Pixel) -> u8 {
+    let red = (pixel.0 >> 11) & 0x1F;
+    let green = (pixel.0 >> 5) & 0x3F;
+    let blue = pixel.0 & 0x1F;
+    let (red, green, blue) = (
+        u32::from(red << 3 | red >> 2),
+        u32::from(green << 2 | green >> 4),
+        u32::from(blue << 3 | blue >> 2),
+    );
+
+    ((red * 77 + green * 150 + blue * 29) >> 8) as u8
+}
+
+/// A Slint `LineBufferProvider` rendering into the framebuffer of a [`GraphicsMode`] and
+/// flushing it band by band, see the [module documentation](self)
+///
+/// `LineBufferProvider` is implemented for `&mut LineBufferAdapter`, so the adapter is still
+/// around for `finish` after `render_by_line` took it.
+pub struct LineBufferAdapter<'a, DI, const N: usize, R, S>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    display: &'a mut GraphicsMode<DI, N, R, S>,
+    dither: Dither,
+    line: [Rgb565Pixel; 160],
+    /// Band of 8 rows that was rendered to last and is not flushed yet
+    band: Option<usize>,
+    /// First error of a flush between bands
+    error: Option<DisplayError>,
+}
+
+impl<'a, DI, const N: usize, R, S> LineBufferAdapter<'a, DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    /// Render the next frame of a Slint window into `display`, packing its pixels as given by
+    /// `dither`
+    pub fn new(display: &'a mut GraphicsMode<DI, N, R, S>, dither: Dither) -> Self {
+        LineBufferAdapter {
+            display,
+            dither,
+            line: [Rgb565Pixel::default(); 160],
+            band: None,
+            error: None,
+        }
+    }
+
+    /// Flush the last band after the frame was rendered. Returns the first error of any flush
+    /// of the frame, after which the remaining bands were only drawn to the framebuffer.
+    pub fn finish(mut self) -> Result<(), DisplayError> {
+        self.flush_band();
+
+        self.error.map_or(Ok(()), Err)
+    }
+
+    /// Flush the band rendered to last, unless a flush failed before
+    fn flush_band(&mut self) {
+        if self.band.take().is_some() && self.error.is_none() {
+            if let Err(error) = self.display.flush_dirty() {
+                self.error = Some(error);
+            }
+        }
+    }
+}
+
+impl<DI, const N: usize, R, S> LineBufferProvider for &mut LineBufferAdapter<'_, DI, N, R, S>
+where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    type TargetPixel = Rgb565Pixel;
+
+    fn process_line(
+        &mut self,
+        line: usize,
+        range: core::ops::Range<usize>,
+        render_fn: impl FnOnce(&mut [Self::TargetPixel]),
+    ) {
+        let (width, height) = self.display.get_dimensions();
+        if line >= height.into() || range.end > width.into() {
+            return;
+        }
+
+        if self.band.is_some_and(|band| band != line / 8) {
+            self.flush_band();
+        }
+        self.band = Some(line / 8);
+
+        let pixels = &mut self.line[range.clone()];
+        render_fn(pixels);
+
+        for (x, &pixel) in range.zip(pixels.iter()) {
+            let on = self.dither.is_on(x, line, luma(pixel));
+            self.display.set_pixel(x as u32, line as u32, on.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_helpers::CaptureInterface, Builder};
+
+    #[test]
+    fn lines_are_packed_and_flushed_per_band() {
+        let iface = CaptureInterface::new();
+        let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+        display.init().unwrap();
+        display.flush().unwrap();
+        iface.clear();
+
+        let white = Rgb565Pixel(0xFFFF);
+        let grey = Rgb565Pixel(0x8410);
+        let mut lines = LineBufferAdapter::new(&mut display, Dither::Threshold(0xC0));
+        (&mut lines).process_line(0, 2..6, |pixels| pixels.fill(white));
+        (&mut lines).process_line(1, 4..5, |pixels| pixels.fill(grey));
+        assert!(iface.data().is_empty());
+
+        // Moving on to the next band flushes the first one
+        (&mut lines).process_line(8, 0..1, |pixels| pixels.fill(Rgb565Pixel(0)));
+        assert_eq!(iface.data(), [1, 1, 1, 1]);
+        (&mut lines).process_line(160, 0..1, |_| unreachable!());
+        lines.finish().unwrap();
+        assert_eq!(iface.data(), [1, 1, 1, 1, 0]);
+        assert!(!display.get_pixel(4, 1));
+
+        // Half of the grey pixels are on
+        let mut lines = LineBufferAdapter::new(&mut display, Dither::Ordered);
+        for line in 8..16 {
+            (&mut lines).process_line(line, 0..128, |pixels| pixels.fill(grey));
+        }
+        lines.finish().unwrap();
+        let on = (0..128)
+            .flat_map(|x| (8..16).map(move |y| (x, y)))
+            .filter(|&(x, y)| display.get_pixel(x, y))
+            .count();
+        assert_eq!(on, 128 * 8 / 2);
+    }
+}
@@ -0,0 +1,300 @@
+//! Golden-image tests of the framebuffer, behind the `test-util` feature
+//!
+//! UI code can be checked on the host by comparing the framebuffer against an expected
+//! [`Snapshot`] with [`assert_framebuffer_eq`], which shows the rows that differ when it fails.
+
+use std::{vec, vec::Vec};
+
+use display_interface::WriteOnlyDataCommand;
+
+use crate::{
+    displayrotation::RotationMode,
+    mode::{FrameBufferStorage, GraphicsMode},
+};
+
+/// An image of the framebuffer for golden-image tests, to compare a display against with
+/// [`assert_framebuffer_eq`]
+///
+/// Expected images can be written as rows of `#` and `.`, loaded from a plain PBM file, unpacked
+/// from a 1bpp bitmap or built pixel by pixel:
+///
+/// ```rust
+/// use sh1108::{mode::GraphicsMode, snapshot::*, test_helpers::StubInterface, Builder};
+///
+/// let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+/// display.set_pixel(1, 0, 1);
+/// display.set_pixel(0, 1, 1);
+///
+/// let expected = Snapshot::new(128, 160).with_rows(0, 0, &[".#", "#."]);
+/// assert_framebuffer_eq(&display, &expected);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    width: u32,
+    height: u32,
+    pixels: Vec<bool>,
+}
+
+impl Snapshot {
+    /// Create an image of `width` x `height` pixels, all off
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![false; (width * height) as usize],
+        }
+    }
+
+    /// Take an image of the framebuffer of `display`, in display coordinates taking rotation into
+    /// account
+    pub fn of<DI, const N: usize, R, S>(display: &GraphicsMode<DI, N, R, S>) -> Self
+    where
+        DI: WriteOnlyDataCommand,
+        R: RotationMode,
+        S: FrameBufferStorage,
+    {
+        let (width, height) = display.get_dimensions();
+        let mut snapshot = Self::new(width.into(), height.into());
+        for y in 0..snapshot.height {
+            for x in 0..snapshot.width {
+                snapshot.set(x, y, display.get_pixel(x, y));
+            }
+        }
+
+        snapshot
+    }
+
+    /// Create an image from rows of `#` for on and `.` or a space for off pixels, all of the
+    /// same length
+    ///
+    /// Panics if the rows differ in length or contain other characters.
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        Self::new(width as u32, rows.len() as u32).with_rows(0, 0, rows)
+    }
+
+    /// Create an image from a plain (`P1`) PBM file, as written by most image editors
+    ///
+    /// Panics if `pbm` is not a valid plain PBM file.
+    pub fn from_pbm(pbm: &str) -> Self {
+        let mut tokens = pbm
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or(""))
+            .flat_map(str::split_whitespace);
+        assert_eq!(tokens.next(), Some("P1"), "not a plain PBM file");
+        let mut dimension = || -> u32 {
+            let token = tokens.next().expect("PBM file without dimensions");
+            token.parse().expect("invalid PBM dimensions")
+        };
+        let (width, height) = (dimension(), dimension());
+
+        // Pixels may or may not be separated by whitespace
+        let bits: Vec<bool> = tokens
+            .flat_map(str::chars)
+            .map(|c| match c {
+                '0' => false,
+                '1' => true,
+                _ => panic!("invalid PBM pixel {:?}", c),
+            })
+            .collect();
+        assert_eq!(
+            bits.len(),
+            (width * height) as usize,
+            "PBM file with the wrong number of pixels"
+        );
+
+        Self {
+            width,
+            height,
+            pixels: bits,
+        }
+    }
+
+    /// Create an image from a packed bitmap in the format of
+    /// [`GraphicsMode::draw_bitmap`](crate::mode::GraphicsMode::draw_bitmap): rows starting on
+    /// a new byte, with the leftmost pixel in the most significant bit. Missing bytes are off.
+    pub fn from_packed(width: u32, height: u32, data: &[u8]) -> Self {
+        let row_bytes = width.div_ceil(8) as usize;
+        let mut snapshot = Self::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let byte = data
+                    .get(y as usize * row_bytes + x as usize / 8)
+                    .copied()
+                    .unwrap_or(0);
+                snapshot.set(x, y, byte & (0x80 >> (x % 8)) != 0);
+            }
+        }
+
+        snapshot
+    }
+
+    /// Turn the pixel at `x`, `y` on or off, pixels outside the image are ignored
+    pub fn with_pixel(mut self, x: u32, y: u32, on: bool) -> Self {
+        self.set(x, y, on);
+        self
+    }
+
+    /// Turn all pixels of the `width` x `height` rectangle at `x`, `y` on or off
+    pub fn with_rect(mut self, x: u32, y: u32, width: u32, height: u32, on: bool) -> Self {
+        for y in y..y.saturating_add(height) {
+            for x in x..x.saturating_add(width) {
+                self.set(x, y, on);
+            }
+        }
+
+        self
+    }
+
+    /// Draw `rows` of `#` and `.` like [`Snapshot::from_rows`] with their top left corner at `x`,
+    /// `y`
+    pub fn with_rows(mut self, x: u32, y: u32, rows: &[&str]) -> Self {
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        for (dy, row) in (0..).zip(rows) {
+            assert_eq!(row.chars().count(), width, "rows of different lengths");
+            for (dx, c) in (0..).zip(row.chars()) {
+                let on = match c {
+                    '#' => true,
+                    '.' | ' ' => false,
+                    _ => panic!("invalid pixel {:?}, expected '#' or '.'", c),
+                };
+                self.set(x + dx, y + dy, on);
+            }
+        }
+
+        self
+    }
+
+    /// Get the width and height of the image
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Whether the pixel at `x`, `y` is on. Pixels outside the image read as off.
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        x < self.width && y < self.height && self.pixels[(y * self.width + x) as usize]
+    }
+
+    /// Compare `actual` against this expected image, returning what differs if anything does
+    pub fn diff(&self, actual: &Snapshot) -> Option<SnapshotDiff> {
+        (self != actual).then(|| SnapshotDiff {
+            expected: self.clone(),
+            actual: actual.clone(),
+        })
+    }
+
+    fn set(&mut self, x: u32, y: u32, on: bool) {
+        if x < self.width && y < self.height {
+            self.pixels[(y * self.width + x) as usize] = on;
+        }
+    }
+}
+
+/// The difference between two [`Snapshot`]s. Its `Display` output lists every row that differs,
+/// with `+` for pixels that are on but should be off and `-` for pixels that are off but should
+/// be on, next to `#` and `.` for the pixels that match.
+#[derive(Debug, Clone)]
+pub struct SnapshotDiff {
+    expected: Snapshot,
+    actual: Snapshot,
+}
+
+impl SnapshotDiff {
+    /// Number of pixels that differ, counting all of them if the sizes differ
+    pub fn differing_pixels(&self) -> usize {
+        if self.expected.dimensions() != self.actual.dimensions() {
+            return self.expected.pixels.len().max(self.actual.pixels.len());
+        }
+
+        (self.expected.pixels.iter())
+            .zip(&self.actual.pixels)
+            .filter(|(expected, actual)| expected != actual)
+            .count()
+    }
+}
+
+impl core::fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let (expected, actual) = (&self.expected, &self.actual);
+        if expected.dimensions() != actual.dimensions() {
+            return write!(
+                f,
+                "expected a {}x{} image, got {}x{}",
+                expected.width, expected.height, actual.width, actual.height
+            );
+        }
+
+        writeln!(f, "{} pixels differ:", self.differing_pixels())?;
+        for y in 0..expected.height {
+            if (0..expected.width).all(|x| expected.get(x, y) == actual.get(x, y)) {
+                continue;
+            }
+
+            write!(f, "{:4} ", y)?;
+            for x in 0..expected.width {
+                let c = match (expected.get(x, y), actual.get(x, y)) {
+                    (true, true) => '#',
+                    (false, false) => '.',
+                    (false, true) => '+',
+                    (true, false) => '-',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that the framebuffer of `display` matches `expected`, see [`Snapshot`]
+///
+/// Panics with the rows that differ if it does not.
+#[track_caller]
+pub fn assert_framebuffer_eq<DI, const N: usize, R, S>(
+    display: &GraphicsMode<DI, N, R, S>,
+    expected: &Snapshot,
+) where
+    DI: WriteOnlyDataCommand,
+    R: RotationMode,
+    S: FrameBufferStorage,
+{
+    if let Some(diff) = expected.diff(&Snapshot::of(display)) {
+        panic!("framebuffer does not match the snapshot, {}", diff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::format;
+
+    use super::*;
+    use crate::{test_helpers::StubInterface, Builder};
+
+    #[test]
+    fn snapshots_compare_and_show_differences() {
+        let pbm = "P1\n# a diagonal\n3 2\n1 0 0\n010\n";
+        let packed = Snapshot::from_packed(3, 2, &[0b1000_0000, 0b0100_0000]);
+        assert_eq!(Snapshot::from_pbm(pbm), packed);
+        assert_eq!(Snapshot::from_rows(&["#..", ".#."]), packed);
+        assert!(packed.diff(&packed.clone()).is_none());
+
+        let diff = packed.diff(&Snapshot::from_rows(&["#.#", "..."])).unwrap();
+        assert_eq!(diff.differing_pixels(), 2);
+        assert_eq!(
+            format!("{}", diff),
+            "2 pixels differ:\n   0 #.+\n   1 .-.\n"
+        );
+
+        let mut display: GraphicsMode<_> = Builder::new().connect(StubInterface).into();
+        display.set_pixel(127, 159, 1);
+        assert_framebuffer_eq(
+            &display,
+            &Snapshot::new(128, 160).with_pixel(127, 159, true),
+        );
+        let diff = Snapshot::new(128, 160)
+            .diff(&Snapshot::of(&display))
+            .unwrap();
+        assert!(format!("{}", diff).ends_with("+\n"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Ready made SPI interfaces for the SH1108, with the `spi` feature
+//!
+//! In 4-wire SPI mode the SH1108 tells commands and display data apart by the level of its A0
+//! (DC) pin: low for commands, high for data. [`SPIDisplayInterface`] creates a
+//! [`display_interface_spi`] interface which toggles the DC pin for every transfer, so no
+//! compatible interface crate has to be found separately:
+//!
+//! ```rust,ignore
+//! let spi = /* an embedded-hal `blocking::spi::Write<u8>` implementor */;
+//! let interface = SPIDisplayInterface::new(spi, dc);
+//! let mut disp: GraphicsMode<_> = Builder::new().connect(interface).into();
+//! ```
+//!
+//! Modules that only break out 3-wire SPI have no DC pin. The SH1108 then reads 9 bit words
+//! instead, a D/C bit followed by the 8 bits of the byte. [`SPI3WireInterface`] packs those words
+//! into the bytes the SPI bus sends, so it works with any 8 bit SPI peripheral, but needs a chip
+//! select pin, see [`SPIDisplayInterface::new_3wire`].
+
+use display_interface::{DataFormat, DisplayError, WriteOnlyDataCommand};
+pub use display_interface_spi::{SPIInterface, SPIInterfaceNoCS};
+use hal::{blocking::spi, digital::v2::OutputPin};
+
+/// Constructor for SPI interfaces set up for the SH1108
+#[derive(Clone, Copy, Debug)]
+pub struct SPIDisplayInterface;
+
+impl SPIDisplayInterface {
+    /// Create an interface from an SPI bus and a DC pin, for a display which has its chip select
+    /// tied low or managed by the SPI peripheral
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<SPI, DC>(spi: SPI, dc: DC) -> SPIInterfaceNoCS<SPI, DC>
+    where
+        SPI: spi::Write<u8>,
+        DC: OutputPin,
+    {
+        SPIInterfaceNoCS::new(spi, dc)
+    }
+
+    /// Create an interface from an SPI bus, a DC pin and a chip select pin, which is asserted
+    /// for the duration of every transfer
+    pub fn new_with_cs<SPI, DC, CS>(spi: SPI, dc: DC, cs: CS) -> SPIInterface<SPI, DC, CS>
+    where
+        SPI: spi::Write<u8>,
+        DC: OutputPin,
+        CS: OutputPin,
+    {
+        SPIInterface::new(spi, dc, cs)
+    }
+
+    /// Create a 3-wire interface from an SPI bus and a chip select pin, for a display without a
+    /// DC pin
+    pub fn new_3wire<SPI, CS>(spi: SPI, cs: CS) -> SPI3WireInterface<SPI, CS>
+    where
+        SPI: spi::Write<u8>,
+        CS: OutputPin,
+    {
+        SPI3WireInterface::new(spi, cs)
+    }
+}
+
+/// Interface for 3-wire SPI, which sends the D/C bit in front of every byte
+///
+/// Every 8 bytes take 9 bytes on the bus. A transfer that does not end on a byte boundary is
+/// padded with zero bits, and chip select is deasserted after every transfer, which resets the
+/// shift register of the SH1108 so the padding bits are dropped. The chip select must therefore
+/// be connected rather than tied low.
+pub struct SPI3WireInterface<SPI, CS> {
+    spi: SPI,
+    cs: CS,
+}
+
+impl<SPI, CS> SPI3WireInterface<SPI, CS>
+where
+    SPI: spi::Write<u8>,
+    CS: OutputPin,
+{
+    /// Create an interface from an SPI bus and a chip select pin
+    pub fn new(spi: SPI, cs: CS) -> Self {
+        Self { spi, cs }
+    }
+
+    /// Release the SPI bus and chip select pin
+    pub fn release(self) -> (SPI, CS) {
+        (self.spi, self.cs)
+    }
+
+    fn send(&mut self, data: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.cs.set_low().map_err(|_| DisplayError::CSError)?;
+        let result = self.send_words(data, format);
+        self.cs.set_high().map_err(|_| DisplayError::CSError)?;
+
+        result
+    }
+
+    fn send_words(&mut self, data: bool, format: DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut packer = Packer::new(data);
+
+        match format {
+            DataFormat::U8(buf) => {
+                for &byte in buf {
+                    packer.push(byte, &mut self.spi)?;
+                }
+            }
+            DataFormat::U8Iter(iter) => {
+                for byte in iter {
+                    packer.push(byte, &mut self.spi)?;
+                }
+            }
+            _ => return Err(DisplayError::DataFormatNotImplemented),
+        }
+
+        packer.finish(&mut self.spi)
+    }
+}
+
+impl<SPI, CS> WriteOnlyDataCommand for SPI3WireInterface<SPI, CS>
+where
+    SPI: spi::Write<u8>,
+    CS: OutputPin,
+{
+    fn send_commands(&mut self, cmd: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(false, cmd)
+    }
+
+    fn send_data(&mut self, buf: DataFormat<'_>) -> Result<(), DisplayError> {
+        self.send(true, buf)
+    }
+}
+
+/// Packs 9 bit words MSB first into bytes and sends them in chunks of 36 bytes, i.e. 32 words
+struct Packer {
+    dc: u16,
+    bits: u32,
+    pending: u8,
+    buffer: [u8; 36],
+    len: usize,
+}
+
+impl Packer {
+    fn new(data: bool) -> Self {
+        Self {
+            dc: u16::from(data) << 8,
+            bits: 0,
+            pending: 0,
+            buffer: [0; 36],
+            len: 0,
+        }
+    }
+
+    fn push<SPI: spi::Write<u8>>(&mut self, byte: u8, spi: &mut SPI) -> Result<(), DisplayError> {
+        // Fewer than 8 bits are pending, so the word and the pending bits fit in 16 bits
+        let word = (u32::from(self.pending) << 9) | u32::from(self.dc | u16::from(byte));
+        let bits = self.bits + 9;
+
+        self.emit((word >> (bits - 8)) as u8, spi)?;
+        let bits = bits - 8;
+        if bits >= 8 {
+            self.emit((word >> (bits - 8)) as u8, spi)?;
+            self.bits = bits - 8;
+        } else {
+            self.bits = bits;
+        }
+        self.pending = (word & ((1 << self.bits) - 1)) as u8;
+
+        Ok(())
+    }
+
+    fn emit<SPI: spi::Write<u8>>(&mut self, byte: u8, spi: &mut SPI) -> Result<(), DisplayError> {
+        self.buffer[self.len] = byte;
+        self.len += 1;
+
+        if self.len == self.buffer.len() {
+            self.len = 0;
+            spi.write(&self.buffer)
+                .map_err(|_| DisplayError::BusWriteError)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish<SPI: spi::Write<u8>>(mut self, spi: &mut SPI) -> Result<(), DisplayError> {
+        if self.bits > 0 {
+            let byte = self.pending << (8 - self.bits);
+            self.emit(byte, spi)?;
+        }
+
+        spi.write(&self.buffer[..self.len])
+            .map_err(|_| DisplayError::BusWriteError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::PinStub;
+    use std::{vec, vec::Vec};
+
+    struct RecordingSpi(Vec<u8>);
+
+    impl spi::Write<u8> for RecordingSpi {
+        type Error = ();
+
+        fn write(&mut self, buf: &[u8]) -> Result<(), ()> {
+            self.0.extend_from_slice(buf);
+            Ok(())
+        }
+    }
+
+    /// Split the sent bits back into 9 bit words, dropping the padding
+    fn words(bytes: &[u8]) -> Vec<u16> {
+        let bits: Vec<u16> = bytes
+            .iter()
+            .flat_map(|byte| (0..8).rev().map(move |bit| u16::from(byte >> bit & 1)))
+            .collect();
+
+        bits.chunks_exact(9)
+            .map(|word| word.iter().fold(0, |acc, bit| acc << 1 | bit))
+            .collect()
+    }
+
+    #[test]
+    fn three_wire_words_carry_the_dc_bit() {
+        let mut iface = SPIDisplayInterface::new_3wire(RecordingSpi(vec![]), PinStub);
+
+        iface
+            .send_commands(DataFormat::U8(&[0xAE, 0x81, 0x80]))
+            .unwrap();
+        let (spi, cs) = iface.release();
+        assert_eq!(spi.0.len(), 4);
+        assert_eq!(words(&spi.0), [0x0AE, 0x081, 0x080]);
+
+        let mut iface = SPI3WireInterface::new(RecordingSpi(vec![]), cs);
+        let data: Vec<u8> = (0..100).collect();
+        iface
+            .send_data(DataFormat::U8Iter(&mut data.iter().copied()))
+            .unwrap();
+        let (spi, _) = iface.release();
+        assert_eq!(spi.0.len(), (100 * 9usize).div_ceil(8));
+        let expected: Vec<u16> = data.iter().map(|&byte| 0x100 | u16::from(byte)).collect();
+        assert_eq!(words(&spi.0), expected);
+    }
+}
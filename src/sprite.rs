@@ -0,0 +1,220 @@
+//! Sprite sheets and offscreen sprites
+//!
+//! A sprite sheet packs equally sized frames, like the steps of an animation or a set of icons,
+//! into one 1bpp bitmap. Frames are laid out in a grid `columns` frames wide and numbered left to
+//! right, top to bottom. Draw a frame with
+//! [`GraphicsMode::draw_sprite`](../mode/graphics/struct.GraphicsMode.html#method.draw_sprite).
+//!
+//! ```rust
+//! use sh1108::sprite::SpriteSheet;
+//!
+//! // Four 8x8 frames side by side
+//! static FRAMES: [u8; 32] = [0; 32];
+//!
+//! let sheet = SpriteSheet::new(&FRAMES, (8, 8), 4);
+//! assert_eq!(sheet.frame_count(), 4);
+//! ```
+//!
+//! A [`Sprite`] is a small offscreen surface with its own buffer. Moving UI elements can be
+//! rendered into one once, through [`Sprite::set_pixel`] or the embedded-graphics `DrawTarget`,
+//! and composited onto the display every frame with
+//! [`GraphicsMode::blit_sprite`](../mode/graphics/struct.GraphicsMode.html#method.blit_sprite).
+//!
+//! ```rust
+//! use sh1108::sprite::Sprite;
+//!
+//! // 12x10 pixels take 2 bytes per row
+//! let mut cursor = Sprite::<20>::new(12, 10);
+//! cursor.set_pixel(0, 0, true);
+//! cursor.set_transparent(true);
+//! assert!(cursor.get_pixel(0, 0));
+//! ```
+
+#[cfg(feature = "graphics")]
+use core::convert::Infallible;
+
+#[cfg(feature = "graphics")]
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::BinaryColor,
+    Pixel,
+};
+
+/// A grid of equally sized frames stored in one packed bitmap
+///
+/// The bitmap rows each start on a new byte, with the leftmost pixel in the most significant bit.
+#[derive(Clone, Copy, Debug)]
+pub struct SpriteSheet<'a> {
+    data: &'a [u8],
+    frame_size: (u32, u32),
+    columns: u32,
+}
+
+impl<'a> SpriteSheet<'a> {
+    /// Create a sprite sheet from a bitmap holding frames of `frame_size` (width, height) pixels,
+    /// `columns` frames per row.
+    pub const fn new(data: &'a [u8], frame_size: (u32, u32), columns: u32) -> Self {
+        Self {
+            data,
+            frame_size,
+            columns,
+        }
+    }
+
+    /// Get the size of a single frame
+    pub fn frame_size(&self) -> (u32, u32) {
+        self.frame_size
+    }
+
+    /// Get the number of complete frames in the sheet
+    pub fn frame_count(&self) -> usize {
+        let frame_rows_bytes = self.row_bytes() * self.frame_size.1 as usize;
+
+        if frame_rows_bytes == 0 {
+            return 0;
+        }
+
+        self.data.len() / frame_rows_bytes * self.columns as usize
+    }
+
+    /// Get the bitmap data of the whole sheet
+    pub(crate) fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Get the number of bytes per row of the bitmap
+    pub(crate) fn row_bytes(&self) -> usize {
+        (self.columns as usize * self.frame_size.0 as usize).div_ceil(8)
+    }
+
+    /// Get the position of the top left pixel of a frame within the bitmap
+    pub(crate) fn frame_origin(&self, index: usize) -> (u32, u32) {
+        let columns = self.columns.max(1) as usize;
+
+        (
+            (index % columns) as u32 * self.frame_size.0,
+            (index / columns) as u32 * self.frame_size.1,
+        )
+    }
+}
+
+/// An offscreen 1bpp surface of up to `N` bytes, see the [module documentation](self)
+///
+/// The buffer has the same layout as a [`SpriteSheet`] bitmap: rows each start on a new byte,
+/// with the leftmost pixel in the most significant bit.
+#[derive(Clone, Copy, Debug)]
+pub struct Sprite<const N: usize> {
+    buffer: [u8; N],
+    width: u32,
+    height: u32,
+    transparent: bool,
+}
+
+impl<const N: usize> Sprite<N> {
+    /// Create a cleared, opaque sprite of `width` by `height` pixels
+    ///
+    /// Panics if `N` is smaller than `width.div_ceil(8) * height` bytes.
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(
+            N >= (width as usize).div_ceil(8) * height as usize,
+            "buffer too small for the sprite size"
+        );
+
+        Self {
+            buffer: [0; N],
+            width,
+            height,
+            transparent: false,
+        }
+    }
+
+    /// Get the sprite width and height in pixels
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// Composite only the lit pixels, leaving the display behind the off pixels showing through.
+    /// Sprites are opaque by default and overwrite everything they cover.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
+    /// Whether the off pixels of the sprite are left out when compositing
+    pub fn is_transparent(&self) -> bool {
+        self.transparent
+    }
+
+    /// Turn off all pixels
+    pub fn clear(&mut self) {
+        self.buffer.fill(0);
+    }
+
+    /// Turn a pixel on or off. Pixels outside the sprite are ignored.
+    pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        if x < self.width && y < self.height {
+            let (index, bit) = self.bit(x, y);
+
+            if on {
+                self.buffer[index] |= bit;
+            } else {
+                self.buffer[index] &= !bit;
+            }
+        }
+    }
+
+    /// Whether a pixel is on. Pixels outside the sprite are off.
+    pub fn get_pixel(&self, x: u32, y: u32) -> bool {
+        if x < self.width && y < self.height {
+            let (index, bit) = self.bit(x, y);
+
+            self.buffer[index] & bit != 0
+        } else {
+            false
+        }
+    }
+
+    /// Get the packed bitmap data
+    pub fn data(&self) -> &[u8] {
+        &self.buffer[..self.row_bytes() * self.height as usize]
+    }
+
+    /// Get the number of bytes per row of the bitmap
+    pub(crate) fn row_bytes(&self) -> usize {
+        (self.width as usize).div_ceil(8)
+    }
+
+    /// Buffer index and bit mask of a pixel inside the sprite
+    fn bit(&self, x: u32, y: u32) -> (usize, u8) {
+        (
+            y as usize * self.row_bytes() + x as usize / 8,
+            0x80 >> (x % 8),
+        )
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const N: usize> DrawTarget for Sprite<N> {
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        for Pixel(pos, color) in pixels {
+            if let (Ok(x), Ok(y)) = (u32::try_from(pos.x), u32::try_from(pos.y)) {
+                self.set_pixel(x, y, color.is_on());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "graphics")]
+impl<const N: usize> OriginDimensions for Sprite<N> {
+    fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+}
@@ -1,16 +1,35 @@
 //! Helpers for use in examples and tests
+//!
+//! Available to downstream crates with the `test-util` feature, which requires `std`. Use
+//! [`CaptureInterface`] to assert on the exact bytes the driver sends, [`RamInterface`] to check
+//! what ends up in display RAM and [`DelayRecorder`] to check the delays it asks for, without any
+//! hardware. For golden-image tests of UI code, see the [`snapshot`](crate::snapshot) module:
+//!
+//! ```rust
+//! use sh1108::{mode::GraphicsMode, test_helpers::CaptureInterface, Builder};
+//!
+//! let iface = CaptureInterface::new();
+//! let mut display: GraphicsMode<_> = Builder::new().connect(iface.clone()).into();
+//! display.init().unwrap();
+//!
+//! // Display on is the last command of the init sequence
+//! assert_eq!(iface.commands().last(), Some(&0xAF));
+//! ```
+//!
 //! Code from https://github.com/jamwaffles/sh1106/blob/master/src/test_helpers.rs
 //! Copyright (c) 2018 James Waples MIT
 
 use display_interface::{DisplayError, WriteOnlyDataCommand};
 use embedded_hal::{
     blocking::{
+        delay::{DelayMs, DelayUs},
         i2c,
         spi::{self, Transfer},
     },
     digital::v2::OutputPin,
 };
 
+/// SPI bus which accepts and discards everything
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct SpiStub;
@@ -31,6 +50,7 @@ impl Transfer<u8> for SpiStub {
     }
 }
 
+/// I2C bus which accepts and discards everything
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct I2cStub;
@@ -43,6 +63,7 @@ impl i2c::Write for I2cStub {
     }
 }
 
+/// Output pin which ignores every change
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct PinStub;
@@ -59,6 +80,7 @@ impl OutputPin for PinStub {
     }
 }
 
+/// Display interface which accepts and discards everything
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub struct StubInterface;
@@ -74,3 +96,304 @@ impl WriteOnlyDataCommand for StubInterface {
         Ok(())
     }
 }
+
+/// A single transfer recorded by [`CaptureInterface`]
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sent {
+    /// Command bytes
+    Commands(std::vec::Vec<u8>),
+    /// Data bytes
+    Data(std::vec::Vec<u8>),
+}
+
+/// Interface which records every command and data burst. Clones share the same log, so a handle
+/// can be kept around after moving the interface into the driver.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct CaptureInterface {
+    log: std::rc::Rc<std::cell::RefCell<std::vec::Vec<Sent>>>,
+    fail_data: std::rc::Rc<std::cell::Cell<bool>>,
+    lent: std::rc::Rc<std::cell::RefCell<Option<std::boxed::Box<dyn std::any::Any>>>>,
+}
+
+#[allow(dead_code)]
+impl CaptureInterface {
+    /// Create an interface with an empty log
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All recorded transfers in order
+    pub fn sent(&self) -> std::vec::Vec<Sent> {
+        self.log.borrow().clone()
+    }
+
+    /// All command bytes, concatenated
+    pub fn commands(&self) -> std::vec::Vec<u8> {
+        self.log
+            .borrow()
+            .iter()
+            .filter_map(|s| match s {
+                Sent::Commands(c) => Some(c.clone()),
+                Sent::Data(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// All data bytes, concatenated
+    pub fn data(&self) -> std::vec::Vec<u8> {
+        self.log
+            .borrow()
+            .iter()
+            .filter_map(|s| match s {
+                Sent::Data(d) => Some(d.clone()),
+                Sent::Commands(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// Make every following data transfer fail with `BusWriteError` without being recorded, to
+    /// simulate a transfer aborted halfway
+    pub fn set_fail_data(&self, fail: bool) {
+        self.fail_data.set(fail);
+    }
+
+    /// Forget everything recorded so far
+    pub fn clear(&self) {
+        self.log.borrow_mut().clear();
+    }
+
+    /// Hold on to `value` until `take_lent`, like an interface keeps a buffer during a transfer
+    pub fn lend<T: 'static>(&self, value: T) {
+        *self.lent.borrow_mut() = Some(std::boxed::Box::new(value));
+    }
+
+    /// Give back the value passed to `lend`, if it is a `T`
+    pub fn take_lent<T: 'static>(&self) -> Option<T> {
+        let lent = self.lent.borrow_mut().take()?;
+
+        lent.downcast().ok().map(|value| *value)
+    }
+
+    fn bytes(format: display_interface::DataFormat<'_>) -> std::vec::Vec<u8> {
+        match format {
+            display_interface::DataFormat::U8(buf) => buf.to_vec(),
+            display_interface::DataFormat::U8Iter(iter) => iter.collect(),
+            _ => unimplemented!("only u8 data formats are used by this driver"),
+        }
+    }
+}
+
+impl WriteOnlyDataCommand for CaptureInterface {
+    fn send_commands(
+        &mut self,
+        cmd: display_interface::DataFormat<'_>,
+    ) -> Result<(), DisplayError> {
+        self.log.borrow_mut().push(Sent::Commands(Self::bytes(cmd)));
+        Ok(())
+    }
+    fn send_data(&mut self, buf: display_interface::DataFormat<'_>) -> Result<(), DisplayError> {
+        if self.fail_data.get() {
+            return Err(DisplayError::BusWriteError);
+        }
+        self.log.borrow_mut().push(Sent::Data(Self::bytes(buf)));
+        Ok(())
+    }
+}
+
+/// A zeroed framebuffer of `len` bytes in `'static` memory, e.g. for flushes through DMA. It is
+/// leaked, which is fine for tests.
+pub fn static_framebuffer(len: usize) -> &'static mut [u8] {
+    std::boxed::Box::leak(std::vec![0; len].into_boxed_slice())
+}
+
+/// Interface simulating the display RAM of an SH1108 in page addressing mode, including the
+/// status and data reads of [`ReadInterface`](crate::read::ReadInterface). Clones share the same
+/// RAM, so a handle can be kept around after moving the interface into the driver.
+///
+/// Only the RAM address, read-modify-write and display on/off commands are interpreted, all
+/// other commands are accepted and ignored.
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub struct RamInterface {
+    state: std::rc::Rc<std::cell::RefCell<RamState>>,
+}
+
+#[derive(Debug)]
+struct RamState {
+    ram: [[u8; 160]; 20],
+    page: u8,
+    column: u8,
+    /// Column to return to at the end of read-modify-write mode, if it is active
+    modify_start: Option<u8>,
+    /// Byte that the last data read loaded into the bus holder, which the next read returns.
+    /// Setting the RAM address empties it, so the first read after that returns a dummy byte.
+    latch: Option<u8>,
+    display_on: bool,
+}
+
+impl Default for RamInterface {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[allow(dead_code)]
+impl RamInterface {
+    /// Create an interface with cleared RAM and the display off
+    pub fn new() -> Self {
+        Self {
+            state: std::rc::Rc::new(std::cell::RefCell::new(RamState {
+                ram: [[0; 160]; 20],
+                page: 0,
+                column: 0,
+                modify_start: None,
+                latch: None,
+                display_on: false,
+            })),
+        }
+    }
+
+    /// Get the RAM contents of `page`, all 160 columns
+    pub fn page(&self, page: u8) -> [u8; 160] {
+        self.state.borrow().ram[usize::from(page)]
+    }
+
+    /// Overwrite RAM from `column` of `page` on with `data`, as if something else drew it
+    pub fn set_ram(&self, column: u8, page: u8, data: &[u8]) {
+        let column = usize::from(column);
+        self.state.borrow_mut().ram[usize::from(page)][column..column + data.len()]
+            .copy_from_slice(data);
+    }
+
+    /// Value of the status register, used by the `ReadInterface` implementation
+    pub(crate) fn status(&self) -> u8 {
+        // The ON/OFF bit is low while the display is on
+        if self.state.borrow().display_on {
+            0
+        } else {
+            0x40
+        }
+    }
+
+    /// Read display data like the controller, used by the `ReadInterface` implementation
+    pub(crate) fn read(&self, buf: &mut [u8]) {
+        let mut state = self.state.borrow_mut();
+
+        // Reads lag one behind the column address: each returns the byte loaded by the read
+        // before and loads the byte at the current column, which moves on outside of
+        // read-modify-write mode
+        for byte in buf {
+            let loaded = state.ram[usize::from(state.page)][usize::from(state.column)];
+            *byte = state.latch.replace(loaded).unwrap_or(0xA5);
+            if state.modify_start.is_none() {
+                state.column = state.column.saturating_add(1).min(159);
+            }
+        }
+    }
+}
+
+impl WriteOnlyDataCommand for RamInterface {
+    fn send_commands(
+        &mut self,
+        cmd: display_interface::DataFormat<'_>,
+    ) -> Result<(), DisplayError> {
+        let bytes = CaptureInterface::bytes(cmd);
+        let mut state = self.state.borrow_mut();
+        let mut bytes = bytes.iter().copied();
+
+        while let Some(byte) = bytes.next() {
+            match byte {
+                0x00..=0x0F => state.column = (state.column & 0xF0) | byte,
+                0x10..=0x1F => state.column = (state.column & 0x0F) | ((byte & 0xF) << 4),
+                0xB0 => state.page = bytes.next().unwrap_or(0),
+                0xAE | 0xAF => state.display_on = byte == 0xAF,
+                0xE0 => state.modify_start = Some(state.column),
+                0xEE => {
+                    if let Some(column) = state.modify_start.take() {
+                        state.column = column;
+                    }
+                }
+                // The second byte of the other two byte commands is not an address
+                0x81 | 0xA9 | 0xAD | 0xD5 | 0xD9 | 0xDB | 0xDC => {
+                    bytes.next();
+                }
+                _ => {}
+            }
+            if matches!(byte, 0x00..=0x1F | 0xB0) {
+                state.latch = None;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn send_data(&mut self, buf: display_interface::DataFormat<'_>) -> Result<(), DisplayError> {
+        let mut state = self.state.borrow_mut();
+
+        for byte in CaptureInterface::bytes(buf) {
+            let (page, column) = (usize::from(state.page), usize::from(state.column));
+            state.ram[page][column] = byte;
+            state.column = state.column.saturating_add(1).min(159);
+            // A write moves the bus holder along with the column, so a read right after it
+            // returns the next byte without another dummy read
+            let loaded = state.ram[page][usize::from(state.column)];
+            state.latch = Some(loaded);
+        }
+
+        Ok(())
+    }
+}
+
+/// Delay provider which returns immediately and records every requested delay, the ones in
+/// milliseconds and the ones in microseconds separately
+#[allow(dead_code)]
+#[derive(Debug, Clone, Default)]
+pub struct DelayRecorder {
+    delays: std::vec::Vec<u32>,
+    delays_us: std::vec::Vec<u32>,
+}
+
+#[allow(dead_code)]
+impl DelayRecorder {
+    /// Create a recorder without any delays
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All requested delays in milliseconds
+    pub fn delays(&self) -> &[u32] {
+        &self.delays
+    }
+
+    /// All requested delays in microseconds
+    pub fn delays_us(&self) -> &[u32] {
+        &self.delays_us
+    }
+
+    /// Sum of all requested delays in milliseconds
+    pub fn total_ms(&self) -> u32 {
+        self.delays.iter().sum()
+    }
+}
+
+impl DelayMs<u8> for DelayRecorder {
+    fn delay_ms(&mut self, ms: u8) {
+        self.delays.push(ms.into());
+    }
+}
+
+impl DelayMs<u16> for DelayRecorder {
+    fn delay_ms(&mut self, ms: u16) {
+        self.delays.push(ms.into());
+    }
+}
+
+impl DelayUs<u32> for DelayRecorder {
+    fn delay_us(&mut self, us: u32) {
+        self.delays_us.push(us);
+    }
+}